@@ -0,0 +1,167 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Read-only auditor mode, with a log of every operation.
+//!
+//! With `--audit-log <path>`, every file opened, every SHA-256 digest
+//! computed and every check performed (with its inputs and result) is
+//! appended to the log, one JSON object per line, so that an auditor can
+//! archive exactly what their verification run saw and did. The log is only
+//! ever appended to.
+//!
+//! The run is read-only: verification results are neither reused from nor
+//! recorded into the local caches and checkpoints, and any other write (see
+//! [crate::utils::create_file]) is refused. A failed check is logged with the
+//! panic it raises.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    sync::{Mutex, OnceLock, PoisonError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{json, Value};
+
+use crate::journal::journal_input;
+
+/// An append-only log of JSON lines
+#[derive(Debug)]
+pub struct AuditLog {
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    /// Opens the log at the given path, appending to it if it exists
+    pub fn open(path: &Path) -> Self {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap_or_else(|err| panic!("Failed to open the audit log {path:?}: {err}"));
+        AuditLog {
+            file: Mutex::new(file),
+        }
+    }
+
+    /// Appends an event, with the given details, timestamped in milliseconds
+    /// since the Unix epoch
+    pub fn record(&self, event: &str, details: Value) {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis());
+        let line = json!({ "time_ms": time, "event": event, "details": details });
+        // Not poisoned by a panic while writing, which is logged as well
+        let written = {
+            let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+            writeln!(file, "{line}").and_then(|_| file.flush())
+        };
+        written.expect("Could not write to the audit log");
+    }
+}
+
+static AUDIT_LOG: OnceLock<AuditLog> = OnceLock::new();
+
+/// Opens the audit log at the given path (appending to it if it exists), and
+/// enters the auditor mode. Panics if the log is already open
+pub fn open_audit_log(path: &Path) {
+    AUDIT_LOG
+        .set(AuditLog::open(path))
+        .expect("An audit log is already open");
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        audit("panic", json!({ "message": info.to_string() }));
+        default_hook(info);
+    }));
+    audit(
+        "start",
+        json!({ "args": std::env::args().collect::<Vec<_>>() }),
+    );
+}
+
+/// Whether the process runs in auditor mode
+pub fn is_auditing() -> bool {
+    AUDIT_LOG.get().is_some()
+}
+
+/// Appends an event, with the given details, to the audit log (if any)
+pub fn audit(event: &str, details: Value) {
+    if let Some(log) = AUDIT_LOG.get() {
+        log.record(event, details);
+    }
+}
+
+/// Records that the file at the given path is opened
+pub fn audit_open(path: &Path) {
+    audit("open", json!({ "path": path.display().to_string() }));
+}
+
+/// Records the SHA-256 digest computed of the file at the given path
+pub fn audit_sha256(path: &Path, digest: &[u8; 32]) {
+    audit(
+        "sha256",
+        json!({ "path": path.display().to_string(), "sha256": hex::encode(digest) }),
+    );
+}
+
+/// Records a check, with its inputs and whether it passed
+pub fn audit_check(check: &str, inputs: Value, passed: bool) {
+    audit(
+        "check",
+        json!({ "check": check, "inputs": inputs, "passed": passed }),
+    );
+}
+
+/// Reads the file at the given path, recording that it is opened if it
+/// exists
+pub fn read_audited(path: &Path) -> io::Result<Vec<u8>> {
+    let bytes = fs::read(path)?;
+    audit_open(path);
+    journal_input(path);
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod audit_tests {
+    use std::{fs, path::Path};
+
+    use serde_json::{json, Value};
+
+    use crate::audit::AuditLog;
+
+    #[test]
+    fn events_are_appended() {
+        let path = Path::new("/tmp/test_audit_log");
+        let _ = fs::remove_file(path);
+        AuditLog::open(path).record("open", json!({ "path": "srs" }));
+        // Reopening the log appends to it
+        AuditLog::open(path).record("check", json!({ "passed": true }));
+
+        let events: Vec<Value> = fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let kinds: Vec<&str> = events
+            .iter()
+            .map(|e| e["event"].as_str().unwrap())
+            .collect();
+        assert_eq!(kinds, ["open", "check"]);
+        assert_eq!(events[0]["details"]["path"], json!("srs"));
+        assert_eq!(events[1]["details"]["passed"], json!(true));
+        assert!(events[0]["time_ms"].as_u64().unwrap() <= events[1]["time_ms"].as_u64().unwrap());
+    }
+}
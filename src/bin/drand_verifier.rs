@@ -22,11 +22,18 @@
 //!
 //! # How it works
 //!
-//! 1. Verifies the commitment matches SHA-256(round || salt)
-//! 2. Fetches the Drand signature for the specified round from the Drand API
-//! 3. Verifies the Drand signature is cryptographically valid
+//! 1. Verifies the commitment matches SHA-256(round || salt). The commitment
+//!    and its opening are given as arguments, or read from `beacon/` (checked
+//!    against the digests recorded in the ceremony manifest)
+//! 2. Fetches the information of the Drand chain (checked against the pinned
+//!    chain hash) and the Drand signature for the specified round from the
+//!    Drand API
+//! 3. Verifies that the commitment was published before the round was produced,
+//!    and that the Drand signature is cryptographically valid
 //! 4. Derives the scalar using the same process as the update:
 //!    - Calls [derive_randomness] to extract randomness from the signature
+//!      (when a range of rounds is committed to, the randomness is SHA-256 of
+//!      the randomness of all rounds of the range)
 //!    - Computes `seed = Blake2b-512(randomness || salt)`
 //!    - Generates `scalar = Scalar::random(ChaCha20Rng::from_seed(seed))`
 //! 5. Reads the last update proof and verifies that `proof.h == proof.g *
@@ -35,15 +42,26 @@
 //! If all checks pass, this proves the last SRS update was created using the
 //! randomness form the committed Drand round and the `salt` used in for such
 //! commitment.
+//!
+//! To check a beacon contribution at any other index (and the SRS it
+//! produced), use `srs_utils verify-beacon` with the randomness printed by
+//! this tool.
+
+use std::{path::Path, process::Command};
 
-use blake2::{Blake2b512, Digest};
-use blstrs::Scalar;
 use clap::Parser;
 use drand_verify::{derive_randomness, verify, G1Pubkey, Pubkey};
-use halo2curves::{ff::Field, group::Curve};
-use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use halo2curves::group::Curve;
 use serde::Deserialize;
-use sha2::Sha256;
+use sha2::{Digest, Sha256};
+use srs::{
+    drand::{
+        beacon_scalar, read_beacon_files, ChainInfo, DEFAULT_CHAIN_HASH, DEFAULT_CHAIN_PUBLIC_KEY,
+        DRAND_API,
+    },
+    net::agent,
+    state::{CeremonyManifest, DEFAULT_MANIFEST_PATH},
+};
 
 #[derive(Parser, Debug)]
 #[command(name = "drand-verifier")]
@@ -57,18 +75,40 @@ use sha2::Sha256;
                   update proof matches this scalar."
 )]
 struct Args {
-    /// The Drand round number used for the update
-    #[arg(short, long)]
-    round: u64,
+    /// The Drand round number used for the update (the first one, if a range
+    /// of rounds is used). If --round, --salt and --commitment are not
+    /// provided, they are read from the beacon files of the repository
+    /// (`beacon/commitment.json` and `beacon/reveal.json`), checked against
+    /// the ceremony manifest
+    #[arg(short, long, requires_all = ["salt", "commitment"])]
+    round: Option<u64>,
+
+    /// The last Drand round of the range of rounds used for the update. The
+    /// randomness of all rounds of the range is aggregated
+    #[arg(long, requires = "round")]
+    last_round: Option<u64>,
 
     /// The salt (hex) used in the commitment to the round number (16 bytes)
-    #[arg(short, long)]
-    salt: String,
+    #[arg(short, long, requires_all = ["round", "commitment"])]
+    salt: Option<String>,
 
     /// The commitment (hex) to the round number, supposedly
-    /// SHA-256(round || salt)
-    #[arg(short, long)]
-    commitment: String,
+    /// SHA-256(round || salt), or SHA-256(round || last_round || salt) for a
+    /// range of rounds
+    #[arg(short, long, requires_all = ["round", "salt"])]
+    commitment: Option<String>,
+
+    /// The hash (hex) of the Drand chain the round belongs to (by default, the
+    /// one of the beacon commitment file, or the Drand mainnet chain). The
+    /// chain information is fetched and checked against it
+    #[arg(long)]
+    chain_hash: Option<String>,
+
+    /// UNIX time at which the commitment was published (e.g. from a signed
+    /// timestamp). If not provided, it is the time of the first commit of the
+    /// repository containing the commitment
+    #[arg(long)]
+    commitment_time: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -80,20 +120,80 @@ struct DrandResponse {
     previous_signature: Option<String>,
 }
 
-/// https://api.drand.sh/v2/beacons/default/info
-const DRAND_PUBLIC_KEY: &str = "868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31";
-
-/// Fetches the Drand information, for the given round number, from the public
-/// Drand API. This information includes the round signature and the previous
-/// signature.
-fn fetch_drand_round(round: u64) -> Result<DrandResponse, std::io::Error> {
-    ureq::get(&format!(
-        "https://api.drand.sh/v2/beacons/default/rounds/{}",
-        round
-    ))
-    .call()
-    .map_err(|e| std::io::Error::other(format!("Error in HTTPS call: {:?}", e)))?
-    .into_json()
+/// Fetches the Drand information, for the given round number of the given
+/// chain, from the public Drand API. This information includes the round
+/// signature and the previous signature.
+fn fetch_drand_round(chain_hash: &str, round: u64) -> Result<DrandResponse, std::io::Error> {
+    agent()
+        .get(&format!("{DRAND_API}/{chain_hash}/public/{round}"))
+        .call()
+        .map_err(|e| std::io::Error::other(format!("Error in HTTPS call: {:?}", e)))?
+        .into_json()
+}
+
+/// Fetches (or reads from the local cache) the information of the chain with
+/// the given hash, checked against that hash. Warns loudly if the public key
+/// of the ceremony chain differs from the pinned one
+fn chain_info(chain_hash: &str) -> ChainInfo {
+    let info = ChainInfo::load_or_fetch(chain_hash);
+
+    if chain_hash == DEFAULT_CHAIN_HASH && info.public_key != DEFAULT_CHAIN_PUBLIC_KEY {
+        eprintln!(
+            "\n!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!\n\
+             WARNING: the public key of the Drand chain {chain_hash}\n\
+             ({}) differs from the pinned one\n\
+             ({DEFAULT_CHAIN_PUBLIC_KEY}).\n\
+             !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!\n",
+            info.public_key
+        );
+    }
+    assert!(
+        ["pedersen-bls-chained", "pedersen-bls-unchained"].contains(&info.scheme_id.as_str()),
+        "Unsupported Drand scheme: {}",
+        info.scheme_id
+    );
+    info
+}
+
+/// UNIX time of the first commit (in the history of the current branch) that
+/// added the given string to the repository, if any
+fn git_first_commit_time(needle: &str) -> Option<i64> {
+    let output = Command::new("git")
+        .args(["log", "--reverse", "--format=%ct", "-S", needle])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout)
+        .ok()?
+        .lines()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Verifies that the commitment was published before the committed round was
+/// produced, so that the round could not be chosen knowing its randomness.
+fn verify_round_timing(
+    info: &ChainInfo,
+    round: u64,
+    commitment: &str,
+    commitment_time: Option<i64>,
+) {
+    let commitment_time = commitment_time
+        .or_else(|| git_first_commit_time(commitment))
+        .expect("Cannot find when the commitment was published; provide --commitment-time.");
+    let round_time = info.round_time(round);
+
+    assert!(
+        commitment_time < round_time,
+        "The commitment was published (at {commitment_time}) after round {round} was produced (at {round_time})."
+    );
+    println!(
+        "The commitment was published {}s before round {round} was produced (UNIX times {commitment_time} and {round_time}).\n",
+        round_time - commitment_time
+    );
 }
 
 /// Verifies the Drand signature for the given round.
@@ -106,13 +206,16 @@ fn verify_signature(round: u64, signature: &[u8], previous_signature: &[u8], pub
     );
 }
 
-/// Verify that `commitment` opens to `round || salt`.
+/// Verify that `commitment` opens to `round || salt`, or to
+/// `round || last_round || salt` when committing to a range of rounds.
 ///
-/// Namely, assert that `commitment == SHA-256(round || salt)`,
-/// where `round` is encoded as 16 bytes in little-endian.
-fn verify_commitment(round: u64, salt: &[u8; 16], commitment: &[u8]) {
-    let mut data = round.to_le_bytes().to_vec();
-    data.resize(16, 0);
+/// Namely, assert that `commitment == SHA-256(round [|| last_round] || salt)`,
+/// where rounds are encoded as 16 bytes in little-endian.
+fn verify_commitment(round: u64, last_round: Option<u64>, salt: &[u8; 16], commitment: &[u8]) {
+    let mut data = Vec::new();
+    for round in std::iter::once(round).chain(last_round) {
+        data.extend_from_slice(&(round as u128).to_le_bytes());
+    }
     data.extend_from_slice(salt);
 
     let hash = Sha256::digest(&data);
@@ -120,51 +223,111 @@ fn verify_commitment(round: u64, salt: &[u8; 16], commitment: &[u8]) {
     assert_eq!(&hash[..], commitment, "Commitment verification failed.");
 }
 
+/// Fetches the given round of the chain and verifies its signature; returns
+/// its randomness.
+fn fetch_verified_randomness(info: &ChainInfo, round: u64) -> [u8; 32] {
+    let drand_response =
+        fetch_drand_round(&info.hash, round).expect("Failed to fetch Drand round.");
+
+    let signature = hex::decode(&drand_response.signature).expect("Failed to decode signature.");
+    // Unchained schemes do not sign the previous signature
+    let previous_sig = match info.scheme_id.as_str() {
+        "pedersen-bls-chained" => drand_response
+            .previous_signature
+            .as_ref()
+            .map(hex::decode)
+            .transpose()
+            .unwrap()
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+
+    verify_signature(round, &signature, &previous_sig, &info.public_key);
+    derive_randomness(&signature)
+}
+
+/// Aggregates the randomness of a range of rounds as
+/// SHA-256(randomness_1 || ... || randomness_k). The randomness of a single
+/// round is used as is.
+fn aggregate_randomness(randomness: &[[u8; 32]]) -> [u8; 32] {
+    match randomness {
+        [single] => *single,
+        _ => Sha256::digest(randomness.concat()).into(),
+    }
+}
+
 fn main() {
-    let args = Args::parse();
+    let mut args = Args::parse();
 
-    let mut salt = [0u8; 16];
-    hex::decode_to_slice(&args.salt, &mut salt).expect("Failed to decode salt.");
+    if args.round.is_none() {
+        let manifest = CeremonyManifest::load(Path::new(DEFAULT_MANIFEST_PATH))
+            .unwrap_or_else(|err| panic!("{err}"));
+        let (commitment, reveal) = read_beacon_files(&manifest);
+        println!("Beacon commitment and reveal read from the repository.\n");
 
-    let commitment = hex::decode(&args.commitment).expect("Failed to decode commitment.");
+        args.round = Some(reveal.round);
+        args.last_round = reveal.last_round;
+        args.salt = Some(reveal.salt);
+        args.commitment = Some(commitment.commitment);
+        args.chain_hash.get_or_insert(commitment.chain_hash);
+    }
+    let round = args.round.unwrap();
+    let salt_hex = args.salt.unwrap();
+    let commitment_hex = args.commitment.unwrap();
+    let chain_hash = args
+        .chain_hash
+        .unwrap_or_else(|| DEFAULT_CHAIN_HASH.to_string());
 
-    verify_commitment(args.round, &salt, &commitment);
-    print!(
-        "Commitment successfully verified!\nSHA-256({}u64 || {}) = {}\n\n",
-        args.round, args.salt, args.commitment,
-    );
+    let mut salt = [0u8; 16];
+    hex::decode_to_slice(&salt_hex, &mut salt).expect("Failed to decode salt.");
 
-    let drand_response = fetch_drand_round(args.round).expect("Failed to fetch Drand round.");
+    let commitment = hex::decode(&commitment_hex).expect("Failed to decode commitment.");
 
-    let signature = hex::decode(&drand_response.signature).expect("Failed to decode signature.");
-    let previous_sig = drand_response
-        .previous_signature
-        .as_ref()
-        .map(hex::decode)
-        .transpose()
-        .unwrap()
-        .unwrap_or_default();
-
-    verify_signature(args.round, &signature, &previous_sig, DRAND_PUBLIC_KEY);
-    let round_randomness = derive_randomness(&signature);
-    print!(
-        "Drand round {} was fetched correctly, its signature is valid!\nThe round randomness is: {}\n\n",
-        args.round,
-        hex::encode(round_randomness)
+    let last_round = args.last_round.unwrap_or(round);
+    assert!(
+        last_round >= round,
+        "The last round cannot precede the first one."
     );
 
-    // Compute the scalar exactly as in the update process, from the Drand
-    // randomness, concatenated with the salt
+    verify_commitment(round, args.last_round, &salt, &commitment);
+    match args.last_round {
+        None => print!(
+            "Commitment successfully verified!\nSHA-256({}u64 || {}) = {}\n\n",
+            round, salt_hex, commitment_hex,
+        ),
+        Some(last_round) => print!(
+            "Commitment successfully verified!\nSHA-256({}u64 || {}u64 || {}) = {}\n\n",
+            round, last_round, salt_hex, commitment_hex,
+        ),
+    }
 
-    let mut buffer = String::new();
-    buffer.push_str(&hex::encode(round_randomness));
-    buffer.push_str(&hex::encode(salt));
+    let info = chain_info(&chain_hash);
+    verify_round_timing(&info, round, &commitment_hex, args.commitment_time);
 
-    let mut hasher = Blake2b512::new();
-    hasher.update(buffer);
+    let randomness: Vec<[u8; 32]> = (round..=last_round)
+        .map(|round| {
+            let randomness = fetch_verified_randomness(&info, round);
+            print!(
+                "Drand round {} was fetched correctly, its signature is valid!\nThe round randomness is: {}\n\n",
+                round,
+                hex::encode(randomness)
+            );
+            randomness
+        })
+        .collect();
+    let round_randomness = aggregate_randomness(&randomness);
+    if randomness.len() > 1 {
+        print!(
+            "The aggregated randomness of rounds {}..={} is: {}\n\n",
+            round,
+            last_round,
+            hex::encode(round_randomness)
+        );
+    }
 
-    let seed: [u8; 32] = hasher.finalize()[0..32].try_into().unwrap();
-    let scalar = Scalar::random(ChaCha20Rng::from_seed(seed));
+    // Compute the scalar exactly as in the update process, from the Drand
+    // randomness, concatenated with the salt
+    let scalar = beacon_scalar(&round_randomness, &salt);
 
     println!(
         "The scalar derived from the Drand round randomness and the provided salt is:\n{scalar}\n",
@@ -172,9 +335,11 @@ fn main() {
 
     // We now take the last two contributions, and check that the last corresponds
     // to an update of the previous with the randomness above
-    let update_proofs = srs::utils::open_update_proof_dirs();
+    let update_proofs = srs::utils::open_update_proof_dirs(&srs::utils::proofs_dir(None))
+        .unwrap_or_else(|err| panic!("{err}"));
     let last_update_proof_file = update_proofs.last().unwrap().path();
-    let last_proof = srs::schnorr::UpdateProof::read_from_file(&last_update_proof_file);
+    let last_proof = srs::schnorr::UpdateProof::read_from_file(&last_update_proof_file)
+        .unwrap_or_else(|err| panic!("{err}"));
 
     // Verify that h = g * scalar (i.e., the last update used our scalar)
     assert_eq!(
@@ -45,20 +45,29 @@
 //! However, provers also require the G1 points to be consistent. This binary
 //! provides tools for verifying consistency between both the G1 and G2 points.
 
-use std::{io::Read, path::Path};
+#[cfg(not(feature = "mmap"))]
+use std::io::Read;
+use std::path::Path;
 
 use blstrs::{G1Affine, G2Affine};
 use clap::Parser;
 use ff::{Field, PrimeField};
-use halo2curves::{fft::best_fft, msm::msm_best};
+use halo2curves::fft::best_fft;
+use indicatif::{MultiProgress, ProgressBar};
 use rand_core::OsRng;
 use rayon::{
     iter::{IntoParallelIterator, ParallelIterator},
     slice::ParallelSlice,
 };
+#[cfg(feature = "mmap")]
+use srs::reader::map_file;
+#[cfg(not(feature = "mmap"))]
+use srs::utils::open_file;
 use srs::{
     ceremony::{G1_SIZE, G2_SIZE},
-    utils::{compare_bytes, initialize_progress_bar, open_file, read_g1_point, read_g2_point},
+    cpu::check_compiled_features,
+    msm::{MsmAlgo, MsmConfig, MAX_MSM_WINDOW},
+    utils::{compare_bytes, initialize_phase_progress_bar, read_g1_point, read_g2_point},
 };
 
 type F = blstrs::Scalar;
@@ -71,6 +80,15 @@ struct Args {
 
     /// Path to the extended SRS file (in both coefficient and Lagrange form).
     extended_srs_path: String,
+
+    /// MSM algorithm used for the commitments.
+    #[arg(long, value_enum, default_value_t = MsmAlgo::Best)]
+    msm_algo: MsmAlgo,
+
+    /// Window size (in bits) for the Pippenger MSM (if not provided, it is
+    /// derived from the SRS length).
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=MAX_MSM_WINDOW as i64))]
+    msm_window: Option<u32>,
 }
 
 /// Extended SRS containing both coefficient and Lagrange representations.
@@ -98,38 +116,62 @@ struct ExtendedSRS {
 
 impl ExtendedSRS {
     fn read_from_file(path: &Path) -> Self {
-        let mut file = open_file(path);
-        let mut bytes = Vec::<u8>::new();
-        file.read_to_end(&mut bytes).expect("Cannot read to end");
+        // With the `mmap` feature, the points are parsed straight from the
+        // mapping of the file
+        #[cfg(feature = "mmap")]
+        let bytes = map_file(path).unwrap_or_else(|err| panic!("{err}"));
+        #[cfg(not(feature = "mmap"))]
+        let bytes = {
+            let mut bytes = Vec::<u8>::new();
+            let mut file = open_file(path).unwrap_or_else(|err| panic!("{err}"));
+            file.read_to_end(&mut bytes).expect("Cannot read to end");
+            bytes
+        };
 
         let k = u32::from_le_bytes(bytes[..4].try_into().unwrap());
         let n = 1 << k;
 
         assert_eq!(bytes.len(), 4 + 2 * n * G1_SIZE + 2 * G2_SIZE);
 
-        let pb = initialize_progress_bar(2 * n, Some("Reading Lagrange SRS".into()));
+        // Parse both bases concurrently, with one progress bar each
+        let mp = MultiProgress::new();
+        let pb_coeff = initialize_phase_progress_bar(
+            &mp,
+            n,
+            "[coeff]",
+            Some("Reading coefficient SRS".into()),
+        );
+        let pb_lagrange = initialize_phase_progress_bar(
+            &mp,
+            n,
+            "[lagrange]",
+            Some("Reading Lagrange SRS".into()),
+        );
 
         let mut offset = 4;
-
-        let g1s_coeff: Vec<G1Affine> = bytes[offset..(offset + G1_SIZE * n)]
-            .par_chunks(G1_SIZE)
-            .inspect(|_| pb.inc(1))
-            .map(read_g1_point)
-            .collect::<Vec<_>>();
+        let coeff_bytes = &bytes[offset..(offset + G1_SIZE * n)];
         offset += G1_SIZE * n;
-
-        let g1s_lagrange: Vec<G1Affine> = bytes[offset..(offset + G1_SIZE * n)]
-            .par_chunks(G1_SIZE)
-            .inspect(|_| pb.inc(1))
-            .map(read_g1_point)
-            .collect::<Vec<_>>();
+        let lagrange_bytes = &bytes[offset..(offset + G1_SIZE * n)];
         offset += G1_SIZE * n;
 
-        pb.finish_and_clear();
+        let read_points = |bytes: &[u8], pb: &ProgressBar| -> Vec<G1Affine> {
+            let points = bytes
+                .par_chunks(G1_SIZE)
+                .inspect(|_| pb.inc(1))
+                .map(|bytes| read_g1_point(bytes).unwrap_or_else(|err| panic!("{err}")))
+                .collect::<Vec<_>>();
+            pb.finish_and_clear();
+            points
+        };
+        let (g1s_coeff, g1s_lagrange) = rayon::join(
+            || read_points(coeff_bytes, &pb_coeff),
+            || read_points(lagrange_bytes, &pb_lagrange),
+        );
 
         let mut _g2s = [G2Affine::default(); 2];
-        _g2s[0] = read_g2_point(&bytes[offset..(offset + G2_SIZE)]);
-        _g2s[1] = read_g2_point(&bytes[(offset + G2_SIZE)..(offset + 2 * G2_SIZE)]);
+        let read_g2 = |bytes: &[u8]| read_g2_point(bytes).unwrap_or_else(|err| panic!("{err}"));
+        _g2s[0] = read_g2(&bytes[offset..(offset + G2_SIZE)]);
+        _g2s[1] = read_g2(&bytes[(offset + G2_SIZE)..(offset + 2 * G2_SIZE)]);
 
         Self {
             g1s_coeff,
@@ -146,19 +188,19 @@ impl ExtendedSRS {
     /// representations. If the commitments differ, the Lagrange basis was
     /// incorrectly derived. This probabilistic check would fail with
     /// overwhelming probability if the representations were inconsistent.
-    fn check_consistency(&self) {
+    fn check_consistency(&self, msm: &MsmConfig) {
         let n = self.g1s_coeff.len();
 
         // Sample a uniformly random polynomial of degree < n.
         let mut random_poly: Vec<F> = (0..n).into_par_iter().map(|_| F::random(OsRng)).collect();
 
         // Commit to the polynomial in coefficients form.
-        let com_coeff = msm_best::<G1Affine>(&random_poly, &self.g1s_coeff);
+        let com_coeff = msm.msm(&random_poly, &self.g1s_coeff);
 
         // Commit to the polynomial in Lagrange form.
         let omega = F::ROOT_OF_UNITY.pow([1u64 << (F::S - self.k)]);
         best_fft(&mut random_poly, omega, self.k);
-        let com_lagrange = msm_best::<G1Affine>(&random_poly, &self.g1s_lagrange);
+        let com_lagrange = msm.msm(&random_poly, &self.g1s_lagrange);
 
         assert_eq!(
             com_coeff, com_lagrange,
@@ -167,8 +209,15 @@ impl ExtendedSRS {
     }
 }
 
+/// The MSM configuration of the `--msm-algo` and `--msm-window` options.
+/// Panics if a window is given for another algorithm than Pippenger
+fn msm_config(algo: MsmAlgo, window: Option<u32>) -> MsmConfig {
+    MsmConfig::new(algo, window.map(|c| c as usize)).unwrap_or_else(|err| panic!("{err}"))
+}
+
 fn main() {
     let args = Args::parse();
+    check_compiled_features();
 
     let path1 = Path::new(&args.powers_of_tau_path);
     let path2 = Path::new(&args.extended_srs_path);
@@ -179,7 +228,7 @@ fn main() {
     // 1. The G1 points of the powers-of-tau file coincide with the extended SRS's
     //    coefficient representation.
     assert!(
-        compare_bytes(path1, path2, 0, 4, n * G1_SIZE),
+        compare_bytes(path1, path2, 0, 4, n * G1_SIZE).unwrap_or_else(|err| panic!("{err}")),
         "G1 points mismatch between powers-of-tau and the extended SRS"
     );
 
@@ -191,13 +240,14 @@ fn main() {
             -2 * G2_SIZE as i64,
             -2 * G2_SIZE as i64,
             2 * G2_SIZE
-        ),
+        )
+        .unwrap_or_else(|err| panic!("{err}")),
         "G2 points mismatch between powers-of-tau and the extended SRS"
     );
 
     // 3. The Lagrange basis in the extended SRS is correctly derived from the
     //    coefficient basis.
-    srs.check_consistency();
+    srs.check_consistency(&msm_config(args.msm_algo, args.msm_window));
 
     println!("All checks passed!")
 }
@@ -13,18 +13,80 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::path::Path;
+use std::{
+    collections::HashSet,
+    fs,
+    io::{IsTerminal, Read, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-use clap::{Parser, Subcommand};
+use blstrs::Scalar;
+use clap::{Args, Parser, Subcommand};
+use halo2curves::{ff::Field, group::Curve, serde::SerdeObject};
 use rand_core::OsRng;
+use serde_json::json;
+use sha2::{Digest, Sha256};
 use srs::{
-    ceremony::{G1_SIZE, SRS},
-    filecoin::extract_g1_point_from_filecoin_srs,
-    schnorr::UpdateProof,
+    audit::{audit_check, audit_sha256, is_auditing, open_audit_log},
+    bundle::{ReleaseBundle, CHECKSUMS_FILE},
+    cache::{
+        cache_path, check_sidecar_digest, extend_transcript, parse_sidecar, read_sidecar,
+        sha256_file, sidecar_content, sidecar_path, write_sidecar, ChainHead, ExhaustiveCheckpoint,
+        VerificationCache, EXHAUSTIVE_CHECKPOINT_PATH, SIDECAR_EXTENSION,
+    },
+    cancel::{install_ctrlc_handler, Secret},
+    ceremony::{is_truncation_of, structure_challenge, G1_SIZE, SRS, UPDATE_WINDOW_SIZE},
+    chain::{precheck_proofs, ChainVerifier},
+    cpu::{check_compiled_features, CpuFeatures},
+    diagnose::diagnose_chain,
+    drand::beacon_scalar,
+    error::SrsError,
+    external::{extract_anchor, ExternalTranscript},
+    filecoin::FilecoinTranscript,
+    formats::{
+        barretenberg_transcript_path, read_barretenberg_transcripts,
+        write_barretenberg_transcripts, BARRETENBERG_POINTS_PER_TRANSCRIPT,
+    },
+    github::{append_participant_row, GitHub, PullRequest, RepoFile, CEREMONY_REPO},
+    journal::{exit, finish_journal, start_journal, verify_journal, JOURNAL_PATH},
+    kzg::kzg_selftest,
+    lagrange::{is_lagrange_basis, lagrange_basis, read_lagrange_basis, write_lagrange_basis},
+    mem::{
+        format_mem_size, in_memory_footprint, max_mem, parse_mem_size, select_max_mem,
+        streaming_window, structure_chunks, MemoryReport, MAX_STRUCTURE_CHUNKS,
+    },
+    metadata::{Attestation, MetadataLevel, ProofMetadata},
+    msm::{MsmAlgo, MsmConfig, MAX_MSM_WINDOW},
+    notify::{ContributionEvent, DiscordWebhook, NotificationSink, SlackWebhook},
+    partial::{aggregate, attest_range, parse_range, PartialAttestation},
+    progress::TerminalProgress,
+    reader::SrsReader,
+    registry::{ContributionSignature, ContributorRegistry, CONTRIBUTORS_PATH},
+    release::{write_release_artifacts, SrsEncoding},
+    report::{read_participants, CeremonyReport},
+    resume::{erase_checkpoint, UpdateCheckpoint, UPDATE_CHECKPOINT_PATH},
+    sapling::SaplingTranscript,
+    schnorr::{ProofFormat, UpdateProof, UPDATE_PROOF_SIZE, UPDATE_PROOF_V2_SIZE},
+    selftest::run_selftest,
+    sniff::sniff_format,
+    state::{
+        chain_state, check_ceremony_id, check_rehearsal, CeremonyContext, CeremonyManifest,
+        CeremonyPhase, Mirror, DEFAULT_MANIFEST_PATH, GENESIS_G1_POINT_PATH,
+    },
+    statement::{
+        statement_files, SignedStatement, StatementReport, VerificationStatement, STATEMENTS_DIR,
+    },
+    storage::{mirror, open_storage, GitHubStorage, ObjectInfo, Storage},
     utils::{
-        derive_new_path, generate_toxic_waste, open_update_proof_dirs, read_g1_point_from_file,
+        create_file, decode_g1_point, derive_new_path, generate_toxic_waste, is_stdin, open_file,
+        open_update_proof_dirs, proofs_dir, read_g1_point_from_file, repo_path,
     },
+    vectors::test_vectors,
 };
+use zeroize::Zeroize;
 
 // Struct to represent command-line arguments
 #[derive(Debug, Parser)]
@@ -33,6 +95,52 @@ struct CLICommand {
     #[command(subcommand)]
     cmd: Command,
     srs_path: String,
+    /// Parameter set (as declared in the ceremony manifest) whose chain of
+    /// update proofs, stored in `proofs/<set>`, is used
+    #[arg(long, global = true)]
+    set: Option<String>,
+    /// Memory budget (e.g. `8G`). `verify-structure` streams the SRS instead
+    /// of loading it when it does not fit (or with `--stream`), and `update`
+    /// and the streamed verification size their window to fit
+    #[arg(long, global = true, value_parser = parse_mem_size)]
+    max_mem: Option<usize>,
+    /// After a successful verification, sign a statement of it (the SHA-256
+    /// digest of the SRS and the check run) with the key stored (hex) at the
+    /// given path, and write it to `./attestations`. A new key is generated
+    /// if the file does not exist
+    #[arg(long, global = true)]
+    sign_statement: Option<PathBuf>,
+    /// Auditor mode: append every file opened, digest computed and check
+    /// performed (with its inputs and result) to the log at the given path,
+    /// and refuse to write anything else
+    #[arg(long, global = true, conflicts_with = "sign_statement")]
+    audit_log: Option<PathBuf>,
+    /// Do not ask for confirmation before overwriting existing output files,
+    /// and let `update` proceed with entropy inputs failing the health tests
+    /// (e.g. a held-down key or an all-zero file)
+    #[arg(long, global = true)]
+    force: bool,
+}
+
+/// Encoding of the points of a converted SRS, see [SrsEncoding]
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum PointEncoding {
+    /// Raw points (96-byte G1, 192-byte G2), as updated in the ceremony
+    Raw,
+    /// Uncompressed points in the standard (zcash) serialization
+    Uncompressed,
+    /// Compressed points in the standard (zcash) serialization
+    Compressed,
+}
+
+impl From<PointEncoding> for SrsEncoding {
+    fn from(encoding: PointEncoding) -> Self {
+        match encoding {
+            PointEncoding::Raw => SrsEncoding::Raw,
+            PointEncoding::Uncompressed => SrsEncoding::Uncompressed,
+            PointEncoding::Compressed => SrsEncoding::Compressed,
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -41,8 +149,80 @@ enum Command {
         /// Asserting 2**log2_len G1 elements in the SRS (incl. the generator)
         #[arg(short, long)]
         log2_len: usize,
+        /// MSM algorithm used for the batched pairing check
+        #[arg(long, value_enum, default_value_t = MsmAlgo::Best)]
+        msm_algo: MsmAlgo,
+        /// Window size (in bits) for the Pippenger MSM (if not provided, it is
+        /// derived from the SRS length)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..=MAX_MSM_WINDOW as i64))]
+        msm_window: Option<u32>,
+        /// Split the batched check into the given number of independent
+        /// batched pairing checks, for smaller working sets (the powers of the
+        /// batching scalar only span one chunk) at the cost of two pairings
+        /// per chunk. By default, the smallest number of chunks fitting the
+        /// memory budget (`--max-mem`) is used
+        #[arg(long, conflicts_with_all = ["exhaustive", "range"])]
+        chunks: Option<usize>,
+        /// Perform one pairing check per G1 point instead of the batched check,
+        /// which does not depend on local randomness but takes hours on a large
+        /// SRS. It can be interrupted (Ctrl-C) and resumed
+        #[arg(long, conflicts_with = "msm_window")]
+        exhaustive: bool,
+        /// Derive the challenge of the batched check from the SHA-256 digest
+        /// of the SRS (Fiat-Shamir style) instead of the local RNG, so that
+        /// the verification can be reproduced by anyone. The challenge is
+        /// printed, and the check recorded as `verify-structure-fiat-shamir`
+        #[arg(long, conflicts_with_all = ["exhaustive", "range"])]
+        deterministic_challenge: bool,
+        /// Stream the SRS by windows of G1 points (sized to `--max-mem`, if
+        /// given) instead of loading it, even if it fits in memory, so that
+        /// only a window of points is held at any time. The SRS file may have
+        /// raw, uncompressed or compressed points
+        #[arg(long, conflicts_with_all = ["exhaustive", "range", "chunks"])]
+        stream: bool,
+        /// Only verify the G1 points START..END, and write a partial
+        /// attestation, to be combined with the ones of the other ranges by
+        /// `aggregate`
+        #[arg(
+            long,
+            value_parser = parse_range,
+            requires_all = ["seed", "attestation"],
+            conflicts_with = "exhaustive"
+        )]
+        range: Option<Range<usize>>,
+        /// Seed (hex, 32 bytes) of the batching scalar, shared by all the
+        /// machines verifying a range, and chosen once the SRS is fixed
+        #[arg(long, requires = "range")]
+        seed: Option<String>,
+        /// Output file of the partial attestation
+        #[arg(long, requires = "range")]
+        attestation: Option<PathBuf>,
+    },
+    /// Combines the partial attestations of `verify-structure --range`, made
+    /// on several machines, into the verification of the structure of the
+    /// whole SRS. The SRS path is ignored
+    Aggregate {
+        /// Asserting 2**log2_len G1 elements in the SRS (incl. the generator)
+        #[arg(short, long)]
+        log2_len: usize,
+        /// Partial attestations, covering the whole SRS
+        #[arg(required = true)]
+        attestations: Vec<PathBuf>,
+    },
+    VerifyChain {
+        /// Verify every proof, ignoring (and not updating) the local cache of
+        /// already verified proofs
+        #[arg(long)]
+        no_cache: bool,
+        /// Storage (see `fetch`) holding the `srs<N>` files, checked against
+        /// the SRS digests recorded in the update proofs when present
+        #[arg(long)]
+        srs_dir: Option<String>,
+        /// Instead of stopping at the first failed check, scan the whole chain
+        /// and report every contribution that breaks it, and why
+        #[arg(long, conflicts_with_all = ["no_cache", "srs_dir"])]
+        diagnose: bool,
     },
-    VerifyChain,
     Update {
         /// Optional entropy string to seed the RNG (if not provided, user will
         /// be prompted)
@@ -50,115 +230,2498 @@ enum Command {
         /// Whether to use OS randomness to seed the RNG (if not provided, user
         /// will be prompted)
         os_randomness: Option<bool>,
+        /// External evidence of the contribution (e.g. a video recording),
+        /// whose hash is recorded in the proof metadata. Can be repeated
+        #[arg(long = "attest-file")]
+        attest_files: Vec<PathBuf>,
+        /// What the proof metadata discloses: `full` (the SRS digest, the
+        /// ceremony ID, the attestations, and the source, file name and
+        /// digest of each entropy input), `minimal` (only the source of each
+        /// entropy input) or `none` (only the digest of the proof). The level
+        /// is recorded in the metadata
+        #[arg(long = "metadata", value_enum, default_value_t = MetadataLevel::Full)]
+        metadata_level: MetadataLevel,
+        /// Also mix randomness from the hardware (the RNG of the CPU if it
+        /// has one, its execution jitter otherwise) into the toxic waste
+        #[arg(long)]
+        hardware_randomness: bool,
+        /// File whose content is mixed into the toxic waste (e.g. the output
+        /// of a hardware RNG). Can be repeated
+        #[arg(long = "entropy-file")]
+        entropy_files: Vec<PathBuf>,
+        /// Mix the standard input (read to its end, e.g. piped from another
+        /// source) into the toxic waste. As the prompts cannot be answered,
+        /// the entropy string and OS randomness arguments are required
+        #[arg(long, requires_all = ["entropy", "os_randomness"])]
+        entropy_stdin: bool,
+        /// Public beacon value (hex, e.g. the randomness of a Drand round)
+        /// mixed into the toxic waste. Can be repeated
+        #[arg(long = "beacon")]
+        beacons: Vec<String>,
+        /// Checkpoint the progress regularly (with the toxic waste encrypted
+        /// under a passphrase, read from SRS_CHECKPOINT_PASSPHRASE or
+        /// prompted), and resume an interrupted update from its checkpoint
+        #[arg(long)]
+        checkpoint: bool,
+        #[command(flatten)]
+        pull_request: PullRequestArgs,
     },
     ExtractFilecoinG1Point,
+    /// Extract [tau]_1 of a Zcash Sapling powers-of-tau transcript (the SRS
+    /// path, a `challenge` or a `response` file), e.g. to anchor a chain of
+    /// update proofs or cross-reference it
+    ExtractSaplingG1Point,
+    /// Print the SHA-256 digest of the SRS, as recorded in PARTICIPANTS.md
+    Hash,
+    /// Print the format of the file at the SRS path (an SRS in any of its
+    /// encodings, a Barretenberg transcript or an update proof), detected
+    /// from its size and first bytes
+    Info,
+    /// Sign the last update proof, stored in a contribution directory, with
+    /// the contributor key stored (hex) at the given path. A new key is
+    /// generated if the file does not exist
+    Sign {
+        key_path: PathBuf,
+    },
+    /// Write the release artifacts of the final SRS (with uncompressed and
+    /// compressed points) to the given directory, check them and record their
+    /// hashes in the ceremony manifest
+    Finalize {
+        output_dir: PathBuf,
+    },
+    /// Package the final SRS with every file of the release directory (the
+    /// artifacts written by `finalize` and any other format or size of the
+    /// SRS), the chain of update proofs, the transcript hash, the beacon files
+    /// and the manifests into `midnight-srs-<version>.tar`, with a top-level
+    /// SHA256SUMS file
+    PackageRelease {
+        /// Directory of the release artifacts
+        release_dir: PathBuf,
+        /// Version of the release, which names the tarball
+        #[arg(long)]
+        version: String,
+        /// Directory the tarball is written to
+        #[arg(long, default_value = ".")]
+        output_dir: PathBuf,
+    },
+    /// Derive the Lagrange basis of the SRS over the 2^log2_len-th roots of
+    /// unity
+    DeriveLagrange {
+        #[arg(short, long)]
+        log2_len: u32,
+        /// Path the basis is written to
+        output: PathBuf,
+    },
+    /// Export the SRS as Barretenberg (Aztec) `transcriptNN.dat` files, with
+    /// BLS12-381 points
+    ExportBarretenberg {
+        /// Directory the transcripts are written to
+        output_dir: PathBuf,
+        /// Maximum number of G1 points per transcript
+        #[arg(long, default_value_t = BARRETENBERG_POINTS_PER_TRANSCRIPT)]
+        points_per_transcript: usize,
+    },
+    /// Convert the SRS (in any format the tool reads) to the given encoding
+    /// of the points, e.g. compressed for half-size downloads
+    Convert {
+        /// Path the converted SRS is written to
+        output: PathBuf,
+        /// Encoding of the points of the converted SRS
+        #[arg(long, value_enum, default_value_t = PointEncoding::Compressed)]
+        to: PointEncoding,
+    },
+    /// Download an artifact from a storage to the SRS path, checked against
+    /// the checksum sidecar (`<key>.sha256`) of the storage when there is one,
+    /// and write its local sidecar
+    Fetch {
+        /// Storage location: a directory, an `http(s)://` URL or
+        /// `github:owner/name@ref`
+        #[arg(long)]
+        from: String,
+        /// Key of the artifact in the storage (e.g. `srs3`). Defaults to the
+        /// file name of the SRS path
+        key: Option<String>,
+    },
+    /// Upload the SRS (or any artifact) at the SRS path to a storage, with
+    /// its checksum sidecar, and check the stored copy
+    Upload {
+        /// Storage location (see `fetch`)
+        #[arg(long)]
+        to: String,
+        /// Key of the artifact in the storage. Defaults to the file name of
+        /// the SRS path
+        key: Option<String>,
+    },
+    /// Copy every artifact of the storage at the SRS path (see `fetch`) to
+    /// each of the given storages, checking every copy, and record the
+    /// mirrors in the ceremony manifest
+    Mirror {
+        /// Storage locations of the mirrors
+        #[arg(long, required = true)]
+        to: Vec<String>,
+    },
+    /// Check that a Lagrange basis over the 2^log2_len-th roots of unity is
+    /// derived from the SRS
+    VerifyLagrange {
+        #[arg(short, long)]
+        log2_len: u32,
+        /// Path of the Lagrange basis
+        lagrange_path: PathBuf,
+        /// MSM algorithm used for the commitments
+        #[arg(long, value_enum, default_value_t = MsmAlgo::Best)]
+        msm_algo: MsmAlgo,
+        /// Window size (in bits) for the Pippenger MSM (if not provided, it is
+        /// derived from the basis length)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..=MAX_MSM_WINDOW as i64))]
+        msm_window: Option<u32>,
+    },
+    /// Check that the SRS is a truncation of a larger SRS: its G1 points are a
+    /// prefix of the larger SRS's and both have the same G2 points
+    VerifyTruncation {
+        /// Path of the larger SRS
+        larger_srs_path: PathBuf,
+    },
+    /// Report the state of the ceremony ending at the given SRS
+    Report {
+        /// Write the report as a static HTML page to the given path
+        #[arg(long)]
+        html: Option<PathBuf>,
+        /// Write the contributions ledger as markdown to the given path
+        #[arg(long)]
+        markdown: Option<PathBuf>,
+    },
+    /// Check signed verification statements against the SRS: that they are
+    /// validly signed, and about this SRS
+    VerifyAttestation {
+        /// Statements to check (by default, all those of `./attestations`)
+        statements: Vec<PathBuf>,
+    },
+    /// Combine the signed verification statements of the auditors into a
+    /// report of how many independent parties verified each artifact. The
+    /// SRS path is ignored
+    StatementReport {
+        /// Directory of the statements (`*.json`)
+        #[arg(long, default_value = STATEMENTS_DIR)]
+        dir: PathBuf,
+        /// Write the report as markdown to the given path
+        #[arg(long)]
+        markdown: Option<PathBuf>,
+    },
+    /// Watch the proofs directory and verify new contributions as they land.
+    /// The SRS path is the storage (see `fetch`) holding the `srs<N>` files;
+    /// when present, each new SRS is checked against its update proof
+    Watch {
+        /// Polling interval in seconds
+        #[arg(long, default_value_t = 60)]
+        interval: u64,
+        /// Also verify the update proofs added by open PRs of the ceremony
+        /// repository, and report the result on each PR as a comment and a
+        /// commit status. The GitHub token is read from the GITHUB_TOKEN
+        /// environment variable
+        #[arg(long)]
+        github_bot: bool,
+        /// Ceremony repository, as owner/name
+        #[arg(long, default_value = CEREMONY_REPO)]
+        github_repo: String,
+        /// Slack incoming webhook URL notified of each verified (or
+        /// rejected) contribution
+        #[arg(long)]
+        slack_webhook: Option<String>,
+        /// Discord webhook URL notified of each verified (or rejected)
+        /// contribution
+        #[arg(long)]
+        discord_webhook: Option<String>,
+    },
+    /// Re-derive the toxic waste of a beacon contribution from the revealed
+    /// beacon randomness and salt, and check that the contribution, which
+    /// produced the given SRS, was performed with it
+    VerifyBeacon {
+        /// Index of the contribution
+        #[arg(long)]
+        index: usize,
+        /// Randomness (hex) of the beacon, aggregated for a range of rounds
+        #[arg(long)]
+        randomness: String,
+        /// Salt (hex) of the beacon commitment
+        #[arg(long)]
+        salt: String,
+        /// SRS the contribution was applied to. If provided, the update is
+        /// replayed and its result compared with the given SRS
+        #[arg(long)]
+        input_srs: Option<PathBuf>,
+    },
+    /// Run a small ceremony, deterministically derived from a seed, and emit
+    /// all its intermediate values as JSON (known-answer test vectors for
+    /// other implementations of the verifier). The SRS path is ignored
+    TestVectors {
+        /// log2 of the number of G1 points of the SRS
+        #[arg(short, long, default_value_t = 3)]
+        log2_len: usize,
+        /// Number of contributions
+        #[arg(long, default_value_t = 3)]
+        contributions: usize,
+        /// Seed (hex, 32 bytes) of the RNG the secrets are drawn from
+        #[arg(
+            long,
+            default_value = "0000000000000000000000000000000000000000000000000000000000000000"
+        )]
+        seed: String,
+        /// Write the vectors to the given file instead of the standard output
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Check this build against golden values embedded in the binary
+    /// (serialization, Schnorr verification, pairing checks and FFT) before
+    /// taking part in the ceremony. The SRS path is ignored
+    Selftest,
+    /// Check that the SRS works for its intended purpose: commit to random
+    /// polynomials with its G1 points, open them at random points and verify
+    /// the openings with its G2 points
+    SelftestKzg {
+        /// Degree of the polynomials (by default, the highest the SRS allows)
+        #[arg(long)]
+        degree: Option<usize>,
+        /// Number of polynomials committed to and opened
+        #[arg(long, default_value_t = 4)]
+        polynomials: usize,
+    },
+    /// Check that no entry of the operations journal at the SRS path (e.g.
+    /// `journal.jsonl`) was removed, reordered or edited
+    VerifyJournal,
+    /// Show the current phase of the ceremony, as recorded in the ceremony
+    /// manifest, or move the ceremony to the next phase
+    Phase {
+        /// Move the ceremony to the given phase, which must directly follow
+        /// the current one
+        #[arg(long)]
+        advance_to: Option<CeremonyPhase>,
+    },
 }
 
-fn verify_chain(last_srs_path: &Path) {
+impl Command {
+    /// Name of the check of the SRS performed by the command, if it is a
+    /// verification command (see `--sign-statement`)
+    fn verification_check(&self) -> Option<&'static str> {
+        match self {
+            Command::VerifyStructure { range: Some(_), .. } => None,
+            Command::VerifyStructure {
+                exhaustive: true, ..
+            } => Some("verify-structure-exhaustive"),
+            Command::VerifyStructure {
+                deterministic_challenge: true,
+                ..
+            } => Some("verify-structure-fiat-shamir"),
+            Command::VerifyStructure { .. } => Some("verify-structure"),
+            Command::VerifyChain {
+                diagnose: false, ..
+            } => Some("verify-chain"),
+            Command::VerifyLagrange { .. } => Some("verify-lagrange"),
+            Command::VerifyTruncation { .. } => Some("verify-truncation"),
+            Command::VerifyBeacon { .. } => Some("verify-beacon"),
+            Command::SelftestKzg { .. } => Some("selftest-kzg"),
+            _ => None,
+        }
+    }
+}
+
+fn verify_chain(
+    ctx: &CeremonyContext,
+    last_srs_path: &Path,
+    no_cache: bool,
+    srs_storage: Option<&dyn Storage>,
+) -> Result<(), SrsError> {
     println!("\nVerifying the chain of update proofs...");
 
-    let first_g1_point = read_g1_point_from_file(Path::new("./filecoin_srs_g1_point"), 0);
-    let last_g1_point = read_g1_point_from_file(last_srs_path, G1_SIZE);
+    let first_g1_point = read_g1_point_from_file(Path::new(GENESIS_G1_POINT_PATH), 0)?;
+    let last_g1_point = read_g1_point_from_file(last_srs_path, G1_SIZE)?;
 
-    let chain_of_proofs: Vec<UpdateProof> = open_update_proof_dirs()
+    let registry = ContributorRegistry::load(Path::new(CONTRIBUTORS_PATH))?;
+
+    // Auditors verify every proof themselves
+    let no_cache = no_cache || is_auditing();
+    let cache_path = &cache_path(ctx.parameter_set.as_deref());
+    let mut cache = if no_cache {
+        VerificationCache::default()
+    } else {
+        VerificationCache::load(cache_path)
+    };
+
+    // All the proof files are decoded and their Schnorr proofs verified (unless
+    // this exact file was verified before) first, in parallel, so that every
+    // malformed or invalid one is reported
+    let entries = open_update_proof_dirs(&ctx.proofs_dir())?;
+    let (files, digests): (Vec<Vec<u8>>, Vec<[u8; 32]>) = entries
         .iter()
-        .map(|e| UpdateProof::read_from_file(&e.path()))
-        .collect();
+        .map(|entry| {
+            let path = entry.path();
+            let mut bytes = Vec::new();
+            open_file(&path)?
+                .read_to_end(&mut bytes)
+                .map_err(SrsError::io("read", &path))?;
+            let digest = Sha256::digest(&bytes).into();
+            audit_sha256(&path, &digest);
+            check_sidecar_digest(&path, &digest)?;
+            Ok((bytes, digest))
+        })
+        .collect::<Result<Vec<_>, SrsError>>()?
+        .into_iter()
+        .unzip();
+    let proofs = match precheck_proofs(&files, |i| cache.get(&digests[i]) == Some(true)) {
+        Ok(proofs) => proofs,
+        Err(mut failures) => {
+            for (i, failure) in &failures {
+                let proof_path = entries[*i].path();
+                audit_check(
+                    "update-proof",
+                    json!({
+                        "proof": proof_path.display().to_string(),
+                        "sha256": hex::encode(digests[*i]),
+                        "failure": failure.to_string(),
+                    }),
+                    false,
+                );
+                eprintln!("{proof_path:?}: {failure}");
+            }
+            eprintln!("{} malformed or invalid update proof(s)", failures.len());
+            return Err(failures.swap_remove(0).1);
+        }
+    };
+
+    let mut chain = ChainVerifier::new(first_g1_point, ctx);
+    let mut nr_cached = 0;
+    for (i, ((entry, proof), digest)) in entries.iter().zip(&proofs).zip(&digests).enumerate() {
+        let proof_path = entry.path();
+        // Rehearsal proofs are rejected outright by a production ceremony
+        check_rehearsal(ctx.rehearsal, proof)
+            .map_err(|failure| SrsError::Verification(format!("{proof_path:?}: {failure}")))?;
+        let linkage = chain.push(proof);
+        audit_check(
+            "chain-linkage",
+            json!({
+                "proof": proof_path.display().to_string(),
+                "g": hex::encode(proof.g.to_compressed()),
+                "h": hex::encode(proof.h.to_compressed()),
+            }),
+            linkage.is_ok(),
+        );
+        if let Err(failure) = linkage {
+            return Err(SrsError::ChainMismatch(format!(
+                "{proof_path:?}: {failure}"
+            )));
+        }
+        if let (Some(srs_hash), Some(storage)) = (proof.srs_hash, srs_storage) {
+            let key = format!("srs{}", i + 1);
+            let stored = storage.stat(&key).map_err(|source| SrsError::Io {
+                action: "stat",
+                path: PathBuf::from(format!("{}/{key}", storage.location())),
+                source,
+            })?;
+            if stored.is_some_and(|info| info.sha256 != srs_hash) {
+                return Err(SrsError::Verification(format!(
+                    "{key} of {} does not match the digest recorded in {proof_path:?}",
+                    storage.location()
+                )));
+            }
+        }
+
+        // The Schnorr proof was verified by the pre-pass, unless cached
+        if cache.get(digest) == Some(true) {
+            nr_cached += 1;
+        } else {
+            cache.insert(digest, true);
+        }
+        audit_check(
+            "update-proof",
+            json!({
+                "proof": proof_path.display().to_string(),
+                "sha256": hex::encode(digest),
+            }),
+            true,
+        );
 
-    let mut g = first_g1_point;
-    for proof in chain_of_proofs {
-        assert_eq!(proof.g, g);
-        assert_ne!(proof.g, proof.h);
-        proof.verify();
-        g = proof.h;
+        let metadata = ProofMetadata::read_for(&proof_path)?;
+        if let Some(metadata) = &metadata {
+            for attestation in &metadata.attestations {
+                println!(
+                    "{:?} is attested by {} (SHA-256: {})",
+                    proof_path, attestation.file_name, attestation.sha256
+                );
+            }
+        }
+        if let Some(registry) = &registry {
+            registry
+                .check(entry, metadata.as_ref())
+                .map_err(SrsError::Verification)?;
+        }
+        check_ceremony_id(ctx, entry.index, proof, metadata.as_ref())
+            .map_err(|failure| SrsError::Verification(format!("{proof_path:?}: {failure}")))?;
     }
 
-    assert_eq!(g, last_g1_point);
+    if !no_cache {
+        cache.save(cache_path)?;
+        println!("Reused {nr_cached} cached proof verification(s).");
+    }
+
+    audit_check(
+        "chain-end",
+        json!({
+            "srs": last_srs_path.display().to_string(),
+            "tau_g1": hex::encode(last_g1_point.to_compressed()),
+            "nr_proofs": chain.len(),
+        }),
+        chain.last_g1_point() == last_g1_point,
+    );
+    if chain.last_g1_point() != last_g1_point {
+        return Err(SrsError::ChainMismatch(format!(
+            "{last_srs_path:?} does not match the end of the chain"
+        )));
+    }
 
     println!("The chain of update proofs is correct!\n");
+    Ok(())
+}
+
+/// Scans the whole chain of update proofs and reports every break. Exits with
+/// an error status if there is any
+fn diagnose(ctx: &CeremonyContext, last_srs_path: &Path) {
+    println!("\nDiagnosing the chain of update proofs...");
+
+    let anchor = or_fail(read_g1_point_from_file(Path::new(GENESIS_G1_POINT_PATH), 0));
+    let final_g1_point = or_fail(read_g1_point_from_file(last_srs_path, G1_SIZE));
+    let proofs: Vec<(usize, Result<UpdateProof, String>)> =
+        or_fail(open_update_proof_dirs(&ctx.proofs_dir()))
+            .iter()
+            .map(|entry| {
+                let bytes = fs::read(entry.path()).map_err(|err| err.to_string());
+                let proof = bytes.and_then(|bytes| {
+                    UpdateProof::from_bytes(&bytes).map_err(|err| err.to_string())
+                });
+                (entry.index, proof)
+            })
+            .collect();
+
+    let breaks = diagnose_chain(anchor, ctx, &proofs, final_g1_point);
+    if breaks.is_empty() {
+        println!("The chain of {} update proofs is correct!\n", proofs.len());
+        return;
+    }
+    println!("The chain of update proofs is broken:");
+    for diagnosis in &breaks {
+        println!("- {diagnosis}");
+    }
+    println!();
+    exit(1);
+}
+
+/// Opt-in submission of the contribution as a GitHub pull request
+#[derive(Args, Debug)]
+struct PullRequestArgs {
+    /// Fork the ceremony repository and open a PR with the update proof, its
+    /// metadata and the participants entry. The GitHub token is read from the
+    /// GITHUB_TOKEN environment variable
+    #[arg(long)]
+    open_pr: bool,
+    /// Ceremony repository, as owner/name
+    #[arg(long, default_value = CEREMONY_REPO)]
+    github_repo: String,
+    /// Branch the PR is opened against
+    #[arg(long, default_value = "main")]
+    github_base: String,
+    /// Name recorded in PARTICIPANTS.md (defaults to the GitHub login)
+    #[arg(long)]
+    participant_name: Option<String>,
+    /// Affiliation recorded in PARTICIPANTS.md
+    #[arg(long, default_value = "")]
+    affiliation: String,
 }
 
-fn update(old_srs_path: &Path, entropy: Option<String>, os_randomness: Option<bool>) {
+/// Reads the passphrase of the update checkpoint from
+/// SRS_CHECKPOINT_PASSPHRASE, or prompts for it (twice, if `confirm`)
+fn read_passphrase(confirm: bool) -> String {
+    if let Ok(passphrase) = std::env::var("SRS_CHECKPOINT_PASSPHRASE") {
+        return passphrase;
+    }
+    let prompt = |message: &str| {
+        println!("{message}");
+        let mut passphrase = String::new();
+        std::io::stdin()
+            .read_line(&mut passphrase)
+            .expect("Failed to read the passphrase");
+        let len = passphrase.trim_end_matches(['\r', '\n']).len();
+        passphrase.truncate(len);
+        passphrase
+    };
+    let passphrase =
+        prompt("\nEnter the passphrase protecting the update checkpoint, then press [ENTER]");
+    if confirm {
+        let mut confirmation = prompt("Enter the passphrase again, then press [ENTER]");
+        let matches = confirmation == passphrase;
+        confirmation.zeroize();
+        assert!(matches, "The passphrases do not match");
+    }
+    assert!(!passphrase.is_empty(), "The passphrase cannot be empty");
+    passphrase
+}
+
+/// Prints the files about to be written, and asks for confirmation before
+/// overwriting any existing one, unless `force`. Exits if it is not given
+fn confirm_outputs(outputs: &[&Path], force: bool) {
+    println!("\nThis will write:");
+    for path in outputs {
+        match path.exists() {
+            true => println!("  {} (exists, will be overwritten)", path.display()),
+            false => println!("  {}", path.display()),
+        }
+    }
+    if force || !outputs.iter().any(|path| path.exists()) {
+        return;
+    }
+    // The standard input may be piped (e.g. entropy), not an answer
+    let confirmed = std::io::stdin().is_terminal() && {
+        print!("Overwrite the existing files? [y/N] ");
+        std::io::stdout().flush().unwrap();
+        let mut answer = String::new();
+        std::io::stdin()
+            .read_line(&mut answer)
+            .expect("Failed to read answer");
+        matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+    };
+    if !confirmed {
+        eprintln!("\nNot overwriting the existing files (pass --force to overwrite them).\n");
+        exit(1);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update(
+    ctx: &CeremonyContext,
+    old_srs_path: &Path,
+    entropy: Option<String>,
+    os_randomness: Option<bool>,
+    attest_files: Vec<PathBuf>,
+    metadata_level: MetadataLevel,
+    hardware_randomness: bool,
+    mut entropy_files: Vec<PathBuf>,
+    entropy_stdin: bool,
+    beacons: Vec<String>,
+    force: bool,
+    checkpoint: bool,
+    pull_request: PullRequestArgs,
+) {
+    let beacons: Vec<Vec<u8>> = beacons
+        .iter()
+        .map(|beacon| {
+            hex::decode(beacon).unwrap_or_else(|err| panic!("Invalid beacon value {beacon}: {err}"))
+        })
+        .collect();
+    assert!(
+        attest_files.is_empty() || metadata_level != MetadataLevel::None,
+        "--attest-file records the evidence in the metadata, which --metadata none leaves out"
+    );
+    if entropy_stdin {
+        assert!(
+            !checkpoint || std::env::var_os("SRS_CHECKPOINT_PASSPHRASE").is_some(),
+            "--entropy-stdin with --checkpoint requires the passphrase in SRS_CHECKPOINT_PASSPHRASE"
+        );
+        entropy_files.push(PathBuf::from("-"));
+    }
+
+    // Fail before the (long) update if the PR cannot be opened
+    let github =
+        pull_request.open_pr.then(|| {
+            GitHub::new(std::env::var("GITHUB_TOKEN").expect(
+                "--open-pr requires a GitHub token in the GITHUB_TOKEN environment variable",
+            ))
+        });
+
+    let manifest = or_fail(CeremonyManifest::load(Path::new(DEFAULT_MANIFEST_PATH)));
+    or_fail(manifest.require(CeremonyPhase::OpenContributions, "update the SRS"));
+    if ctx.rehearsal {
+        println!("\nThis ceremony is a rehearsal: the update proof cannot enter the real one.");
+    }
+
     println!("\nRe-randomizing the existing SRS...");
+    println!(
+        "Platform: {}-{}, detected CPU features: {}",
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        CpuFeatures::detect()
+    );
 
-    let (new_srs_path, new_proof_path) = derive_new_path(old_srs_path);
+    let (new_srs_path, new_proof_path) = or_fail(derive_new_path(&ctx.proofs_dir(), old_srs_path));
 
-    let nu = generate_toxic_waste(OsRng, entropy, os_randomness);
+    // Ctrl-C (or SIGTERM) stops the update at a safe point instead of killing
+    // the process, so that the secret material can be erased
+    install_ctrlc_handler();
 
-    let mut srs = SRS::read_from_file(old_srs_path);
+    let checkpoint_path = Path::new(UPDATE_CHECKPOINT_PATH);
+    let resumed = match checkpoint {
+        true => or_fail(UpdateCheckpoint::load(checkpoint_path)),
+        false => None,
+    }
+    .filter(|resumed| or_fail(resumed.applies_to(old_srs_path, &new_srs_path)));
+    // A resumed update carries on writing its output
+    if resumed.is_none() {
+        confirm_outputs(&[&new_srs_path, &new_proof_path], force);
+    }
+    let (nu, entropy_inputs, mut update_checkpoint) = match resumed {
+        Some(resumed) => {
+            println!(
+                "Resuming the interrupted update into {new_srs_path:?} after {} G1 points.",
+                resumed.processed
+            );
+            let mut passphrase = read_passphrase(false);
+            let nu = resumed.secret(&passphrase);
+            passphrase.zeroize();
+            let nu = nu.unwrap_or_else(|err| panic!("Cannot resume the update: {err}"));
+            (nu, resumed.entropy.clone(), Some(resumed))
+        }
+        None => {
+            let (nu, entropy_inputs) = or_fail(generate_toxic_waste(
+                OsRng,
+                entropy,
+                os_randomness,
+                hardware_randomness,
+                &entropy_files,
+                &beacons,
+                force,
+            ));
+            let update_checkpoint = checkpoint.then(|| {
+                let mut passphrase = read_passphrase(true);
+                let update_checkpoint = UpdateCheckpoint::new(
+                    old_srs_path,
+                    &new_srs_path,
+                    &nu,
+                    &passphrase,
+                    entropy_inputs.clone(),
+                );
+                passphrase.zeroize();
+                let update_checkpoint = or_fail(update_checkpoint);
+                or_fail(update_checkpoint.save(checkpoint_path));
+                update_checkpoint
+            });
+            (nu, entropy_inputs, update_checkpoint)
+        }
+    };
+    let nu = Secret::new(nu, Scalar::ZERO);
 
     // Check that current_g = previous_h
     // I.e., the current update correctly extends the previous update
-    assert_eq!(
-        srs.g1s[1],
-        UpdateProof::read_from_file(&open_update_proof_dirs().last().unwrap().path()).h,
-        "SRS doesn't match chain of updates"
-    );
+    // (the first update of a parameter set extends the Filecoin SRS)
+    fs::create_dir_all(ctx.proofs_dir()).expect("Could not create the proofs directory");
+    let previous_h = match or_fail(open_update_proof_dirs(&ctx.proofs_dir())).last() {
+        Some(entry) => or_fail(UpdateProof::read_from_file(&entry.path())).h,
+        None => or_fail(read_g1_point_from_file(Path::new(GENESIS_G1_POINT_PATH), 0)),
+    };
+    if or_fail(read_g1_point_from_file(old_srs_path, G1_SIZE)) != previous_h {
+        fail(SrsError::ChainMismatch(
+            "SRS doesn't match chain of updates".to_string(),
+        ));
+    }
+
+    // The SRS is streamed from the old file to the new one, so that only a
+    // window of points is held in memory
+    let terminal = TerminalProgress::default();
+    let report = MemoryReport::new(&terminal);
+    let window_size = max_mem().map_or(UPDATE_WINDOW_SIZE, streaming_window);
+    let start = update_checkpoint.as_ref().map_or(0, |c| c.processed);
+    let resumable = update_checkpoint.is_some();
+    let mut save_checkpoint = |processed| {
+        if let Some(update_checkpoint) = update_checkpoint.as_mut() {
+            update_checkpoint.processed = processed;
+            or_fail(update_checkpoint.save(checkpoint_path));
+        }
+    };
+    let proof = or_fail(SRS::update_file_resumable(
+        ctx,
+        old_srs_path,
+        &new_srs_path,
+        &nu,
+        window_size,
+        start,
+        resumable.then_some(&mut save_checkpoint as &mut dyn FnMut(usize)),
+        &report,
+    ));
+    drop(nu);
+    report.print();
+
+    if proof.is_none() && resumable {
+        println!(
+            "\nThe update was interrupted and checkpointed to {checkpoint_path:?}; the secret has been erased from memory.\n\
+             Run `srs_utils {} update --checkpoint` again to resume it.\n",
+            old_srs_path.display()
+        );
+        exit(130);
+    }
+    let Some(proof) = proof else {
+        println!(
+            "\nThe update was interrupted. The incomplete SRS {:?} has been removed and the secret has been erased from memory.\n\
+             Run `srs_utils {} update` again to start a fresh contribution.\n",
+            new_srs_path,
+            old_srs_path.display()
+        );
+        exit(130);
+    };
+
+    if resumable {
+        or_fail(erase_checkpoint(checkpoint_path));
+    }
 
-    let proof = srs.update(&nu);
+    print!("Writing the update proof to file...");
+    or_fail(proof.write_to_file_validated(&new_proof_path));
 
-    print!("Writing the SRS to file...");
-    srs.write_to_file(&new_srs_path);
-    proof.write_to_file(&new_proof_path);
+    let mut metadata = or_fail(ProofMetadata::new(&new_proof_path));
+    metadata.attestations = or_fail(
+        attest_files
+            .iter()
+            .map(|path| Attestation::from_file(path))
+            .collect(),
+    );
+    metadata.entropy = entropy_inputs;
+    metadata.ceremony_id = proof.ceremony_id.map(hex::encode);
+    metadata.srs_sha256 = proof.srs_hash.map(hex::encode);
+    metadata.cpu_features = Some(
+        CpuFeatures::detect()
+            .names()
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+    );
+    let metadata = metadata.restrict(metadata_level);
+    or_fail(metadata.write_for(&new_proof_path));
 
     println!(
-        "\rThank you for your participation!\n\nThe SRS in {:?} has been successfully updated and saved to {:?}.\n",
+        "\rThank you for your participation!\n\nThe SRS in {:?} has been successfully updated and saved to {:?} (SHA-256: {}).\n",
         old_srs_path.canonicalize().unwrap(),
-        new_srs_path.canonicalize().unwrap()
+        new_srs_path.canonicalize().unwrap(),
+        proof.srs_hash.map(hex::encode).unwrap_or_default()
     );
 
-    println!(
-        "Make sure you upload your updated SRS to the SFTP server and open a PR with your validity proof (saved at {:?}).\n",
-        new_proof_path.canonicalize().unwrap()
+    let pr_url = github.and_then(|github| {
+        println!("Opening the pull request of the contribution...");
+        open_pull_request(
+            &github,
+            &pull_request,
+            &ctx.proofs_dir(),
+            &new_srs_path,
+            &new_proof_path,
+            &metadata,
+        )
+        .map_err(|err| println!("Could not open the pull request: {err}\n"))
+        .ok()
+    });
+
+    match pr_url {
+        Some(url) => println!(
+            "Make sure you upload your updated SRS to the SFTP server. Your pull request has been opened at {url}.\n"
+        ),
+        None => println!(
+            "Make sure you upload your updated SRS to the SFTP server and open a PR with your validity proof (saved at {:?}) and its metadata (saved at {:?}).\n",
+            new_proof_path.canonicalize().unwrap(),
+            ProofMetadata::path_for(&new_proof_path).canonicalize().unwrap()
+        ),
+    }
+}
+
+/// Commits the update proof, its metadata and the participants entry to a
+/// branch of the contributor's fork and opens the PR. Returns its URL
+fn open_pull_request(
+    github: &GitHub,
+    args: &PullRequestArgs,
+    proofs_dir: &Path,
+    new_srs_path: &Path,
+    new_proof_path: &Path,
+    metadata: &ProofMetadata,
+) -> Result<String, std::io::Error> {
+    let login = github.login()?;
+    let name = args
+        .participant_name
+        .clone()
+        .unwrap_or_else(|| login.clone());
+    // The digest was computed while the SRS was written
+    let srs_sha256 = match metadata.srs_sha256.clone() {
+        Some(srs_sha256) => srs_sha256,
+        None => hex::encode(sha256_file(new_srs_path)?),
+    };
+    let proof = UpdateProof::read_from_file(new_proof_path)?;
+
+    // The proof and its metadata were written to the proofs directory
+    let proof_repo_path = repo_path(new_proof_path);
+    let metadata_path = ProofMetadata::path_for(new_proof_path);
+
+    let participants = GitHubStorage::new(github.clone(), &args.github_repo, &args.github_base)
+        .get_bytes("PARTICIPANTS.md")?;
+    let participants = append_participant_row(
+        &String::from_utf8_lossy(&participants),
+        &name,
+        &login,
+        &args.affiliation,
+        &srs_sha256,
     );
+
+    let files = [
+        RepoFile {
+            path: proof_repo_path.clone(),
+            content: fs::read(new_proof_path)?,
+        },
+        RepoFile {
+            path: repo_path(&metadata_path),
+            content: fs::read(&metadata_path)?,
+        },
+        RepoFile {
+            path: "PARTICIPANTS.md".to_string(),
+            content: participants.into_bytes(),
+        },
+    ];
+
+    let mut body = format!(
+        "Contribution of {name} (@{login}).\n\n\
+         | | |\n|---|---|\n\
+         | Update proof | `{proof_repo_path}` |\n\
+         | Proof SHA-256 | `{}` |\n\
+         | Updated SRS SHA-256 | `{srs_sha256}` |\n\
+         | Previous [tau]_1 (g) | `{}` |\n\
+         | New [tau]_1 (h) | `{}` |\n",
+        metadata.proof_sha256,
+        hex::encode(proof.g.to_raw_bytes()),
+        hex::encode(proof.h.to_raw_bytes()),
+    );
+    if !metadata.attestations.is_empty() {
+        body.push_str("\nAttestations:\n\n");
+        for attestation in &metadata.attestations {
+            body.push_str(&format!(
+                "- `{}` (SHA-256: `{}`)\n",
+                attestation.file_name, attestation.sha256
+            ));
+        }
+    }
+
+    // The index of the proof in the chain, whatever the layout of the proofs
+    // directory
+    let index = open_update_proof_dirs(proofs_dir)?
+        .into_iter()
+        .find(|entry| entry.path() == new_proof_path)
+        .map(|entry| entry.index)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{new_proof_path:?} is not an update proof of {proofs_dir:?}"),
+            )
+        })?;
+    github.open_pull_request(
+        &args.github_repo,
+        &args.github_base,
+        &format!("contribution-{index}-{login}"),
+        &files,
+        &format!("Contribution {index}: {name}"),
+        &body,
+    )
 }
 
-fn verify_structure(srs_path: &Path, length: usize) {
+fn verify_structure(
+    srs_path: &Path,
+    length: usize,
+    msm: MsmConfig,
+    chunks: Option<usize>,
+    exhaustive: bool,
+    deterministic_challenge: bool,
+    stream: bool,
+) {
     println!("\nVerifying structure of the SRS...");
+    let terminal = TerminalProgress::default();
+    let report = MemoryReport::new(&terminal);
 
-    let srs = SRS::read_from_file(srs_path);
+    let challenge = if deterministic_challenge {
+        assert!(
+            !is_stdin(srs_path),
+            "The challenge cannot be derived from an SRS read from the standard input"
+        );
+        let srs_sha256 = or_fail(sha256_file(srs_path));
+        let challenge = structure_challenge(&srs_sha256);
+        println!(
+            "Challenge derived from the SHA-256 digest of the SRS ({}): {}",
+            hex::encode(srs_sha256),
+            hex::encode(challenge.to_bytes_be())
+        );
+        challenge
+    } else {
+        Scalar::random(OsRng)
+    };
 
     let expected_len = 1 << length;
+    let check_len = |nr_g1_points: usize| {
+        if nr_g1_points != expected_len {
+            fail(SrsError::Verification(format!(
+                "Expected {expected_len} elements in G1, but found {nr_g1_points}."
+            )));
+        }
+    };
+    // Unless given, the number of chunks of the batched check is the smallest
+    // fitting the memory budget
+    let nr_chunks = chunks.unwrap_or_else(|| {
+        max_mem().map_or(1, |max_mem| {
+            structure_chunks(expected_len, max_mem).unwrap_or(MAX_STRUCTURE_CHUNKS)
+        })
+    });
+    let footprint = in_memory_footprint(expected_len, nr_chunks);
+    // The SRS is streamed when asked to, or when it does not fit in the memory
+    // budget
+    let over_budget = max_mem().filter(|max_mem| footprint > *max_mem);
+    if let Some(max_mem) = over_budget {
+        assert!(
+            !exhaustive,
+            "Verifying this SRS needs about {} of memory, more than the budget of {}",
+            format_mem_size(footprint),
+            format_mem_size(max_mem)
+        );
+        println!(
+            "The SRS does not fit in {}, it is streamed instead.",
+            format_mem_size(max_mem)
+        );
+    }
+    if stream || over_budget.is_some() {
+        assert!(
+            !is_stdin(srs_path),
+            "An SRS read from the standard input cannot be streamed"
+        );
+        check_len(or_fail(SRS::verify_structure_file_with_challenge(
+            srs_path,
+            max_mem().map_or(UPDATE_WINDOW_SIZE, streaming_window),
+            &challenge,
+            &msm,
+            &report,
+        )));
+    } else {
+        let srs = or_fail(SRS::read_from_file_with_progress(srs_path, &report));
+        check_len(srs.g1s.len());
+        if exhaustive {
+            verify_structure_exhaustive(&srs);
+        } else {
+            if nr_chunks > 1 {
+                println!("The batched check is split into {nr_chunks} chunks.");
+            }
+            or_fail(srs.verify_structure_with_challenge(&challenge, nr_chunks, &msm, &report));
+        }
+    }
+    report.print();
+
+    println!(
+        "The structure of the SRS in {} is correct!\n",
+        display_input(srs_path)
+    )
+}
+
+/// Verifies the G1 points `range` of the SRS, and writes the partial
+/// attestation of the range to `output`
+fn verify_range(
+    srs_path: &Path,
+    log2_len: usize,
+    range: Range<usize>,
+    seed: &str,
+    output: &Path,
+    msm: MsmConfig,
+) {
+    let seed: [u8; 32] = hex::decode(seed)
+        .ok()
+        .and_then(|seed| seed.try_into().ok())
+        .expect("The seed must be 32 bytes, in hex");
+    println!(
+        "\nVerifying the G1 points {}..{} of the SRS...",
+        range.start, range.end
+    );
+
+    let attestation = or_fail(attest_range(
+        srs_path,
+        range,
+        &seed,
+        &msm,
+        &TerminalProgress::default(),
+    ));
+    if attestation.nr_g1_points != 1 << log2_len {
+        fail(SrsError::Verification(format!(
+            "Expected {} elements in G1, but found {}.",
+            1 << log2_len,
+            attestation.nr_g1_points,
+        )));
+    }
+    or_fail(attestation.save(output));
+
+    println!(
+        "The G1 points of the range are valid. The partial attestation is written to {output:?}: combine it with the ones of the other ranges with `aggregate`.\n"
+    )
+}
+
+/// Combines the partial attestations stored in the given files into the
+/// verification of the structure of the whole SRS
+fn aggregate_attestations(log2_len: usize, paths: &[PathBuf]) {
+    println!("\nAggregating {} partial attestations...", paths.len());
+    let attestations: Vec<PartialAttestation> = or_fail(
+        paths
+            .iter()
+            .map(|path| PartialAttestation::load(path))
+            .collect(),
+    );
+
+    let nr_g1_points = aggregate(&attestations).unwrap_or_else(|err| panic!("{err}"));
     assert_eq!(
-        srs.g1s.len(),
-        expected_len,
+        nr_g1_points,
+        1 << log2_len,
         "Expected {} elements in G1, but found {}.",
-        expected_len,
-        srs.g1s.len(),
+        1 << log2_len,
+        nr_g1_points,
     );
 
-    srs.verify_structure();
+    println!("The partial attestations prove that the structure of the SRS is correct!\n")
+}
+
+/// Verifies the structure of the SRS one pairing at a time, resuming from (and
+/// recording progress into) the local checkpoint
+fn verify_structure_exhaustive(srs: &SRS) {
+    install_ctrlc_handler();
+    let checkpoint_path = Path::new(EXHAUSTIVE_CHECKPOINT_PATH);
+    let srs_sha256 = hex::encode(srs.sha256());
+
+    let start = ExhaustiveCheckpoint::resume_point(checkpoint_path, &srs_sha256);
+    if start > 0 {
+        println!("Resuming after {start} verified pairings...");
+    }
+
+    let completed = or_fail(srs.verify_structure_exhaustive(
+        start,
+        |verified| {
+            ExhaustiveCheckpoint {
+                srs_sha256: srs_sha256.clone(),
+                verified,
+            }
+            .save(checkpoint_path)
+        },
+        &TerminalProgress::default(),
+    ));
+    if !completed {
+        println!("Verification interrupted, run the same command again to resume it.");
+        exit(130);
+    }
+    if !is_auditing() {
+        let _ = fs::remove_file(checkpoint_path);
+    }
+}
+
+/// The canonical path of an input file, or `stdin` for `-`
+fn display_input(path: &Path) -> String {
+    if is_stdin(path) {
+        "stdin".to_string()
+    } else {
+        format!("{:?}", path.canonicalize().unwrap())
+    }
+}
+
+fn info(path: &Path) {
+    let len = fs::metadata(path)
+        .unwrap_or_else(|err| panic!("Cannot read {path:?}: {err}"))
+        .len();
+    let format = or_fail(sniff_format(path));
+    println!("\n{}: {len} bytes", display_input(path));
+    println!("Format: {format}");
+    if let Some(n) = format.nr_g1_points().filter(|n| n.is_power_of_two()) {
+        println!("Size: 2^{} G1 points", n.trailing_zeros());
+    }
+    match or_fail(read_sidecar(path)) {
+        Some(digest) => println!("Checksum sidecar: {}\n", hex::encode(digest)),
+        None => println!("No checksum sidecar\n"),
+    }
+}
 
+fn hash(srs_path: &Path) {
     println!(
-        "The structure of the SRS in {:?} is correct!\n",
-        srs_path.canonicalize().unwrap()
-    )
+        "SHA-256 of the SRS in {}: {}\n",
+        display_input(srs_path),
+        hex::encode(or_fail(sha256_file(srs_path)))
+    );
+}
+
+/// Reads the secret key stored (hex) at the given path, or generates one
+/// and saves it there if the file does not exist
+fn load_or_generate_key(key_path: &Path) -> Scalar {
+    if key_path.exists() {
+        let key = fs::read_to_string(key_path).expect("Cannot read the key");
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice(key.trim(), &mut bytes).expect("Malformed key");
+        Option::from(Scalar::from_bytes_be(&bytes)).expect("Malformed key")
+    } else {
+        let secret_key = Scalar::random(OsRng);
+        fs::write(key_path, hex::encode(secret_key.to_bytes_be()))
+            .expect("Could not write the key");
+        println!("\nA new key has been generated and saved to {key_path:?}.");
+        secret_key
+    }
+}
+
+fn sign_last_proof(proofs_dir: &Path, key_path: &Path) {
+    let entry = or_fail(open_update_proof_dirs(proofs_dir))
+        .pop()
+        .expect("There is no update proof to sign");
+    let signature_path = entry
+        .signature_path()
+        .expect("Only proofs stored in a contribution directory can be signed");
+
+    let secret_key = load_or_generate_key(key_path);
+    let signature = ContributionSignature::sign(&secret_key, &or_fail(sha256_file(&entry.path())));
+    or_fail(signature.write_to_file(&signature_path));
+    println!(
+        "\nThe update proof {:?} has been signed, see {:?}.\nPublic key (as registered in {CONTRIBUTORS_PATH}): {}\n",
+        entry.path(),
+        signature_path,
+        signature.public_key_hex()
+    );
+}
+
+/// Verifies the proofs appended to the chain since the cached head, then
+/// updates the head. Proofs that were already verified are only re-hashed, to
+/// detect any modification of the verified part of the chain. Failures are
+/// reported to the sinks (see [report_failure]) and recorded in the cache,
+/// and the watch goes on: the head only moves up to the first failing proof
+fn verify_new_contributions(
+    ctx: &CeremonyContext,
+    srs_storage: &dyn Storage,
+    cache: &mut VerificationCache,
+    sinks: &[Box<dyn NotificationSink>],
+) {
+    let first_g1_point = or_fail(read_g1_point_from_file(Path::new(GENESIS_G1_POINT_PATH), 0));
+    let mut head = cache
+        .head
+        .clone()
+        .unwrap_or_else(|| ChainHead::genesis(&first_g1_point));
+    cache.head = Some(head.clone());
+
+    let proof_dirs = match open_update_proof_dirs(&ctx.proofs_dir()) {
+        Ok(proof_dirs) => proof_dirs,
+        Err(err) => {
+            println!("Could not read the update proofs: {err}");
+            return;
+        }
+    };
+    let digests: Vec<[u8; 32]> = match proof_dirs
+        .iter()
+        .map(|entry| sha256_file(&entry.path()))
+        .collect()
+    {
+        Ok(digests) => digests,
+        Err(err) => {
+            println!("Could not read the update proofs: {err}");
+            return;
+        }
+    };
+
+    let nr_verified = head.nr_proofs.min(proof_dirs.len());
+    let transcript = digests[..nr_verified]
+        .iter()
+        .fold([0u8; 32], |transcript, digest| {
+            extend_transcript(&transcript, digest)
+        });
+    if nr_verified < head.nr_proofs || transcript != head.transcript_bytes() {
+        let failure = if nr_verified < head.nr_proofs {
+            format!(
+                "{} update proofs were verified, but only {} are left",
+                head.nr_proofs,
+                proof_dirs.len()
+            )
+        } else {
+            "The already verified update proofs have been modified".to_string()
+        };
+        // The first proof that is not the verified one
+        let i = digests[..nr_verified]
+            .iter()
+            .position(|digest| cache.get(digest) != Some(true))
+            .unwrap_or(nr_verified);
+        let event = ContributionEvent {
+            index: i + 1,
+            contributor: None,
+            proof_sha256: digests.get(i).map(hex::encode).unwrap_or_default(),
+            srs_sha256: None,
+            transcript: hex::encode(transcript),
+            verification_time: Duration::ZERO,
+            failure: Some(failure),
+        };
+        // The broken chain is recorded under its transcript hash
+        report_failure(cache, &transcript, &event, sinks);
+        return;
+    }
+
+    for (entry, digest) in proof_dirs.iter().zip(&digests).skip(head.nr_proofs) {
+        let start = Instant::now();
+        let proof_path = entry.path();
+        let proof = UpdateProof::read_from_file(&proof_path);
+        let srs_key = format!("srs{}", head.nr_proofs + 1);
+        let srs_check = proof
+            .as_ref()
+            .ok()
+            .and_then(|proof| check_srs(srs_storage, &srs_key, proof));
+
+        let failure = match &proof {
+            Err(err) => Some(format!("{proof_path:?}: {err}")),
+            Ok(proof) => {
+                if let Err(failure) = check_rehearsal(ctx.rehearsal, proof) {
+                    Some(format!("{proof_path:?}: {failure}"))
+                } else if proof.g != or_fail(head.g1_point()) {
+                    Some(format!("{proof_path:?} does not extend the chain"))
+                } else if proof.g == proof.h {
+                    Some(format!("{proof_path:?} does not update the SRS"))
+                } else if !proof.is_valid() {
+                    Some(format!("The update proof {proof_path:?} is invalid"))
+                } else if let Err(failure) = ProofMetadata::read_for(&proof_path)
+                    .map_err(|err| err.to_string())
+                    .and_then(|metadata| {
+                        check_ceremony_id(ctx, entry.index, proof, metadata.as_ref())
+                    })
+                {
+                    Some(format!("{proof_path:?}: {failure}"))
+                } else if srs_check.is_some_and(|(_, matches)| !matches) {
+                    Some(format!(
+                        "{srs_key} of {} does not match {proof_path:?}",
+                        srs_storage.location()
+                    ))
+                } else {
+                    None
+                }
+            }
+        };
+        let verification_time = start.elapsed();
+
+        let srs_sha256 = srs_check.map(|(info, _)| hex::encode(info.sha256));
+        let contributor = srs_sha256.as_ref().and_then(|srs_sha256| {
+            read_participants(Path::new("./PARTICIPANTS.md"))
+                .into_iter()
+                .find(|p| &p.srs_sha256 == srs_sha256)
+                .map(|p| format!("{} ({})", p.name, p.handle))
+        });
+        let mut event = ContributionEvent {
+            index: entry.index,
+            contributor,
+            proof_sha256: hex::encode(digest),
+            srs_sha256,
+            transcript: head.transcript.clone(),
+            verification_time,
+            failure,
+        };
+
+        let Some(proof) = proof.ok().filter(|_| event.failure.is_none()) else {
+            // The chain cannot be extended past this proof until it is fixed
+            report_failure(cache, digest, &event, sinks);
+            break;
+        };
+        cache.insert(digest, true);
+        head = head.extend(digest, &proof.h);
+        event.transcript = head.transcript.clone();
+        notify(sinks, &event);
+
+        println!(
+            "Contribution {} ({:?}) verified, chain transcript: {}",
+            head.nr_proofs, proof_path, head.transcript
+        );
+    }
+
+    cache.head = Some(head);
+}
+
+/// Reports a failure of the watched chain, unless it was already reported:
+/// the failure is recorded in the cache under `digest` (of the failing proof,
+/// or of the broken chain) as a failed verification
+fn report_failure(
+    cache: &mut VerificationCache,
+    digest: &[u8; 32],
+    event: &ContributionEvent,
+    sinks: &[Box<dyn NotificationSink>],
+) {
+    if cache.get(digest) == Some(false) {
+        return;
+    }
+    cache.insert(digest, false);
+    if let Some(failure) = &event.failure {
+        println!("Contribution {} rejected: {failure}", event.index);
+    }
+    notify(sinks, event);
+}
+
+/// Sends the event to every sink, reporting the sinks that cannot be reached
+fn notify(sinks: &[Box<dyn NotificationSink>], event: &ContributionEvent) {
+    for sink in sinks {
+        if let Err(err) = sink.notify(event) {
+            println!("Could not send the notification: {err}");
+        }
+    }
+}
+
+fn watch(
+    ctx: &CeremonyContext,
+    srs_storage: &dyn Storage,
+    interval: u64,
+    github_repo: Option<String>,
+    sinks: Vec<Box<dyn NotificationSink>>,
+) {
+    println!("\nWatching the chain of update proofs (every {interval}s)...");
+
+    let github = github_repo.map(|repo| {
+        let token = std::env::var("GITHUB_TOKEN").expect(
+            "--github-bot requires a GitHub token in the GITHUB_TOKEN environment variable",
+        );
+        (GitHub::new(token), repo)
+    });
+    // PR heads already reviewed against the current chain head
+    let mut reviewed = HashSet::new();
+
+    let cache_path = &cache_path(ctx.parameter_set.as_deref());
+    let mut cache = VerificationCache::load(cache_path);
+    loop {
+        verify_new_contributions(ctx, srs_storage, &mut cache, &sinks);
+        or_fail(cache.save(cache_path));
+        if let Some((github, repo)) = &github {
+            review_pull_requests(
+                ctx,
+                github,
+                repo,
+                srs_storage,
+                cache.head.as_ref().unwrap(),
+                &mut reviewed,
+            );
+        }
+        thread::sleep(Duration::from_secs(interval));
+    }
+}
+
+/// Reviews every open PR whose head has not been reviewed against the given
+/// chain head yet. Failures to reach GitHub are reported and retried at the
+/// next poll
+fn review_pull_requests(
+    ctx: &CeremonyContext,
+    github: &GitHub,
+    repo: &str,
+    srs_storage: &dyn Storage,
+    head: &ChainHead,
+    reviewed: &mut HashSet<String>,
+) {
+    let pulls = match github.open_pull_requests(repo) {
+        Ok(pulls) => pulls,
+        Err(err) => {
+            println!("Could not list the pull requests of {repo}: {err}");
+            return;
+        }
+    };
+
+    for pr in pulls {
+        let key = format!("{}@{}", pr.head_sha, head.transcript);
+        if reviewed.contains(&key) {
+            continue;
+        }
+        match review_pull_request(ctx, github, repo, srs_storage, head, &pr) {
+            Ok(()) => {
+                reviewed.insert(key);
+            }
+            Err(err) => println!("Could not review PR #{}: {err}", pr.number),
+        }
+    }
+}
+
+/// Verifies the update proof added by the given PR (if any) as the next
+/// contribution of the chain, then comments the report on the PR and sets its
+/// commit status
+fn review_pull_request(
+    ctx: &CeremonyContext,
+    github: &GitHub,
+    repo: &str,
+    srs_storage: &dyn Storage,
+    head: &ChainHead,
+    pr: &PullRequest,
+) -> Result<(), std::io::Error> {
+    let files = github.pull_request_files(repo, pr.number)?;
+    let proof_prefix = proof_prefix(ctx);
+    let proofs: Vec<_> = files
+        .iter()
+        .filter(|file| {
+            file.status == "added"
+                && file
+                    .path
+                    .strip_prefix(&proof_prefix)
+                    .is_some_and(|index| index.parse::<usize>().is_ok())
+        })
+        .collect();
+    // Not a contribution
+    if proofs.is_empty() {
+        return Ok(());
+    }
+
+    let start = Instant::now();
+    let result = match proofs.as_slice() {
+        [proof] => {
+            let bytes =
+                GitHubStorage::new(github.clone(), repo, &pr.head_sha).get_bytes(&proof.path)?;
+            check_contribution(ctx, &proof.path, &bytes, srs_storage, head)
+        }
+        _ => Err("A contribution must add exactly one update proof".to_string()),
+    };
+    let elapsed = start.elapsed();
+
+    let body = match &result {
+        Ok(report) => format!("✅ **The contribution is valid.**\n\n{report}"),
+        Err(failure) => format!("❌ **The contribution is invalid:** {failure}"),
+    };
+    let body = format!(
+        "{body}\n\nVerified against the chain of {} contributions (transcript `{}`) in {:.2?}, at commit {}.",
+        head.nr_proofs, head.transcript, elapsed, pr.head_sha
+    );
+    github.comment(repo, pr.number, &body)?;
+    github.set_status(
+        repo,
+        &pr.head_sha,
+        "srs-verification",
+        result.is_ok(),
+        match &result {
+            Ok(_) => "The update proof extends the chain",
+            Err(_) => "The update proof is invalid, see the PR comments",
+        },
+    )?;
+
+    println!(
+        "PR #{} reviewed: {}",
+        pr.number,
+        if result.is_ok() { "valid" } else { "INVALID" }
+    );
+    Ok(())
+}
+
+/// Prefix of the repository paths of the update proofs of the chain, e.g.
+/// `proofs/proof`
+fn proof_prefix(ctx: &CeremonyContext) -> String {
+    format!("{}/proof", repo_path(&ctx.proofs_dir()))
+}
+
+/// Checks that `bytes`, added at `path` (`proofs/proof<N>`), is a valid update
+/// proof extending the chain with the given head. When the updated SRS is
+/// available as `srs<N>` in `srs_storage`, it is checked against the proof too.
+/// Returns a markdown report on success, the reason of the failure otherwise
+fn check_contribution(
+    ctx: &CeremonyContext,
+    path: &str,
+    bytes: &[u8],
+    srs_storage: &dyn Storage,
+    head: &ChainHead,
+) -> Result<String, String> {
+    let index: usize = path.trim_start_matches(&proof_prefix(ctx)).parse().unwrap();
+    if index != head.nr_proofs + 1 {
+        return Err(format!(
+            "`{path}` is not the next contribution, expected `proofs/proof{}`",
+            head.nr_proofs + 1
+        ));
+    }
+    if ProofFormat::of(bytes).is_none() {
+        return Err(format!(
+            "`{path}` has {} bytes, expected {UPDATE_PROOF_SIZE} (v1) or {UPDATE_PROOF_V2_SIZE} (v2)",
+            bytes.len()
+        ));
+    }
+    let proof =
+        UpdateProof::from_bytes(bytes).map_err(|err| format!("`{path}` is malformed: {err}"))?;
+
+    check_rehearsal(ctx.rehearsal, &proof).map_err(|failure| format!("`{path}`: {failure}"))?;
+    if proof.g != head.g1_point().map_err(|err| err.to_string())? {
+        return Err(format!("`{path}` does not extend the chain"));
+    }
+    if proof.g == proof.h {
+        return Err(format!("`{path}` does not update the SRS"));
+    }
+    if !proof.is_valid() {
+        return Err(format!("The update proof `{path}` is invalid"));
+    }
+    check_ceremony_id(ctx, index, &proof, None)
+        .map_err(|failure| format!("`{path}`: {failure}"))?;
+
+    let srs_check = match check_srs(srs_storage, &format!("srs{index}"), &proof) {
+        Some((_, false)) => {
+            return Err(format!(
+                "The updated SRS `srs{index}` does not match `{path}`"
+            ));
+        }
+        Some((_, true)) => format!("The updated SRS `srs{index}` matches the proof."),
+        None => format!("The updated SRS `srs{index}` was not available to the verifier."),
+    };
+
+    Ok(format!(
+        "| | |\n|---|---|\n\
+         | Contribution | {index} |\n\
+         | Proof SHA-256 | `{}` |\n\
+         | New [tau]_1 (h) | `{}` |\n\n\
+         {srs_check}",
+        hex::encode(Sha256::digest(bytes)),
+        hex::encode(proof.h.to_raw_bytes()),
+    ))
+}
+
+/// Checks the updated SRS stored at `key` (if any) against the update proof
+/// producing it: its [tau]_1 must be the new one of the proof, and its digest
+/// the one the proof commits to. Returns its size and digest, and whether it
+/// matches. The SRS is reported as unavailable if the storage cannot be read
+fn check_srs(storage: &dyn Storage, key: &str, proof: &UpdateProof) -> Option<(ObjectInfo, bool)> {
+    let check = || -> Result<_, std::io::Error> {
+        let Some(info) = storage.stat(key)? else {
+            return Ok(None);
+        };
+        let tau = storage.get_range(key, G1_SIZE as u64..2 * G1_SIZE as u64)?;
+        let matches = decode_g1_point(&tau) == Some(proof.h)
+            && proof
+                .srs_hash
+                .is_none_or(|srs_hash| info.sha256 == srs_hash);
+        Ok(Some((info, matches)))
+    };
+    check().unwrap_or_else(|err| {
+        println!("Could not read {key} of {}: {err}", storage.location());
+        None
+    })
+}
+
+fn report(
+    final_srs_path: &Path,
+    proofs_dir: &Path,
+    html: Option<PathBuf>,
+    markdown: Option<PathBuf>,
+) {
+    println!("\nCollecting the ceremony report...");
+
+    let report = or_fail(CeremonyReport::collect(
+        final_srs_path,
+        proofs_dir,
+        Path::new("./PARTICIPANTS.md"),
+    ));
+
+    for c in report.contributions.iter().filter(|c| !c.is_valid()) {
+        println!("Contribution {}: {}", c.index, c.failure.as_ref().unwrap());
+    }
+    println!(
+        "{} contributions, chain {}, final SRS SHA-256: {}",
+        report.contributions.len(),
+        if report.is_valid() {
+            "valid"
+        } else {
+            "INVALID"
+        },
+        report.final_srs_sha256
+    );
+
+    if let Some(path) = html {
+        fs::write(&path, report.to_html()).expect("Could not write the HTML report");
+        println!("HTML report written to {path:?}\n");
+    }
+
+    if let Some(path) = markdown {
+        fs::write(&path, report.to_markdown()).expect("Could not write the markdown ledger");
+        println!("Markdown ledger written to {path:?}\n");
+    }
+}
+
+/// Signs the statement that the given check of the SRS passed, and writes it
+/// to the statements directory
+fn sign_statement(srs_path: &Path, check: &str, key_path: &Path) {
+    let secret_key = load_or_generate_key(key_path);
+    let statement =
+        VerificationStatement::new("srs", &or_fail(sha256_file(srs_path)), &[check], true);
+    let signed = SignedStatement::sign(statement, &secret_key);
+
+    fs::create_dir_all(STATEMENTS_DIR).expect("Could not create the statements directory");
+    let path = signed.path_in(Path::new(STATEMENTS_DIR));
+    or_fail(signed.save(&path));
+    println!(
+        "Signed statement of the verification written to {path:?} (public key {}).\n",
+        signed.signer().unwrap()
+    );
+}
+
+/// Checks the given statements (or all those of the statements directory)
+/// against the SRS. Exits with an error if any does not hold
+fn verify_attestations(srs_path: &Path, paths: Vec<PathBuf>) {
+    let paths = if paths.is_empty() {
+        statement_files(Path::new(STATEMENTS_DIR))
+    } else {
+        paths
+    };
+    let srs_sha256 = or_fail(sha256_file(srs_path));
+    println!(
+        "\nChecking {} statements against the SRS in {} (SHA-256 {})...",
+        paths.len(),
+        display_input(srs_path),
+        hex::encode(srs_sha256)
+    );
+
+    let mut nr_invalid = 0;
+    for path in &paths {
+        let checked = SignedStatement::load(path).and_then(|signed| {
+            let signer = signed.check_against(&srs_sha256)?;
+            Ok((signed.statement, signer))
+        });
+        match checked {
+            Ok((statement, signer)) => println!(
+                "[ OK ] {path:?}: {} {} by {signer} at time {}",
+                statement.checks.join(","),
+                if statement.passed { "passed" } else { "FAILED" },
+                statement.timestamp
+            ),
+            Err(reason) => {
+                nr_invalid += 1;
+                println!("[FAIL] {path:?} {reason}");
+            }
+        }
+    }
+
+    if nr_invalid > 0 {
+        println!("\n{nr_invalid} statements do not hold.\n");
+        exit(1);
+    }
+    println!("\nAll the statements hold.\n");
+}
+
+fn statement_report(dir: &Path, markdown: Option<PathBuf>) {
+    println!("\nCollecting the verification statements of {dir:?}...");
+
+    let report = StatementReport::collect_dir(dir);
+    for (source, reason) in &report.rejected {
+        println!("Rejected: {source} {reason}");
+    }
+    for (sha256, artifact) in &report.artifacts {
+        println!(
+            "{} ({sha256}): verified by {} independent parties, failed for {}",
+            artifact
+                .names
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", "),
+            artifact.passed.len(),
+            artifact.failed.len()
+        );
+    }
+
+    if let Some(path) = markdown {
+        fs::write(&path, report.to_markdown()).expect("Could not write the markdown report");
+        println!("Markdown report written to {path:?}\n");
+    }
+}
+
+fn finalize(final_srs_path: &Path, output_dir: &Path, force: bool) {
+    let manifest_path = Path::new(DEFAULT_MANIFEST_PATH);
+    let mut manifest = or_fail(CeremonyManifest::load(manifest_path));
+    or_fail(manifest.require(CeremonyPhase::Finalization, "finalize the SRS"));
+
+    println!("\nWriting the release artifacts of the final SRS...");
+
+    fs::create_dir_all(output_dir).expect("Could not create the output directory");
+    let name = final_srs_path.file_name().unwrap().to_string_lossy();
+    let uncompressed_path = output_dir.join(format!("{name}.uncompressed"));
+    let compressed_path = output_dir.join(format!("{name}.compressed"));
+    confirm_outputs(&[&uncompressed_path, &compressed_path], force);
+    let artifacts = or_fail(write_release_artifacts(
+        final_srs_path,
+        &uncompressed_path,
+        &compressed_path,
+    ));
+
+    manifest.release = Some(artifacts.clone());
+    or_fail(manifest.save(manifest_path));
+
+    if !artifacts.encodings_match {
+        fail(SrsError::Verification(
+            "The release artifacts do not decode to the points of the final SRS".to_string(),
+        ));
+    }
+    println!(
+        "Uncompressed SRS: {:?} (SHA-256: {})\nCompressed SRS: {:?} (SHA-256: {})\n",
+        uncompressed_path,
+        artifacts.uncompressed_sha256,
+        compressed_path,
+        artifacts.compressed_sha256
+    );
+}
+
+/// Bundles the final SRS, its release artifacts and everything needed to
+/// verify it into a versioned tarball
+fn package_release(
+    ctx: &CeremonyContext,
+    final_srs_path: &Path,
+    release_dir: &Path,
+    version: &str,
+    output_dir: &Path,
+) {
+    let manifest_path = Path::new(DEFAULT_MANIFEST_PATH);
+    let manifest = or_fail(CeremonyManifest::load(manifest_path));
+    let release = manifest
+        .release
+        .as_ref()
+        .expect("The SRS is not finalized: run `finalize` first");
+
+    println!("\nPackaging release {version}...");
+
+    // The artifacts must be the ones recorded at finalization
+    let name = final_srs_path.file_name().unwrap().to_string_lossy();
+    for (suffix, sha256) in [
+        ("uncompressed", &release.uncompressed_sha256),
+        ("compressed", &release.compressed_sha256),
+    ] {
+        let path = release_dir.join(format!("{name}.{suffix}"));
+        if &hex::encode(or_fail(sha256_file(&path))) != sha256 {
+            fail(SrsError::Verification(format!(
+                "{path:?} does not match the digest recorded in the ceremony manifest"
+            )));
+        }
+    }
+
+    let srs_sha256 = hex::encode(or_fail(sha256_file(final_srs_path)));
+    let bundled_srs_path = release_dir.join(&*name);
+    if bundled_srs_path.exists()
+        && hex::encode(or_fail(sha256_file(&bundled_srs_path))) != srs_sha256
+    {
+        fail(SrsError::Verification(format!(
+            "{bundled_srs_path:?} is not the final SRS"
+        )));
+    }
+    let (nr_proofs, transcript) = or_fail(chain_state(&ctx.proofs_dir()));
+    let transcript = json!({
+        "version": version,
+        "ceremony_id": hex::encode(ctx.ceremony_id),
+        "srs_sha256": srs_sha256,
+        "nr_proofs": nr_proofs,
+        "transcript_hash": hex::encode(transcript),
+    });
+
+    let mut bundle = ReleaseBundle::default();
+    if !bundled_srs_path.exists() {
+        bundle
+            .files
+            .push((format!("srs/{name}"), final_srs_path.to_path_buf()));
+    }
+    bundle
+        .dirs
+        .push(("srs".to_string(), release_dir.to_path_buf()));
+    bundle.dirs.push(("proofs".to_string(), "proofs".into()));
+    for path in [
+        DEFAULT_MANIFEST_PATH,
+        GENESIS_G1_POINT_PATH,
+        CONTRIBUTORS_PATH,
+        "./PARTICIPANTS.md",
+    ]
+    .map(Path::new)
+    .into_iter()
+    .filter(|path| path.exists())
+    {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        bundle.files.push((name, path.to_path_buf()));
+    }
+    if manifest.beacon.is_some() {
+        bundle.dirs.push(("beacon".to_string(), "beacon".into()));
+    }
+    if Path::new(STATEMENTS_DIR).exists() {
+        bundle
+            .dirs
+            .push(("attestations".to_string(), STATEMENTS_DIR.into()));
+    }
+    bundle.generated.push((
+        "transcript.json".to_string(),
+        serde_json::to_vec_pretty(&transcript).unwrap(),
+    ));
+
+    fs::create_dir_all(output_dir).expect("Could not create the output directory");
+    let root = format!("midnight-srs-{version}");
+    let path = output_dir.join(format!("{root}.tar"));
+    let digest = or_fail(bundle.write(&path, &root));
+
+    println!(
+        "Release bundle: {path:?} (SHA-256: {}), listing the digest of every file in {root}/{CHECKSUMS_FILE}\n",
+        hex::encode(digest)
+    );
+}
+
+fn derive_lagrange(srs_path: &Path, log2_len: u32, output: &Path, force: bool) {
+    confirm_outputs(&[output], force);
+    println!("\nDeriving the Lagrange basis over the 2^{log2_len}-th roots of unity...");
+
+    let srs = or_fail(SRS::read_from_file(srs_path));
+    or_fail(write_lagrange_basis(
+        &lagrange_basis(&srs.g1s, log2_len),
+        output,
+    ));
+
+    println!("The Lagrange basis has been saved to {output:?}.\n");
+}
+
+fn export_barretenberg(
+    srs_path: &Path,
+    output_dir: &Path,
+    points_per_transcript: usize,
+    force: bool,
+) {
+    // The generator is not exported
+    let nr_points = or_fail(sniff_format(srs_path))
+        .nr_g1_points()
+        .unwrap_or_else(|| {
+            fail(SrsError::Deserialization(format!(
+                "{srs_path:?} is not an SRS"
+            )))
+        })
+        - 1;
+    let paths: Vec<PathBuf> = (0..nr_points.div_ceil(points_per_transcript.max(1)))
+        .map(|number| barretenberg_transcript_path(output_dir, number))
+        .collect();
+    confirm_outputs(
+        &paths.iter().map(PathBuf::as_path).collect::<Vec<_>>(),
+        force,
+    );
+    println!("\nExporting the SRS as Barretenberg transcripts...");
+
+    let srs = or_fail(SRS::read_from_file(srs_path));
+    let paths = or_fail(write_barretenberg_transcripts(
+        &srs,
+        output_dir,
+        points_per_transcript,
+    ));
+    if or_fail(read_barretenberg_transcripts(output_dir)) != srs {
+        fail(SrsError::Verification(
+            "The transcripts do not decode to the SRS".to_string(),
+        ));
+    }
+
+    println!(
+        "{} transcripts have been saved to {output_dir:?} and checked.\n",
+        paths.len()
+    );
+}
+
+fn convert(srs_path: &Path, output: &Path, encoding: SrsEncoding, force: bool) {
+    confirm_outputs(&[output], force);
+    println!("\nConverting the SRS ({encoding:?} points)...");
+
+    let srs = or_fail(SRS::read_from_file(srs_path));
+    let digest = or_fail(srs.write_to_file_as(output, encoding));
+    if or_fail(SRS::read_from_file(output)) != srs {
+        fail(SrsError::Verification(
+            "The converted SRS does not decode to the SRS".to_string(),
+        ));
+    }
+
+    println!(
+        "The SRS has been converted to {output:?} (SHA-256: {}) and checked.\n",
+        hex::encode(digest)
+    );
 }
 
-fn extract(phase1radix_path: &Path) {
-    extract_g1_point_from_filecoin_srs(phase1radix_path, 19);
+/// Key of the SRS path in a storage, when not given
+fn default_key(srs_path: &Path) -> String {
+    srs_path
+        .file_name()
+        .expect("The SRS path has no file name, give the key of the artifact")
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn fetch(srs_path: &Path, location: &str, key: Option<String>, force: bool) {
+    confirm_outputs(&[srs_path], force);
+    let storage = open_storage(location);
+    let key = key.unwrap_or_else(|| default_key(srs_path));
+    println!("\nFetching {key} from {}...", storage.location());
+
+    let sidecar_key = format!("{key}.{SIDECAR_EXTENSION}");
+    let expected = match storage.get_bytes(&sidecar_key) {
+        Ok(content) => Some(
+            parse_sidecar(&content)
+                .unwrap_or_else(|| panic!("Malformed checksum sidecar {sidecar_key}")),
+        ),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => panic!("Could not fetch {sidecar_key}: {err}"),
+    };
+
+    let mut file = or_fail(create_file(srs_path));
+    let result = match &expected {
+        Some(sha256) => storage.get_verified(&key, &mut file, sha256),
+        None => storage.get(&key, &mut file),
+    };
+    let info = result.unwrap_or_else(|err| {
+        // Do not leave a partial (or corrupted) artifact behind
+        let _ = fs::remove_file(srs_path);
+        panic!("Could not fetch {key}: {err}")
+    });
+    or_fail(write_sidecar(srs_path, &info.sha256));
+
+    println!(
+        "{key} ({} bytes, SHA-256: {}) has been fetched to {srs_path:?}{}.\n",
+        info.size,
+        hex::encode(info.sha256),
+        if expected.is_some() {
+            " and matches its checksum sidecar"
+        } else {
+            ", there is no checksum sidecar to check it against"
+        }
+    );
+}
 
+fn upload(srs_path: &Path, location: &str, key: Option<String>) {
+    let storage = open_storage(location);
+    let key = key.unwrap_or_else(|| default_key(srs_path));
     println!(
-        "First G1 point succesfully extracted from {:?}!\n",
-        phase1radix_path.canonicalize().unwrap()
+        "\nUploading {srs_path:?} to {key} of {}...",
+        storage.location()
+    );
+
+    let info = storage
+        .put_verified(&key, &mut or_fail(open_file(srs_path)))
+        .unwrap_or_else(|err| panic!("Could not upload {key}: {err}"));
+    or_fail(check_sidecar_digest(srs_path, &info.sha256));
+    let file_name = key.rsplit('/').next().unwrap();
+    storage
+        .put_verified(
+            &format!("{key}.{SIDECAR_EXTENSION}"),
+            &mut sidecar_content(file_name, &info.sha256).as_bytes(),
+        )
+        .unwrap_or_else(|err| panic!("Could not upload the checksum sidecar of {key}: {err}"));
+
+    println!(
+        "{key} ({} bytes, SHA-256: {}) has been uploaded to {} and checked.\n",
+        info.size,
+        hex::encode(info.sha256),
+        storage.location()
+    );
+}
+
+fn mirror_storage(source_location: &str, targets: &[String]) {
+    let manifest_path = Path::new(DEFAULT_MANIFEST_PATH);
+    let mut manifest = or_fail(CeremonyManifest::load(manifest_path));
+    let source = open_storage(source_location);
+    let terminal = TerminalProgress::default();
+
+    for location in targets {
+        println!("\nMirroring {} to {location}...", source.location());
+        let target = open_storage(location);
+        let report = mirror(&*source, &*target, &terminal)
+            .unwrap_or_else(|err| panic!("Could not mirror to {location}: {err}"));
+        manifest.record_mirror(Mirror {
+            location: location.clone(),
+            synced_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            nr_objects: report.copied + report.unchanged,
+            listing: hex::encode(report.listing),
+        });
+        // Saved after each mirror, so that a failure does not lose the others
+        or_fail(manifest.save(manifest_path));
+        println!(
+            "{} objects copied, {} already up to date (listing digest: {}).",
+            report.copied,
+            report.unchanged,
+            hex::encode(report.listing)
+        );
+    }
+
+    println!("\nThe mirrors have been synchronized and recorded in {manifest_path:?}.\n");
+}
+
+fn verify_lagrange(
+    srs_path: &Path,
+    log2_len: u32,
+    lagrange_path: &Path,
+    msm: MsmConfig,
+) -> Result<(), SrsError> {
+    println!("\nVerifying the Lagrange basis over the 2^{log2_len}-th roots of unity...");
+
+    let srs = SRS::read_from_file(srs_path)?;
+    let lagrange = read_lagrange_basis(lagrange_path)?;
+    if !is_lagrange_basis(&srs.g1s, &lagrange, log2_len, &msm) {
+        return Err(SrsError::Verification(format!(
+            "{lagrange_path:?} is not the Lagrange basis of {srs_path:?} over the 2^{log2_len}-th roots of unity"
+        )));
+    }
+
+    println!("The Lagrange basis in {lagrange_path:?} is correct!\n");
+    Ok(())
+}
+
+fn verify_truncation(srs_path: &Path, larger_srs_path: &Path) -> Result<(), SrsError> {
+    println!("\nVerifying that {srs_path:?} is a truncation of {larger_srs_path:?}...");
+
+    if !is_truncation_of(srs_path, larger_srs_path)? {
+        return Err(SrsError::Verification(format!(
+            "{srs_path:?} is not a truncation of {larger_srs_path:?}"
+        )));
+    }
+
+    println!("The SRS is a truncation of the larger SRS!\n");
+    Ok(())
+}
+
+fn verify_beacon(
+    ctx: &CeremonyContext,
+    srs_path: &Path,
+    index: usize,
+    randomness: &str,
+    salt: &str,
+    input_srs: Option<&Path>,
+) -> Result<(), SrsError> {
+    println!("\nVerifying beacon contribution {index}...");
+
+    let decode = |value: &str, name: &str| {
+        hex::decode(value)
+            .map_err(|err| SrsError::Deserialization(format!("Failed to decode the {name}: {err}")))
+    };
+    let randomness = decode(randomness, "beacon randomness")?;
+    let salt = decode(salt, "salt")?;
+    let nu = beacon_scalar(&randomness, &salt);
+    println!("The scalar derived from the beacon randomness and the salt is:\n{nu}\n");
+
+    let proof_path = open_update_proof_dirs(&ctx.proofs_dir())?
+        .into_iter()
+        .find(|entry| entry.index == index)
+        .ok_or_else(|| SrsError::Verification(format!("There is no proof{index}")))?
+        .path();
+    let proof = UpdateProof::read_from_file(&proof_path)?;
+    let verification = |failure| SrsError::Verification(format!("{proof_path:?}: {failure}"));
+    check_rehearsal(ctx.rehearsal, &proof).map_err(verification)?;
+    if !proof.is_valid() {
+        return Err(SrsError::Verification(format!(
+            "The update proof {proof_path:?} is invalid"
+        )));
+    }
+    check_ceremony_id(ctx, index, &proof, None).map_err(verification)?;
+    audit_check(
+        "beacon-scalar",
+        json!({
+            "proof": proof_path.display().to_string(),
+            "randomness": hex::encode(&randomness),
+            "salt": hex::encode(&salt),
+        }),
+        (proof.g * nu).to_affine() == proof.h,
+    );
+    if (proof.g * nu).to_affine() != proof.h {
+        return Err(SrsError::Verification(format!(
+            "Contribution {index} (proved in file {proof_path:?}) was NOT performed with the derived scalar"
+        )));
+    }
+    if let Some(g2_update) = &proof.g2_update {
+        if (g2_update.g2 * nu).to_affine() != g2_update.h2 {
+            return Err(SrsError::Verification(format!(
+                "The G2 side of contribution {index} was NOT performed with the derived scalar"
+            )));
+        }
+    }
+    println!("Contribution {index} (proved in file {proof_path:?}) was performed with the derived scalar.");
+
+    if read_g1_point_from_file(srs_path, G1_SIZE)? != proof.h {
+        return Err(SrsError::ChainMismatch(format!(
+            "{srs_path:?} was not produced by contribution {index}"
+        )));
+    }
+    if let Some(srs_hash) = proof.srs_hash {
+        if sha256_file(srs_path)? != srs_hash {
+            return Err(SrsError::Verification(format!(
+                "{srs_path:?} does not match the SRS digest recorded in {proof_path:?}"
+            )));
+        }
+    }
+
+    if let Some(input_srs) = input_srs {
+        if read_g1_point_from_file(input_srs, G1_SIZE)? != proof.g {
+            return Err(SrsError::ChainMismatch(format!(
+                "Contribution {index} was not applied to {input_srs:?}"
+            )));
+        }
+        println!("Replaying the update of {input_srs:?}...");
+        let replayed_path = srs_path.with_extension("replayed");
+        let replayed = SRS::update_file(
+            ctx,
+            input_srs,
+            &replayed_path,
+            &nu,
+            UPDATE_WINDOW_SIZE,
+            &TerminalProgress::default(),
+        )?
+        .ok_or_else(|| {
+            SrsError::Verification("The replay of the update was interrupted".to_string())
+        })?;
+        fs::remove_file(&replayed_path).map_err(SrsError::io("remove", &replayed_path))?;
+        let _ = fs::remove_file(sidecar_path(&replayed_path));
+        if replayed.srs_hash != Some(sha256_file(srs_path)?) {
+            return Err(SrsError::Verification(format!(
+                "{srs_path:?} is NOT the update of {input_srs:?} with the derived scalar"
+            )));
+        }
+        println!("{srs_path:?} is the update of {input_srs:?} with the derived scalar.");
+    }
+
+    println!("\nAll checks passed!\n");
+    Ok(())
+}
+
+fn emit_test_vectors(log2_len: usize, contributions: usize, seed: &str, output: Option<&Path>) {
+    let mut seed_bytes = [0u8; 32];
+    hex::decode_to_slice(seed, &mut seed_bytes).expect("The seed must be 32 bytes of hex");
+
+    let vectors = test_vectors(seed_bytes, log2_len, contributions);
+    match output {
+        Some(path) => {
+            serde_json::to_writer_pretty(or_fail(create_file(path)), &vectors)
+                .expect("Could not write the test vectors");
+            println!("Test vectors written to {path:?}");
+        }
+        None => println!("{}", serde_json::to_string_pretty(&vectors).unwrap()),
+    }
+}
+
+fn selftest() {
+    let outcomes = run_selftest();
+    for (name, outcome) in &outcomes {
+        match outcome {
+            Ok(()) => println!("[ OK ] {name}"),
+            Err(reason) => println!("[FAIL] {name}: {reason}"),
+        }
+    }
+    if outcomes.iter().any(|(_, outcome)| outcome.is_err()) {
+        eprintln!("\nThe self-test failed: this build must not be used for the ceremony.\n");
+        exit(1);
+    }
+    println!("\nThe self-test passed.\n");
+}
+
+fn selftest_kzg(srs_path: &Path, degree: Option<usize>, nr_polynomials: usize) {
+    let reader = or_fail(SrsReader::open(srs_path));
+    let n = degree.map_or(reader.len(), |degree| degree + 1);
+    assert!(
+        n <= reader.len(),
+        "The SRS has {} G1 points, a polynomial of degree {} needs {n}",
+        reader.len(),
+        n - 1
+    );
+    println!(
+        "\nCommitting to {nr_polynomials} random polynomials of degree {} and opening them...",
+        n - 1
+    );
+    let g1s = reader.g1_points(0..n);
+    match kzg_selftest(
+        &g1s,
+        &reader.g2_points(),
+        nr_polynomials,
+        &MsmConfig::default(),
+    ) {
+        Ok(()) => println!("\nThe KZG self-test passed.\n"),
+        Err(reason) => {
+            eprintln!("\nThe KZG self-test failed: {reason}\n");
+            exit(1);
+        }
+    }
+}
+
+fn verify_journal_file(journal_path: &Path) {
+    match verify_journal(journal_path) {
+        Ok(nr_entries) => println!("\nThe journal is intact ({nr_entries} entries).\n"),
+        Err(reason) => {
+            eprintln!("\nThe journal was tampered with: {reason}\n");
+            exit(1);
+        }
+    }
+}
+
+fn phase(proofs_dir: &Path, advance_to: Option<CeremonyPhase>) {
+    let manifest_path = Path::new(DEFAULT_MANIFEST_PATH);
+    let mut manifest = or_fail(CeremonyManifest::load(manifest_path));
+
+    if let Some(phase) = advance_to {
+        let (nr_proofs, transcript) = or_fail(chain_state(proofs_dir));
+        manifest.advance(phase, nr_proofs, transcript);
+        or_fail(manifest.save(manifest_path));
+        println!(
+            "\nThe ceremony moved to the {phase} phase after {nr_proofs} contributions (chain transcript: {}).\n",
+            hex::encode(transcript)
+        );
+    } else {
+        println!("\nThe ceremony is in the {} phase.\n", manifest.phase);
+    }
+}
+
+fn extract<T: ExternalTranscript>(transcript_path: &Path, force: bool) {
+    confirm_outputs(&[Path::new(T::G1_POINT_PATH)], force);
+    let g1_point = or_fail(extract_anchor::<T>(transcript_path));
+
+    println!(
+        "[tau]_1 = {} succesfully extracted from {:?} to {}!\n",
+        hex::encode(g1_point.to_compressed()),
+        transcript_path.canonicalize().unwrap(),
+        T::G1_POINT_PATH
     )
 }
 
+/// The MSM configuration of the `--msm-algo` and `--msm-window` options.
+/// Panics if a window is given for another algorithm than Pippenger
+fn msm_config(algo: MsmAlgo, window: Option<u32>) -> MsmConfig {
+    MsmConfig::new(algo, window.map(|c| c as usize)).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Reports the error to the operator, then exits with its exit code (see
+/// [SrsError::exit_code])
+fn fail(err: SrsError) -> ! {
+    eprintln!("Error: {err}");
+    exit(err.exit_code())
+}
+
+fn or_fail<T>(result: Result<T, SrsError>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(err) => fail(err),
+    }
+}
+
+/// Reports panics as a plain error message, without the location and the
+/// backtrace meant for developers (unless `RUST_BACKTRACE` is set)
+fn install_panic_hook() {
+    if std::env::var_os("RUST_BACKTRACE").is_some() {
+        return;
+    }
+    std::panic::set_hook(Box::new(|info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unexpected failure");
+        eprintln!("Error: {message}");
+    }));
+}
+
 fn main() {
     let args = CLICommand::parse();
+    // Installed first, so that the journal and audit hooks run before it
+    install_panic_hook();
+    // The run is journaled, unless in (read-only) auditor mode
+    if let Some(path) = &args.audit_log {
+        open_audit_log(path);
+    } else {
+        start_journal(Path::new(JOURNAL_PATH));
+    }
+    check_compiled_features();
+    if let Some(max_mem) = args.max_mem {
+        select_max_mem(max_mem);
+    }
+
+    if let Some(set) = &args.set {
+        let manifest = or_fail(CeremonyManifest::load(Path::new(DEFAULT_MANIFEST_PATH)));
+        let set = manifest.parameter_set(set);
+        println!(
+            "Using the parameter set '{}' (2^{} points)",
+            set.name, set.log2_len
+        );
+    }
+    // The context of the chain is only loaded by the commands working on it
+    let ctx = || or_fail(CeremonyContext::load(args.set.as_deref()));
+    let proofs_dir = proofs_dir(args.set.as_deref());
+
+    // The statement of a successful verification is signed once it is done
+    let check = args.cmd.verification_check();
+    if args.sign_statement.is_some() {
+        assert!(
+            check.is_some(),
+            "--sign-statement only applies to the verification commands"
+        );
+        assert!(
+            !is_stdin(Path::new(&args.srs_path)),
+            "Cannot sign a statement about an SRS read from the standard input"
+        );
+    }
 
     match args.cmd {
-        Command::VerifyStructure { log2_len } => {
-            verify_structure(Path::new(&args.srs_path), log2_len)
+        Command::VerifyStructure {
+            log2_len,
+            msm_algo,
+            msm_window,
+            range: Some(range),
+            seed,
+            attestation,
+            ..
+        } => verify_range(
+            Path::new(&args.srs_path),
+            log2_len,
+            range,
+            &seed.unwrap(),
+            &attestation.unwrap(),
+            msm_config(msm_algo, msm_window),
+        ),
+        Command::VerifyStructure {
+            log2_len,
+            msm_algo,
+            msm_window,
+            chunks,
+            exhaustive,
+            deterministic_challenge,
+            stream,
+            ..
+        } => verify_structure(
+            Path::new(&args.srs_path),
+            log2_len,
+            msm_config(msm_algo, msm_window),
+            chunks,
+            exhaustive,
+            deterministic_challenge,
+            stream,
+        ),
+        Command::Aggregate {
+            log2_len,
+            attestations,
+        } => aggregate_attestations(log2_len, &attestations),
+        Command::VerifyChain { diagnose: true, .. } => diagnose(&ctx(), Path::new(&args.srs_path)),
+        Command::VerifyChain {
+            no_cache, srs_dir, ..
+        } => {
+            let srs_storage = srs_dir.as_deref().map(open_storage);
+            or_fail(verify_chain(
+                &ctx(),
+                Path::new(&args.srs_path),
+                no_cache,
+                srs_storage.as_deref(),
+            ))
         }
-        Command::VerifyChain => verify_chain(Path::new(&args.srs_path)),
         Command::Update {
             entropy,
             os_randomness,
-        } => update(Path::new(&args.srs_path), entropy, os_randomness),
-        Command::ExtractFilecoinG1Point => extract(Path::new(&args.srs_path)),
+            attest_files,
+            metadata_level,
+            hardware_randomness,
+            entropy_files,
+            entropy_stdin,
+            beacons,
+            checkpoint,
+            pull_request,
+        } => update(
+            &ctx(),
+            Path::new(&args.srs_path),
+            entropy,
+            os_randomness,
+            attest_files,
+            metadata_level,
+            hardware_randomness,
+            entropy_files,
+            entropy_stdin,
+            beacons,
+            args.force,
+            checkpoint,
+            pull_request,
+        ),
+        Command::ExtractFilecoinG1Point => {
+            extract::<FilecoinTranscript>(Path::new(&args.srs_path), args.force)
+        }
+        Command::ExtractSaplingG1Point => {
+            extract::<SaplingTranscript>(Path::new(&args.srs_path), args.force)
+        }
+        Command::Hash => hash(Path::new(&args.srs_path)),
+        Command::Info => info(Path::new(&args.srs_path)),
+        Command::Sign { key_path } => sign_last_proof(&proofs_dir, &key_path),
+        Command::Finalize { output_dir } => {
+            finalize(Path::new(&args.srs_path), &output_dir, args.force)
+        }
+        Command::PackageRelease {
+            release_dir,
+            version,
+            output_dir,
+        } => package_release(
+            &ctx(),
+            Path::new(&args.srs_path),
+            &release_dir,
+            &version,
+            &output_dir,
+        ),
+        Command::DeriveLagrange { log2_len, output } => {
+            derive_lagrange(Path::new(&args.srs_path), log2_len, &output, args.force)
+        }
+        Command::ExportBarretenberg {
+            output_dir,
+            points_per_transcript,
+        } => export_barretenberg(
+            Path::new(&args.srs_path),
+            &output_dir,
+            points_per_transcript,
+            args.force,
+        ),
+        Command::Convert { output, to } => {
+            convert(Path::new(&args.srs_path), &output, to.into(), args.force)
+        }
+        Command::Fetch { from, key } => fetch(Path::new(&args.srs_path), &from, key, args.force),
+        Command::Upload { to, key } => upload(Path::new(&args.srs_path), &to, key),
+        Command::Mirror { to } => mirror_storage(&args.srs_path, &to),
+        Command::VerifyLagrange {
+            log2_len,
+            lagrange_path,
+            msm_algo,
+            msm_window,
+        } => or_fail(verify_lagrange(
+            Path::new(&args.srs_path),
+            log2_len,
+            &lagrange_path,
+            msm_config(msm_algo, msm_window),
+        )),
+        Command::VerifyTruncation { larger_srs_path } => or_fail(verify_truncation(
+            Path::new(&args.srs_path),
+            &larger_srs_path,
+        )),
+        Command::Report { html, markdown } => {
+            report(Path::new(&args.srs_path), &proofs_dir, html, markdown)
+        }
+        Command::VerifyAttestation { statements } => {
+            verify_attestations(Path::new(&args.srs_path), statements)
+        }
+        Command::StatementReport { dir, markdown } => statement_report(&dir, markdown),
+        Command::VerifyBeacon {
+            index,
+            randomness,
+            salt,
+            input_srs,
+        } => or_fail(verify_beacon(
+            &ctx(),
+            Path::new(&args.srs_path),
+            index,
+            &randomness,
+            &salt,
+            input_srs.as_deref(),
+        )),
+        Command::TestVectors {
+            log2_len,
+            contributions,
+            seed,
+            output,
+        } => emit_test_vectors(log2_len, contributions, &seed, output.as_deref()),
+        Command::Selftest => selftest(),
+        Command::SelftestKzg {
+            degree,
+            polynomials,
+        } => selftest_kzg(Path::new(&args.srs_path), degree, polynomials),
+        Command::VerifyJournal => verify_journal_file(Path::new(&args.srs_path)),
+        Command::Phase { advance_to } => phase(&proofs_dir, advance_to),
+        Command::Watch {
+            interval,
+            github_bot,
+            github_repo,
+            slack_webhook,
+            discord_webhook,
+        } => {
+            let mut sinks: Vec<Box<dyn NotificationSink>> = Vec::new();
+            if let Some(url) = slack_webhook {
+                sinks.push(Box::new(SlackWebhook { url }));
+            }
+            if let Some(url) = discord_webhook {
+                sinks.push(Box::new(DiscordWebhook { url }));
+            }
+            watch(
+                &ctx(),
+                &*open_storage(&args.srs_path),
+                interval,
+                github_bot.then_some(github_repo),
+                sinks,
+            )
+        }
     };
 
+    if let (Some(key_path), Some(check)) = (&args.sign_statement, check) {
+        sign_statement(Path::new(&args.srs_path), check, key_path);
+    }
+    finish_journal("ok");
+
     println!(
         "
 ▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓
@@ -0,0 +1,300 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Release bundles.
+//!
+//! The published artifacts of the ceremony (the final SRS in all its formats,
+//! the chain of update proofs, the manifests and the beacon files) are
+//! gathered into a single tarball (ustar), under a versioned top-level
+//! directory, together with a `SHA256SUMS` file listing the digest of every
+//! other entry, as `sha256sum --check` expects it.
+//!
+//! Entries are stored with a zero timestamp and owner, so that the same
+//! artifacts always give the same tarball.
+
+use std::{
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    cache::write_sidecar,
+    error::SrsError,
+    utils::{create_file, open_dir, open_file},
+};
+
+/// Name of the checksum file of a bundle
+pub const CHECKSUMS_FILE: &str = "SHA256SUMS";
+
+/// Size of a tar block
+const BLOCK_SIZE: usize = 512;
+
+/// Largest entry whose size fits in the 11 octal digits of a ustar header
+const MAX_ENTRY_SIZE: u64 = (1 << 33) - 1;
+
+/// Writes a field of a ustar header as zero-padded octal, NUL terminated
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{value:0width$o}", width = field.len() - 1);
+    assert!(
+        digits.len() < field.len(),
+        "{value} does not fit in a tar header"
+    );
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+}
+
+/// The ustar header of a regular file with the given name and size. Names
+/// longer than 100 bytes are split into a prefix (at a `/`) and a name
+fn tar_header(name: &str, size: u64) -> [u8; BLOCK_SIZE] {
+    assert!(
+        size <= MAX_ENTRY_SIZE,
+        "{name} is too large for a tar entry"
+    );
+    let (prefix, name) = match name.len() {
+        0..=100 => ("", name),
+        _ => name[..name.len().min(156)]
+            .rfind('/')
+            .map(|split| (&name[..split], &name[split + 1..]))
+            .filter(|(_, name)| name.len() <= 100)
+            .unwrap_or_else(|| panic!("{name} is too long for a tar entry")),
+    };
+
+    let mut header = [0u8; BLOCK_SIZE];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o644);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], size);
+    write_octal(&mut header[136..148], 0);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    // The checksum is computed with its own field filled with spaces
+    header[148..156].fill(b' ');
+    let checksum: u64 = header.iter().map(|&byte| byte as u64).sum();
+    write_octal(&mut header[148..155], checksum);
+    header
+}
+
+/// Writer of a tarball, hashing every entry on the way
+pub struct BundleWriter<W: Write> {
+    writer: W,
+    /// Top-level directory of the entries
+    root: String,
+    /// Digest (hex) and name of every entry so far
+    checksums: Vec<(String, String)>,
+}
+
+impl<W: Write> BundleWriter<W> {
+    pub fn new(writer: W, root: &str) -> Self {
+        BundleWriter {
+            writer,
+            root: root.to_string(),
+            checksums: Vec::new(),
+        }
+    }
+
+    /// Appends an entry with the given name (relative to the top-level
+    /// directory), whose `size` bytes are read from `reader`
+    fn append(&mut self, name: &str, size: u64, reader: &mut impl Read) -> io::Result<()> {
+        let header = tar_header(&format!("{}/{name}", self.root), size);
+        self.writer.write_all(&header)?;
+
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0u8; 1 << 16];
+        let mut remaining = size;
+        while remaining > 0 {
+            let len = (buffer.len() as u64).min(remaining) as usize;
+            reader.read_exact(&mut buffer[..len])?;
+            hasher.update(&buffer[..len]);
+            self.writer.write_all(&buffer[..len])?;
+            remaining -= len as u64;
+        }
+        let padding = (BLOCK_SIZE - (size as usize % BLOCK_SIZE)) % BLOCK_SIZE;
+        self.writer.write_all(&[0u8; BLOCK_SIZE][..padding])?;
+
+        self.checksums
+            .push((hex::encode(hasher.finalize()), name.to_string()));
+        Ok(())
+    }
+
+    /// Appends the file stored at the given path
+    pub fn append_file(&mut self, name: &str, path: &Path) -> Result<(), SrsError> {
+        let mut file = open_file(path)?;
+        let size = file.metadata().map_err(SrsError::io("read", path))?.len();
+        self.append(name, size, &mut file)
+            .map_err(SrsError::io("bundle", path))
+    }
+
+    /// Appends an entry with the given content
+    pub fn append_bytes(&mut self, name: &str, bytes: &[u8]) -> io::Result<()> {
+        self.append(name, bytes.len() as u64, &mut &bytes[..])
+    }
+
+    /// Appends the files of the given directory (recursively, in name order),
+    /// under `name`
+    pub fn append_dir(&mut self, name: &str, dir: &Path) -> Result<(), SrsError> {
+        let mut paths: Vec<PathBuf> = open_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<_>>()
+            .map_err(SrsError::io("read", dir))?;
+        paths.sort();
+        for path in paths {
+            let entry_name = format!("{name}/{}", path.file_name().unwrap().to_string_lossy());
+            if path.is_dir() {
+                self.append_dir(&entry_name, &path)?;
+            } else {
+                self.append_file(&entry_name, &path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends the checksum file of all the entries and the end-of-archive
+    /// marker, and returns the checksum file
+    pub fn finish(mut self) -> io::Result<String> {
+        let checksums: String = self
+            .checksums
+            .iter()
+            .map(|(digest, name)| format!("{digest}  {name}\n"))
+            .collect();
+        self.append_bytes(CHECKSUMS_FILE, checksums.as_bytes())?;
+        self.writer.write_all(&[0u8; 2 * BLOCK_SIZE])?;
+        self.writer.flush()?;
+        Ok(checksums)
+    }
+}
+
+/// Files of a release bundle: their name in the bundle and their path
+#[derive(Clone, Debug, Default)]
+pub struct ReleaseBundle {
+    pub files: Vec<(String, PathBuf)>,
+    pub dirs: Vec<(String, PathBuf)>,
+    /// Entries generated at packaging time, with their content
+    pub generated: Vec<(String, Vec<u8>)>,
+}
+
+impl ReleaseBundle {
+    /// Writes the bundle to the given path, under the top-level directory
+    /// `root`, with a checksum sidecar. Returns the SHA-256 digest of the
+    /// tarball
+    pub fn write(&self, path: &Path, root: &str) -> Result<[u8; 32], SrsError> {
+        let write_error = SrsError::io("write", path);
+        let mut writer = BundleWriter::new(BufWriter::new(create_file(path)?), root);
+        for (name, file) in &self.files {
+            writer.append_file(name, file)?;
+        }
+        for (name, dir) in &self.dirs {
+            writer.append_dir(name, dir)?;
+        }
+        for (name, bytes) in &self.generated {
+            writer.append_bytes(name, bytes).map_err(write_error)?;
+        }
+        writer.finish().map_err(write_error)?;
+
+        let mut hasher = Sha256::new();
+        io::copy(&mut open_file(path)?, &mut hasher).map_err(SrsError::io("read", path))?;
+        let digest = hasher.finalize().into();
+        write_sidecar(path, &digest)?;
+        Ok(digest)
+    }
+}
+
+#[cfg(test)]
+mod bundle_tests {
+    use std::{fs, path::Path};
+
+    use sha2::{Digest, Sha256};
+
+    use crate::bundle::{tar_header, ReleaseBundle, BLOCK_SIZE, CHECKSUMS_FILE};
+
+    /// The names and contents of the entries of a tarball
+    fn read_entries(bytes: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let field = |bytes: &[u8]| {
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            String::from_utf8(bytes[..end].to_vec()).unwrap()
+        };
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        while bytes[offset..offset + BLOCK_SIZE].iter().any(|&b| b != 0) {
+            let header = &bytes[offset..offset + BLOCK_SIZE];
+            let prefix = field(&header[345..500]);
+            let name = field(&header[..100]);
+            let name = if prefix.is_empty() {
+                name
+            } else {
+                format!("{prefix}/{name}")
+            };
+            let size = usize::from_str_radix(&field(&header[124..135]), 8).unwrap();
+            offset += BLOCK_SIZE;
+            entries.push((name, bytes[offset..offset + size].to_vec()));
+            offset += size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+        }
+        entries
+    }
+
+    #[test]
+    fn bundles_are_checksummed() {
+        let dir = Path::new("/tmp/test_bundle_dir");
+        let _ = fs::remove_dir_all(dir);
+        fs::create_dir_all(dir.join("proof1")).unwrap();
+        fs::write(dir.join("proof1/proof"), [1u8; 700]).unwrap();
+        fs::write("/tmp/test_bundle_srs", [2u8; 512]).unwrap();
+
+        let bundle = ReleaseBundle {
+            files: vec![("srs/final".to_string(), "/tmp/test_bundle_srs".into())],
+            dirs: vec![("proofs".to_string(), dir.into())],
+            generated: vec![("transcript.json".to_string(), b"{}".to_vec())],
+        };
+        let path = Path::new("/tmp/test_bundle.tar");
+        let digest = bundle.write(path, "srs-v1").unwrap();
+        let bytes = fs::read(path).unwrap();
+        assert_eq!(digest, <[u8; 32]>::from(Sha256::digest(&bytes)));
+        // The same artifacts give the same bundle
+        assert_eq!(bundle.write(path, "srs-v1").unwrap(), digest);
+
+        let entries = read_entries(&bytes);
+        let names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "srs-v1/srs/final",
+                "srs-v1/proofs/proof1/proof",
+                "srs-v1/transcript.json",
+                "srs-v1/SHA256SUMS"
+            ]
+        );
+        assert_eq!(entries[1].1, [1u8; 700]);
+        let checksums = String::from_utf8(entries[3].1.clone()).unwrap();
+        assert_eq!(checksums.lines().count(), 3);
+        assert!(checksums.contains(&format!(
+            "{}  proofs/proof1/proof",
+            hex::encode(Sha256::digest([1u8; 700]))
+        )));
+        assert!(!checksums.contains(CHECKSUMS_FILE));
+    }
+
+    #[test]
+    fn long_names_are_split() {
+        let name = format!("{}/{}", "d".repeat(120), "f".repeat(90));
+        let header = tar_header(&name, 1);
+        assert_eq!(&header[..90], "f".repeat(90).as_bytes());
+        assert_eq!(&header[345..465], "d".repeat(120).as_bytes());
+        assert!(std::panic::catch_unwind(|| tar_header(&"x".repeat(120), 1)).is_err());
+    }
+}
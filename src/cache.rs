@@ -0,0 +1,339 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Local cache of update proof verification results.
+//!
+//! Proofs are identified by the SHA-256 digest of their file, so a proof that
+//! is modified (or replaced) is verified again. The cache only ever saves work
+//! on the local machine; deleting it (or running with `--no-cache`) re-verifies
+//! the whole chain.
+//!
+//! The cache also stores the head of the verified chain ([ChainHead]), which
+//! lets the watcher verify only newly appended contributions.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use blstrs::G1Affine;
+use halo2curves::serde::SerdeObject;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    audit::{audit_check, audit_sha256, is_auditing, read_audited},
+    error::SrsError,
+    utils::{create_file, is_stdin, open_input, read_g1_point},
+};
+
+/// Default location of the verification cache
+pub const DEFAULT_CACHE_PATH: &str = "./.verified_proofs.json";
+
+/// Location of the verification cache of the given parameter set (of the main
+/// chain if `None`)
+pub fn cache_path(parameter_set: Option<&str>) -> PathBuf {
+    match parameter_set {
+        None => PathBuf::from(DEFAULT_CACHE_PATH),
+        Some(set) => PathBuf::from(format!("./.verified_proofs-{set}.json")),
+    }
+}
+
+/// Default location of the checkpoint of an exhaustive structure verification
+pub const EXHAUSTIVE_CHECKPOINT_PATH: &str = "./.exhaustive_checkpoint.json";
+
+/// Progress of an exhaustive structure verification (see
+/// [crate::ceremony::SRS::verify_structure_exhaustive]), to resume it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExhaustiveCheckpoint {
+    /// SHA-256 digest (hex) of the verified SRS, see
+    /// [crate::ceremony::SRS::sha256]
+    pub srs_sha256: String,
+    /// Number of pairs of consecutive G1 points already verified
+    pub verified: usize,
+}
+
+impl ExhaustiveCheckpoint {
+    /// Number of pairs of the SRS with the given digest already verified
+    /// according to the checkpoint in the given file, if any. Auditors always
+    /// start from scratch
+    pub fn resume_point(path: &Path, srs_sha256: &str) -> usize {
+        if is_auditing() {
+            return 0;
+        }
+        fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Self>(&bytes).ok())
+            .filter(|checkpoint| checkpoint.srs_sha256 == srs_sha256)
+            .map_or(0, |checkpoint| checkpoint.verified)
+    }
+
+    /// Writes the checkpoint to the given file (except in auditor mode)
+    pub fn save(&self, path: &Path) -> Result<(), SrsError> {
+        if is_auditing() {
+            return Ok(());
+        }
+        write_json(path, self)
+    }
+}
+
+/// Maps SHA-256 digests (hex) of proof files to their verification result
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct VerificationCache {
+    results: BTreeMap<String, bool>,
+    /// Head of the verified chain of update proofs
+    #[serde(default)]
+    pub head: Option<ChainHead>,
+}
+
+/// Head of a verified prefix of the chain of update proofs
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChainHead {
+    /// Number of verified proofs
+    pub nr_proofs: usize,
+    /// Transcript hash (hex) of the verified proof files, see
+    /// [ChainHead::extend]
+    pub transcript: String,
+    /// [tau]_1 (hex of its raw encoding) after the last verified proof
+    pub g1_point: String,
+}
+
+impl ChainHead {
+    /// The head of the empty chain, anchored at the given [tau]_1
+    pub fn genesis(g1_point: &G1Affine) -> Self {
+        ChainHead {
+            nr_proofs: 0,
+            transcript: hex::encode([0u8; 32]),
+            g1_point: hex::encode(g1_point.to_raw_bytes()),
+        }
+    }
+
+    /// Appends a verified proof, whose file has the given digest and whose
+    /// resulting [tau]_1 is `g1_point`. The new transcript hash is
+    /// SHA-256(transcript || digest)
+    pub fn extend(&self, digest: &[u8; 32], g1_point: &G1Affine) -> Self {
+        ChainHead {
+            nr_proofs: self.nr_proofs + 1,
+            transcript: hex::encode(extend_transcript(&self.transcript_bytes(), digest)),
+            g1_point: hex::encode(g1_point.to_raw_bytes()),
+        }
+    }
+
+    /// The transcript hash as bytes
+    pub fn transcript_bytes(&self) -> [u8; 32] {
+        let mut transcript = [0u8; 32];
+        hex::decode_to_slice(&self.transcript, &mut transcript)
+            .expect("Invalid transcript hash in the verification cache");
+        transcript
+    }
+
+    /// The [tau]_1 point after the last verified proof
+    pub fn g1_point(&self) -> Result<G1Affine, SrsError> {
+        let bytes = hex::decode(&self.g1_point).map_err(|_| {
+            SrsError::Deserialization("Invalid point in the verification cache".to_string())
+        })?;
+        read_g1_point(&bytes)
+    }
+}
+
+/// Writes the given value to the file at the given path, as pretty JSON
+pub(crate) fn write_json(path: &Path, value: &impl Serialize) -> Result<(), SrsError> {
+    serde_json::to_writer_pretty(create_file(path)?, value).map_err(|err| SrsError::Io {
+        action: "write",
+        path: path.to_path_buf(),
+        source: err.into(),
+    })
+}
+
+/// Computes SHA-256(transcript || digest)
+pub fn extend_transcript(transcript: &[u8; 32], digest: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(transcript);
+    hasher.update(digest);
+    hasher.finalize().into()
+}
+
+impl VerificationCache {
+    /// Loads the cache from the given file. A missing or malformed cache is
+    /// treated as empty
+    pub fn load(path: &Path) -> Self {
+        let Ok(bytes) = fs::read(path) else {
+            return Self::default();
+        };
+        serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+            eprintln!("Ignoring malformed verification cache {path:?}: {err}");
+            Self::default()
+        })
+    }
+
+    /// Writes the cache to the given file
+    pub fn save(&self, path: &Path) -> Result<(), SrsError> {
+        write_json(path, self)
+    }
+
+    /// The recorded verification result of the file with the given digest
+    pub fn get(&self, digest: &[u8; 32]) -> Option<bool> {
+        self.results.get(&hex::encode(digest)).copied()
+    }
+
+    /// Records the verification result of the file with the given digest
+    pub fn insert(&mut self, digest: &[u8; 32], result: bool) {
+        self.results.insert(hex::encode(digest), result);
+    }
+}
+
+/// Computes the SHA-256 digest of the file at the given path (or of the
+/// standard input if the path is `-`), streaming its content
+pub fn sha256_file(path: &Path) -> Result<[u8; 32], SrsError> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut open_input(path)?, &mut hasher).map_err(|source| SrsError::Io {
+        action: "read",
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let digest = hasher.finalize().into();
+    audit_sha256(path, &digest);
+    Ok(digest)
+}
+
+/// Extension appended to the name of an artifact (SRS, update proof, Lagrange
+/// basis) for its checksum sidecar
+pub const SIDECAR_EXTENSION: &str = "sha256";
+
+/// Path of the checksum sidecar of the artifact stored at the given path
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(SIDECAR_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Writes the checksum sidecar of the artifact stored at the given path, with
+/// its SHA-256 digest, in the format of `sha256sum`
+pub fn write_sidecar(path: &Path, digest: &[u8; 32]) -> Result<(), SrsError> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let sidecar_path = sidecar_path(path);
+    fs::write(&sidecar_path, sidecar_content(&file_name, digest)).map_err(|source| SrsError::Io {
+        action: "write",
+        path: sidecar_path,
+        source,
+    })
+}
+
+/// Content of the checksum sidecar of an artifact with the given file name
+/// and digest
+pub fn sidecar_content(file_name: &str, digest: &[u8; 32]) -> String {
+    format!("{}  {file_name}\n", hex::encode(digest))
+}
+
+/// The digest recorded in the given content of a checksum sidecar, `None` if
+/// it is malformed
+pub fn parse_sidecar(content: &[u8]) -> Option<[u8; 32]> {
+    let mut digest = [0u8; 32];
+    String::from_utf8_lossy(content)
+        .split_whitespace()
+        .next()
+        .and_then(|hex_digest| hex::decode_to_slice(hex_digest, &mut digest).ok())?;
+    Some(digest)
+}
+
+/// The digest recorded in the checksum sidecar of the artifact stored at the
+/// given path, if there is one. Fails if the sidecar is malformed
+pub fn read_sidecar(path: &Path) -> Result<Option<[u8; 32]>, SrsError> {
+    if is_stdin(path) {
+        return Ok(None);
+    }
+    let sidecar_path = sidecar_path(path);
+    let Ok(content) = read_audited(&sidecar_path) else {
+        return Ok(None);
+    };
+    let digest = parse_sidecar(&content).ok_or_else(|| {
+        SrsError::Deserialization(format!("Malformed checksum sidecar {sidecar_path:?}"))
+    })?;
+    Ok(Some(digest))
+}
+
+/// Checks the digest of the artifact stored at the given path against its
+/// checksum sidecar, if there is one. Fails if they do not match
+pub fn check_sidecar_digest(path: &Path, digest: &[u8; 32]) -> Result<(), SrsError> {
+    if let Some(expected) = read_sidecar(path)? {
+        audit_check(
+            "checksum-sidecar",
+            json!({
+                "path": path.display().to_string(),
+                "sha256": hex::encode(digest),
+                "expected": hex::encode(expected),
+            }),
+            *digest == expected,
+        );
+        if *digest != expected {
+            return Err(SrsError::Verification(format!(
+                "{path:?} does not match its checksum sidecar {:?}: the file is corrupted",
+                sidecar_path(path)
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Same as [check_sidecar_digest], hashing the file first if it has a
+/// sidecar. This is meant to run before any expensive parsing of the file
+pub fn check_sidecar(path: &Path) -> Result<(), SrsError> {
+    if read_sidecar(path)?.is_some() {
+        check_sidecar_digest(path, &sha256_file(path)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod cache_tests {
+    use std::{fs, path::Path};
+
+    use sha2::{Digest, Sha256};
+
+    use crate::{
+        cache::{check_sidecar, read_sidecar, sidecar_path, write_sidecar},
+        error::SrsError,
+    };
+
+    #[test]
+    fn sidecars_catch_corruption() {
+        let path = Path::new("/tmp/test_sidecar_artifact.bin");
+        fs::write(path, b"artifact").unwrap();
+        let _ = fs::remove_file(sidecar_path(path));
+        assert_eq!(read_sidecar(path).unwrap(), None);
+        check_sidecar(path).unwrap();
+
+        let digest: [u8; 32] = Sha256::digest(b"artifact").into();
+        write_sidecar(path, &digest).unwrap();
+        assert_eq!(
+            sidecar_path(path),
+            Path::new("/tmp/test_sidecar_artifact.bin.sha256")
+        );
+        assert_eq!(read_sidecar(path).unwrap(), Some(digest));
+        check_sidecar(path).unwrap();
+
+        fs::write(path, b"artifacT").unwrap();
+        assert!(matches!(
+            check_sidecar(path),
+            Err(SrsError::Verification(_))
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cooperative cancellation of long-running operations.
+//!
+//! Interrupting the process (SIGINT or SIGTERM) only raises a flag: the heavy
+//! loops check it at safe points (between chunks of points) and stop, so that
+//! the caller can remove incomplete outputs and erase secrets (see [Secret])
+//! before exiting. A second signal exits immediately, once the live secrets
+//! are wiped.
+
+use std::{
+    ops::Deref,
+    sync::{
+        atomic::{compiler_fence, AtomicBool, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// The live [Secret]s, by the address of their value, with the function
+/// wiping it
+static LIVE_SECRETS: Mutex<Vec<(usize, unsafe fn(usize))>> = Mutex::new(Vec::new());
+
+fn live_secrets() -> MutexGuard<'static, Vec<(usize, unsafe fn(usize))>> {
+    LIVE_SECRETS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Installs a SIGINT/SIGTERM handler that requests the cancellation of the
+/// running operation. A second signal exits immediately, after wiping the
+/// live secrets (see [wipe_live_secrets]).
+pub fn install_ctrlc_handler() {
+    ctrlc::set_handler(|| {
+        if CANCELLED.swap(true, Ordering::SeqCst) {
+            wipe_live_secrets();
+            std::process::exit(130);
+        }
+        eprintln!(
+            "\nInterrupted! Stopping at the next safe point (press Ctrl-C again to exit immediately)..."
+        );
+    })
+    .expect("Failed to install the Ctrl-C handler");
+}
+
+/// Requests the cancellation of the running operation
+pub fn cancel() {
+    CANCELLED.store(true, Ordering::SeqCst);
+}
+
+/// Whether the cancellation of the running operation has been requested
+pub fn is_cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
+
+/// Overwrites `secret` with the non-secret value `blank`, in a way that cannot
+/// be optimized away by the compiler
+pub fn wipe<T>(secret: &mut T, blank: T) {
+    // SAFETY: `secret` is a valid, aligned and exclusive reference; the old
+    // value is overwritten without being dropped, which is fine for the plain
+    // data (scalars, RNG states) this is used on
+    unsafe { std::ptr::write_volatile(secret, blank) };
+    compiler_fence(Ordering::SeqCst);
+}
+
+/// Wipes every live [Secret], e.g. before exiting without unwinding. The
+/// process must exit right after, as the secrets may be in use
+pub fn wipe_live_secrets() {
+    for &(address, wipe_at) in live_secrets().iter() {
+        // SAFETY: the address is the one of the (boxed) value of a live
+        // secret, registered with the wiping function of its type, and only
+        // unregistered when the secret is dropped
+        unsafe { wipe_at(address) };
+    }
+}
+
+/// The value of a [Secret] and its blank value, at a fixed address
+struct SecretCell<T: Copy> {
+    value: T,
+    blank: T,
+}
+
+/// Wipes the [SecretCell] at the given address
+///
+/// # Safety
+///
+/// The address must be the one of a live `SecretCell<T>`
+unsafe fn wipe_cell<T: Copy>(address: usize) {
+    let cell = &mut *(address as *mut SecretCell<T>);
+    wipe(&mut cell.value, cell.blank);
+}
+
+/// A secret value, overwritten with a non-secret blank value when dropped (or
+/// explicitly wiped). The value is boxed and registered for
+/// [wipe_live_secrets] while the secret is alive
+pub struct Secret<T: Copy> {
+    cell: Box<SecretCell<T>>,
+}
+
+impl<T: Copy> Secret<T> {
+    /// Wraps `value`, which will be overwritten with `blank` when wiped
+    pub fn new(value: T, blank: T) -> Self {
+        let secret = Secret {
+            cell: Box::new(SecretCell { value, blank }),
+        };
+        live_secrets().push((secret.address(), wipe_cell::<T>));
+        secret
+    }
+
+    fn address(&self) -> usize {
+        &*self.cell as *const SecretCell<T> as usize
+    }
+
+    /// Overwrites the secret value with the blank value
+    pub fn wipe(&mut self) {
+        wipe(&mut self.cell.value, self.cell.blank);
+    }
+}
+
+impl<T: Copy> Deref for Secret<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.cell.value
+    }
+}
+
+impl<T: Copy> Drop for Secret<T> {
+    fn drop(&mut self) {
+        // Unregistered first, so that it is not wiped while being freed
+        let address = self.address();
+        live_secrets().retain(|&(live, _)| live != address);
+        self.wipe();
+    }
+}
+
+#[cfg(test)]
+mod cancel_tests {
+    use blstrs::Scalar;
+    use halo2curves::ff::Field;
+    use rand_core::OsRng;
+
+    use crate::cancel::Secret;
+
+    #[test]
+    fn secret_is_wiped() {
+        let nu = Scalar::random(OsRng);
+        let mut secret = Secret::new(nu, Scalar::ZERO);
+        assert_eq!(*secret, nu);
+
+        secret.wipe();
+        assert_eq!(*secret, Scalar::ZERO);
+    }
+}
@@ -14,24 +14,45 @@
 // limitations under the License.
 
 use std::{
-    io::{Read, Write},
+    fs,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    ops::Range,
     path::Path,
+    time::{Duration, Instant},
 };
 
-use blstrs::{pairing, G1Affine, G2Affine, Scalar};
+use blake2::Blake2b512;
+use blstrs::{pairing, G1Affine, G1Projective, G2Affine, Scalar};
 use halo2curves::{
-    ff::Field,
-    group::{prime::PrimeCurveAffine, Curve},
-    msm::msm_best,
+    ff::{Field, FromUniformBytes},
+    group::{prime::PrimeCurveAffine, Curve, Group},
     serde::SerdeObject,
 };
 use rand_core::OsRng;
 use rayon::prelude::*;
+use serde_json::json;
+use sha2::{Digest, Sha256};
 
+#[cfg(feature = "mmap")]
+use crate::reader::map_file;
 use crate::{
+    audit::audit_check,
+    cache::{check_sidecar, check_sidecar_digest, write_sidecar},
+    cancel::is_cancelled,
+    error::SrsError,
+    formats::read_barretenberg_transcripts,
+    journal::journal_output,
+    mem::{g1_layout_is_raw, huge_vec, read_g1_points_in_place},
+    msm::MsmConfig,
+    progress::{ProgressSink, TerminalProgress},
+    release::{read_release_srs, SrsEncoding},
     schnorr::UpdateProof,
+    sniff::{sniff_format, FileFormat},
+    state::CeremonyContext,
+    stream::{stream_reader, stream_writer},
     utils::{
-        create_file, initialize_progress_bar, open_file, powers, read_g1_point, read_g2_point,
+        compare_bytes, create_file, decode_g1_point, decode_g2_point, fill_buffer, is_reduced,
+        is_stdin, open_file, open_input, powers, read_g2_point, TranscriptHasher,
     },
 };
 
@@ -41,6 +62,44 @@ pub const G1_SIZE: usize = 96;
 pub const G2_SIZE: usize = 192;
 pub const SCALAR_SIZE: usize = 32;
 
+// Size of compressed G1 and G2 points
+pub const G1_COMPRESSED_SIZE: usize = 48;
+pub const G2_COMPRESSED_SIZE: usize = 96;
+
+// Number of G1 points re-randomized at once by each worker during an update
+pub const UPDATE_CHUNK_SIZE: usize = 1 << 12;
+
+// Number of G1 points held in memory at once by a streaming update (~96 MB)
+pub const UPDATE_WINDOW_SIZE: usize = 1 << 20;
+
+// Number of G1 points decoded at once when an SRS is read (~6 MB)
+pub const READ_CHUNK_SIZE: usize = 1 << 16;
+
+// Number of pairing checks of an exhaustive verification between two
+// checkpoints
+pub const EXHAUSTIVE_CHUNK_SIZE: usize = 1 << 12;
+
+// Interval between two checkpoints of a resumable update
+pub const UPDATE_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(60);
+
+// Number of chunks in which each phase of the batched structure check (the
+// powers of the challenge and the two MSMs) is computed, to report its progress
+pub const STRUCTURE_PROGRESS_STEPS: usize = 16;
+
+/// Domain tag of the challenge of the batched structure check derived from the
+/// SRS (see [structure_challenge])
+const STRUCTURE_CHALLENGE_DOMAIN: &[u8] = b"midnight-srs/structure-challenge";
+
+/// Challenge of the batched structure check derived (Fiat-Shamir style) from
+/// the SHA-256 digest of the SRS file, instead of the local RNG, so that
+/// anyone can reproduce a published verification
+pub fn structure_challenge(srs_sha256: &[u8; 32]) -> Scalar {
+    let mut hasher = Blake2b512::init();
+    hasher.absorb(STRUCTURE_CHALLENGE_DOMAIN);
+    hasher.absorb(srs_sha256);
+    Scalar::from_uniform_bytes(&hasher.squeeze())
+}
+
 #[derive(Clone, Debug, PartialEq)]
 #[allow(clippy::upper_case_acronyms)]
 pub struct SRS {
@@ -52,103 +111,1112 @@ pub struct SRS {
 
 // Necessary functionality for Ceremony
 impl SRS {
-    /// Verifies the SRS structure. Panics if the structure is not correct
-    pub fn verify_structure(&self) {
-        assert!(
-            self.g1s.par_iter().all(|&p| p != G1Affine::identity()),
-            "Some G1 point is zero"
-        );
+    /// Verifies the SRS structure, returning why it is not correct
+    pub fn verify_structure(&self) -> Result<(), SrsError> {
+        self.verify_structure_with(&MsmConfig::default(), &TerminalProgress::default())
+    }
 
-        assert_eq!(self.g1s[0], G1Affine::generator(), "Expected G1 generator");
-        assert_eq!(self.g2s[0], G2Affine::generator(), "Expected G2 generator");
+    /// Verifies the SRS structure, performing the batched MSMs with the given
+    /// configuration and reporting into `progress`
+    pub fn verify_structure_with(
+        &self,
+        msm: &MsmConfig,
+        progress: &dyn ProgressSink,
+    ) -> Result<(), SrsError> {
+        self.verify_structure_with_challenge(&Scalar::random(OsRng), 1, msm, progress)
+    }
 
-        assert_ne!(self.g2s[1], G2Affine::identity(), "Scaled G2 point is zero");
-        assert_ne!(self.g2s[1], self.g2s[0], "Scaled G2 point is the generator");
+    /// Same as [SRS::verify_structure_with], batching the G1 points with the
+    /// powers of the given challenge (e.g. a [structure_challenge]) instead
+    /// of a random one, in `nr_chunks` independent batched pairing checks.
+    ///
+    /// Each chunk of consecutive pairs of G1 points is batched with the same
+    /// powers 1, r, r^2, ..., so that only the powers for one chunk are held
+    /// in memory, for two more pairings per chunk (see
+    /// [crate::mem::structure_chunks])
+    pub fn verify_structure_with_challenge(
+        &self,
+        r: &Scalar,
+        nr_chunks: usize,
+        msm: &MsmConfig,
+        progress: &dyn ProgressSink,
+    ) -> Result<(), SrsError> {
+        assert!(
+            nr_chunks > 0,
+            "The structure check needs at least one chunk"
+        );
+        self.check_generators()?;
 
         // Check that the SRS has the correct structure. Instead of doing N individual
         // pairing checks, batch the G1 points via a random linear combination and do
-        // only one pairing check
-        let r_powers = powers(&Scalar::random(OsRng), self.g1s.len() - 1);
-        let batched_lhs_g1 = msm_best(&r_powers, &self.g1s[..self.g1s.len() - 1]).to_affine();
-        let batched_rhs_g1 = msm_best(&r_powers, &self.g1s[1..]).to_affine();
+        // only one pairing check (per chunk). Each phase reports its progress, as the
+        // MSMs take longer than reading the SRS
+        let n = self.g1s.len() - 1;
+        let chunk_size = n.div_ceil(nr_chunks).max(1);
+        let chunks: Vec<Range<usize>> = (0..n)
+            .step_by(chunk_size)
+            .map(|start| start..(start + chunk_size).min(n))
+            .collect();
+        progress.start(
+            chunk_size.min(n),
+            "Computing the powers of the random challenge",
+        );
+        let r_powers = powers_with_progress(r, chunk_size.min(n), progress);
+        progress.finish();
 
-        assert_eq!(
-            pairing(&batched_lhs_g1, &self.g2s[1]),
-            pairing(&batched_rhs_g1, &self.g2s[0])
+        let batch = |offset: usize, phase: &str| -> Vec<G1Projective> {
+            progress.start(n, phase);
+            let batched = chunks
+                .iter()
+                .map(|chunk| {
+                    let bases = &self.g1s[chunk.start + offset..chunk.end + offset];
+                    msm_with_progress(msm, &r_powers[..chunk.len()], bases, progress)
+                })
+                .collect();
+            progress.finish();
+            batched
+        };
+        let batched_lhs_g1 = batch(0, "Batching the G1 points (MSM 1/2)");
+        let batched_rhs_g1 = batch(1, "Batching the G1 points (MSM 2/2)");
+
+        let checks: Vec<_> = (batched_lhs_g1.into_iter().zip(batched_rhs_g1))
+            .map(|(lhs, rhs)| [(lhs, self.g2s[1]), (rhs, self.g2s[0])])
+            .collect();
+        let failed = pairing_checks(&checks, progress);
+        audit_check(
+            "verify-structure",
+            json!({
+                "nr_g1_points": self.g1s.len(),
+                "tau_g1": hex::encode(self.g1s[1].to_compressed()),
+                "tau_g2": hex::encode(self.g2s[1].to_compressed()),
+                "challenge": hex::encode(r.to_bytes_be()),
+                "nr_chunks": chunks.len(),
+            }),
+            failed.is_none(),
+        );
+        match failed {
+            None => Ok(()),
+            Some(_) if nr_chunks == 1 => Err(SrsError::Verification(
+                "The batched pairing check fails".to_string(),
+            )),
+            Some(i) => Err(SrsError::Verification(format!(
+                "The batched pairing check fails (chunk of the G1 points {}..{})",
+                chunks[i].start,
+                chunks[i].end + 1
+            ))),
+        }
+    }
+
+    /// Verifies the structure of the SRS stored in the given file, as
+    /// [SRS::verify_structure_with] does, but streaming it by windows of
+    /// `window_size` G1 points so that it is never fully loaded. The points
+    /// may be in any [SrsEncoding], detected as [SRS::read_from_file] does.
+    /// Returns its number of G1 points
+    ///
+    /// With S = sum_i r^i [tau^i]_1, accumulated window by window, the batched
+    /// points are S - r^{n-1} [tau^{n-1}]_1 and (S - [1]_1) / r.
+    pub fn verify_structure_file(
+        path: &Path,
+        window_size: usize,
+        msm: &MsmConfig,
+        progress: &dyn ProgressSink,
+    ) -> Result<usize, SrsError> {
+        Self::verify_structure_file_with_challenge(
+            path,
+            window_size,
+            &Scalar::random(OsRng),
+            msm,
+            progress,
         )
     }
 
+    /// Same as [SRS::verify_structure_file], with the given challenge (see
+    /// [SRS::verify_structure_with_challenge])
+    pub fn verify_structure_file_with_challenge(
+        path: &Path,
+        window_size: usize,
+        r: &Scalar,
+        msm: &MsmConfig,
+        progress: &dyn ProgressSink,
+    ) -> Result<usize, SrsError> {
+        assert!(window_size > 0, "The verification window cannot be empty");
+        let format = sniff_format(path)?;
+        let (encoding, n) = match (format.srs_encoding(), format.nr_g1_points()) {
+            (Some(encoding), Some(n)) if n >= 2 => (encoding, n),
+            _ => (SrsEncoding::Raw, nr_g1_points(path)?),
+        };
+        let (g1_size, g2_size) = encoding.point_sizes();
+        let read_error = SrsError::io("read", path);
+        let mut reader = stream_reader(path)?;
+        let mut hasher = Sha256::new();
+        progress.start(n, "Verifying the structure of the SRS");
+
+        let (sum, last_g1_point) = batch_g1_points(
+            path,
+            &mut reader,
+            encoding,
+            0..n,
+            r,
+            window_size,
+            msm,
+            &mut hasher,
+            progress,
+        )?;
+
+        let mut g2_bytes = vec![0u8; 2 * g2_size];
+        reader.read_exact(&mut g2_bytes).map_err(read_error)?;
+        hasher.update(&g2_bytes);
+        check_sidecar_digest(path, &hasher.finalize().into())?;
+
+        let mut tau_g1_bytes = vec![0u8; g1_size];
+        let mut file = open_file(path)?;
+        file.seek(SeekFrom::Start(g1_size as u64))
+            .and_then(|_| file.read_exact(&mut tau_g1_bytes))
+            .map_err(read_error)?;
+        let tau_g1 = encoding
+            .decode_g1(&tau_g1_bytes)
+            .ok_or_else(|| SrsError::Deserialization("Failed to read G1 point".to_string()))?;
+        let g2s = if encoding == SrsEncoding::Raw {
+            // Decoded without validation, so that an invalid point is
+            // diagnosed by the checks of the G2 points
+            [
+                G2Affine::from_raw_bytes_unchecked(&g2_bytes[..G2_SIZE]),
+                G2Affine::from_raw_bytes_unchecked(&g2_bytes[G2_SIZE..]),
+            ]
+        } else {
+            // The other encodings only decode canonical points of the G2
+            // subgroup
+            let decode_g2 = |bytes: &[u8]| {
+                encoding.decode_g2(bytes).ok_or_else(|| {
+                    SrsError::Deserialization(format!("Invalid G2 point in {path:?}"))
+                })
+            };
+            [
+                decode_g2(&g2_bytes[..g2_size])?,
+                decode_g2(&g2_bytes[g2_size..])?,
+            ]
+        };
+        check_g2_points(
+            &tau_g1,
+            &g2s,
+            (encoding == SrsEncoding::Raw).then_some(&g2_bytes[..]),
+        )?;
+
+        progress.finish();
+
+        let r_inv = r.invert().unwrap();
+        let r_last = r.pow_vartime([n as u64 - 1]);
+        let batched_lhs_g1 = sum - last_g1_point * r_last;
+        let batched_rhs_g1 = (sum - G1Projective::generator()) * r_inv;
+        let passed = pairing_checks(
+            &[[(batched_lhs_g1, g2s[1]), (batched_rhs_g1, g2s[0])]],
+            progress,
+        )
+        .is_none();
+        audit_check(
+            "verify-structure",
+            json!({
+                "path": path.display().to_string(),
+                "nr_g1_points": n,
+                "tau_g2": hex::encode(g2s[1].to_compressed()),
+                "challenge": hex::encode(r.to_bytes_be()),
+            }),
+            passed,
+        );
+        if !passed {
+            return Err(SrsError::Verification(
+                "The batched pairing check fails".to_string(),
+            ));
+        }
+        Ok(n)
+    }
+
+    /// Verifies the SRS structure with one pairing check
+    /// e([tau^{i+1}]_1, [1]_2) = e([tau^i]_1, [tau]_2) per G1 point, which
+    /// (contrary to the batched check) does not depend on local randomness.
+    ///
+    /// The checks start at the `start`-th pair of consecutive points, and
+    /// `checkpoint` is called with the number of pairs verified so far after
+    /// every [EXHAUSTIVE_CHUNK_SIZE] of them, so that an interrupted
+    /// verification can be resumed. Returns false if it was cancelled (see
+    /// [crate::cancel])
+    pub fn verify_structure_exhaustive(
+        &self,
+        start: usize,
+        mut checkpoint: impl FnMut(usize) -> Result<(), SrsError>,
+        progress: &dyn ProgressSink,
+    ) -> Result<bool, SrsError> {
+        self.check_generators()?;
+
+        let nr_checks = self.g1s.len() - 1;
+        progress.start(nr_checks, "Checking the pairings of the SRS one by one");
+        progress.inc(start.min(nr_checks));
+
+        let mut verified = start;
+        while verified < nr_checks {
+            if is_cancelled() {
+                progress.finish();
+                return Ok(false);
+            }
+            let end = (verified + EXHAUSTIVE_CHUNK_SIZE).min(nr_checks);
+            let failure = (verified..end).into_par_iter().find_first(|&i| {
+                let valid =
+                    pairing(&self.g1s[i + 1], &self.g2s[0]) == pairing(&self.g1s[i], &self.g2s[1]);
+                progress.inc(1);
+                !valid
+            });
+            if let Some(i) = failure {
+                audit_check(
+                    "verify-structure-exhaustive",
+                    json!({ "nr_g1_points": self.g1s.len(), "failed_pair": i }),
+                    false,
+                );
+                return Err(SrsError::Verification(format!(
+                    "Pairing check failed between G1 points {i} and {}",
+                    i + 1
+                )));
+            }
+            verified = end;
+            checkpoint(verified)?;
+        }
+        audit_check(
+            "verify-structure-exhaustive",
+            json!({ "nr_g1_points": self.g1s.len(), "start": start }),
+            true,
+        );
+        progress.finish();
+        Ok(true)
+    }
+
+    /// Checks that no G1 point is zero, that the SRS starts with the G1
+    /// generator and that its G2 points are sound (see [check_g2_points])
+    fn check_generators(&self) -> Result<(), SrsError> {
+        if !self.g1s.par_iter().all(|&p| p != G1Affine::identity()) {
+            return Err(SrsError::Verification("Some G1 point is zero".to_string()));
+        }
+        if self.g1s[0] != G1Affine::generator() {
+            return Err(SrsError::Verification("Expected G1 generator".to_string()));
+        }
+        check_g2_points(&self.g1s[1], &self.g2s, None)
+    }
+
     /// Updates the given SRS (mutating it) with the given toxic waste `nu`,
-    /// returns a proof of validity of the update
-    pub fn update(&mut self, nu: &Scalar) -> UpdateProof {
-        let n = self.g1s.len();
-        let pb = initialize_progress_bar(n, Some(String::from("Adding randomness to the SRS")));
+    /// returns a proof of validity of the update for the given ceremony
+    pub fn update(&mut self, ctx: &CeremonyContext, nu: &Scalar) -> UpdateProof {
+        self.update_with_progress(ctx, nu, &TerminalProgress::default())
+    }
+
+    /// Same as [SRS::update], reporting into `progress`
+    pub fn update_with_progress(
+        &mut self,
+        ctx: &CeremonyContext,
+        nu: &Scalar,
+        progress: &dyn ProgressSink,
+    ) -> UpdateProof {
+        progress.start(self.g1s.len(), "Adding randomness to the SRS");
 
         let old_g1_point = self.g1s[1];
+        let input_srs_hash = self.sha256();
 
         // Update G1 points with fresh random scalar and compute
         // [nu * tau]_1, [nu^2 * tau^2]_1, ..., [nu^{N-1} * tau^{N-1}]_1
-        self.g1s
-            .par_iter_mut()
-            .zip(powers(nu, n).par_iter())
-            .inspect(|_| pb.inc(1))
-            .for_each(|(point, power)| {
-                *point = (*point * power).to_affine();
-            });
+        rerandomize(&mut self.g1s, nu, 0, progress);
 
-        pb.finish_and_clear();
+        progress.finish();
 
+        let old_g2_point = self.g2s[1];
         self.g2s[1] = (self.g2s[1] * nu).to_affine();
 
-        UpdateProof::create(old_g1_point, self.g1s[1], nu)
+        UpdateProof::create(ctx, old_g1_point, self.g1s[1], nu)
+            .with_g2_update(old_g2_point, self.g2s[1])
+            .with_srs_hashes(input_srs_hash, self.sha256())
+    }
+
+    /// Updates the SRS stored at `old_path` with the given toxic waste `nu`,
+    /// writing the result to `new_path` and reporting into `progress`; returns
+    /// a proof of validity of the update for the given ceremony.
+    ///
+    /// Contrary to [SRS::update], the SRS is never fully loaded: only a window
+    /// of `window_size` G1 points is held in memory at any time. The points
+    /// may be in any [SrsEncoding], detected as [SRS::read_from_file] does;
+    /// the updated SRS is written raw.
+    ///
+    /// Cancellation (see [crate::cancel]) is checked between windows; if it is
+    /// requested, the incomplete output file is removed and `None` is returned.
+    pub fn update_file(
+        ctx: &CeremonyContext,
+        old_path: &Path,
+        new_path: &Path,
+        nu: &Scalar,
+        window_size: usize,
+        progress: &dyn ProgressSink,
+    ) -> Result<Option<UpdateProof>, SrsError> {
+        Self::update_file_resumable(ctx, old_path, new_path, nu, window_size, 0, None, progress)
+    }
+
+    /// Same as [SRS::update_file], resuming after the first `start` G1
+    /// points, already written (raw) to `new_path` with the same `nu`.
+    ///
+    /// With a `checkpoint`, the output file is synced to disk and
+    /// `checkpoint` is called with the number of G1 points written so far
+    /// every [UPDATE_CHECKPOINT_INTERVAL], so that an update interrupted by a
+    /// crash can be resumed from there. A cancelled update is then
+    /// checkpointed instead of removed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_file_resumable(
+        ctx: &CeremonyContext,
+        old_path: &Path,
+        new_path: &Path,
+        nu: &Scalar,
+        window_size: usize,
+        start: usize,
+        mut checkpoint: Option<&mut dyn FnMut(usize)>,
+        progress: &dyn ProgressSink,
+    ) -> Result<Option<UpdateProof>, SrsError> {
+        assert!(window_size > 0, "The update window cannot be empty");
+        check_sidecar(old_path)?;
+
+        let format = sniff_format(old_path)?;
+        let (encoding, n) = match (format.srs_encoding(), format.nr_g1_points()) {
+            (Some(encoding), Some(n)) if n >= 2 => (encoding, n),
+            _ => (SrsEncoding::Raw, nr_g1_points(old_path)?),
+        };
+        let (g1_size, g2_size) = encoding.point_sizes();
+        if start >= n {
+            return Err(SrsError::Verification(format!(
+                "Cannot resume the update of {old_path:?} after its last G1 point"
+            )));
+        }
+        let read_error = SrsError::io("read", old_path);
+        let write_error = SrsError::io("write", new_path);
+
+        let mut reader = stream_reader(old_path)?;
+        progress.start(n, "Adding randomness to the SRS");
+
+        // Both files are hashed on the fly, to be recorded in the proof
+        let mut input_hasher = Sha256::new();
+        let mut output_hasher = Sha256::new();
+
+        let mut writer: Box<dyn Write + Send> = if start == 0 {
+            stream_writer(new_path)?
+        } else {
+            // The points already written are hashed again, and the output is
+            // truncated after them in case the crash left a partial window
+            let input_prefix_len = (start * g1_size) as u64;
+            let prefix_len = (start * G1_SIZE) as u64;
+            io::copy(&mut (&mut reader).take(input_prefix_len), &mut input_hasher)
+                .map_err(read_error)?;
+            io::copy(
+                &mut open_file(new_path)?.take(prefix_len),
+                &mut output_hasher,
+            )
+            .map_err(SrsError::io("read", new_path))?;
+            journal_output(new_path);
+            let mut file = fs::OpenOptions::new()
+                .write(true)
+                .open(new_path)
+                .map_err(SrsError::io("open", new_path))?;
+            file.set_len(prefix_len)
+                .and_then(|_| file.seek(SeekFrom::End(0)))
+                .map_err(write_error)?;
+            progress.inc(start);
+            Box::new(BufWriter::new(file))
+        };
+
+        let decode_g1 = |bytes: &[u8]| {
+            encoding
+                .decode_g1(bytes)
+                .ok_or_else(|| SrsError::Deserialization("Failed to read G1 point".to_string()))
+        };
+        let decode_g2 = |bytes: &[u8]| {
+            encoding.decode_g2(bytes).ok_or_else(|| {
+                SrsError::Deserialization(format!("Invalid G2 point in {old_path:?}"))
+            })
+        };
+        let mut bytes = vec![0u8; window_size.min(n) * g1_size];
+        let mut old_g1_point = match start {
+            0 | 1 => G1Affine::identity(),
+            _ => {
+                let mut tau_g1_bytes = vec![0u8; g1_size];
+                let mut file = open_file(old_path)?;
+                file.seek(SeekFrom::Start(g1_size as u64))
+                    .and_then(|_| file.read_exact(&mut tau_g1_bytes))
+                    .map_err(read_error)?;
+                decode_g1(&tau_g1_bytes)?
+            }
+        };
+        let mut last_checkpoint = Instant::now();
+        let mut offset = start;
+        while offset < n {
+            let cancelled = is_cancelled();
+            if let Some(checkpoint) = checkpoint.as_mut() {
+                if cancelled || last_checkpoint.elapsed() >= UPDATE_CHECKPOINT_INTERVAL {
+                    writer.flush().map_err(write_error)?;
+                    open_file(new_path)?.sync_all().map_err(write_error)?;
+                    checkpoint(offset);
+                    last_checkpoint = Instant::now();
+                }
+            }
+            if cancelled {
+                progress.finish();
+                drop(writer);
+                if checkpoint.is_none() {
+                    fs::remove_file(new_path).map_err(SrsError::io("remove", new_path))?;
+                }
+                return Ok(None);
+            }
+
+            let len = window_size.min(n - offset);
+            let bytes = &mut bytes[..len * g1_size];
+            reader.read_exact(bytes).map_err(read_error)?;
+            input_hasher.update(&*bytes);
+
+            let mut window: Vec<G1Affine> = bytes
+                .par_chunks(g1_size)
+                .map(decode_g1)
+                .collect::<Result<_, _>>()?;
+            if offset <= 1 && offset + len > 1 {
+                old_g1_point = window[1 - offset];
+            }
+
+            rerandomize(&mut window, nu, offset, progress);
+
+            for g1_point in &window {
+                let bytes = g1_point.to_raw_bytes();
+                output_hasher.update(&bytes);
+                writer.write_all(&bytes).map_err(write_error)?;
+            }
+            offset += len;
+        }
+
+        progress.finish();
+
+        let mut g2_bytes = vec![0u8; g2_size];
+        reader.read_exact(&mut g2_bytes).map_err(read_error)?;
+        input_hasher.update(&g2_bytes);
+        let g2_generator = decode_g2(&g2_bytes)?.to_raw_bytes();
+        output_hasher.update(&g2_generator);
+        writer.write_all(&g2_generator).map_err(write_error)?;
+
+        reader.read_exact(&mut g2_bytes).map_err(read_error)?;
+        input_hasher.update(&g2_bytes);
+        let old_g2_point = decode_g2(&g2_bytes)?;
+        let new_g2_point = (old_g2_point * nu).to_affine();
+        output_hasher.update(new_g2_point.to_raw_bytes());
+        writer
+            .write_all(&new_g2_point.to_raw_bytes())
+            .and_then(|_| writer.flush())
+            .map_err(write_error)?;
+        let srs_hash = output_hasher.finalize().into();
+        write_sidecar(new_path, &srs_hash)?;
+
+        let new_g1_point = (old_g1_point * nu).to_affine();
+        Ok(Some(
+            UpdateProof::create(ctx, old_g1_point, new_g1_point, nu)
+                .with_g2_update(old_g2_point, new_g2_point)
+                .with_srs_hashes(input_hasher.finalize().into(), srs_hash),
+        ))
     }
 }
 
+/// Accumulates sum_j r^j [tau^j]_1 over the G1 points `range` of the SRS
+/// stored at `path`, read from `reader` (positioned at the start of the range)
+/// in the given encoding by windows of `window_size` points, and hashed into
+/// `hasher`. Checks that no point is zero, and that the first point of the SRS
+/// is the generator. Returns the sum and the last point of the range
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn batch_g1_points(
+    path: &Path,
+    reader: &mut impl Read,
+    encoding: SrsEncoding,
+    range: Range<usize>,
+    r: &Scalar,
+    window_size: usize,
+    msm: &MsmConfig,
+    hasher: &mut Sha256,
+    progress: &dyn ProgressSink,
+) -> Result<(G1Projective, G1Affine), SrsError> {
+    assert!(!range.is_empty(), "The range of G1 points cannot be empty");
+    // r^offset, for the window starting at offset
+    let mut r_offset = r.pow_vartime([range.start as u64]);
+    let mut sum = G1Projective::identity();
+    let mut last_g1_point = G1Affine::identity();
+
+    let (g1_size, _) = encoding.point_sizes();
+    let mut bytes = vec![0u8; window_size.min(range.len()) * g1_size];
+    let mut offset = range.start;
+    while offset < range.end {
+        let len = window_size.min(range.end - offset);
+        let bytes = &mut bytes[..len * g1_size];
+        reader
+            .read_exact(bytes)
+            .map_err(SrsError::io("read", path))?;
+        hasher.update(&*bytes);
+
+        let window: Vec<G1Affine> = bytes
+            .par_chunks(g1_size)
+            .map(|bytes| encoding.decode_g1(bytes))
+            .collect::<Option<_>>()
+            .ok_or_else(|| SrsError::Deserialization("Failed to read G1 point".to_string()))?;
+        if !window.par_iter().all(|&p| p != G1Affine::identity()) {
+            return Err(SrsError::Verification("Some G1 point is zero".to_string()));
+        }
+        if offset == 0 && window[0] != G1Affine::generator() {
+            return Err(SrsError::Verification("Expected G1 generator".to_string()));
+        }
+
+        let coeffs: Vec<Scalar> = powers(r, len).iter().map(|p| p * r_offset).collect();
+        sum += msm.msm(&coeffs, &window);
+        r_offset = coeffs[len - 1] * r;
+        last_g1_point = window[len - 1];
+
+        offset += len;
+        progress.inc(len);
+    }
+    Ok((sum, last_g1_point))
+}
+
+/// Returns n powers of the given scalar, as [powers] does, computed in
+/// parallel in [STRUCTURE_PROGRESS_STEPS] chunks (reporting one step per power)
+fn powers_with_progress(s: &Scalar, n: usize, progress: &dyn ProgressSink) -> Vec<Scalar> {
+    let chunk_size = n.div_ceil(STRUCTURE_PROGRESS_STEPS).max(1);
+    let starts: Vec<usize> = (0..n).step_by(chunk_size).collect();
+    starts
+        .into_par_iter()
+        .flat_map_iter(|start| {
+            let len = chunk_size.min(n - start);
+            let s_start = s.pow_vartime([start as u64]);
+            let chunk: Vec<Scalar> = powers(s, len).iter().map(|p| p * s_start).collect();
+            progress.inc(len);
+            chunk
+        })
+        .collect()
+}
+
+/// MSM of the given scalars and points, computed in [STRUCTURE_PROGRESS_STEPS]
+/// chunks (reporting one step per point)
+fn msm_with_progress(
+    msm: &MsmConfig,
+    coeffs: &[Scalar],
+    bases: &[G1Affine],
+    progress: &dyn ProgressSink,
+) -> G1Projective {
+    let chunk_size = bases.len().div_ceil(STRUCTURE_PROGRESS_STEPS).max(1);
+    coeffs
+        .chunks(chunk_size)
+        .zip(bases.chunks(chunk_size))
+        .map(|(coeffs, bases)| {
+            let sum = msm.msm(coeffs, bases);
+            progress.inc(bases.len());
+            sum
+        })
+        .sum()
+}
+
+/// Checks that e(a_1, b_1) = e(a_2, b_2) for each of the given pairs [(a_1,
+/// b_1), (a_2, b_2)], in parallel, reporting each pairing as a step of its own
+/// phase. Returns the index of the first failing check, if any
+fn pairing_checks(
+    checks: &[[(G1Projective, G2Affine); 2]],
+    progress: &dyn ProgressSink,
+) -> Option<usize> {
+    progress.start(2 * checks.len(), "Computing the pairings");
+    let results: Vec<bool> = checks
+        .par_iter()
+        .map(|pairs| {
+            let [lhs, rhs] = pairs.map(|(a, b)| {
+                let result = pairing(&a.to_affine(), &b);
+                progress.inc(1);
+                result
+            });
+            lhs == rhs
+        })
+        .collect();
+    progress.finish();
+    results.iter().position(|passed| !passed)
+}
+
+/// Checks the G2 points of an SRS whose [tau]_1 is given, decoded from
+/// `g2_bytes` if any. Each check is reported separately, so that a corrupted
+/// G2 tail is diagnosed precisely rather than by the batched pairing check:
+/// - "g2-encoding": the bytes are the canonical encoding of the points,
+/// - "g2-subgroup": the points are on the curve and in the G2 subgroup,
+/// - "g2-generators": the SRS has the G2 generator, and [tau]_2 is neither zero
+///   nor the generator,
+/// - "tau-consistency": e([tau]_1, [1]_2) = e([1]_1, [tau]_2).
+///
+/// Returns the first failed check
+pub fn check_g2_points(
+    tau_g1: &G1Affine,
+    g2s: &[G2Affine; 2],
+    g2_bytes: Option<&[u8]>,
+) -> Result<(), SrsError> {
+    let fail = |msg: &str| Err(SrsError::Verification(msg.to_string()));
+    let names = ["[1]_2", "[tau]_2"];
+    let points = json!({
+        "one_g2": hex::encode(g2s[0].to_raw_bytes()),
+        "tau_g2": hex::encode(g2s[1].to_raw_bytes()),
+    });
+
+    if let Some(g2_bytes) = g2_bytes {
+        let invalid = (0..2).find(|&i| {
+            let bytes = &g2_bytes[i * G2_SIZE..(i + 1) * G2_SIZE];
+            !is_reduced(bytes) || g2s[i].to_raw_bytes() != bytes
+        });
+        audit_check("g2-encoding", points.clone(), invalid.is_none());
+        if let Some(i) = invalid {
+            return fail(&format!(
+                "The G2 point {} is not canonically encoded",
+                names[i]
+            ));
+        }
+    }
+
+    let invalid = (0..2)
+        .find(|&i| !bool::from(g2s[i].is_on_curve()) || !bool::from(g2s[i].is_torsion_free()));
+    audit_check("g2-subgroup", points.clone(), invalid.is_none());
+    if let Some(i) = invalid {
+        return fail(&format!(
+            "The G2 point {} is not in the G2 subgroup",
+            names[i]
+        ));
+    }
+
+    let passed =
+        g2s[0] == G2Affine::generator() && g2s[1] != G2Affine::identity() && g2s[1] != g2s[0];
+    audit_check("g2-generators", points, passed);
+    if g2s[0] != G2Affine::generator() {
+        return fail("Expected G2 generator");
+    }
+    if g2s[1] == G2Affine::identity() {
+        return fail("Scaled G2 point is zero");
+    }
+    if g2s[1] == g2s[0] {
+        return fail("Scaled G2 point is the generator");
+    }
+
+    let passed = pairing(tau_g1, &g2s[0]) == pairing(&G1Affine::generator(), &g2s[1]);
+    audit_check(
+        "tau-consistency",
+        json!({
+            "tau_g1": hex::encode(tau_g1.to_compressed()),
+            "tau_g2": hex::encode(g2s[1].to_compressed()),
+        }),
+        passed,
+    );
+    if !passed {
+        return fail("[tau]_2 does not match [tau]_1: e([tau]_1, [1]_2) != e([1]_1, [tau]_2)");
+    }
+    Ok(())
+}
+
+/// Whether `len` bytes is the size of an SRS (with at least one G1 point)
+fn is_srs_len(len: u64) -> bool {
+    let g2s_len = 2 * G2_SIZE as u64;
+    len > g2s_len && (len - g2s_len) % G1_SIZE as u64 == 0
+}
+
+/// Number of G1 points of the SRS stored in the given file, derived from its
+/// size. Fails if the size is not the one of an SRS
+pub(crate) fn nr_g1_points(path: &Path) -> Result<usize, SrsError> {
+    let file_len = fs::metadata(path)
+        .map_err(SrsError::io("read", path))?
+        .len() as usize;
+    if file_len < 2 * G1_SIZE + 2 * G2_SIZE || (file_len - 2 * G2_SIZE) % G1_SIZE != 0 {
+        return Err(SrsError::Deserialization(format!(
+            "Invalid SRS file size of {path:?}: {file_len} bytes"
+        )));
+    }
+    Ok((file_len - 2 * G2_SIZE) / G1_SIZE)
+}
+
+/// Whether the SRS stored at `small_path` is a truncation of the one stored at
+/// `large_path`: its G1 points are, byte for byte, the first G1 points of the
+/// larger SRS, and both have the same G2 points. Both files are streamed
+pub fn is_truncation_of(small_path: &Path, large_path: &Path) -> Result<bool, SrsError> {
+    let passed = compare_truncation(small_path, large_path)?;
+    audit_check(
+        "truncation",
+        json!({
+            "srs": small_path.display().to_string(),
+            "larger_srs": large_path.display().to_string(),
+        }),
+        passed,
+    );
+    Ok(passed)
+}
+
+fn compare_truncation(small_path: &Path, large_path: &Path) -> Result<bool, SrsError> {
+    let n = nr_g1_points(small_path)?;
+    if n > nr_g1_points(large_path)? {
+        return Ok(false);
+    }
+
+    let mut small = BufReader::new(open_file(small_path)?);
+    let mut large = BufReader::new(open_file(large_path)?);
+    let mut small_bytes = vec![0u8; UPDATE_CHUNK_SIZE.min(n) * G1_SIZE];
+    let mut large_bytes = small_bytes.clone();
+    let mut offset = 0;
+    while offset < n {
+        let len = UPDATE_CHUNK_SIZE.min(n - offset) * G1_SIZE;
+        small
+            .read_exact(&mut small_bytes[..len])
+            .map_err(SrsError::io("read", small_path))?;
+        large
+            .read_exact(&mut large_bytes[..len])
+            .map_err(SrsError::io("read", large_path))?;
+        if small_bytes[..len] != large_bytes[..len] {
+            return Ok(false);
+        }
+        offset += len / G1_SIZE;
+    }
+
+    compare_bytes(
+        small_path,
+        large_path,
+        -2 * G2_SIZE as i64,
+        -2 * G2_SIZE as i64,
+        2 * G2_SIZE,
+    )
+}
+
+/// Multiplies the i-th point of `points` by nu^{offset + i}.
+///
+/// Instead of materializing all powers of nu, each chunk of
+/// [UPDATE_CHUNK_SIZE] points starts from nu^{chunk offset} and keeps a
+/// running power
+fn rerandomize(points: &mut [G1Affine], nu: &Scalar, offset: usize, progress: &dyn ProgressSink) {
+    points
+        .par_chunks_mut(UPDATE_CHUNK_SIZE)
+        .enumerate()
+        .for_each(|(i, chunk)| {
+            let mut power = nu.pow_vartime([(offset + i * UPDATE_CHUNK_SIZE) as u64]);
+            for point in chunk.iter_mut() {
+                *point = (*point * &power).to_affine();
+                power *= nu;
+            }
+            progress.inc(chunk.len());
+        });
+}
+
 // (De-)Serialization functionality
 impl SRS {
-    pub fn write_to_file(&self, path: &Path) {
-        let mut file = create_file(path);
+    /// Writes the SRS to the given file, and returns the SHA-256 digest of
+    /// the file, hashed on the fly
+    pub fn write_to_file(&self, path: &Path) -> Result<[u8; 32], SrsError> {
+        self.write_to_file_as(path, SrsEncoding::Raw)
+    }
 
-        for g1_point in &self.g1s {
-            file.write_all(&g1_point.to_raw_bytes())
-                .expect("Cannot write to file");
+    /// Same as [SRS::write_to_file], with the points in the given encoding
+    /// (e.g. compressed, for a half-size file)
+    pub fn write_to_file_as(
+        &self,
+        path: &Path,
+        encoding: SrsEncoding,
+    ) -> Result<[u8; 32], SrsError> {
+        let mut writer = BufWriter::new(create_file(path)?);
+        let digest = self
+            .write_as(&mut writer, encoding)
+            .and_then(|digest| writer.flush().map(|_| digest))
+            .map_err(SrsError::io("write", path))?;
+        write_sidecar(path, &digest)?;
+        Ok(digest)
+    }
+
+    /// Writes the SRS to any writer, in the format of [SRS::write_to_file],
+    /// and returns the SHA-256 digest of the written bytes
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<[u8; 32]> {
+        self.write_as(writer, SrsEncoding::Raw)
+    }
+
+    /// Same as [SRS::write_to], with the points in the given encoding
+    pub fn write_as(&self, writer: &mut impl Write, encoding: SrsEncoding) -> io::Result<[u8; 32]> {
+        let mut hasher = Sha256::new();
+        for bytes in self.encoded_points(encoding) {
+            hasher.update(&bytes);
+            writer.write_all(&bytes)?;
         }
+        Ok(hasher.finalize().into())
+    }
 
-        file.write_all(&self.g2s[0].to_raw_bytes())
-            .expect("Cannot write to file");
-        file.write_all(&self.g2s[1].to_raw_bytes())
-            .expect("Cannot write to file");
+    /// The points of the SRS in the given encoding, in the order they are
+    /// written
+    fn encoded_points(&self, encoding: SrsEncoding) -> impl Iterator<Item = Vec<u8>> + '_ {
+        let points = self.g1s.iter().map(move |point| encoding.encode_g1(point));
+        let g2s = self.g2s.iter().map(move |point| encoding.encode_g2(point));
+        points.chain(g2s)
     }
 
-    pub fn read_from_file(path: &Path) -> Self {
-        let mut file = open_file(path);
-        let mut bytes = Vec::<u8>::new();
-        file.read_to_end(&mut bytes).expect("Cannot read to end");
+    /// SHA-256 digest of the SRS, as written by [SRS::write_to_file]
+    pub fn sha256(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for bytes in self.encoded_points(SrsEncoding::Raw) {
+            hasher.update(&bytes);
+        }
+        hasher.finalize().into()
+    }
+
+    /// Reads the SRS stored in the given file. The file is read by chunks
+    /// decoded into the points, so that its bytes are never held whole in
+    /// memory. To verify an SRS without loading its points, see
+    /// [SRS::verify_structure_file]
+    pub fn read_from_file(path: &Path) -> Result<Self, SrsError> {
+        Self::read_from_file_with_progress(path, &TerminalProgress::default())
+    }
+
+    /// Same as [SRS::read_from_file], reporting into `progress`. The SRS is
+    /// read from the standard input if the path is `-`. If the file has a
+    /// checksum sidecar, it is checked before a decoding error is reported.
+    ///
+    /// The format of the file is detected (see [crate::sniff]): SRS with
+    /// uncompressed or compressed points (see [SRS::write_to_file_as]) and
+    /// Barretenberg transcripts (the first one, `transcript00.dat`) are read
+    /// as well
+    pub fn read_from_file_with_progress(
+        path: &Path,
+        progress: &dyn ProgressSink,
+    ) -> Result<Self, SrsError> {
+        let format = match is_stdin(path) {
+            true => None,
+            false => Some(sniff_format(path)?),
+        };
+        match format {
+            Some(FileFormat::UncompressedSrs { .. }) => {
+                check_sidecar(path)?;
+                return read_release_srs(path, SrsEncoding::Uncompressed);
+            }
+            Some(FileFormat::CompressedSrs { .. }) => {
+                check_sidecar(path)?;
+                return read_release_srs(path, SrsEncoding::Compressed);
+            }
+            Some(FileFormat::BarretenbergTranscript { .. }) => {
+                return read_barretenberg_transcripts(path.parent().unwrap());
+            }
+            Some(format @ FileFormat::UpdateProof(_)) => {
+                return Err(SrsError::Deserialization(format!(
+                    "{path:?} is not an SRS but an {format}"
+                )))
+            }
+            _ => (),
+        }
+
+        // The points are parsed straight from the mapping of the file
+        #[cfg(feature = "mmap")]
+        if let Some(FileFormat::RawSrs { .. }) = format {
+            let mmap = map_file(path)?;
+            let (srs, digest) = Self::decode_mapped(&mmap, progress);
+            check_sidecar_digest(path, &digest)?;
+            return srs.map_err(SrsError::Deserialization);
+        }
+
+        let (reader, len): (Box<dyn Read>, _) = if is_stdin(path) {
+            (open_input(path)?, None)
+        } else {
+            let file = open_file(path)?;
+            let len = file.metadata().map_err(SrsError::io("open", path))?;
+            (Box::new(file), Some(len.len()))
+        };
+        let (srs, digest) = match len {
+            Some(len) if is_srs_len(len) && g1_layout_is_raw() => {
+                Self::read_in_place(reader, len, progress)
+            }
+            _ => Self::decode_stream(reader, len, progress),
+        }
+        .map_err(SrsError::io("read", path))?;
+        check_sidecar_digest(path, &digest)?;
+        srs.map_err(|err| match format {
+            Some(format) => {
+                SrsError::Deserialization(format!("{err} (detected format of {path:?}: {format})"))
+            }
+            None => SrsError::Deserialization(err),
+        })
+    }
 
+    /// Reads an SRS written with [SRS::write_to_file] from any reader (e.g.
+    /// an upload), reporting into `progress`. A reader failing is reported as
+    /// a truncated SRS
+    pub fn from_reader(reader: impl Read, progress: &dyn ProgressSink) -> Result<Self, SrsError> {
+        let (srs, _) = Self::decode_stream(reader, None, progress)
+            .map_err(|err| SrsError::Deserialization(format!("Cannot read the SRS: {err}")))?;
+        srs.map_err(SrsError::Deserialization)
+    }
+
+    /// Reads an SRS of `len` bytes (a valid size) from `reader`, its G1
+    /// points straight into their vector (see [read_g1_points_in_place]).
+    /// Fails on an I/O error, otherwise returns the SRS, or why it cannot be
+    /// decoded, with the SHA-256 digest of the bytes read
+    fn read_in_place(
+        mut reader: impl Read,
+        len: u64,
+        progress: &dyn ProgressSink,
+    ) -> io::Result<(Result<Self, String>, [u8; 32])> {
+        let n = (len as usize - 2 * G2_SIZE) / G1_SIZE;
+        progress.start(n, "Reading the existing SRS");
+        let mut hasher = Sha256::new();
+        let g1s = read_g1_points_in_place(&mut reader, n, |bytes| {
+            hasher.update(bytes);
+            progress.inc(bytes.len() / G1_SIZE);
+        })?;
+        progress.finish();
+
+        let mut g2_bytes = [0u8; 2 * G2_SIZE];
+        let read = fill_buffer(&mut reader, &mut g2_bytes)?;
+        hasher.update(&g2_bytes[..read]);
+        let trailing = fill_buffer(&mut reader, &mut [0u8])?;
+        let digest = hasher.finalize().into();
+
+        let srs = g1s.and_then(|g1s| {
+            if read < 2 * G2_SIZE || trailing > 0 {
+                return Err(format!("The SRS does not have the {len} bytes announced"));
+            }
+            let g2s = Self::decode_g2_points(&g2_bytes)?;
+            Ok(Self { g1s, g2s })
+        });
+        Ok((srs, digest))
+    }
+
+    /// Decodes an SRS streamed from `reader`, of `len` bytes if known, by
+    /// chunks of [READ_CHUNK_SIZE] points, so that the bytes of the whole SRS
+    /// are never held in memory next to its points. Fails on an I/O error,
+    /// otherwise returns the SRS, or why it cannot be decoded, with the
+    /// SHA-256 digest of all the bytes read
+    fn decode_stream(
+        mut reader: impl Read,
+        len: Option<u64>,
+        progress: &dyn ProgressSink,
+    ) -> io::Result<(Result<Self, String>, [u8; 32])> {
+        let nr_g1_points =
+            len.map(|len| (len.saturating_sub(2 * G2_SIZE as u64) / G1_SIZE as u64) as usize);
+        progress.start(nr_g1_points.unwrap_or(0), "Reading the existing SRS");
+        let mut g1s: Vec<G1Affine> = huge_vec(nr_g1_points.unwrap_or(0));
+
+        let mut hasher = Sha256::new();
+        let mut malformed = false;
+        // The last bytes read are held back, as they may be the G2 points
+        let mut buffer = vec![0u8; READ_CHUNK_SIZE * G1_SIZE + 2 * G2_SIZE];
+        let (mut filled, mut total) = (0, 0);
+        loop {
+            let n = fill_buffer(&mut reader, &mut buffer[filled..])?;
+            filled += n;
+            total += n;
+            let end_of_stream = filled < buffer.len();
+            let g1_bytes = filled.saturating_sub(2 * G2_SIZE) / G1_SIZE * G1_SIZE;
+            hasher.update(&buffer[..g1_bytes]);
+            if !malformed {
+                match buffer[..g1_bytes]
+                    .par_chunks(G1_SIZE)
+                    .map(decode_g1_point)
+                    .collect::<Option<Vec<_>>>()
+                {
+                    Some(chunk) => g1s.extend(chunk),
+                    // The rest is still hashed, for the sidecar to be checked
+                    None => malformed = true,
+                }
+                progress.inc(g1_bytes / G1_SIZE);
+            }
+            buffer.copy_within(g1_bytes..filled, 0);
+            filled -= g1_bytes;
+            if end_of_stream {
+                break;
+            }
+        }
+        hasher.update(&buffer[..filled]);
+        progress.finish();
+        let digest = hasher.finalize().into();
+
+        if filled != 2 * G2_SIZE || (!malformed && g1s.is_empty()) {
+            let err = format!("An SRS has n * {G1_SIZE} + 2 * {G2_SIZE} bytes, got {total}");
+            return Ok((Err(err), digest));
+        }
+        if malformed {
+            return Ok((Err("Failed to read G1 point".to_string()), digest));
+        }
+        let srs = Self::decode_g2_points(&buffer[..2 * G2_SIZE]).map(|g2s| Self { g1s, g2s });
+        Ok((srs, digest))
+    }
+
+    /// Decodes the two G2 points ending an SRS, [1]_2 and [tau]_2
+    fn decode_g2_points(bytes: &[u8]) -> Result<[G2Affine; 2], String> {
+        let point = |bytes: &[u8]| {
+            decode_g2_point(bytes).ok_or_else(|| "Failed to read G2 point".to_string())
+        };
+        Ok([point(&bytes[..G2_SIZE])?, point(&bytes[G2_SIZE..])?])
+    }
+
+    /// Decodes an SRS written with [SRS::write_to_file] from the mapping of
+    /// its file, without copying its bytes. Returns the SRS, or why it cannot
+    /// be decoded, with the SHA-256 digest of the file
+    #[cfg(feature = "mmap")]
+    fn decode_mapped(
+        bytes: &[u8],
+        progress: &dyn ProgressSink,
+    ) -> (Result<Self, String>, [u8; 32]) {
+        let digest: [u8; 32] = Sha256::digest(bytes).into();
+        if !is_srs_len(bytes.len() as u64) {
+            let err = format!(
+                "An SRS has n * {G1_SIZE} + 2 * {G2_SIZE} bytes, got {}",
+                bytes.len()
+            );
+            return (Err(err), digest);
+        }
         let offset = bytes.len() - 2 * G2_SIZE;
-        let pb = initialize_progress_bar(
-            offset / G1_SIZE,
-            Some(String::from("Reading the existing SRS")),
-        );
-        let g1s: Vec<G1Affine> = bytes[..offset]
+        progress.start(offset / G1_SIZE, "Reading the existing SRS");
+        let g1s = bytes[..offset]
             .par_chunks(G1_SIZE)
-            .inspect(|_| pb.inc(1))
-            .map(read_g1_point)
-            .collect::<Vec<_>>();
+            .inspect(|_| progress.inc(1))
+            .map(decode_g1_point)
+            .collect::<Option<Vec<_>>>();
+        progress.finish();
+
+        let srs = g1s
+            .ok_or_else(|| "Failed to read G1 point".to_string())
+            .and_then(|g1s| {
+                let g2s = Self::decode_g2_points(&bytes[offset..])?;
+                Ok(Self { g1s, g2s })
+            });
+        (srs, digest)
+    }
+
+    /// Decodes an SRS written with [SRS::write_to_file], reporting into
+    /// `progress`. Fails if the size of the buffer is not the one of an SRS,
+    /// or if a point is not canonically encoded
+    pub fn from_bytes(bytes: &[u8], progress: &dyn ProgressSink) -> Result<Self, SrsError> {
+        if !is_srs_len(bytes.len() as u64) {
+            return Err(SrsError::Deserialization(format!(
+                "An SRS has n * {G1_SIZE} + 2 * {G2_SIZE} bytes, got {}",
+                bytes.len()
+            )));
+        }
+        let offset = bytes.len() - 2 * G2_SIZE;
+        progress.start(offset / G1_SIZE, "Reading the existing SRS");
+        let mut g1s: Vec<G1Affine> = huge_vec(offset / G1_SIZE);
+        g1s.par_extend(
+            bytes[..offset]
+                .par_chunks(G1_SIZE)
+                .inspect(|_| progress.inc(1))
+                .map(decode_g1_point)
+                .while_some(),
+        );
 
-        pb.finish_and_clear();
+        progress.finish();
 
-        let mut g2s = [G2Affine::generator(); 2];
-        g2s[0] = read_g2_point(&bytes[offset..offset + G2_SIZE]);
-        g2s[1] = read_g2_point(&bytes[offset + G2_SIZE..offset + 2 * G2_SIZE]);
+        if g1s.len() != offset / G1_SIZE {
+            return Err(SrsError::Deserialization(
+                "Failed to read G1 point".to_string(),
+            ));
+        }
+        let g2s = [
+            read_g2_point(&bytes[offset..offset + G2_SIZE])?,
+            read_g2_point(&bytes[offset + G2_SIZE..offset + 2 * G2_SIZE])?,
+        ];
 
-        Self { g1s, g2s }
+        Ok(Self { g1s, g2s })
     }
 }
 
 #[cfg(test)]
 mod srs_tests {
-    use std::path::Path;
+    use std::{fs, path::Path};
 
     use blstrs::{pairing, G1Affine, G2Affine, Scalar};
     use halo2curves::{
@@ -159,7 +1227,16 @@ mod srs_tests {
     use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 
     use crate::{
-        ceremony::{G1_SIZE, SRS},
+        cache::sha256_file,
+        ceremony::{
+            is_truncation_of, structure_challenge, EXHAUSTIVE_CHUNK_SIZE, G1_SIZE, READ_CHUNK_SIZE,
+            SRS, UPDATE_CHUNK_SIZE,
+        },
+        error::SrsError,
+        msm::MsmConfig,
+        progress::NoProgress,
+        release::SrsEncoding,
+        state::CeremonyContext,
         utils::{powers, read_g1_point_from_file},
     };
 
@@ -170,7 +1247,7 @@ mod srs_tests {
         /// Generate a random SRS of length n:
         /// [1]_1, [tau]_1,..., [tau^{n-1}]_1
         /// [1]_2, [tau]_2
-        fn generate<R: RngCore>(n: usize, rng: R) -> Self {
+        pub(crate) fn generate<R: RngCore>(n: usize, rng: R) -> Self {
             let tau = Scalar::random(rng);
 
             // Scalar powers: 1, tau, tau^2,..., tau^{n-1}
@@ -192,30 +1269,277 @@ mod srs_tests {
     #[test]
     fn generate_srs() {
         let srs = SRS::generate(1 << 12, OsRng);
-        srs.verify_structure();
+        srs.verify_structure().unwrap();
 
         let path = Path::new("/tmp/test");
-        srs.write_to_file(path);
+        srs.write_to_file(path).unwrap();
+
+        let srs_deser = SRS::read_from_file(path).unwrap();
+        srs_deser.verify_structure().unwrap();
+    }
+
+    #[test]
+    fn srs_is_hashed_on_write() {
+        let srs = SRS::generate(1 << 6, OsRng);
+        let path = Path::new("/tmp/test_srs_hashed_on_write");
+        let digest = srs.write_to_file(path).unwrap();
+        assert_eq!(digest, sha256_file(path).unwrap());
+        assert_eq!(digest, srs.sha256());
+    }
+
+    #[test]
+    fn structure_challenge_is_derived_from_the_srs() {
+        let mut srs = SRS::generate(1 << 6, OsRng);
+        let path = Path::new("/tmp/test_structure_challenge");
+        let digest = srs.write_to_file(path).unwrap();
+        let challenge = structure_challenge(&digest);
+        assert_eq!(challenge, structure_challenge(&sha256_file(path).unwrap()));
+        assert_ne!(challenge, structure_challenge(&[0u8; 32]));
+
+        let msm = MsmConfig::default();
+        srs.verify_structure_with_challenge(&challenge, 1, &msm, &NoProgress)
+            .unwrap();
+        SRS::verify_structure_file_with_challenge(path, 10, &challenge, &msm, &NoProgress).unwrap();
+
+        srs.g1s.swap(3, 4);
+        let challenge = structure_challenge(&srs.sha256());
+        let result = srs.verify_structure_with_challenge(&challenge, 1, &msm, &NoProgress);
+        assert!(result.is_err());
+    }
 
-        let srs_deser = SRS::read_from_file(path);
-        srs_deser.verify_structure();
+    #[test]
+    fn structure_is_checked_by_chunks() {
+        let mut srs = SRS::generate(100, OsRng);
+        let msm = MsmConfig::default();
+        let r = Scalar::random(OsRng);
+        for nr_chunks in [1, 3, 4, 99, 200] {
+            srs.verify_structure_with_challenge(&r, nr_chunks, &msm, &NoProgress)
+                .unwrap();
+        }
+
+        srs.g1s.swap(70, 71);
+        let result = srs.verify_structure_with_challenge(&r, 4, &msm, &NoProgress);
+        assert!(
+            matches!(&result, Err(SrsError::Verification(msg)) if msg.contains("chunk of the G1 points 50..76")),
+            "{result:?}"
+        );
+    }
+
+    #[test]
+    fn srs_is_decoded_from_buffers() {
+        let srs = SRS::generate(1 << 6, OsRng);
+        let mut bytes = Vec::new();
+        srs.write_to(&mut bytes).unwrap();
+        assert_eq!(
+            SRS::from_reader(bytes.as_slice(), &NoProgress).unwrap(),
+            srs
+        );
+
+        // Several chunks, the last one partial
+        let srs = SRS::generate(2 * READ_CHUNK_SIZE + 3, OsRng);
+        let path = Path::new("/tmp/test_srs_read_by_chunks");
+        srs.write_to_file(path).unwrap();
+        assert_eq!(
+            SRS::read_from_file_with_progress(path, &NoProgress).unwrap(),
+            srs
+        );
+        bytes.clear();
+        srs.write_to(&mut bytes).unwrap();
+        let truncated = SRS::from_reader(&bytes[..bytes.len() - 1], &NoProgress);
+        assert!(matches!(truncated, Err(SrsError::Deserialization(_))));
+
+        // A G2 point off the curve
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        let corrupted = SRS::from_reader(corrupted.as_slice(), &NoProgress);
+        assert!(matches!(corrupted, Err(SrsError::Deserialization(msg)) if msg.contains("G2")));
+
+        assert!(SRS::from_bytes(&bytes[1..], &NoProgress).is_err());
     }
 
     #[test]
     fn generate_srs_with_update() {
         let mut srs = SRS::generate(1 << 10, OsRng);
-        srs.verify_structure();
+        srs.verify_structure().unwrap();
         let path = Path::new("/tmp/test_update");
-        srs.write_to_file(path);
+        srs.write_to_file(path).unwrap();
 
         let nu = Scalar::random(OsRng);
-        let update_proof = srs.update(&nu);
+        let update_proof = srs.update(&CeremonyContext::for_tests(), &nu);
 
-        srs.verify_structure();
+        srs.verify_structure().unwrap();
 
-        let old_g1_point = read_g1_point_from_file(path, G1_SIZE);
+        let old_g1_point = read_g1_point_from_file(path, G1_SIZE).unwrap();
         assert_eq!(old_g1_point, update_proof.g);
-        update_proof.verify()
+        assert!(update_proof.is_valid());
+    }
+
+    #[test]
+    fn update_across_chunks() {
+        let n = 3 * UPDATE_CHUNK_SIZE + 5;
+        let mut srs = SRS::generate(n, OsRng);
+        let old_g1s = srs.g1s.clone();
+
+        let nu = Scalar::random(OsRng);
+        srs.update(&CeremonyContext::for_tests(), &nu);
+        srs.verify_structure().unwrap();
+
+        for i in [0, 1, UPDATE_CHUNK_SIZE - 1, UPDATE_CHUNK_SIZE, n - 1] {
+            let expected = (old_g1s[i] * nu.pow_vartime([i as u64])).to_affine();
+            assert_eq!(srs.g1s[i], expected, "Wrong update of point {i}");
+        }
+    }
+
+    #[test]
+    fn update_file_matches_update() {
+        let srs = SRS::generate(1 << 10, OsRng);
+        let old_path = Path::new("/tmp/test_update_file_old");
+        let new_path = Path::new("/tmp/test_update_file_new");
+        srs.write_to_file(old_path).unwrap();
+
+        let nu = Scalar::random(OsRng);
+        let mut expected = srs.clone();
+        let ctx = CeremonyContext::for_tests();
+        let expected_proof = expected.update(&ctx, &nu);
+
+        // A window size that does not divide the SRS length
+        let proof = SRS::update_file(&ctx, old_path, new_path, &nu, 100, &NoProgress)
+            .unwrap()
+            .unwrap();
+        assert!(proof.is_valid());
+        assert_eq!(proof.g, expected_proof.g);
+        assert_eq!(proof.h, expected_proof.h);
+        assert_eq!(SRS::read_from_file(new_path).unwrap(), expected);
+
+        assert_eq!(proof.input_srs_hash, Some(sha256_file(old_path).unwrap()));
+        assert_eq!(proof.srs_hash, Some(sha256_file(new_path).unwrap()));
+        assert_eq!(proof.srs_hash, expected_proof.srs_hash);
+    }
+
+    #[test]
+    fn update_file_is_resumed() {
+        let srs = SRS::generate(1 << 10, OsRng);
+        let old_path = Path::new("/tmp/test_update_resumed_old");
+        let new_path = Path::new("/tmp/test_update_resumed_new");
+
+        let nu = Scalar::random(OsRng);
+        let mut expected = srs.clone();
+        let ctx = CeremonyContext::for_tests();
+        let expected_proof = expected.update(&ctx, &nu);
+        let mut expected_bytes = Vec::new();
+        expected.write_to(&mut expected_bytes).unwrap();
+
+        // The input may be in any encoding, the output is raw
+        for (encoding, start) in [
+            (SrsEncoding::Raw, 1),
+            (SrsEncoding::Raw, 250),
+            (SrsEncoding::Compressed, 1),
+            (SrsEncoding::Compressed, 250),
+        ] {
+            srs.write_to_file_as(old_path, encoding).unwrap();
+
+            // A crash after `start` points, in the middle of the next window
+            let written = (start + 30) * G1_SIZE;
+            fs::write(new_path, &expected_bytes[..written]).unwrap();
+
+            let mut checkpoints = Vec::new();
+            let proof = SRS::update_file_resumable(
+                &ctx,
+                old_path,
+                new_path,
+                &nu,
+                100,
+                start,
+                Some(&mut |processed| checkpoints.push(processed)),
+                &NoProgress,
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(proof.g, expected_proof.g);
+            assert_eq!(proof.h, expected_proof.h);
+            assert_eq!(proof.input_srs_hash, Some(sha256_file(old_path).unwrap()));
+            assert_eq!(proof.srs_hash, expected_proof.srs_hash);
+            assert_eq!(fs::read(new_path).unwrap(), expected_bytes);
+            // The first checkpoint is only due after the interval
+            assert!(checkpoints.is_empty());
+        }
+    }
+
+    #[test]
+    fn structure_is_verified_by_windows() {
+        let mut srs = SRS::generate(1 << 10, OsRng);
+        let path = Path::new("/tmp/test_verify_structure_file");
+        srs.write_to_file(path).unwrap();
+        let msm = MsmConfig::default();
+        // A window size that does not divide the SRS length
+        assert_eq!(
+            SRS::verify_structure_file(path, 100, &msm, &NoProgress).unwrap(),
+            1 << 10
+        );
+
+        for encoding in [SrsEncoding::Uncompressed, SrsEncoding::Compressed] {
+            srs.write_to_file_as(path, encoding).unwrap();
+            assert_eq!(
+                SRS::verify_structure_file(path, 100, &msm, &NoProgress).unwrap(),
+                1 << 10
+            );
+        }
+
+        srs.g1s.swap(3, 4);
+        for encoding in [SrsEncoding::Raw, SrsEncoding::Compressed] {
+            srs.write_to_file_as(path, encoding).unwrap();
+            assert!(SRS::verify_structure_file(path, 100, &msm, &NoProgress).is_err());
+        }
+    }
+
+    #[test]
+    fn truncation_is_detected() {
+        let srs = SRS::generate(UPDATE_CHUNK_SIZE + 10, OsRng);
+        let large_path = Path::new("/tmp/test_truncation_large");
+        let small_path = Path::new("/tmp/test_truncation_small");
+        srs.write_to_file(large_path).unwrap();
+
+        let mut small = SRS {
+            g1s: srs.g1s[..UPDATE_CHUNK_SIZE + 1].to_vec(),
+            g2s: srs.g2s,
+        };
+        small.write_to_file(small_path).unwrap();
+        assert!(is_truncation_of(small_path, large_path).unwrap());
+        assert!(!is_truncation_of(large_path, small_path).unwrap());
+
+        small.g1s[UPDATE_CHUNK_SIZE] = G1Affine::generator();
+        small.write_to_file(small_path).unwrap();
+        assert!(!is_truncation_of(small_path, large_path).unwrap());
+    }
+
+    #[test]
+    fn exhaustive_verification_is_resumable() {
+        let mut srs = SRS::generate(2 * EXHAUSTIVE_CHUNK_SIZE + 10, OsRng);
+        let mut checkpoints = vec![];
+        let checkpoint = |n| {
+            checkpoints.push(n);
+            Ok(())
+        };
+        assert!(srs
+            .verify_structure_exhaustive(0, checkpoint, &NoProgress)
+            .unwrap());
+        assert_eq!(
+            checkpoints,
+            [
+                EXHAUSTIVE_CHUNK_SIZE,
+                2 * EXHAUSTIVE_CHUNK_SIZE,
+                2 * EXHAUSTIVE_CHUNK_SIZE + 9
+            ]
+        );
+
+        // A wrong point before the resumption point is not checked again
+        srs.g1s[5] = srs.g1s[6];
+        assert!(srs
+            .verify_structure_exhaustive(EXHAUSTIVE_CHUNK_SIZE, |_| Ok(()), &NoProgress)
+            .unwrap());
+        assert!(srs
+            .verify_structure_exhaustive(0, |_| Ok(()), &NoProgress)
+            .is_err());
     }
 
     #[test]
@@ -224,7 +1548,7 @@ mod srs_tests {
         let mut srs = SRS::generate(1 << 10, OsRng);
         let k = OsRng.next_u64() % (srs.g1s.len() as u64);
         srs.g1s[k as usize] = G1Affine::identity();
-        srs.verify_structure()
+        srs.verify_structure().unwrap()
     }
 
     #[test]
@@ -232,7 +1556,7 @@ mod srs_tests {
     fn srs_with_wrong_g1s_case2() {
         let mut srs = SRS::generate(1 << 12, OsRng);
         srs.g1s[1] = G1Affine::generator();
-        srs.verify_structure();
+        srs.verify_structure().unwrap();
     }
 
     #[test]
@@ -240,7 +1564,7 @@ mod srs_tests {
     fn srs_with_wrong_g2s_case1() {
         let mut srs = SRS::generate(1 << 10, OsRng);
         srs.g2s[1] = G2Affine::identity();
-        srs.verify_structure()
+        srs.verify_structure().unwrap()
     }
 
     #[test]
@@ -248,7 +1572,15 @@ mod srs_tests {
     fn srs_with_wrong_g2s_case2() {
         let mut srs = SRS::generate(1 << 10, OsRng);
         srs.g2s[1] = G2Affine::generator();
-        srs.verify_structure()
+        srs.verify_structure().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "[tau]_2 does not match [tau]_1")]
+    fn srs_with_wrong_g2s_case3() {
+        let mut srs = SRS::generate(1 << 10, OsRng);
+        srs.g2s[1] = (G2Affine::generator() * Scalar::random(OsRng)).to_affine();
+        srs.verify_structure().unwrap()
     }
 
     #[test]
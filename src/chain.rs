@@ -0,0 +1,352 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verification of a chain of update proofs held in memory.
+//!
+//! `verify-chain` reads the proofs from the contribution directories, but
+//! services (e.g. a coordinator checking an upload, or a WASM build) hold
+//! them in buffers: [ChainVerifier] checks that each proof extends the chain,
+//! and [verify_chain] checks a whole chain, Schnorr proofs included, without
+//! touching the file system.
+//!
+//! Before the chain logic, [precheck_proofs] decodes and verifies all the
+//! proofs in parallel, so that every malformed or invalid proof is reported at
+//! once rather than only the first one met.
+
+use blstrs::{G1Affine, G2Affine};
+use rayon::prelude::*;
+
+use crate::{error::SrsError, schnorr::UpdateProof, state::CeremonyContext};
+
+/// State of a chain of update proofs being verified, one proof at a time
+#[derive(Clone, Debug)]
+pub struct ChainVerifier {
+    ceremony_id: [u8; 32],
+    /// [tau]_1 after the last update, the anchor first
+    g: G1Affine,
+    /// [tau]_2 after the last update, if it carried its G2 side
+    g2: Option<G2Affine>,
+    /// Digest of the SRS resulting from the last update, if recorded
+    srs_hash: Option<[u8; 32]>,
+    /// Index of the first proof that must be stamped, see
+    /// [CeremonyContext::stamped_from]
+    stamped_from: usize,
+    len: usize,
+}
+
+impl ChainVerifier {
+    /// Starts a chain of the given ceremony at `anchor`
+    pub fn new(anchor: G1Affine, ctx: &CeremonyContext) -> Self {
+        ChainVerifier {
+            ceremony_id: ctx.ceremony_id,
+            g: anchor,
+            g2: None,
+            srs_hash: None,
+            stamped_from: ctx.stamped_from,
+            len: 0,
+        }
+    }
+
+    /// Checks that the proof extends the chain (on the G1 and G2 sides, with
+    /// the SRS digests and the ceremony ID, all mandatory from
+    /// [CeremonyContext::stamped_from] on) and appends it. Its Schnorr proof
+    /// is not verified (see [UpdateProof::is_valid]). Fails with a
+    /// [SrsError::ChainMismatch] otherwise
+    pub fn push(&mut self, proof: &UpdateProof) -> Result<(), SrsError> {
+        self.check_extension(proof)
+            .map_err(SrsError::ChainMismatch)?;
+        self.g = proof.h;
+        self.g2 = proof.g2_update.as_ref().map(|update| update.h2);
+        self.srs_hash = proof.srs_hash;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn check_extension(&self, proof: &UpdateProof) -> Result<(), String> {
+        if proof.g != self.g {
+            return Err("The proof does not update the previous contribution".to_string());
+        }
+        if proof.g == proof.h {
+            return Err("The proof does not change the SRS".to_string());
+        }
+        let index = self.len + 1;
+        if index >= self.stamped_from {
+            if proof.ceremony_id.is_none() {
+                return Err(format!(
+                    "The proof is not stamped with the ceremony ID, mandatory from proof{}",
+                    self.stamped_from
+                ));
+            }
+            if proof.g2_update.is_none()
+                || proof.input_srs_hash.is_none()
+                || proof.srs_hash.is_none()
+            {
+                return Err(format!(
+                    "The proof lacks the G2 update or the SRS digests, mandatory from proof{}",
+                    self.stamped_from
+                ));
+            }
+            // Only the first mandatory proof may follow a legacy one
+            if index > self.stamped_from && (self.g2.is_none() || self.srs_hash.is_none()) {
+                return Err("The previous proof lacks the G2 update or the SRS digests".to_string());
+            }
+        }
+        if let (Some(update), Some(g2)) = (&proof.g2_update, self.g2) {
+            if update.g2 != g2 {
+                return Err("The G2 update does not extend the chain".to_string());
+            }
+        }
+        if let (Some(input_srs_hash), Some(srs_hash)) = (proof.input_srs_hash, self.srs_hash) {
+            if input_srs_hash != srs_hash {
+                return Err(
+                    "The proof does not update the SRS produced by the previous contribution"
+                        .to_string(),
+                );
+            }
+        }
+        if let Some(id) = proof.ceremony_id.filter(|id| *id != self.ceremony_id) {
+            return Err(format!(
+                "The proof belongs to ceremony {}, not to this ceremony ({})",
+                hex::encode(id),
+                hex::encode(self.ceremony_id)
+            ));
+        }
+        Ok(())
+    }
+
+    /// [tau]_1 after the last update
+    pub fn last_g1_point(&self) -> G1Affine {
+        self.g
+    }
+
+    /// Number of proofs in the chain
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Checks that the proofs form a valid chain of the given ceremony from
+/// `anchor` to `final_g1_point` ([tau]_1 of the final SRS). Returns
+/// the reason of the failure, with the index (from 1) of the faulty proof, if
+/// it fails
+pub fn verify_chain<'a>(
+    anchor: G1Affine,
+    ctx: &CeremonyContext,
+    proofs: impl IntoIterator<Item = &'a UpdateProof>,
+    final_g1_point: G1Affine,
+) -> Result<(), SrsError> {
+    let mut chain = ChainVerifier::new(anchor, ctx);
+    for (index, proof) in (1..).zip(proofs) {
+        chain
+            .push(proof)
+            .map_err(|failure| SrsError::ChainMismatch(format!("proof{index}: {failure}")))?;
+        if !proof.is_valid() {
+            return Err(SrsError::Verification(format!(
+                "proof{index}: Invalid update proof"
+            )));
+        }
+    }
+    if chain.last_g1_point() != final_g1_point {
+        return Err(SrsError::ChainMismatch(
+            "The final SRS does not match the end of the chain".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Decodes the serialized proofs (checking their length and that their points
+/// are canonically encoded) and verifies their Schnorr proofs, except those
+/// `verified` reports as already verified, in parallel. Returns the decoded
+/// proofs, or the reason of the failure of every malformed or invalid proof,
+/// with its index in `proofs`
+pub fn precheck_proofs(
+    proofs: &[Vec<u8>],
+    verified: impl Fn(usize) -> bool + Sync,
+) -> Result<Vec<UpdateProof>, Vec<(usize, SrsError)>> {
+    let results: Vec<Result<UpdateProof, SrsError>> = proofs
+        .par_iter()
+        .enumerate()
+        .map(|(i, bytes)| {
+            let proof = UpdateProof::from_bytes(bytes)?;
+            if !verified(i) && !proof.is_valid() {
+                return Err(SrsError::Verification("Invalid update proof".to_string()));
+            }
+            Ok(proof)
+        })
+        .collect();
+
+    let (proofs, failures): (Vec<_>, Vec<_>) = results
+        .into_iter()
+        .enumerate()
+        .partition(|(_, result)| result.is_ok());
+    if failures.is_empty() {
+        Ok(proofs
+            .into_iter()
+            .map(|(_, proof)| proof.unwrap())
+            .collect())
+    } else {
+        Err(failures
+            .into_iter()
+            .map(|(i, failure)| (i, failure.unwrap_err()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod chain_tests {
+    use blstrs::{G1Affine, G2Affine, Scalar};
+    use halo2curves::{
+        ff::Field,
+        group::{prime::PrimeCurveAffine, Curve},
+    };
+    use rand_core::OsRng;
+
+    use crate::{
+        chain::{precheck_proofs, verify_chain},
+        error::SrsError,
+        schnorr::{ProofFormat, UpdateProof},
+        state::CeremonyContext,
+    };
+
+    /// A valid chain of `n` proofs of the test ceremony, with its anchor. The
+    /// first `nr_legacy` proofs are v1 proofs, the others are stamped
+    fn chain(n: usize, nr_legacy: usize) -> (G1Affine, Vec<UpdateProof>) {
+        let anchor = (G1Affine::generator() * Scalar::random(OsRng)).to_affine();
+        let mut g = anchor;
+        let mut g2 = (G2Affine::generator() * Scalar::random(OsRng)).to_affine();
+        let proofs = (0..n)
+            .map(|i| {
+                let (proof, h2) = extend(g, g2, i, i >= nr_legacy);
+                (g, g2) = (proof.h, h2);
+                proof
+            })
+            .collect();
+        (anchor, proofs)
+    }
+
+    /// The `i`-th update (from 0) of `g` and `g2`, stamped or not, with the
+    /// updated G2 point
+    fn extend(g: G1Affine, g2: G2Affine, i: usize, stamped: bool) -> (UpdateProof, G2Affine) {
+        let x = Scalar::random(OsRng);
+        let h = (g * x).to_affine();
+        let h2 = (g2 * x).to_affine();
+        let proof = if stamped {
+            UpdateProof::create(&CeremonyContext::for_tests(), g, h, &x)
+                .with_g2_update(g2, h2)
+                .with_srs_hashes([i as u8; 32], [i as u8 + 1; 32])
+        } else {
+            UpdateProof::create_with(g, h, &x, None, None, OsRng)
+        };
+        (proof, h2)
+    }
+
+    #[test]
+    fn chains_are_verified_in_memory() {
+        let (anchor, mut proofs) = chain(3, 0);
+        let last = proofs.last().unwrap().h;
+        let ctx = CeremonyContext::for_tests();
+        assert!(verify_chain(anchor, &ctx, &proofs, last).is_ok());
+        let other = CeremonyContext {
+            ceremony_id: [2u8; 32],
+            ..ctx.clone()
+        };
+        assert!(verify_chain(anchor, &other, &proofs, last)
+            .unwrap_err()
+            .to_string()
+            .starts_with("proof1: The proof belongs to ceremony"));
+        assert!(verify_chain(anchor, &ctx, &proofs, anchor).is_err());
+
+        proofs.swap(1, 2);
+        assert!(verify_chain(anchor, &ctx, &proofs, last)
+            .unwrap_err()
+            .to_string()
+            .starts_with("proof2: The proof does not update"));
+    }
+
+    #[test]
+    fn stamps_cannot_be_downgraded() {
+        let ctx = CeremonyContext {
+            stamped_from: 3,
+            ..CeremonyContext::for_tests()
+        };
+        let (anchor, mut proofs) = chain(4, 2);
+        let last = proofs[3].h;
+        assert!(verify_chain(anchor, &ctx, &proofs, last).is_ok());
+        assert!(
+            verify_chain(anchor, &CeremonyContext::for_tests(), &proofs, last)
+                .unwrap_err()
+                .to_string()
+                .starts_with("proof1: The proof is not stamped with the ceremony ID")
+        );
+
+        // A legacy proof after the stamped ones
+        let g2 = proofs[2].g2_update.as_ref().unwrap().h2;
+        let (legacy, _) = extend(proofs[2].h, g2, 3, false);
+        proofs[3] = legacy;
+        assert!(verify_chain(anchor, &ctx, &proofs, proofs[3].h)
+            .unwrap_err()
+            .to_string()
+            .starts_with("proof4: The proof is not stamped with the ceremony ID"));
+
+        // A stamped proof without the G2 update nor the SRS digests
+        let x = Scalar::random(OsRng);
+        let h = (proofs[2].h * x).to_affine();
+        proofs[3] = UpdateProof::create(&ctx, proofs[2].h, h, &x);
+        assert!(verify_chain(anchor, &ctx, &proofs, h)
+            .unwrap_err()
+            .to_string()
+            .starts_with("proof4: The proof lacks the G2 update or the SRS digests"));
+    }
+
+    #[test]
+    fn all_malformed_proofs_are_reported() {
+        // Legacy v1 proofs, the third of which does not verify
+        let proofs: Vec<UpdateProof> = (0..4)
+            .map(|i| {
+                let g = (G1Affine::generator() * Scalar::random(OsRng)).to_affine();
+                let x = Scalar::random(OsRng);
+                let h = (g * x).to_affine();
+                let x = if i == 2 { Scalar::random(OsRng) } else { x };
+                UpdateProof::create_with(g, h, &x, None, None, OsRng)
+            })
+            .collect();
+        let mut files: Vec<Vec<u8>> = proofs
+            .iter()
+            .map(|proof| proof.to_bytes(ProofFormat::V1))
+            .collect();
+        files[0].pop();
+        files[3][10] ^= 1;
+
+        let failures = precheck_proofs(&files, |_| false).unwrap_err();
+        assert_eq!(
+            failures.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            vec![0, 2, 3]
+        );
+        assert!(matches!(failures[0].1, SrsError::Deserialization(_)));
+        assert!(failures[0].1.to_string().starts_with("An update proof has"));
+        assert!(matches!(failures[1].1, SrsError::Verification(_)));
+        assert_eq!(failures[1].1.to_string(), "Invalid update proof");
+
+        // Proofs already verified are only decoded
+        files[0] = proofs[0].to_bytes(ProofFormat::V1);
+        files[3] = proofs[3].to_bytes(ProofFormat::V1);
+        let decoded = precheck_proofs(&files, |i| i == 2).unwrap();
+        assert_eq!(decoded[1], proofs[1]);
+    }
+}
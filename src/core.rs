@@ -0,0 +1,252 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verification core, without `std`.
+//!
+//! The pure verification math (Schnorr proofs of update, the G2 side of an
+//! update and the structure of an SRS over provided points) only needs
+//! `alloc`, so that embedded and on-chain-adjacent environments can reuse the
+//! exact logic of the ceremony tools: the rest of the crate calls into this
+//! module. Built with `--no-default-features`, the crate is reduced to it.
+//!
+//! Without an OS RNG, the randomness of the batched structure check is
+//! provided by the caller.
+
+use blake2::{Blake2b512, Digest};
+use blstrs::{pairing, G1Affine, G2Affine, Scalar};
+use halo2curves::{
+    ff::FromUniformBytes,
+    group::{prime::PrimeCurveAffine, Curve},
+    serde::SerdeObject,
+};
+use serde::{Deserialize, Serialize};
+use sha3::Sha3_512;
+
+/// Domain tag of the Fiat-Shamir transcript of the Schnorr proofs of v2 update
+/// proofs
+pub const SCHNORR_DOMAIN: &[u8] = b"midnight-srs/update-proof/schnorr";
+
+/// A hash function with 64-byte outputs, used for Fiat-Shamir transcripts
+pub trait TranscriptHasher {
+    fn init() -> Self;
+    fn absorb(&mut self, bytes: &[u8]);
+    fn squeeze(self) -> [u8; 64];
+}
+
+impl TranscriptHasher for Blake2b512 {
+    fn init() -> Self {
+        Blake2b512::new()
+    }
+
+    fn absorb(&mut self, bytes: &[u8]) {
+        self.update(bytes)
+    }
+
+    fn squeeze(self) -> [u8; 64] {
+        self.finalize().into()
+    }
+}
+
+impl TranscriptHasher for Sha3_512 {
+    fn init() -> Self {
+        Sha3_512::new()
+    }
+
+    fn absorb(&mut self, bytes: &[u8]) {
+        self.update(bytes)
+    }
+
+    fn squeeze(self) -> [u8; 64] {
+        self.finalize().into()
+    }
+}
+
+/// BLAKE3 in XOF mode, squeezed to 64 bytes
+impl TranscriptHasher for blake3::Hasher {
+    fn init() -> Self {
+        blake3::Hasher::new()
+    }
+
+    fn absorb(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn squeeze(self) -> [u8; 64] {
+        let mut output = [0u8; 64];
+        self.finalize_xof().fill(&mut output);
+        output
+    }
+}
+
+/// Hashes (with the specified hash function) the domain tag, followed by the
+/// given slice of points
+pub fn hash_points<H: TranscriptHasher>(domain: &[u8], points: &[G1Affine]) -> [u8; 64] {
+    let mut hasher = H::init();
+    hasher.absorb(domain);
+    for p in points {
+        hasher.absorb(&p.to_raw_bytes());
+    }
+    hasher.squeeze()
+}
+
+/// Hash function of the Fiat-Shamir transcript of the update proofs, as set by
+/// the ceremony policy
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TranscriptHash {
+    #[default]
+    #[serde(rename = "blake2b-512")]
+    Blake2b512,
+    #[serde(rename = "sha3-512")]
+    Sha3_512,
+    #[serde(rename = "blake3-xof")]
+    Blake3Xof,
+}
+
+impl TranscriptHash {
+    /// Identifier of the hash function, recorded in the proof header
+    pub fn id(self) -> u8 {
+        match self {
+            TranscriptHash::Blake2b512 => 1,
+            TranscriptHash::Sha3_512 => 2,
+            TranscriptHash::Blake3Xof => 3,
+        }
+    }
+
+    /// The hash function with the given identifier, if any
+    pub fn from_id(id: u8) -> Option<Self> {
+        [
+            TranscriptHash::Blake2b512,
+            TranscriptHash::Sha3_512,
+            TranscriptHash::Blake3Xof,
+        ]
+        .into_iter()
+        .find(|hash| hash.id() == id)
+    }
+
+    /// Same as [hash_points], with this hash function
+    pub fn hash_points(self, domain: &[u8], points: &[G1Affine]) -> [u8; 64] {
+        match self {
+            TranscriptHash::Blake2b512 => hash_points::<Blake2b512>(domain, points),
+            TranscriptHash::Sha3_512 => hash_points::<Sha3_512>(domain, points),
+            TranscriptHash::Blake3Xof => hash_points::<blake3::Hasher>(domain, points),
+        }
+    }
+}
+
+/// Fiat-Shamir challenge of a Schnorr proof. With a transcript hash function
+/// (v2 proofs), the points are hashed after [SCHNORR_DOMAIN] and the ceremony
+/// ID, if any; otherwise (v1 proofs) they are hashed with Blake2b512, without
+/// domain tag
+pub fn schnorr_challenge(
+    transcript_hash: Option<TranscriptHash>,
+    ceremony_id: Option<[u8; 32]>,
+    points: &[G1Affine],
+) -> Scalar {
+    let bytes = match (transcript_hash, ceremony_id) {
+        (None, _) => hash_points::<Blake2b512>(b"", points),
+        (Some(hash), None) => hash.hash_points(SCHNORR_DOMAIN, points),
+        (Some(hash), Some(id)) => hash.hash_points(&[SCHNORR_DOMAIN, &id].concat(), points),
+    };
+    Scalar::from_uniform_bytes(&bytes)
+}
+
+/// Whether (a, z) is a valid Schnorr proof of knowledge of the dlog of h in
+/// base g, with the given transcript hash function and ceremony ID
+pub fn verify_schnorr(
+    g: G1Affine,
+    h: G1Affine,
+    (a, z): (G1Affine, Scalar),
+    transcript_hash: Option<TranscriptHash>,
+    ceremony_id: Option<[u8; 32]>,
+) -> bool {
+    let e = schnorr_challenge(transcript_hash, ceremony_id, &[g, h, a]);
+    g * z == h * e + a
+}
+
+/// Whether the same scalar was applied to the G2 point g2 (updated to h2) as
+/// to the G1 point g (updated to h), i.e. e(h, g2) = e(g, h2)
+pub fn verify_g2_update(g: G1Affine, h: G1Affine, g2: G2Affine, h2: G2Affine) -> bool {
+    !bool::from(h2.is_identity()) && pairing(&h, &g2) == pairing(&g, &h2)
+}
+
+/// Whether `g1s` and `g2s` form an SRS: [1]_1, [tau]_1, ..., [tau^{n-1}]_1 and
+/// [1]_2, [tau]_2, for some tau other than 0 and 1. The consecutive pairs of
+/// G1 points are batched with the powers of `r`, which must be drawn
+/// uniformly at random by the caller
+pub fn verify_srs_structure(g1s: &[G1Affine], g2s: &[G2Affine; 2], r: Scalar) -> bool {
+    if g1s.len() < 2
+        || g1s.iter().any(|p| bool::from(p.is_identity()))
+        || g1s[0] != G1Affine::generator()
+        || g2s[0] != G2Affine::generator()
+        || bool::from(g2s[1].is_identity())
+        || g2s[1] == g2s[0]
+    {
+        return false;
+    }
+
+    // sum_i r^i [tau^i]_1 and sum_i r^i [tau^{i+1}]_1, by Horner's rule
+    let n = g1s.len() - 1;
+    let (lhs, rhs) = (0..n).rev().fold(
+        (
+            G1Affine::identity().to_curve(),
+            G1Affine::identity().to_curve(),
+        ),
+        |(lhs, rhs), i| (lhs * r + g1s[i], rhs * r + g1s[i + 1]),
+    );
+    pairing(&lhs.to_affine(), &g2s[1]) == pairing(&rhs.to_affine(), &g2s[0])
+}
+
+#[cfg(test)]
+mod core_tests {
+    use blstrs::{G1Affine, Scalar};
+    use halo2curves::{
+        ff::Field,
+        group::{prime::PrimeCurveAffine, Curve},
+    };
+    use rand_core::OsRng;
+
+    use crate::{
+        ceremony::SRS,
+        core::{verify_schnorr, verify_srs_structure},
+        schnorr::UpdateProof,
+        utils::TranscriptHash,
+    };
+
+    #[test]
+    fn core_matches_the_ceremony_tools() {
+        let g = (G1Affine::generator() * Scalar::random(OsRng)).to_affine();
+        let x = Scalar::random(OsRng);
+        let h = (g * x).to_affine();
+        let hash = Some(TranscriptHash::Sha3_512);
+        let proof = UpdateProof::create_with(g, h, &x, hash, Some([3u8; 32]), OsRng);
+        let (a, z) = (proof.schnorr_proof().a(), proof.schnorr_proof().z());
+        assert!(verify_schnorr(g, h, (a, z), hash, Some([3u8; 32])));
+        assert!(!verify_schnorr(g, h, (a, z), hash, None));
+        assert!(!verify_schnorr(h, g, (a, z), hash, Some([3u8; 32])));
+
+        let mut srs = SRS::generate(1 << 5, OsRng);
+        assert!(verify_srs_structure(
+            &srs.g1s,
+            &srs.g2s,
+            Scalar::random(OsRng)
+        ));
+        srs.g1s.swap(3, 4);
+        assert!(!verify_srs_structure(
+            &srs.g1s,
+            &srs.g2s,
+            Scalar::random(OsRng)
+        ));
+    }
+}
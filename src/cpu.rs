@@ -0,0 +1,171 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime detection of the CPU features relevant to the field arithmetic.
+//!
+//! Binaries built with e.g. `-C target-cpu=native` on a recent machine crash
+//! with an illegal instruction on older CPUs. [check_compiled_features]
+//! detects it before any heavy work begins, and hands over to a portable
+//! build of the binary (built without CPU-specific features) if there is one,
+//! or fails with an explicit error message otherwise.
+
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Environment variable giving the path of the portable build of the running
+/// binary, `<binary>-portable` next to it by default
+pub const PORTABLE_BINARY_VAR: &str = "SRS_PORTABLE_BINARY";
+
+/// CPU features used by the optimized field arithmetic backends
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CpuFeatures {
+    pub adx: bool,
+    pub bmi2: bool,
+    pub avx2: bool,
+    pub neon: bool,
+}
+
+impl CpuFeatures {
+    /// Detects the features available on the running CPU
+    #[cfg(target_arch = "x86_64")]
+    pub fn detect() -> Self {
+        CpuFeatures {
+            adx: std::arch::is_x86_feature_detected!("adx"),
+            bmi2: std::arch::is_x86_feature_detected!("bmi2"),
+            avx2: std::arch::is_x86_feature_detected!("avx2"),
+            neon: false,
+        }
+    }
+
+    /// Detects the features available on the running CPU
+    #[cfg(target_arch = "aarch64")]
+    pub fn detect() -> Self {
+        CpuFeatures {
+            neon: std::arch::is_aarch64_feature_detected!("neon"),
+            ..Default::default()
+        }
+    }
+
+    /// Detects the features available on the running CPU
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    pub fn detect() -> Self {
+        CpuFeatures::default()
+    }
+
+    /// Features the binary was compiled to assume
+    pub fn compiled() -> Self {
+        CpuFeatures {
+            adx: cfg!(target_feature = "adx"),
+            bmi2: cfg!(target_feature = "bmi2"),
+            avx2: cfg!(target_feature = "avx2"),
+            neon: cfg!(target_feature = "neon"),
+        }
+    }
+
+    /// Names of the enabled features
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            (self.adx, "adx"),
+            (self.bmi2, "bmi2"),
+            (self.avx2, "avx2"),
+            (self.neon, "neon"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect()
+    }
+
+    /// Names of the features enabled in `self` but not in `other`
+    pub fn missing_from(&self, other: &CpuFeatures) -> Vec<&'static str> {
+        let other = other.names();
+        self.names()
+            .into_iter()
+            .filter(|name| !other.contains(name))
+            .collect()
+    }
+}
+
+impl fmt::Display for CpuFeatures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self.names();
+        if names.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", names.join(" "))
+        }
+    }
+}
+
+/// Path of the portable build of the running binary (see
+/// [PORTABLE_BINARY_VAR]), if there is one
+pub fn portable_binary() -> Option<PathBuf> {
+    let path = match env::var_os(PORTABLE_BINARY_VAR) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let binary = env::current_exe().ok()?;
+            let mut name = binary.file_name()?.to_os_string();
+            name.push("-portable");
+            binary.with_file_name(name)
+        }
+    };
+    path.is_file().then_some(path)
+}
+
+/// Runs the portable build at `path` with the arguments of the running
+/// binary, and exits with its exit code
+fn run_portable(path: &Path) -> ! {
+    let mut command = Command::new(path);
+    command.args(env::args_os().skip(1));
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let err = command.exec();
+        panic!("Cannot run the portable build {path:?}: {err}");
+    }
+    #[cfg(not(unix))]
+    {
+        let status = command
+            .status()
+            .unwrap_or_else(|err| panic!("Cannot run the portable build {path:?}: {err}"));
+        std::process::exit(status.code().unwrap_or(1))
+    }
+}
+
+/// Checks that the running CPU has the features the binary was compiled for.
+/// If it lacks some, runs the portable build of the binary instead (see
+/// [portable_binary]), or panics with an explanatory message if there is none
+pub fn check_compiled_features() {
+    let missing = CpuFeatures::compiled().missing_from(&CpuFeatures::detect());
+    if missing.is_empty() {
+        return;
+    }
+    if let Some(path) = portable_binary() {
+        eprintln!(
+            "This binary was compiled for CPU features that are not available on this machine \
+             ({}), running the portable build {path:?} instead.",
+            missing.join(", ")
+        );
+        run_portable(&path);
+    }
+    panic!(
+        "This binary was compiled for CPU features that are not available on this machine ({}). \
+         Please rebuild it without `-C target-cpu=native` (or enable the `portable` feature of blst), \
+         or install such a build as `<binary>-portable` (or at ${PORTABLE_BINARY_VAR}).",
+        missing.join(", ")
+    );
+}
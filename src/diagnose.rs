@@ -0,0 +1,267 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Diagnosis of a broken chain of update proofs.
+//!
+//! Contrary to `verify-chain`, which stops at the first failed check, the
+//! diagnosis scans the whole chain and classifies every break, so that the
+//! contributions responsible for it can be identified. After a linkage
+//! mismatch, the scan carries on from the point the faulty proof claims to
+//! update, so that a single break is reported once.
+
+use std::fmt;
+
+use blstrs::G1Affine;
+
+use crate::{schnorr::UpdateProof, state::CeremonyContext};
+
+/// Why the chain breaks at a contribution
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChainBreak {
+    /// The proof file cannot be decoded
+    Malformed(String),
+    /// The first proof does not update the anchor point
+    AnchorMismatch,
+    /// The proof does not update [tau]_1 after the previous contribution. If
+    /// it updates the one after an earlier contribution (or the anchor, 0),
+    /// the proofs in between were likely reordered or replaced
+    LinkageMismatch { extends: Option<usize> },
+    /// The proof does not change [tau]_1
+    NoUpdate,
+    /// The Schnorr proof (or the G2 side of the update) is invalid
+    InvalidProof,
+    /// The proof is stamped with the ID of another ceremony
+    ForeignCeremony,
+    /// [tau]_1 of the final SRS does not match the end of the chain
+    FinalSrsMismatch,
+}
+
+impl fmt::Display for ChainBreak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainBreak::Malformed(reason) => write!(f, "malformed proof file ({reason})"),
+            ChainBreak::AnchorMismatch => write!(f, "does not update the anchor point"),
+            ChainBreak::LinkageMismatch { extends: None } => {
+                write!(f, "does not update the previous contribution")
+            }
+            ChainBreak::LinkageMismatch { extends: Some(0) } => write!(
+                f,
+                "does not update the previous contribution, but the anchor point"
+            ),
+            ChainBreak::LinkageMismatch {
+                extends: Some(index),
+            } => write!(
+                f,
+                "does not update the previous contribution, but contribution {index}"
+            ),
+            ChainBreak::NoUpdate => write!(f, "does not change the SRS (g == h)"),
+            ChainBreak::InvalidProof => write!(f, "invalid update proof"),
+            ChainBreak::ForeignCeremony => write!(f, "belongs to another ceremony"),
+            ChainBreak::FinalSrsMismatch => {
+                write!(f, "the final SRS does not match the end of the chain")
+            }
+        }
+    }
+}
+
+/// A break of the chain, at the given contribution (`None` for the final SRS)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnosis {
+    pub index: Option<usize>,
+    pub kind: ChainBreak,
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.index {
+            Some(index) => write!(f, "Contribution {index}: {}", self.kind),
+            None => write!(f, "Final SRS: {}", self.kind),
+        }
+    }
+}
+
+/// Scans the chain of proofs (with their index, or the reason they cannot be
+/// decoded) of the given ceremony, starting at `anchor` and ending
+/// at `final_g1_point`, and returns all the breaks found, in order. The chain
+/// is valid iff there are none
+pub fn diagnose_chain(
+    anchor: G1Affine,
+    ctx: &CeremonyContext,
+    proofs: &[(usize, Result<UpdateProof, String>)],
+    final_g1_point: G1Affine,
+) -> Vec<Diagnosis> {
+    let mut breaks = Vec::new();
+    // [tau]_1 after each contribution so far, the anchor first
+    let mut points = vec![anchor];
+
+    for (index, proof) in proofs {
+        let mut report = |kind| {
+            breaks.push(Diagnosis {
+                index: Some(*index),
+                kind,
+            })
+        };
+        let proof = match proof {
+            Ok(proof) => proof,
+            Err(reason) => {
+                report(ChainBreak::Malformed(reason.clone()));
+                // The next contribution is checked against the last known point
+                continue;
+            }
+        };
+
+        let previous = *points.last().unwrap();
+        if proof.g != previous {
+            if points.len() == 1 {
+                report(ChainBreak::AnchorMismatch);
+            } else {
+                let extends = points.iter().rposition(|point| *point == proof.g);
+                report(ChainBreak::LinkageMismatch { extends });
+            }
+        }
+        if proof.g == proof.h {
+            report(ChainBreak::NoUpdate);
+        } else if !proof.is_valid() {
+            report(ChainBreak::InvalidProof);
+        }
+        if proof.ceremony_id.is_some_and(|id| id != ctx.ceremony_id) {
+            report(ChainBreak::ForeignCeremony);
+        }
+        points.push(proof.h);
+    }
+
+    if *points.last().unwrap() != final_g1_point {
+        breaks.push(Diagnosis {
+            index: None,
+            kind: ChainBreak::FinalSrsMismatch,
+        });
+    }
+    breaks
+}
+
+#[cfg(test)]
+mod diagnose_tests {
+    use blstrs::{G1Affine, Scalar};
+    use halo2curves::{
+        ff::Field,
+        group::{prime::PrimeCurveAffine, Curve},
+    };
+    use rand_core::OsRng;
+
+    use crate::{
+        diagnose::{diagnose_chain, ChainBreak, Diagnosis},
+        schnorr::UpdateProof,
+        state::CeremonyContext,
+    };
+
+    /// A valid chain of `n` proofs, with its anchor
+    fn chain(n: usize) -> (G1Affine, Vec<UpdateProof>) {
+        let anchor = (G1Affine::generator() * Scalar::random(OsRng)).to_affine();
+        let mut g = anchor;
+        let proofs = (0..n)
+            .map(|_| {
+                let x = Scalar::random(OsRng);
+                let h = (g * x).to_affine();
+                let proof = UpdateProof::create(&CeremonyContext::for_tests(), g, h, &x);
+                g = h;
+                proof
+            })
+            .collect();
+        (anchor, proofs)
+    }
+
+    fn indexed(proofs: Vec<UpdateProof>) -> Vec<(usize, Result<UpdateProof, String>)> {
+        (1..).zip(proofs.into_iter().map(Ok)).collect()
+    }
+
+    #[test]
+    fn valid_chain_has_no_breaks() {
+        let (anchor, proofs) = chain(4);
+        let last = proofs.last().unwrap().h;
+        assert!(diagnose_chain(
+            anchor,
+            &CeremonyContext::for_tests(),
+            &indexed(proofs),
+            last
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn breaks_are_classified() {
+        let (anchor, mut proofs) = chain(5);
+        let last = proofs.last().unwrap().h;
+
+        // Contributions 2 and 3 are swapped
+        proofs.swap(1, 2);
+        let breaks = diagnose_chain(
+            anchor,
+            &CeremonyContext::for_tests(),
+            &indexed(proofs.clone()),
+            last,
+        );
+        assert_eq!(
+            breaks,
+            vec![
+                Diagnosis {
+                    index: Some(2),
+                    kind: ChainBreak::LinkageMismatch { extends: None },
+                },
+                Diagnosis {
+                    index: Some(3),
+                    kind: ChainBreak::LinkageMismatch { extends: Some(1) },
+                },
+                Diagnosis {
+                    index: Some(4),
+                    kind: ChainBreak::LinkageMismatch { extends: Some(2) },
+                },
+            ]
+        );
+
+        // The Schnorr proof of contribution 2 does not match its points, and
+        // the chain does not end at the final SRS
+        proofs.swap(1, 2);
+        proofs[1] = UpdateProof::create(
+            &CeremonyContext::for_tests(),
+            proofs[1].g,
+            proofs[1].h,
+            &Scalar::random(OsRng),
+        );
+        let mut indexed = indexed(proofs);
+        indexed[3].1 = Err("truncated".to_string());
+        let breaks = diagnose_chain(anchor, &CeremonyContext::for_tests(), &indexed, anchor);
+        assert_eq!(
+            breaks,
+            vec![
+                Diagnosis {
+                    index: Some(2),
+                    kind: ChainBreak::InvalidProof,
+                },
+                Diagnosis {
+                    index: Some(4),
+                    kind: ChainBreak::Malformed("truncated".to_string()),
+                },
+                Diagnosis {
+                    index: Some(5),
+                    kind: ChainBreak::LinkageMismatch { extends: None },
+                },
+                Diagnosis {
+                    index: None,
+                    kind: ChainBreak::FinalSrsMismatch,
+                },
+            ]
+        );
+    }
+}
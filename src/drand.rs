@@ -0,0 +1,281 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Drand chain information.
+//!
+//! A Drand chain is identified by its chain hash, which commits to its public
+//! key, period, genesis time, group and scheme (see [ChainInfo::compute_hash]).
+//! Pinning the chain hash is therefore enough to authenticate the chain
+//! information fetched from (untrusted) Drand relays, which is then cached
+//! locally.
+
+use std::path::{Path, PathBuf};
+
+use blake2::Blake2b512;
+use blstrs::Scalar;
+use halo2curves::ff::Field;
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    audit::{is_auditing, read_audited},
+    cache::sha256_file,
+    net::agent,
+    state::CeremonyManifest,
+    utils::create_file,
+};
+
+/// Public Drand API
+pub const DRAND_API: &str = "https://api.drand.sh";
+
+/// Chain hash of the Drand mainnet "default" chain, used in the ceremony
+pub const DEFAULT_CHAIN_HASH: &str =
+    "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce";
+
+/// Public key of the "default" chain, as declared for the ceremony
+pub const DEFAULT_CHAIN_PUBLIC_KEY: &str = "868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31";
+
+/// Scheme of the chains whose hash does not include the scheme
+const DEFAULT_SCHEME: &str = "pedersen-bls-chained";
+
+/// Information of a Drand chain, as returned by the `/info` endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainInfo {
+    /// Public key (hex) of the group
+    pub public_key: String,
+    /// Time between two rounds, in seconds
+    pub period: u32,
+    /// UNIX time of the first round
+    pub genesis_time: i64,
+    /// Chain hash (hex)
+    pub hash: String,
+    /// Hash (hex) of the group file
+    #[serde(rename = "groupHash")]
+    pub group_hash: String,
+    #[serde(rename = "schemeID")]
+    pub scheme_id: String,
+    #[serde(default)]
+    pub metadata: ChainMetadata,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainMetadata {
+    #[serde(rename = "beaconID", default)]
+    pub beacon_id: String,
+}
+
+impl ChainInfo {
+    /// Recomputes the chain hash from the chain information, as
+    /// SHA-256(period || genesis_time || public_key || group_hash [|| scheme]
+    /// [|| beacon_id]), where the scheme and beacon ID are only included if
+    /// they are not the default ones
+    pub fn compute_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.period.to_be_bytes());
+        hasher.update(self.genesis_time.to_be_bytes());
+        hasher.update(hex::decode(&self.public_key).expect("Invalid Drand public key"));
+        hasher.update(hex::decode(&self.group_hash).expect("Invalid Drand group hash"));
+        if self.scheme_id != DEFAULT_SCHEME {
+            hasher.update(self.scheme_id.as_bytes());
+        }
+        let beacon_id = &self.metadata.beacon_id;
+        if !beacon_id.is_empty() && beacon_id != "default" {
+            hasher.update(beacon_id.as_bytes());
+        }
+        hasher.finalize().into()
+    }
+
+    /// UNIX time at which the given round is produced
+    pub fn round_time(&self, round: u64) -> i64 {
+        assert!(round > 0, "Drand rounds start at 1");
+        self.genesis_time + (round as i64 - 1) * self.period as i64
+    }
+
+    /// Whether the information is the one of the chain with the given hash
+    pub fn matches(&self, chain_hash: &str) -> bool {
+        self.hash == chain_hash && hex::encode(self.compute_hash()) == chain_hash
+    }
+
+    /// Fetches the information of the chain with the given hash from the
+    /// Drand API
+    pub fn fetch(chain_hash: &str) -> Result<Self, std::io::Error> {
+        agent()
+            .get(&format!("{DRAND_API}/{chain_hash}/info"))
+            .call()
+            .map_err(|e| std::io::Error::other(format!("Error in HTTPS call: {:?}", e)))?
+            .into_json()
+    }
+
+    /// Path of the local cache of the information of the given chain
+    pub fn cache_path(chain_hash: &str) -> PathBuf {
+        PathBuf::from(format!("./.drand_chain_{chain_hash}.json"))
+    }
+
+    /// The information of the chain with the given hash, read from the local
+    /// cache or fetched (and cached, except in auditor mode). Panics if the
+    /// information does not match the chain hash
+    pub fn load_or_fetch(chain_hash: &str) -> Self {
+        let cache_path = Self::cache_path(chain_hash);
+        if let Some(info) = read_audited(&cache_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Self>(&bytes).ok())
+            .filter(|info| info.matches(chain_hash))
+        {
+            return info;
+        }
+
+        let info = Self::fetch(chain_hash).expect("Failed to fetch the Drand chain information.");
+        assert!(
+            info.matches(chain_hash),
+            "The fetched Drand chain information does not match the pinned chain hash {chain_hash}"
+        );
+        if !is_auditing() {
+            let file = create_file(&cache_path).unwrap_or_else(|err| panic!("{err}"));
+            serde_json::to_writer_pretty(file, &info)
+                .expect("Could not cache the Drand chain information");
+        }
+        info
+    }
+}
+
+/// Canonical location of the beacon commitment in the ceremony repository
+pub const BEACON_COMMITMENT_PATH: &str = "./beacon/commitment.json";
+
+/// Canonical location of the opening of the beacon commitment
+pub const BEACON_REVEAL_PATH: &str = "./beacon/reveal.json";
+
+/// Commitment to the Drand round(s) seeding the last update, published before
+/// the round is produced
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeaconCommitment {
+    /// Hash (hex) of the Drand chain
+    pub chain_hash: String,
+    /// SHA-256(round [|| last_round] || salt) (hex)
+    pub commitment: String,
+}
+
+/// Opening of the beacon commitment
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeaconReveal {
+    pub round: u64,
+    /// Last round, when committing to a range of rounds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_round: Option<u64>,
+    /// Salt (hex, 16 bytes)
+    pub salt: String,
+}
+
+/// SHA-256 digests (hex) of the beacon files, as recorded in the ceremony
+/// manifest
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeaconFiles {
+    pub commitment_sha256: String,
+    /// Recorded once the commitment is opened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reveal_sha256: Option<String>,
+}
+
+/// Reads a beacon file, after checking its digest against the one recorded
+/// in the manifest. Panics if it does not match
+fn read_beacon_file<T: serde::de::DeserializeOwned>(path: &Path, sha256: Option<&String>) -> T {
+    let sha256 = sha256.unwrap_or_else(|| panic!("The ceremony manifest does not record {path:?}"));
+    assert_eq!(
+        &hex::encode(sha256_file(path).unwrap_or_else(|err| panic!("{err}"))),
+        sha256,
+        "{path:?} does not match the digest recorded in the ceremony manifest"
+    );
+    let bytes = read_audited(path).unwrap_or_else(|err| panic!("Cannot read {path:?}: {err}"));
+    serde_json::from_slice(&bytes).unwrap_or_else(|err| panic!("Malformed {path:?}: {err}"))
+}
+
+/// Reads the beacon commitment and its opening from their canonical files,
+/// checked against the ceremony manifest
+pub fn read_beacon_files(manifest: &CeremonyManifest) -> (BeaconCommitment, BeaconReveal) {
+    let files = manifest
+        .beacon
+        .as_ref()
+        .expect("The ceremony manifest does not record any beacon commitment");
+    (
+        read_beacon_file(
+            Path::new(BEACON_COMMITMENT_PATH),
+            Some(&files.commitment_sha256),
+        ),
+        read_beacon_file(Path::new(BEACON_REVEAL_PATH), files.reveal_sha256.as_ref()),
+    )
+}
+
+/// Derives the toxic waste of a beacon contribution from the beacon
+/// randomness (aggregated, for a range of rounds) and the salt of the
+/// commitment. This is the scalar the update derives from the entropy string
+/// `hex(randomness) || hex(salt)`, without OS randomness:
+/// `Scalar::random(ChaCha20Rng::from_seed(Blake2b-512(hex(randomness) ||
+/// hex(salt))[..32]))`
+pub fn beacon_scalar(randomness: &[u8], salt: &[u8]) -> Scalar {
+    let mut hasher = Blake2b512::new();
+    hasher.update(hex::encode(randomness));
+    hasher.update(hex::encode(salt));
+
+    let seed: [u8; 32] = hasher.finalize()[0..32].try_into().unwrap();
+    Scalar::random(ChaCha20Rng::from_seed(seed))
+}
+
+#[cfg(test)]
+mod drand_tests {
+    use rand_core::OsRng;
+
+    use crate::{
+        drand::{
+            beacon_scalar, ChainInfo, ChainMetadata, DEFAULT_CHAIN_HASH, DEFAULT_CHAIN_PUBLIC_KEY,
+            DEFAULT_SCHEME,
+        },
+        utils::generate_toxic_waste,
+    };
+
+    #[test]
+    fn beacon_scalar_matches_update() {
+        let (randomness, salt) = ([3u8; 32], [5u8; 16]);
+        let entropy = hex::encode(randomness) + &hex::encode(salt);
+        let (nu, _) =
+            generate_toxic_waste(OsRng, Some(entropy), Some(false), false, &[], &[], false)
+                .unwrap();
+        assert_eq!(beacon_scalar(&randomness, &salt), nu);
+    }
+
+    #[test]
+    fn default_chain_hash() {
+        let mut info = ChainInfo {
+            public_key: DEFAULT_CHAIN_PUBLIC_KEY.to_string(),
+            period: 30,
+            genesis_time: 1595431050,
+            hash: DEFAULT_CHAIN_HASH.to_string(),
+            group_hash: "176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a"
+                .to_string(),
+            scheme_id: DEFAULT_SCHEME.to_string(),
+            metadata: ChainMetadata {
+                beacon_id: "default".to_string(),
+            },
+        };
+        assert!(info.matches(DEFAULT_CHAIN_HASH));
+
+        assert_eq!(info.round_time(1), 1595431050);
+        // Dec 18, 2025, 04:06:30 UTC, as stated in the README
+        assert_eq!(info.round_time(5686659), 1766030790);
+
+        info.period = 3;
+        assert!(!info.matches(DEFAULT_CHAIN_HASH));
+    }
+}
@@ -0,0 +1,93 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Errors of the library.
+//!
+//! Every operation that can fail on its input (opening and creating files
+//! ([crate::utils::open_file]), decoding SRS files and update proofs (e.g.
+//! [crate::ceremony::SRS::from_reader],
+//! [crate::schnorr::UpdateProof::read_from_file]), and the structure and chain
+//! checks (e.g. [crate::ceremony::SRS::verify_structure_with_challenge],
+//! [crate::chain::verify_chain])) returns a [SrsError], so that a library
+//! consumer can recover from it. The CLI reports it and exits with its
+//! [SrsError::exit_code].
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Why an operation of the library failed
+#[derive(Debug, thiserror::Error)]
+pub enum SrsError {
+    /// A file cannot be opened, created, read or written
+    #[error("Failed to {action} file '{path:?}': {source}")]
+    Io {
+        /// What was done with the file (e.g. `open`)
+        action: &'static str,
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// An artifact is not a (canonical) encoding of what it should be
+    #[error("{0}")]
+    Deserialization(String),
+    /// An artifact is well-formed, but a check of it fails
+    #[error("{0}")]
+    Verification(String),
+    /// An update proof does not extend the chain
+    #[error("{0}")]
+    ChainMismatch(String),
+}
+
+impl SrsError {
+    /// Exit code of the CLI for this error: 1 for a failed check (as the
+    /// other verification failures), 2 for an I/O error, 3 for a malformed
+    /// artifact and 4 for a broken chain
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            SrsError::Verification(_) => 1,
+            SrsError::Io { .. } => 2,
+            SrsError::Deserialization(_) => 3,
+            SrsError::ChainMismatch(_) => 4,
+        }
+    }
+
+    /// Maps an I/O error of the file at the given path to a [SrsError::Io],
+    /// e.g. `.map_err(SrsError::io("read", path))`
+    pub fn io<'a>(
+        action: &'static str,
+        path: &'a Path,
+    ) -> impl Fn(io::Error) -> SrsError + Copy + 'a {
+        move |source| SrsError::Io {
+            action,
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+impl From<SrsError> for io::Error {
+    /// Keeps the kind of the I/O errors, so that e.g. a missing file can
+    /// still be told apart
+    fn from(err: SrsError) -> Self {
+        let kind = match &err {
+            SrsError::Io { source, .. } => source.kind(),
+            SrsError::Deserialization(_) => io::ErrorKind::InvalidData,
+            SrsError::Verification(_) | SrsError::ChainMismatch(_) => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, err.to_string())
+    }
+}
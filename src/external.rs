@@ -0,0 +1,77 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transcripts of external powers-of-tau ceremonies.
+//!
+//! The chain of update proofs starts at [tau]_1 of the SRS of another
+//! ceremony over BLS12-381 (the Filecoin SRS for Midnight's ceremony). Each
+//! supported format implements [ExternalTranscript], and [extract_anchor]
+//! extracts and checks the anchor of any of them.
+
+use std::path::Path;
+
+use blstrs::{G1Affine, G2Affine};
+use halo2curves::group::prime::PrimeCurveAffine;
+
+use crate::{ceremony::SRS, error::SrsError, utils::create_file};
+
+/// Number of powers of tau read from an external transcript to extract its
+/// anchor, so that their structure is checked along with it
+pub const ANCHOR_NR_POWERS: usize = 1 << 4;
+
+/// The SRS of an external powers-of-tau ceremony
+pub trait ExternalTranscript: Sized {
+    /// Name of the ceremony
+    const NAME: &'static str;
+
+    /// Location [tau]_1 is extracted to
+    const G1_POINT_PATH: &'static str;
+
+    /// Parses the first `nr_powers` powers of tau in G1, and [tau]_2, of the
+    /// transcript stored at the given path
+    fn parse(path: &Path, nr_powers: usize) -> Result<Self, SrsError>;
+
+    /// The parsed powers of tau in G1, in coefficient form: [1]_1, [tau]_1,
+    /// [tau^2]_1, ...
+    fn tau_powers_g1(&self) -> &[G1Affine];
+
+    /// [tau]_2
+    fn tau_g2(&self) -> G2Affine;
+
+    /// Checks that the parsed powers form an SRS
+    fn validate(&self) -> Result<(), SrsError> {
+        SRS {
+            g1s: self.tau_powers_g1().to_vec(),
+            g2s: [G2Affine::generator(), self.tau_g2()],
+        }
+        .verify_structure()
+    }
+}
+
+/// Extracts [tau]_1 of the transcript stored at the given path to
+/// [ExternalTranscript::G1_POINT_PATH], once the first [ANCHOR_NR_POWERS]
+/// powers are checked, so that it can anchor a chain of update proofs
+pub fn extract_anchor<T: ExternalTranscript>(path: &Path) -> Result<G1Affine, SrsError> {
+    println!("Parsing {} transcript", T::NAME);
+    let transcript = T::parse(path, ANCHOR_NR_POWERS)?;
+    transcript.validate()?;
+
+    let g1_point = transcript.tau_powers_g1()[1];
+    let anchor_path = Path::new(T::G1_POINT_PATH);
+    g1_point
+        .write_raw(&mut create_file(anchor_path)?)
+        .map_err(SrsError::io("write", anchor_path))?;
+    Ok(g1_point)
+}
@@ -17,79 +17,176 @@ use std::{
     path::Path,
 };
 
-use blstrs::{G1Projective, Scalar};
+use blstrs::{G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
 use halo2curves::{
     ff::{Field, PrimeField},
     fft::best_fft,
     group::Curve,
-    serde::SerdeObject,
 };
 
 use crate::{
     ceremony::{G1_SIZE, G2_SIZE},
-    utils::{create_file, open_file, read_g1_point},
+    error::SrsError,
+    external::ExternalTranscript,
+    state::GENESIS_G1_POINT_PATH,
+    utils::{decode_g1_point, decode_g2_point, open_file},
 };
 
-/// Converts Filecoin SRS from evaluation form to coefficient form
-pub fn extract_g1_point_from_filecoin_srs(path: &Path, k: usize) {
-    let mut file = open_file(path);
-
-    // Read the phase1radix2m19 file, the result of running the following script:
-    // https://github.com/filecoin-project/powersoftau/blob/ab8f85c28f04af5a99cfcc93a3b1f74c06f94105/src/bin/create_lagrange.rs
-    // The first three elements correspond to:
-    //
-    // * [alpha]_1
-    // * [beta]_1
-    // * [beta]_2
-    //
-    // We are only interested in [tau]_1, so we ignore these three.
-
-    let nr_powers = 1 << k;
-    let offset: u64 = (G1_SIZE + G1_SIZE + G2_SIZE) as u64;
-    file.seek(SeekFrom::Start(offset)).unwrap();
-
-    println!("Parsing phase1radix2m19 file");
-    let mut g1s: Vec<G1Projective> = Vec::<G1Projective>::with_capacity(nr_powers);
-    let mut bytes = [0u8; G1_SIZE];
-    for _ in 0..nr_powers {
-        file.read_exact(&mut bytes).unwrap();
-        g1s.push(read_g1_point(&bytes).into());
+/// log2 of the number of powers of the Filecoin SRS (phase1radix2m19)
+pub const FILECOIN_LOG2_LEN: usize = 19;
+
+/// The Filecoin SRS, converted from evaluation form to coefficient form
+pub struct FilecoinTranscript {
+    tau_powers_g1: Vec<G1Affine>,
+    tau_g2: G2Affine,
+}
+
+impl FilecoinTranscript {
+    /// Reads the phase1radix2m`k` file stored at the given path, and keeps the
+    /// first `nr_powers` powers of tau in G1
+    pub fn read(path: &Path, k: usize, nr_powers: usize) -> Result<Self, SrsError> {
+        let mut file = open_file(path)?;
+        let io_error = SrsError::io("read", path);
+
+        // Read the phase1radix2m19 file, the result of running the following script:
+        // https://github.com/filecoin-project/powersoftau/blob/ab8f85c28f04af5a99cfcc93a3b1f74c06f94105/src/bin/create_lagrange.rs
+        // The first three elements correspond to:
+        //
+        // * [alpha]_1
+        // * [beta]_1
+        // * [beta]_2
+        //
+        // We are only interested in the powers of tau, so we ignore these three.
+        // They are followed by the 2^k powers of tau in G1, then in G2, in
+        // evaluation form.
+
+        let len = 1 << k;
+        if nr_powers > len {
+            return Err(SrsError::Verification(format!(
+                "The Filecoin SRS has {len} powers, not {nr_powers}"
+            )));
+        }
+        let offset: u64 = (G1_SIZE + G1_SIZE + G2_SIZE) as u64;
+        file.seek(SeekFrom::Start(offset)).map_err(io_error)?;
+
+        let mut g1s: Vec<G1Projective> = Vec::<G1Projective>::with_capacity(len);
+        let mut bytes = [0u8; G1_SIZE];
+        for _ in 0..len {
+            file.read_exact(&mut bytes).map_err(io_error)?;
+            let point = decode_g1_point(&bytes)
+                .ok_or_else(|| SrsError::Deserialization("Failed to read G1 point".to_string()))?;
+            g1s.push(point.into());
+        }
+        let mut g2s: Vec<G2Projective> = Vec::<G2Projective>::with_capacity(len);
+        let mut bytes = [0u8; G2_SIZE];
+        for _ in 0..len {
+            file.read_exact(&mut bytes).map_err(io_error)?;
+            let point = decode_g2_point(&bytes)
+                .ok_or_else(|| SrsError::Deserialization("Failed to read G2 point".to_string()))?;
+            g2s.push(point.into());
+        }
+
+        // Converting points from eval form --> coeff form
+        let omega = Scalar::ROOT_OF_UNITY.pow([1 << (Scalar::S - k as u32) as u64]);
+        best_fft(&mut g1s, omega, k as u32);
+        best_fft(&mut g2s, omega, k as u32);
+
+        let mut tau_powers_g1 = vec![G1Affine::default(); nr_powers];
+        G1Projective::batch_normalize(&g1s[..nr_powers], &mut tau_powers_g1);
+        Ok(FilecoinTranscript {
+            tau_powers_g1,
+            tau_g2: g2s[1].to_affine(),
+        })
     }
+}
 
-    assert_eq!(
-        nr_powers,
-        g1s.len(),
-        "# of read G1 points doesn't match # of expected points"
-    );
+impl ExternalTranscript for FilecoinTranscript {
+    const NAME: &'static str = "Filecoin";
 
-    println!("Converting G1 points from eval form --> coeff form");
-    let omega = Scalar::ROOT_OF_UNITY.pow([1 << (Scalar::S - k as u32) as u64]);
-    best_fft(&mut g1s, omega, k as u32);
+    const G1_POINT_PATH: &'static str = GENESIS_G1_POINT_PATH;
 
-    let g1_point = g1s[1].to_affine();
+    fn parse(path: &Path, nr_powers: usize) -> Result<Self, SrsError> {
+        Self::read(path, FILECOIN_LOG2_LEN, nr_powers)
+    }
+
+    fn tau_powers_g1(&self) -> &[G1Affine] {
+        &self.tau_powers_g1
+    }
 
-    let mut file = create_file(Path::new("./filecoin_srs_g1_point"));
-    g1_point
-        .write_raw(&mut file)
-        .expect("Could not write to file");
+    fn tau_g2(&self) -> G2Affine {
+        self.tau_g2
+    }
 }
 
 #[cfg(test)]
 mod srs_tests {
-    use std::{io::Read, path::Path};
+    use std::{fs, io::Read, path::Path};
+
+    use blstrs::{G1Affine, G2Affine, Scalar};
+    use halo2curves::{
+        ff::{Field, PrimeField},
+        group::{prime::PrimeCurveAffine, Curve},
+        serde::SerdeObject,
+    };
+    use rand_core::OsRng;
 
     use crate::{
         ceremony::{G1_SIZE, G2_SIZE},
-        utils::open_file,
+        filecoin::FilecoinTranscript,
+        utils::{open_file, powers},
     };
 
+    #[test]
+    fn filecoin_transcript_is_converted() {
+        let k = 3;
+        let n = 1 << k;
+        let tau = Scalar::random(OsRng);
+        let omega = Scalar::ROOT_OF_UNITY.pow([1 << (Scalar::S - k as u32) as u64]);
+
+        // L_i(tau) = 1/n sum_j tau^j w^-ij
+        let tau_powers = powers(&tau, n);
+        let n_inv = Scalar::from(n as u64).invert().unwrap();
+        let lagrange: Vec<Scalar> = powers(&omega.invert().unwrap(), n)
+            .iter()
+            .map(|w_inv| {
+                let w_inv_powers = powers(w_inv, n);
+                let sum: Scalar = tau_powers
+                    .iter()
+                    .zip(&w_inv_powers)
+                    .map(|(t, w)| t * w)
+                    .sum();
+                sum * n_inv
+            })
+            .collect();
+
+        let mut bytes = G1Affine::generator().to_raw_bytes();
+        bytes.extend(G1Affine::generator().to_raw_bytes());
+        bytes.extend(G2Affine::generator().to_raw_bytes());
+        for l in &lagrange {
+            bytes.extend((G1Affine::generator() * l).to_affine().to_raw_bytes());
+        }
+        for l in &lagrange {
+            bytes.extend((G2Affine::generator() * l).to_affine().to_raw_bytes());
+        }
+        let path = Path::new("/tmp/test_filecoin_transcript");
+        fs::write(path, bytes).unwrap();
+
+        let transcript = FilecoinTranscript::read(path, k, 4).unwrap();
+        let expected: Vec<G1Affine> = tau_powers[..4]
+            .iter()
+            .map(|t| (G1Affine::generator() * t).to_affine())
+            .collect();
+        assert_eq!(transcript.tau_powers_g1, expected);
+        assert_eq!(transcript.tau_g2, (G2Affine::generator() * tau).to_affine());
+    }
+
     // In order to run this test case, it is required to have the file
     // 'phase1radix2m19' in the project's root directory
     #[test]
     #[ignore = "This test requires having downloaded the phase1radix2m19 file"]
     fn test_phase1radix2m19_byte_structure() {
         let path = Path::new("./phase1radix2m19");
-        let mut file = open_file(path);
+        let mut file = open_file(path).unwrap();
 
         let mut buffer: Vec<u8> = Vec::new();
         let _ = file.read_to_end(&mut buffer);
@@ -0,0 +1,347 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! SRS layouts of other proving stacks.
+//!
+//! Barretenberg (Aztec) splits its SRS into `transcriptNN.dat` files, each
+//! made of a manifest (seven big-endian `u32`), a range of G1 points, the G2
+//! points (in the first transcript only) and the Blake2b-512 checksum of all
+//! the preceding bytes. The generator is implicit: the G1 points start at
+//! [tau]_1 and the only G2 point is [tau]_2. Coordinates are written in
+//! canonical (non-Montgomery) form, as 64-bit limbs from the least to the most
+//! significant, each in big-endian order; G2 coordinates as c0 then c1.
+//!
+//! Barretenberg reads these transcripts over BN254: the exported files keep its
+//! layout with BLS12-381 points (six limbs per coordinate), for tooling of the
+//! Aztec stack parametrized over BLS12-381.
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use blake2::{Blake2b512, Digest};
+use blstrs::{G1Affine, G2Affine};
+use halo2curves::group::prime::PrimeCurveAffine;
+
+use crate::{
+    ceremony::{G1_SIZE, G2_SIZE, SRS},
+    error::SrsError,
+    utils::create_file,
+};
+
+/// Number of G1 points of a full Barretenberg transcript
+pub const BARRETENBERG_POINTS_PER_TRANSCRIPT: usize = 5_040_000;
+
+/// Size of a serialized [BarretenbergManifest]
+const MANIFEST_SIZE: usize = 7 * 4;
+
+/// Size of the checksum closing a transcript
+const CHECKSUM_SIZE: usize = 64;
+
+/// Size of a limb of a coordinate
+const LIMB_SIZE: usize = 8;
+
+/// Header of a Barretenberg transcript
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BarretenbergManifest {
+    pub transcript_number: u32,
+    pub total_transcripts: u32,
+    pub total_g1_points: u32,
+    pub total_g2_points: u32,
+    pub num_g1_points: u32,
+    pub num_g2_points: u32,
+    /// Index (in the whole SRS, from [tau]_1) of the first G1 point
+    pub start_from: u32,
+}
+
+impl BarretenbergManifest {
+    pub fn to_bytes(&self) -> [u8; MANIFEST_SIZE] {
+        let fields = [
+            self.transcript_number,
+            self.total_transcripts,
+            self.total_g1_points,
+            self.total_g2_points,
+            self.num_g1_points,
+            self.num_g2_points,
+            self.start_from,
+        ];
+        let mut bytes = [0u8; MANIFEST_SIZE];
+        for (chunk, field) in bytes.chunks_exact_mut(4).zip(fields) {
+            chunk.copy_from_slice(&field.to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; MANIFEST_SIZE]) -> Self {
+        let mut fields = bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_be_bytes(chunk.try_into().unwrap()));
+        let mut next = || fields.next().unwrap();
+        BarretenbergManifest {
+            transcript_number: next(),
+            total_transcripts: next(),
+            total_g1_points: next(),
+            total_g2_points: next(),
+            num_g1_points: next(),
+            num_g2_points: next(),
+            start_from: next(),
+        }
+    }
+}
+
+/// The manifest of a file of `len` bytes starting with `head`, if it is
+/// consistent with the file being a Barretenberg transcript
+pub(crate) fn barretenberg_manifest(head: &[u8], len: u64) -> Option<BarretenbergManifest> {
+    let manifest = BarretenbergManifest::from_bytes(head.get(..MANIFEST_SIZE)?.try_into().unwrap());
+    let points_len = manifest.num_g1_points as u64 * G1_SIZE as u64
+        + manifest.num_g2_points as u64 * G2_SIZE as u64;
+    (manifest.transcript_number < manifest.total_transcripts
+        && manifest.num_g1_points > 0
+        && manifest.num_g1_points <= manifest.total_g1_points
+        && len == (MANIFEST_SIZE + CHECKSUM_SIZE) as u64 + points_len)
+        .then_some(manifest)
+}
+
+/// Converts between a big-endian coordinate and its Barretenberg encoding
+/// (the same bytes, with the order of the limbs reversed). The conversion is
+/// an involution
+fn swap_limbs(bytes: &[u8]) -> Vec<u8> {
+    bytes.rchunks_exact(LIMB_SIZE).flatten().copied().collect()
+}
+
+/// Barretenberg encoding of a G1 point, which must not be the identity
+fn encode_g1(point: &G1Affine) -> Vec<u8> {
+    assert!(
+        !bool::from(point.is_identity()),
+        "Barretenberg cannot encode the identity"
+    );
+    // The flags of the uncompressed encoding are unset for other points
+    let bytes = point.to_uncompressed();
+    let (x, y) = bytes.split_at(G1_SIZE / 2);
+    [swap_limbs(x), swap_limbs(y)].concat()
+}
+
+fn decode_g1(bytes: &[u8]) -> Option<G1Affine> {
+    let (x, y) = bytes.split_at(G1_SIZE / 2);
+    let uncompressed: [u8; G1_SIZE] = [swap_limbs(x), swap_limbs(y)].concat().try_into().unwrap();
+    Option::<G1Affine>::from(G1Affine::from_uncompressed(&uncompressed))
+        .filter(|point| point.to_uncompressed() == uncompressed)
+}
+
+/// Barretenberg encoding of a G2 point, which must not be the identity
+fn encode_g2(point: &G2Affine) -> Vec<u8> {
+    assert!(
+        !bool::from(point.is_identity()),
+        "Barretenberg cannot encode the identity"
+    );
+    // The uncompressed encoding is x.c1 | x.c0 | y.c1 | y.c0
+    let bytes = point.to_uncompressed();
+    let c = |i: usize| swap_limbs(&bytes[i * G1_SIZE / 2..(i + 1) * G1_SIZE / 2]);
+    [c(1), c(0), c(3), c(2)].concat()
+}
+
+fn decode_g2(bytes: &[u8]) -> Option<G2Affine> {
+    let c = |i: usize| swap_limbs(&bytes[i * G1_SIZE / 2..(i + 1) * G1_SIZE / 2]);
+    let uncompressed: [u8; G2_SIZE] = [c(1), c(0), c(3), c(2)].concat().try_into().unwrap();
+    Option::<G2Affine>::from(G2Affine::from_uncompressed(&uncompressed))
+        .filter(|point| point.to_uncompressed() == uncompressed)
+}
+
+/// Path of the `number`-th transcript in the given directory
+pub fn barretenberg_transcript_path(dir: &Path, number: usize) -> PathBuf {
+    dir.join(format!("transcript{number:02}.dat"))
+}
+
+/// Writes the SRS as Barretenberg transcripts of (at most)
+/// `points_per_transcript` G1 points each in the given directory, and returns
+/// their paths
+pub fn write_barretenberg_transcripts(
+    srs: &SRS,
+    output_dir: &Path,
+    points_per_transcript: usize,
+) -> Result<Vec<PathBuf>, SrsError> {
+    assert!(points_per_transcript > 0);
+    if srs.g1s[0] != G1Affine::generator() {
+        return Err(SrsError::Verification(
+            "The SRS does not start with the generator".to_string(),
+        ));
+    }
+    fs::create_dir_all(output_dir).map_err(|source| SrsError::Io {
+        action: "create",
+        path: output_dir.to_path_buf(),
+        source,
+    })?;
+
+    let g1s = &srs.g1s[1..];
+    let chunks: Vec<&[G1Affine]> = g1s.chunks(points_per_transcript).collect();
+    let mut start_from = 0;
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(number, chunk)| {
+            let num_g2_points = if number == 0 { 1 } else { 0 };
+            let manifest = BarretenbergManifest {
+                transcript_number: number as u32,
+                total_transcripts: chunks.len() as u32,
+                total_g1_points: g1s.len() as u32,
+                total_g2_points: 1,
+                num_g1_points: chunk.len() as u32,
+                num_g2_points,
+                start_from,
+            };
+            start_from += chunk.len() as u32;
+
+            let mut bytes = manifest.to_bytes().to_vec();
+            bytes.extend(chunk.iter().flat_map(encode_g1));
+            if num_g2_points == 1 {
+                bytes.extend(encode_g2(&srs.g2s[1]));
+            }
+            let checksum = Blake2b512::digest(&bytes);
+            bytes.extend(checksum);
+
+            let path = barretenberg_transcript_path(output_dir, number);
+            create_file(&path)?
+                .write_all(&bytes)
+                .map_err(|source| SrsError::Io {
+                    action: "write",
+                    path: path.clone(),
+                    source,
+                })?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Reads the SRS back from the Barretenberg transcripts in the given
+/// directory. Fails if a transcript is missing, malformed or inconsistent
+/// with the others
+pub fn read_barretenberg_transcripts(dir: &Path) -> Result<SRS, SrsError> {
+    let mut g1s = vec![G1Affine::generator()];
+    let mut g2 = None;
+    let mut total_transcripts = 1;
+    let mut number = 0;
+    while number < total_transcripts {
+        let path = barretenberg_transcript_path(dir, number);
+        let malformed = |msg: &str| Err(SrsError::Deserialization(format!("{msg} {path:?}")));
+        let bytes = fs::read(&path).map_err(|source| SrsError::Io {
+            action: "read",
+            path: path.clone(),
+            source,
+        })?;
+        if bytes.len() < MANIFEST_SIZE + CHECKSUM_SIZE {
+            return malformed("Truncated transcript");
+        }
+        let (bytes, checksum) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+        if Blake2b512::digest(bytes).as_slice() != checksum {
+            return malformed("Wrong checksum of");
+        }
+
+        let (manifest, points) = bytes.split_at(MANIFEST_SIZE);
+        let manifest = BarretenbergManifest::from_bytes(manifest.try_into().unwrap());
+        if number == 0 {
+            total_transcripts = manifest.total_transcripts as usize;
+        }
+        if manifest.transcript_number as usize != number
+            || manifest.total_transcripts as usize != total_transcripts
+            || manifest.start_from as usize != g1s.len() - 1
+            || manifest.total_g2_points != 1
+            || manifest.num_g2_points != u32::from(number == 0)
+        {
+            return malformed("Inconsistent manifest of");
+        }
+        let g1_len = manifest.num_g1_points as usize * G1_SIZE;
+        if points.len() != g1_len + manifest.num_g2_points as usize * G2_SIZE {
+            return malformed("Wrong size of");
+        }
+
+        let (g1_bytes, g2_bytes) = points.split_at(g1_len);
+        for bytes in g1_bytes.chunks_exact(G1_SIZE) {
+            match decode_g1(bytes) {
+                Some(point) => g1s.push(point),
+                None => return malformed("Invalid G1 point in"),
+            }
+        }
+        if !g2_bytes.is_empty() {
+            match decode_g2(g2_bytes) {
+                Some(point) => g2 = Some(point),
+                None => return malformed("Invalid G2 point in"),
+            }
+        }
+        if number + 1 == total_transcripts && g1s.len() - 1 != manifest.total_g1_points as usize {
+            return Err(SrsError::Deserialization(format!(
+                "Missing G1 points in {dir:?}"
+            )));
+        }
+        number += 1;
+    }
+
+    Ok(SRS {
+        g1s,
+        g2s: [G2Affine::generator(), g2.unwrap()],
+    })
+}
+
+#[cfg(test)]
+mod formats_tests {
+    use std::{fs, path::Path};
+
+    use rand_core::OsRng;
+
+    use crate::{
+        ceremony::SRS,
+        error::SrsError,
+        formats::{
+            barretenberg_transcript_path, read_barretenberg_transcripts,
+            write_barretenberg_transcripts, BarretenbergManifest,
+        },
+    };
+
+    #[test]
+    fn barretenberg_transcripts_round_trip() {
+        let dir = Path::new("/tmp/test_barretenberg_transcripts");
+        let srs = SRS::generate(1 << 6, OsRng);
+
+        // 63 points, in transcripts of 20, 20, 20 and 3 points
+        let paths = write_barretenberg_transcripts(&srs, dir, 20).unwrap();
+        assert_eq!(paths.len(), 4);
+        let bytes = fs::read(&paths[3]).unwrap();
+        let manifest = BarretenbergManifest::from_bytes(bytes[..28].try_into().unwrap());
+        assert_eq!(
+            manifest,
+            BarretenbergManifest {
+                transcript_number: 3,
+                total_transcripts: 4,
+                total_g1_points: 63,
+                total_g2_points: 1,
+                num_g1_points: 3,
+                num_g2_points: 0,
+                start_from: 60,
+            }
+        );
+        assert_eq!(read_barretenberg_transcripts(dir).unwrap(), srs);
+
+        // Flip a bit of a point of the second transcript
+        let path = barretenberg_transcript_path(dir, 1);
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[100] ^= 1;
+        fs::write(&path, bytes).unwrap();
+        assert!(matches!(
+            read_barretenberg_transcripts(dir),
+            Err(SrsError::Deserialization(_))
+        ));
+    }
+}
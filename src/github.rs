@@ -0,0 +1,346 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal GitHub REST API client, used to open the pull request of a
+//! contribution on behalf of the contributor.
+//!
+//! Files are committed through the contents API, so the resulting commits are
+//! signed by GitHub on behalf of the token owner.
+
+use std::{io::Read, thread, time::Duration};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Value};
+
+use crate::net::agent;
+
+const GITHUB_API: &str = "https://api.github.com";
+
+/// The ceremony repository, as owner/name
+pub const CEREMONY_REPO: &str = "midnightntwrk/midnight-trusted-setup";
+
+/// A file to be committed, given by its path in the repository
+pub struct RepoFile {
+    pub path: String,
+    pub content: Vec<u8>,
+}
+
+/// An open pull request, with the commit it currently points to
+pub struct PullRequest {
+    pub number: u64,
+    pub head_sha: String,
+}
+
+/// A file changed by a pull request
+pub struct PullRequestFile {
+    /// Path of the file in the repository
+    pub path: String,
+    /// Status of the file ("added", "modified", "removed", ...)
+    pub status: String,
+    /// URL of the raw content of the file at the head of the pull request
+    pub raw_url: String,
+}
+
+/// A GitHub API client authenticated with a personal access token
+#[derive(Clone)]
+pub struct GitHub {
+    token: String,
+}
+
+impl GitHub {
+    pub fn new(token: String) -> Self {
+        GitHub { token }
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        agent()
+            .request(method, &format!("{GITHUB_API}{path}"))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .set("Accept", "application/vnd.github+json")
+    }
+
+    fn get(&self, path: &str) -> Result<Value, std::io::Error> {
+        self.request("GET", path)
+            .call()
+            .map_err(|e| match e {
+                ureq::Error::Status(404, _) => std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("GET {path}: not found"),
+                ),
+                e => std::io::Error::other(format!("GET {path} failed: {e}")),
+            })?
+            .into_json()
+    }
+
+    fn send(&self, method: &str, path: &str, body: Value) -> Result<Value, std::io::Error> {
+        self.request(method, path)
+            .send_json(body)
+            .map_err(|e| std::io::Error::other(format!("{method} {path} failed: {e}")))?
+            .into_json()
+    }
+
+    /// Login of the token owner
+    pub fn login(&self) -> Result<String, std::io::Error> {
+        let user = self.get("/user")?;
+        json_str(&user, "login")
+    }
+
+    /// Forks `repo` ("owner/name") into the account of the token owner (if
+    /// not forked yet), brings the fork's `base` branch up to date, commits
+    /// `files` to a new `branch` of the fork and opens a pull request against
+    /// `base`. Returns the URL of the pull request
+    pub fn open_pull_request(
+        &self,
+        repo: &str,
+        base: &str,
+        branch: &str,
+        files: &[RepoFile],
+        title: &str,
+        body: &str,
+    ) -> Result<String, std::io::Error> {
+        let login = self.login()?;
+        let name = repo.split('/').nth(1).unwrap_or(repo);
+        let fork = format!("{login}/{name}");
+
+        // Forks are created asynchronously
+        self.send("POST", &format!("/repos/{repo}/forks"), json!({}))?;
+        let mut attempts = 0;
+        while self.get(&format!("/repos/{fork}")).is_err() {
+            attempts += 1;
+            if attempts > 30 {
+                return Err(std::io::Error::other(format!(
+                    "Fork {fork} is not available"
+                )));
+            }
+            thread::sleep(Duration::from_secs(2));
+        }
+
+        self.send(
+            "POST",
+            &format!("/repos/{fork}/merge-upstream"),
+            json!({ "branch": base }),
+        )?;
+
+        let base_ref = self.get(&format!("/repos/{repo}/git/ref/heads/{base}"))?;
+        let base_sha = json_str(&base_ref["object"], "sha")?;
+        self.send(
+            "POST",
+            &format!("/repos/{fork}/git/refs"),
+            json!({ "ref": format!("refs/heads/{branch}"), "sha": base_sha }),
+        )?;
+
+        for file in files {
+            self.put_file(&fork, branch, file, &format!("Add {}", file.path))?;
+        }
+
+        let pr = self.send(
+            "POST",
+            &format!("/repos/{repo}/pulls"),
+            json!({
+                "title": title,
+                "head": format!("{login}:{branch}"),
+                "base": base,
+                "body": body,
+            }),
+        )?;
+        json_str(&pr, "html_url")
+    }
+
+    /// Commits `file` to `branch` of `repo`, creating or replacing it
+    pub fn put_file(
+        &self,
+        repo: &str,
+        branch: &str,
+        file: &RepoFile,
+        message: &str,
+    ) -> Result<(), std::io::Error> {
+        let path = format!("/repos/{repo}/contents/{}", file.path);
+        let mut body = json!({
+            "message": message,
+            "content": STANDARD.encode(&file.content),
+            "branch": branch,
+        });
+        // Updating an existing file requires the hash of its current blob
+        if let Ok(existing) = self.get(&format!("{path}?ref={branch}")) {
+            body["sha"] = existing["sha"].clone();
+        }
+        self.send("PUT", &path, body).map(|_| ())
+    }
+
+    /// Open pull requests of `repo`
+    pub fn open_pull_requests(&self, repo: &str) -> Result<Vec<PullRequest>, std::io::Error> {
+        let pulls = self.get(&format!("/repos/{repo}/pulls?state=open&per_page=100"))?;
+        pulls
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|pr| {
+                Ok(PullRequest {
+                    number: pr["number"].as_u64().ok_or_else(|| {
+                        std::io::Error::other("Missing 'number' in GitHub response")
+                    })?,
+                    head_sha: json_str(&pr["head"], "sha")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Files changed by the given pull request of `repo`
+    pub fn pull_request_files(
+        &self,
+        repo: &str,
+        number: u64,
+    ) -> Result<Vec<PullRequestFile>, std::io::Error> {
+        let files = self.get(&format!("/repos/{repo}/pulls/{number}/files?per_page=100"))?;
+        files
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|file| {
+                Ok(PullRequestFile {
+                    path: json_str(file, "filename")?,
+                    status: json_str(file, "status")?,
+                    raw_url: json_str(file, "raw_url")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Downloads the content at the given URL, authenticated so that files of
+    /// private forks can be read
+    pub fn download(&self, url: &str) -> Result<Vec<u8>, std::io::Error> {
+        let response = agent()
+            .get(url)
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .call()
+            .map_err(|e| std::io::Error::other(format!("GET {url} failed: {e}")))?;
+        let mut bytes = Vec::new();
+        response.into_reader().read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Posts a comment on the given pull request (or issue) of `repo`
+    pub fn comment(&self, repo: &str, number: u64, body: &str) -> Result<(), std::io::Error> {
+        self.send(
+            "POST",
+            &format!("/repos/{repo}/issues/{number}/comments"),
+            json!({ "body": body }),
+        )
+        .map(|_| ())
+    }
+
+    /// Sets the commit status `context` of the given commit of `repo`
+    pub fn set_status(
+        &self,
+        repo: &str,
+        sha: &str,
+        context: &str,
+        success: bool,
+        description: &str,
+    ) -> Result<(), std::io::Error> {
+        self.send(
+            "POST",
+            &format!("/repos/{repo}/statuses/{sha}"),
+            json!({
+                "state": if success { "success" } else { "failure" },
+                "context": context,
+                "description": description,
+            }),
+        )
+        .map(|_| ())
+    }
+
+    /// Content of the file at `path` of `repo` on branch `base`
+    pub fn file_content(
+        &self,
+        repo: &str,
+        base: &str,
+        path: &str,
+    ) -> Result<Vec<u8>, std::io::Error> {
+        let file = self.get(&format!("/repos/{repo}/contents/{path}?ref={base}"))?;
+        let content: String = json_str(&file, "content")?
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        STANDARD
+            .decode(content)
+            .map_err(|e| std::io::Error::other(format!("Invalid content of {path}: {e}")))
+    }
+
+    /// Paths of all the files of `repo` on branch `base`, in every directory.
+    /// Fails if GitHub truncates the listing, rather than returning part of it
+    pub fn tree_files(&self, repo: &str, base: &str) -> Result<Vec<String>, std::io::Error> {
+        let tree = self.get(&format!("/repos/{repo}/git/trees/{base}?recursive=1"))?;
+        if tree["truncated"].as_bool().unwrap_or(false) {
+            return Err(std::io::Error::other(format!(
+                "The tree of {repo}@{base} is too large to be listed in full"
+            )));
+        }
+        tree["tree"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry["type"] == "blob")
+            .map(|entry| json_str(entry, "path"))
+            .collect()
+    }
+}
+
+fn json_str(value: &Value, key: &str) -> Result<String, std::io::Error> {
+    value[key]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| std::io::Error::other(format!("Missing '{key}' in GitHub response")))
+}
+
+/// Appends a row to the participants table of the given markdown content
+pub fn append_participant_row(
+    participants: &str,
+    name: &str,
+    handle: &str,
+    affiliation: &str,
+    srs_sha256: &str,
+) -> String {
+    let row = format!("| {name:<25} | {handle:<18} | {affiliation:<19} | {srs_sha256} |");
+
+    let mut lines: Vec<&str> = participants.lines().collect();
+    let insert_at = lines
+        .iter()
+        .rposition(|line| line.trim_start().starts_with('|'))
+        .map_or(lines.len(), |i| i + 1);
+    lines.insert(insert_at, &row);
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    content
+}
+
+#[cfg(test)]
+mod github_tests {
+    use crate::github::append_participant_row;
+
+    #[test]
+    fn participant_row_is_appended_to_table() {
+        let participants =
+            "# Participants\n\n| Name | Handle |\n| ---- | ------ |\n| A | a |\n\nFooter\n";
+        let updated = append_participant_row(participants, "B", "b", "", "00");
+
+        let lines: Vec<&str> = updated.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert!(lines[5].starts_with("| B "));
+        assert!(lines[5].ends_with("| 00 |"));
+        assert_eq!(lines[7], "Footer");
+    }
+}
@@ -0,0 +1,128 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Health tests of the entropy inputs of the toxic waste.
+//!
+//! Lightweight versions of the tests of NIST SP 800-90B, run over each input
+//! (byte by byte) before it is used, to catch degenerate ones such as a
+//! held-down key or an all-zero read of an RNG. Passing them does not prove
+//! that an input is random; failing them shows that it is not.
+//!
+//! Every byte is assumed to carry at least 1 bit of entropy, which sets the
+//! cutoffs of the repetition count and adaptive proportion tests for a false
+//! positive probability of 2^-20.
+
+/// Longest run of a repeated byte allowed: `1 + 20 / H`, for H = 1
+pub const REPETITION_CUTOFF: usize = 21;
+
+/// Size of the windows of the adaptive proportion test
+pub const ADAPTIVE_WINDOW: usize = 512;
+
+/// Occurrences of the first byte of a window allowed in that window: the
+/// `1 - 2^-20` quantile of the binomial distribution B(512, 1/2), plus 1
+pub const ADAPTIVE_CUTOFF: usize = 311;
+
+/// Estimated min-entropy (in bits) an input must have as a whole
+pub const MIN_ENTROPY_BITS: f64 = 16.0;
+
+/// Length of the longest run of a repeated byte
+fn longest_run(input: &[u8]) -> usize {
+    input
+        .chunk_by(|a, b| a == b)
+        .map(<[u8]>::len)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Largest number of occurrences of the first byte of a window, over the
+/// (complete or final) windows of the adaptive proportion test
+fn adaptive_proportion(input: &[u8]) -> usize {
+    input
+        .chunks(ADAPTIVE_WINDOW)
+        .map(|window| window.iter().filter(|byte| **byte == window[0]).count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Estimates the min-entropy (in bits) of the input with the most common
+/// value estimator: the upper bound of the 99% confidence interval of the
+/// probability of the most common byte gives a lower bound of the entropy
+/// per byte
+pub fn min_entropy_estimate(input: &[u8]) -> f64 {
+    if input.len() < 2 {
+        return 0.0;
+    }
+    let mut counts = [0usize; 256];
+    for byte in input {
+        counts[*byte as usize] += 1;
+    }
+    let n = input.len() as f64;
+    let p = *counts.iter().max().unwrap() as f64 / n;
+    let p_upper = (p + 2.576 * (p * (1.0 - p) / (n - 1.0)).sqrt()).min(1.0);
+    -p_upper.log2() * n
+}
+
+/// Runs the health tests on an entropy input. Returns why it looks
+/// degenerate, if it does
+pub fn health_check(input: &[u8]) -> Result<(), String> {
+    let run = longest_run(input);
+    if run >= REPETITION_CUTOFF {
+        return Err(format!("a byte is repeated {run} times in a row"));
+    }
+    let occurrences = adaptive_proportion(input);
+    if occurrences >= ADAPTIVE_CUTOFF {
+        return Err(format!(
+            "a byte occurs {occurrences} times in a window of {ADAPTIVE_WINDOW} bytes"
+        ));
+    }
+    let estimate = min_entropy_estimate(input);
+    if estimate < MIN_ENTROPY_BITS {
+        return Err(format!(
+            "its estimated min-entropy is {estimate:.1} bits (< {MIN_ENTROPY_BITS})"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod health_tests {
+    use rand_core::{OsRng, RngCore};
+
+    use crate::health::health_check;
+
+    #[test]
+    fn degenerate_inputs_fail() {
+        let mut random = [0u8; 4096];
+        OsRng.fill_bytes(&mut random);
+        assert!(health_check(&random).is_ok());
+        assert!(health_check(b"keyboard mashing").is_ok());
+
+        assert!(health_check(&[]).is_err());
+        assert!(health_check(&[0u8; 512])
+            .unwrap_err()
+            .contains("repeated 512 times"));
+        assert!(health_check(b"qwertyjjjjjjjjjjjjjjjjjjjjjjjjjjjjjjjuiop").is_err());
+
+        // Short runs, but a biased distribution
+        let biased: Vec<u8> = (0..1024)
+            .map(|i| if i % 4 == 3 { i as u8 } else { 0 })
+            .collect();
+        assert!(health_check(&biased)
+            .unwrap_err()
+            .contains("in a window of 512 bytes"));
+
+        assert!(health_check(b"asdf").unwrap_err().contains("min-entropy"));
+    }
+}
@@ -0,0 +1,170 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Randomness from the hardware, mixed into the toxic waste in addition to
+//! the OS randomness, for contributors who do not fully trust the CSPRNG of
+//! their operating system.
+//!
+//! The random number generator of the CPU is used when available (`RDSEED` on
+//! x86_64, `RNDR` on aarch64). Otherwise, the randomness is collected from the
+//! jitter of the CPU execution time, as in jitterentropy: the timing of a
+//! memory-bound loop is sampled many times and the samples are hashed
+//! together.
+
+use std::{fmt, hint::black_box, time::Instant};
+
+use blake2::{Blake2b512, Digest};
+
+/// Size of the randomness drawn from the hardware
+pub const HARDWARE_INPUT_SIZE: usize = 64;
+
+/// Number of timing samples per byte of jitter randomness, assuming (very
+/// conservatively) 1/8 bit of entropy per sample
+const JITTER_SAMPLES_PER_BYTE: usize = 64;
+
+/// Maximal number of attempts of `RDSEED` or `RNDR` per word, as they fail
+/// when their entropy pool is (transiently) exhausted
+const MAX_RETRIES: usize = 1024;
+
+/// Generator of the hardware randomness
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HardwareGenerator {
+    /// The `RDSEED` instruction of x86_64 CPUs
+    Rdseed,
+    /// The `RNDR` instruction of aarch64 CPUs
+    Rndr,
+    /// The jitter of the CPU execution time
+    Jitter,
+}
+
+impl fmt::Display for HardwareGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HardwareGenerator::Rdseed => write!(f, "RDSEED"),
+            HardwareGenerator::Rndr => write!(f, "RNDR"),
+            HardwareGenerator::Jitter => write!(f, "CPU jitter"),
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "rdseed")]
+unsafe fn rdseed_fill(buffer: &mut [u8]) -> bool {
+    use std::arch::x86_64::_rdseed64_step;
+
+    for chunk in buffer.chunks_mut(8) {
+        let mut word = 0u64;
+        if !(0..MAX_RETRIES).any(|_| unsafe { _rdseed64_step(&mut word) } == 1) {
+            return false;
+        }
+        chunk.copy_from_slice(&word.to_le_bytes()[..chunk.len()]);
+    }
+    true
+}
+
+#[cfg(target_arch = "aarch64")]
+fn rndr_fill(buffer: &mut [u8]) -> bool {
+    for chunk in buffer.chunks_mut(8) {
+        let mut word = 0u64;
+        let filled = (0..MAX_RETRIES).any(|_| {
+            let ok: u64;
+            // RNDR (encoded as a system register, as older assemblers do not
+            // know its name) sets the Z flag if it failed to return a number
+            unsafe {
+                std::arch::asm!(
+                    "mrs {word}, s3_3_c2_c4_0",
+                    "cset {ok}, ne",
+                    word = out(reg) word,
+                    ok = out(reg) ok,
+                    options(nomem, nostack),
+                )
+            };
+            ok == 1
+        });
+        if !filled {
+            return false;
+        }
+        chunk.copy_from_slice(&word.to_le_bytes()[..chunk.len()]);
+    }
+    true
+}
+
+/// Fills the buffer with the random number generator of the CPU, if there is
+/// one and it works
+fn cpu_randomness(buffer: &mut [u8]) -> Option<HardwareGenerator> {
+    #[cfg(target_arch = "x86_64")]
+    if std::arch::is_x86_feature_detected!("rdseed") && unsafe { rdseed_fill(buffer) } {
+        return Some(HardwareGenerator::Rdseed);
+    }
+    #[cfg(target_arch = "aarch64")]
+    if std::arch::is_aarch64_feature_detected!("rand") && rndr_fill(buffer) {
+        return Some(HardwareGenerator::Rndr);
+    }
+    let _ = buffer;
+    None
+}
+
+/// Fills the buffer with randomness collected from the jitter of the CPU
+/// execution time
+pub fn jitter_randomness(buffer: &mut [u8]) {
+    let mut memory = vec![0u8; 1 << 16];
+    let mut hasher = Blake2b512::new();
+    for (counter, chunk) in buffer.chunks_mut(64).enumerate() {
+        hasher.update((counter as u64).to_le_bytes());
+        let mut previous = Instant::now();
+        let mut index = 0usize;
+        for _ in 0..chunk.len() * JITTER_SAMPLES_PER_BYTE {
+            // Memory accesses spread over the cache make the timing vary
+            for _ in 0..64 {
+                index = (index + 4099) % memory.len();
+                memory[index] = black_box(memory[index].wrapping_add(1));
+            }
+            let now = Instant::now();
+            hasher.update(now.duration_since(previous).as_nanos().to_le_bytes());
+            previous = now;
+        }
+        let output = hasher.finalize_reset();
+        chunk.copy_from_slice(&output[..chunk.len()]);
+    }
+}
+
+/// Fills the buffer with randomness from the hardware: the random number
+/// generator of the CPU if available, its execution jitter otherwise. Returns
+/// the generator used
+pub fn hardware_randomness(buffer: &mut [u8]) -> HardwareGenerator {
+    cpu_randomness(buffer).unwrap_or_else(|| {
+        jitter_randomness(buffer);
+        HardwareGenerator::Jitter
+    })
+}
+
+#[cfg(test)]
+mod hwrng_tests {
+    use crate::hwrng::{hardware_randomness, jitter_randomness, HARDWARE_INPUT_SIZE};
+
+    #[test]
+    fn hardware_randomness_varies() {
+        let mut first = [0u8; HARDWARE_INPUT_SIZE];
+        let mut second = [0u8; HARDWARE_INPUT_SIZE];
+        jitter_randomness(&mut first);
+        jitter_randomness(&mut second);
+        assert_ne!(first, second);
+
+        hardware_randomness(&mut first);
+        hardware_randomness(&mut second);
+        assert_ne!(first, [0u8; HARDWARE_INPUT_SIZE]);
+        assert_ne!(first, second);
+    }
+}
@@ -0,0 +1,265 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Journal of the operations run against the ceremony directory.
+//!
+//! Every run of `srs_utils` appends an entry to `./journal.jsonl`, one JSON
+//! object per line: its arguments, when it started and how long it took, the
+//! files it read and wrote with their SHA-256 digests, and its result. It is
+//! meant for the operational history of the audit package, not for
+//! debugging.
+//!
+//! Each entry records the SHA-256 digest of the previous line, so that
+//! removing, reordering or editing entries breaks the chain, which
+//! [verify_journal] checks. The journal is not written in auditor mode, whose
+//! log records the run instead.
+
+use std::{
+    collections::BTreeSet,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, OnceLock, PoisonError,
+    },
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::utils::is_stdin;
+
+/// Default location of the journal
+pub const JOURNAL_PATH: &str = "./journal.jsonl";
+
+/// A file read or written by an operation
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileRecord {
+    pub path: String,
+    /// SHA-256 digest (hex) of the file once the operation is done, if it
+    /// can be read (not for the standard input)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+/// An entry of the journal
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Start of the operation, in milliseconds since the Unix epoch
+    pub start_ms: u64,
+    pub duration_ms: u64,
+    /// Command line of the operation
+    pub args: Vec<String>,
+    pub inputs: Vec<FileRecord>,
+    pub outputs: Vec<FileRecord>,
+    /// "ok", or how the operation failed
+    pub result: String,
+    /// SHA-256 digest (hex) of the previous line of the journal (zeros for
+    /// the first one)
+    pub previous: String,
+}
+
+/// SHA-256 digest (hex) of a line of the journal, without its line ending
+fn line_sha256(line: &str) -> String {
+    hex::encode(Sha256::digest(line.as_bytes()))
+}
+
+/// Appends the entry to the journal at the given path, chaining it to the
+/// last line
+pub fn append_entry(path: &Path, mut entry: JournalEntry) {
+    entry.previous = match fs::read_to_string(path) {
+        Ok(journal) => journal
+            .lines()
+            .last()
+            .map_or_else(|| hex::encode([0u8; 32]), line_sha256),
+        Err(_) => hex::encode([0u8; 32]),
+    };
+    let line = serde_json::to_string(&entry).unwrap();
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{line}").and_then(|_| file.sync_all()))
+        .unwrap_or_else(|err| panic!("Could not write to the journal {path:?}: {err}"));
+}
+
+/// Checks the chain of the journal at the given path, and returns its number
+/// of entries, or the first entry breaking it
+pub fn verify_journal(path: &Path) -> Result<usize, String> {
+    let journal =
+        fs::read_to_string(path).unwrap_or_else(|err| panic!("Cannot read {path:?}: {err}"));
+    let mut previous = hex::encode([0u8; 32]);
+    for (i, line) in journal.lines().enumerate() {
+        let entry: JournalEntry = serde_json::from_str(line)
+            .map_err(|err| format!("Entry {} is malformed: {err}", i + 1))?;
+        if entry.previous != previous {
+            return Err(format!(
+                "Entry {} does not follow the previous one: an entry was removed, \
+                 reordered or edited",
+                i + 1
+            ));
+        }
+        previous = line_sha256(line);
+    }
+    Ok(journal.lines().count())
+}
+
+/// The operation being journaled
+struct Session {
+    path: PathBuf,
+    start: SystemTime,
+    started: Instant,
+    inputs: Mutex<BTreeSet<PathBuf>>,
+    outputs: Mutex<BTreeSet<PathBuf>>,
+    finished: AtomicBool,
+}
+
+static SESSION: OnceLock<Session> = OnceLock::new();
+
+/// Starts journaling the current operation into the journal at the given
+/// path. A panic finishes it as failed. Panics if it is already started
+pub fn start_journal(path: &Path) {
+    let session = Session {
+        path: path.to_path_buf(),
+        start: SystemTime::now(),
+        started: Instant::now(),
+        inputs: Mutex::default(),
+        outputs: Mutex::default(),
+        finished: AtomicBool::new(false),
+    };
+    assert!(
+        SESSION.set(session).is_ok(),
+        "The journal is already started"
+    );
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        finish_journal(&format!("panicked: {info}"));
+        default_hook(info);
+    }));
+}
+
+/// Records that the operation reads the file at the given path
+pub fn journal_input(path: &Path) {
+    if let Some(session) = SESSION.get() {
+        let mut inputs = session
+            .inputs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        inputs.insert(path.to_path_buf());
+    }
+}
+
+/// Records that the operation writes the file at the given path
+pub fn journal_output(path: &Path) {
+    if let Some(session) = SESSION.get() {
+        let mut outputs = session
+            .outputs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        outputs.insert(path.to_path_buf());
+    }
+}
+
+/// The record of a file, hashed without going through the (journaled)
+/// readers of the crate
+fn file_record(path: &Path) -> FileRecord {
+    let sha256 = (!is_stdin(path))
+        .then(|| {
+            let mut hasher = Sha256::new();
+            File::open(path)
+                .and_then(|mut file| io::copy(&mut file, &mut hasher))
+                .ok()
+                .map(|_| hex::encode(hasher.finalize()))
+        })
+        .flatten();
+    FileRecord {
+        path: path.display().to_string(),
+        sha256,
+    }
+}
+
+/// Appends the entry of the current operation, with the given result, to the
+/// journal (if it is started). Only the first call has an effect
+pub fn finish_journal(result: &str) {
+    let Some(session) = SESSION.get() else {
+        return;
+    };
+    if session.finished.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let records = |files: &Mutex<BTreeSet<PathBuf>>| -> Vec<FileRecord> {
+        let files = files.lock().unwrap_or_else(PoisonError::into_inner);
+        files.iter().map(|path| file_record(path)).collect()
+    };
+    append_entry(
+        &session.path,
+        JournalEntry {
+            start_ms: session
+                .start
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_millis() as u64),
+            duration_ms: session.started.elapsed().as_millis() as u64,
+            args: std::env::args().collect(),
+            inputs: records(&session.inputs),
+            outputs: records(&session.outputs),
+            result: result.to_string(),
+            previous: String::new(),
+        },
+    );
+}
+
+/// Finishes the journal with the given exit code, then exits
+pub fn exit(code: i32) -> ! {
+    finish_journal(&format!("exited with code {code}"));
+    std::process::exit(code)
+}
+
+#[cfg(test)]
+mod journal_tests {
+    use std::{fs, path::Path};
+
+    use crate::journal::{append_entry, verify_journal, JournalEntry};
+
+    #[test]
+    fn entries_are_chained() {
+        let path = Path::new("/tmp/test_journal.jsonl");
+        let _ = fs::remove_file(path);
+        let entry = |command: &str| JournalEntry {
+            start_ms: 0,
+            duration_ms: 0,
+            args: vec!["srs_utils".to_string(), command.to_string()],
+            inputs: vec![],
+            outputs: vec![],
+            result: "ok".to_string(),
+            previous: String::new(),
+        };
+        for command in ["hash", "verify-chain", "update"] {
+            append_entry(path, entry(command));
+        }
+        assert_eq!(verify_journal(path), Ok(3));
+
+        let journal = fs::read_to_string(path).unwrap();
+        let lines: Vec<&str> = journal.lines().collect();
+        let without_second = format!("{}\n{}\n", lines[0], lines[2]);
+        fs::write(path, without_second).unwrap();
+        assert!(verify_journal(path).unwrap_err().starts_with("Entry 2"));
+
+        let edited = journal.replacen("verify-chain", "verify-structure", 1);
+        fs::write(path, edited).unwrap();
+        assert!(verify_journal(path).unwrap_err().starts_with("Entry 3"));
+    }
+}
@@ -0,0 +1,148 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! KZG commitments with the SRS.
+//!
+//! The structure checks show that the SRS is made of powers of the same tau;
+//! [kzg_selftest] shows that it works for its intended purpose: it commits to
+//! random polynomials, opens them at random points and verifies the openings
+//! with the G2 points, e([p(X)]_1 - [p(z)]_1, [1]_2) = e([q(X)]_1, [tau]_2 -
+//! [z]_2) where q(X) = (p(X) - p(z)) / (X - z).
+
+use blstrs::{pairing, G1Affine, G2Affine, Scalar};
+use halo2curves::{
+    ff::Field,
+    group::{prime::PrimeCurveAffine, Curve},
+};
+use rand_core::OsRng;
+use serde_json::json;
+
+use crate::{audit::audit_check, msm::MsmConfig};
+
+/// Commits to the polynomial of the given coefficients, of degree less than
+/// the number of G1 points `g1s`
+pub fn commit(g1s: &[G1Affine], coeffs: &[Scalar], msm: &MsmConfig) -> G1Affine {
+    assert!(
+        coeffs.len() <= g1s.len(),
+        "The SRS is too short to commit to a polynomial of degree {}",
+        coeffs.len() - 1
+    );
+    msm.msm(coeffs, &g1s[..coeffs.len()]).to_affine()
+}
+
+/// Opens the polynomial of the given coefficients at `z`: returns p(z) and
+/// the commitment to the quotient (p(X) - p(z)) / (X - z)
+pub fn open(
+    g1s: &[G1Affine],
+    coeffs: &[Scalar],
+    z: &Scalar,
+    msm: &MsmConfig,
+) -> (Scalar, G1Affine) {
+    // Synthetic division by X - z, from the leading coefficient
+    let mut quotient = vec![Scalar::ZERO; coeffs.len().saturating_sub(1)];
+    let mut value = Scalar::ZERO;
+    for (i, coeff) in coeffs.iter().enumerate().rev() {
+        if i > 0 {
+            quotient[i - 1] = value * z + coeff;
+            value = quotient[i - 1];
+        } else {
+            value = value * z + coeff;
+        }
+    }
+    (value, commit(g1s, &quotient, msm))
+}
+
+/// Whether `proof` opens `commitment` to `value` at `z`, with the G2 points
+/// [1]_2 and [tau]_2
+pub fn verify_opening(
+    g2s: &[G2Affine; 2],
+    commitment: &G1Affine,
+    z: &Scalar,
+    value: &Scalar,
+    proof: &G1Affine,
+) -> bool {
+    let lhs = (commitment.to_curve() - G1Affine::generator() * value).to_affine();
+    let rhs = (g2s[1].to_curve() - g2s[0] * z).to_affine();
+    pairing(&lhs, &g2s[0]) == pairing(proof, &rhs)
+}
+
+/// Commits to `nr_polynomials` random polynomials of the highest degree the
+/// G1 points allow, opens each at a random point and verifies the opening.
+/// A wrong value must be rejected too, so that the check is not vacuous.
+/// Returns why it fails, if it does
+pub fn kzg_selftest(
+    g1s: &[G1Affine],
+    g2s: &[G2Affine; 2],
+    nr_polynomials: usize,
+    msm: &MsmConfig,
+) -> Result<(), String> {
+    let mut result = Ok(());
+    for i in 0..nr_polynomials {
+        let coeffs: Vec<Scalar> = (0..g1s.len()).map(|_| Scalar::random(OsRng)).collect();
+        let z = Scalar::random(OsRng);
+        let commitment = commit(g1s, &coeffs, msm);
+        let (value, proof) = open(g1s, &coeffs, &z, msm);
+        if !verify_opening(g2s, &commitment, &z, &value, &proof) {
+            result = Err(format!("The opening of polynomial {i} is rejected"));
+        } else if verify_opening(g2s, &commitment, &z, &(value + Scalar::ONE), &proof) {
+            result = Err(format!("A wrong opening of polynomial {i} is accepted"));
+        }
+        if result.is_err() {
+            break;
+        }
+    }
+    audit_check(
+        "kzg-selftest",
+        json!({
+            "degree": g1s.len() - 1,
+            "nr_polynomials": nr_polynomials,
+            "tau_g2": hex::encode(g2s[1].to_compressed()),
+        }),
+        result.is_ok(),
+    );
+    result
+}
+
+#[cfg(test)]
+mod kzg_tests {
+    use blstrs::{G2Affine, Scalar};
+    use halo2curves::{
+        ff::Field,
+        group::{prime::PrimeCurveAffine, Curve},
+    };
+    use rand_core::OsRng;
+
+    use crate::{
+        ceremony::SRS,
+        kzg::{commit, kzg_selftest, open},
+        msm::MsmConfig,
+    };
+
+    #[test]
+    fn openings_are_verified() {
+        let msm = MsmConfig::default();
+        let mut srs = SRS::generate(1 << 8, OsRng);
+        assert_eq!(kzg_selftest(&srs.g1s, &srs.g2s, 2, &msm), Ok(()));
+
+        // p(X) = 3 + 2X + X^2 at z = 5, with q(X) = 7 + X
+        let coeffs = [3, 2, 1].map(Scalar::from);
+        let (value, proof) = open(&srs.g1s, &coeffs, &Scalar::from(5), &msm);
+        assert_eq!(value, Scalar::from(38));
+        assert_eq!(proof, commit(&srs.g1s, &[7, 1].map(Scalar::from), &msm));
+
+        srs.g2s[1] = (G2Affine::generator() * Scalar::random(OsRng)).to_affine();
+        assert!(kzg_selftest(&srs.g1s, &srs.g2s, 1, &msm).is_err());
+    }
+}
@@ -0,0 +1,174 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lagrange bases of the SRS over subdomains.
+//!
+//! For m <= k, the Lagrange basis over the 2^m-th roots of unity
+//! `[L_0(tau)]_1, ..., [L_{2^m-1}(tau)]_1` only depends on the first 2^m points
+//! of the coefficient SRS: it is their inverse FFT "in the exponent".
+//!
+//! A claimed basis is verified as in `srs_consistency`, by committing to a
+//! random polynomial in both forms and comparing the commitments.
+
+use std::{
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use ff::{Field, PrimeField};
+use halo2curves::{fft::best_fft, group::Curve, serde::SerdeObject};
+use rand_core::OsRng;
+use rayon::prelude::*;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    audit::{audit_check, read_audited},
+    cache::{check_sidecar_digest, write_sidecar},
+    ceremony::G1_SIZE,
+    error::SrsError,
+    mem::huge_vec,
+    msm::MsmConfig,
+    utils::{create_file, read_g1_point},
+};
+
+/// Generator of the 2^k-th roots of unity
+pub fn root_of_unity(k: u32) -> Scalar {
+    assert!(k <= Scalar::S, "There are no 2^{k}-th roots of unity");
+    Scalar::ROOT_OF_UNITY.pow([1u64 << (Scalar::S - k)])
+}
+
+/// Derives the Lagrange basis over the 2^k-th roots of unity from the first
+/// 2^k points of the coefficient SRS `g1s`
+pub fn lagrange_basis(g1s: &[G1Affine], k: u32) -> Vec<G1Affine> {
+    let n = 1usize << k;
+    assert!(
+        g1s.len() >= n,
+        "The SRS has {} points, a subdomain of size 2^{k} needs {n}",
+        g1s.len()
+    );
+
+    let mut points: Vec<G1Projective> = huge_vec(n);
+    points.par_extend(g1s[..n].par_iter().map(G1Projective::from));
+    best_fft(&mut points, root_of_unity(k).invert().unwrap(), k);
+
+    let n_inv = Scalar::from(n as u64).invert().unwrap();
+    points.par_iter_mut().for_each(|point| *point *= n_inv);
+
+    let mut lagrange = huge_vec(n);
+    lagrange.resize(n, G1Affine::default());
+    G1Projective::batch_normalize(&points, &mut lagrange);
+    lagrange
+}
+
+/// Whether `lagrange` is the Lagrange basis over the 2^k-th roots of unity of
+/// the coefficient SRS `g1s`. This check would fail with overwhelming
+/// probability if it is not
+pub fn is_lagrange_basis(g1s: &[G1Affine], lagrange: &[G1Affine], k: u32, msm: &MsmConfig) -> bool {
+    let n = 1usize << k;
+    let passed = g1s.len() >= n && lagrange.len() == n && commitments_agree(g1s, lagrange, k, msm);
+    audit_check(
+        "lagrange-basis",
+        json!({
+            "log2_len": k,
+            "nr_g1_points": g1s.len(),
+            "nr_lagrange_points": lagrange.len(),
+        }),
+        passed,
+    );
+    passed
+}
+
+/// Whether a random polynomial of degree < 2^k has the same commitment in
+/// coefficient form (with `g1s`) and in Lagrange form (with `lagrange`)
+fn commitments_agree(g1s: &[G1Affine], lagrange: &[G1Affine], k: u32, msm: &MsmConfig) -> bool {
+    let n = 1usize << k;
+
+    // Commit to a uniformly random polynomial of degree < n in both forms
+    let mut random_poly: Vec<Scalar> = (0..n)
+        .into_par_iter()
+        .map(|_| Scalar::random(OsRng))
+        .collect();
+    let com_coeff = msm.msm(&random_poly, &g1s[..n]);
+
+    best_fft(&mut random_poly, root_of_unity(k), k);
+    let com_lagrange = msm.msm(&random_poly, lagrange);
+
+    com_coeff == com_lagrange
+}
+
+/// Writes the basis to the given file, as the concatenation of the raw
+/// encodings of its points
+pub fn write_lagrange_basis(lagrange: &[G1Affine], path: &Path) -> Result<(), SrsError> {
+    let write_error = SrsError::io("write", path);
+    let mut writer = BufWriter::new(create_file(path)?);
+    let mut hasher = Sha256::new();
+    for point in lagrange {
+        let bytes = point.to_raw_bytes();
+        hasher.update(&bytes);
+        writer.write_all(&bytes).map_err(write_error)?;
+    }
+    writer.flush().map_err(write_error)?;
+    write_sidecar(path, &hasher.finalize().into())
+}
+
+/// Reads a basis written with [write_lagrange_basis], checking it against its
+/// checksum sidecar first, if there is one
+pub fn read_lagrange_basis(path: &Path) -> Result<Vec<G1Affine>, SrsError> {
+    let bytes = read_audited(path).map_err(SrsError::io("read", path))?;
+    check_sidecar_digest(path, &Sha256::digest(&bytes).into())?;
+    if bytes.len() % G1_SIZE != 0 {
+        return Err(SrsError::Deserialization(
+            "Invalid Lagrange basis file size".to_string(),
+        ));
+    }
+    bytes.par_chunks(G1_SIZE).map(read_g1_point).collect()
+}
+
+#[cfg(test)]
+mod lagrange_tests {
+    use blstrs::{G1Affine, G1Projective};
+    use halo2curves::group::prime::PrimeCurveAffine;
+    use rand_core::OsRng;
+
+    use crate::{
+        ceremony::SRS,
+        lagrange::{is_lagrange_basis, lagrange_basis},
+        msm::MsmConfig,
+    };
+
+    #[test]
+    fn subdomain_lagrange_basis() {
+        let srs = SRS::generate(1 << 6, OsRng);
+        let msm = MsmConfig::default();
+
+        for k in [0, 3, 6] {
+            let mut lagrange = lagrange_basis(&srs.g1s, k);
+
+            // The Lagrange polynomials sum to 1
+            let sum = lagrange
+                .iter()
+                .fold(G1Projective::from(G1Affine::identity()), |sum, point| {
+                    sum + G1Projective::from(point)
+                });
+            assert_eq!(G1Affine::from(sum), G1Affine::generator());
+            assert!(is_lagrange_basis(&srs.g1s, &lagrange, k, &msm));
+
+            lagrange[0] = srs.g1s[1];
+            assert!(!is_lagrange_basis(&srs.g1s, &lagrange, k, &msm));
+        }
+    }
+}
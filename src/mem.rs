@@ -0,0 +1,398 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Memory of the large point buffers.
+//!
+//! The G1 points of an SRS take several GB, and the MSMs and FFTs over them
+//! suffer from TLB misses with 4 KiB pages. With the `huge-pages` feature, on
+//! Linux, the large buffers are advised (`madvise(MADV_HUGEPAGE)`) to be
+//! backed by transparent huge pages before they are filled. This requires
+//! transparent huge pages to be enabled (`always` or `madvise` in
+//! `/sys/kernel/mm/transparent_hugepage/enabled`); otherwise, and on other
+//! platforms, the advice is a no-op.
+//!
+//! When the in-memory representation of a [G1Affine] is its raw encoding (as
+//! checked by [g1_layout_is_raw]), the G1 points of an SRS file are read
+//! straight into the memory of their vector, and validated there, instead of
+//! being decoded one by one from a separate byte buffer.
+//!
+//! The peak resident set size (RSS) of each phase of a command can be
+//! recorded with [MemoryReport], and `--max-mem` bounds the memory of the
+//! commands that can stream the SRS instead of loading it (see
+//! [streaming_window]).
+
+use std::{
+    fs,
+    io::{self, Read},
+    mem::MaybeUninit,
+    ptr, slice,
+    sync::{Mutex, OnceLock},
+};
+
+use blstrs::{G1Affine, Scalar};
+use halo2curves::{
+    group::{prime::PrimeCurveAffine, Curve},
+    serde::SerdeObject,
+};
+use rayon::prelude::*;
+
+use crate::{
+    ceremony::{G1_SIZE, READ_CHUNK_SIZE, SCALAR_SIZE, UPDATE_WINDOW_SIZE},
+    progress::ProgressSink,
+    utils::{decode_g1_point, fill_buffer},
+};
+
+/// Size (in bytes) from which a buffer is advised to use huge pages
+pub const HUGE_PAGE_THRESHOLD: usize = 1 << 26;
+
+/// An empty vector with capacity for `n` elements, backed by huge pages if it
+/// is large enough (see the module documentation)
+pub fn huge_vec<T>(n: usize) -> Vec<T> {
+    let mut vec = Vec::with_capacity(n);
+    advise_huge_pages(vec.spare_capacity_mut());
+    vec
+}
+
+#[cfg(all(feature = "huge-pages", target_os = "linux"))]
+fn advise_huge_pages<T>(buffer: &mut [MaybeUninit<T>]) {
+    let len = std::mem::size_of_val(buffer);
+    if len < HUGE_PAGE_THRESHOLD {
+        return;
+    }
+
+    // madvise only takes whole pages
+    // SAFETY: sysconf has no preconditions
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    let start = buffer.as_mut_ptr() as usize;
+    let aligned_start = start.next_multiple_of(page_size);
+    let aligned_end = (start + len) / page_size * page_size;
+    if aligned_end > aligned_start {
+        // SAFETY: the range lies within the allocation of the buffer, and the
+        // advice does not change its content. A failure (e.g. transparent huge
+        // pages being disabled) only means that the advice is not followed
+        unsafe {
+            libc::madvise(
+                aligned_start as *mut libc::c_void,
+                aligned_end - aligned_start,
+                libc::MADV_HUGEPAGE,
+            )
+        };
+    }
+}
+
+#[cfg(not(all(feature = "huge-pages", target_os = "linux")))]
+fn advise_huge_pages<T>(_buffer: &mut [MaybeUninit<T>]) {}
+
+/// Whether a [G1Affine] is laid out in memory as its raw encoding (see
+/// [SerdeObject::to_raw_bytes]). blstrs does not guarantee it, so it is
+/// checked on sample points
+pub fn g1_layout_is_raw() -> bool {
+    let samples = [
+        G1Affine::identity(),
+        G1Affine::generator(),
+        (G1Affine::generator() * Scalar::from(7)).to_affine(),
+    ];
+    std::mem::size_of::<G1Affine>() == G1_SIZE
+        && samples
+            .iter()
+            .all(|point| g1_bytes(slice::from_ref(point)) == point.to_raw_bytes())
+}
+
+/// Bytes of the in-memory representation of the points
+pub fn g1_bytes(points: &[G1Affine]) -> &[u8] {
+    // SAFETY: the points are plain data (the limbs of their coordinates),
+    // viewed as bytes for the lifetime of the borrow
+    unsafe { slice::from_raw_parts(points.as_ptr() as *const u8, std::mem::size_of_val(points)) }
+}
+
+/// Reads `n` raw G1 points from `reader` straight into the memory of their
+/// vector, by chunks of [READ_CHUNK_SIZE] points, each of which is passed to
+/// `on_chunk` as it is read. The points are then validated in place, exactly
+/// as [crate::utils::decode_g1_point] does. Fails on an I/O error, otherwise
+/// returns the points or why they cannot be decoded. Panics if the layout of
+/// the points is not their raw encoding (see [g1_layout_is_raw])
+pub fn read_g1_points_in_place(
+    reader: &mut impl Read,
+    n: usize,
+    mut on_chunk: impl FnMut(&[u8]),
+) -> io::Result<Result<Vec<G1Affine>, String>> {
+    assert!(g1_layout_is_raw(), "G1 points cannot be read in place");
+
+    let mut points: Vec<G1Affine> = huge_vec(n);
+    // SAFETY: the vector has capacity for `n` points, and zeroed limbs are a
+    // valid point representation (the identity)
+    unsafe {
+        ptr::write_bytes(points.as_mut_ptr(), 0, n);
+        points.set_len(n);
+    }
+    // SAFETY: as in [g1_bytes], any byte of the limbs is valid plain data
+    let bytes = unsafe { slice::from_raw_parts_mut(points.as_mut_ptr() as *mut u8, n * G1_SIZE) };
+    for chunk in bytes.chunks_mut(READ_CHUNK_SIZE * G1_SIZE) {
+        if fill_buffer(reader, chunk)? < chunk.len() {
+            return Ok(Err("Unexpected end of the G1 points".to_string()));
+        }
+        on_chunk(chunk);
+    }
+
+    let valid = points
+        .par_iter()
+        .all(|point| decode_g1_point(g1_bytes(slice::from_ref(point))) == Some(*point));
+    Ok(valid
+        .then_some(points)
+        .ok_or_else(|| "Failed to read G1 point".to_string()))
+}
+
+static MAX_MEM: OnceLock<usize> = OnceLock::new();
+
+/// Selects the memory budget (in bytes) of the process. Panics if one is
+/// already selected
+pub fn select_max_mem(bytes: usize) {
+    MAX_MEM
+        .set(bytes)
+        .expect("A memory budget is already selected");
+}
+
+/// The selected memory budget, if any
+pub fn max_mem() -> Option<usize> {
+    MAX_MEM.get().copied()
+}
+
+/// Memory used per G1 point of a streaming window: its bytes, the decoded
+/// point, and the scalar (with the MSM scratch space) multiplying it
+pub const STREAMING_BYTES_PER_POINT: usize = 2 * G1_SIZE + 2 * SCALAR_SIZE;
+
+/// Smallest streaming window, below which the per-window overhead dominates
+pub const MIN_STREAMING_WINDOW: usize = 1 << 10;
+
+/// Largest number of chunks the batched structure check of an SRS loaded in
+/// memory is split into
+pub const MAX_STRUCTURE_CHUNKS: usize = 64;
+
+/// Memory needed to verify the structure of an SRS of `n` G1 points loaded in
+/// memory, in `nr_chunks` chunks: the points, and the powers of the batching
+/// scalar for one chunk (with the MSM scratch space)
+pub fn in_memory_footprint(n: usize, nr_chunks: usize) -> usize {
+    n * G1_SIZE + n.div_ceil(nr_chunks) * 2 * SCALAR_SIZE
+}
+
+/// Smallest number of chunks (a power of two, at most [MAX_STRUCTURE_CHUNKS])
+/// the structure check of an SRS of `n` G1 points loaded in memory must be
+/// split into to fit in `max_mem` bytes, if any
+pub fn structure_chunks(n: usize, max_mem: usize) -> Option<usize> {
+    std::iter::successors(Some(1), |nr_chunks| Some(nr_chunks * 2))
+        .take_while(|nr_chunks| *nr_chunks <= MAX_STRUCTURE_CHUNKS)
+        .find(|nr_chunks| in_memory_footprint(n, *nr_chunks) <= max_mem)
+}
+
+/// Number of G1 points of the streaming windows fitting in `max_mem` bytes
+/// (at most [UPDATE_WINDOW_SIZE], and at least [MIN_STREAMING_WINDOW])
+pub fn streaming_window(max_mem: usize) -> usize {
+    (max_mem / STREAMING_BYTES_PER_POINT).clamp(MIN_STREAMING_WINDOW, UPDATE_WINDOW_SIZE)
+}
+
+/// Parses a memory size in bytes, with an optional binary suffix (`K`, `M`,
+/// `G` or `T`, optionally followed by `B` or `iB`), e.g. `8G` or `512MiB`
+pub fn parse_mem_size(size: &str) -> Result<usize, String> {
+    let size = size.trim();
+    let digits = size.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let unit = match size[digits.len()..].to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        unit => return Err(format!("Unknown memory unit {unit:?}")),
+    };
+    let value: usize = digits
+        .trim()
+        .parse()
+        .map_err(|err| format!("Invalid memory size {size:?}: {err}"))?;
+    value
+        .checked_mul(unit)
+        .ok_or_else(|| format!("Memory size {size:?} out of range"))
+}
+
+/// Formats a number of bytes in MiB
+pub fn format_mem_size(bytes: usize) -> String {
+    format!("{} MiB", bytes >> 20)
+}
+
+/// Peak resident set size of the process (in bytes) since it started, or since
+/// the last [reset_peak_rss]. Only reported on Linux
+pub fn peak_rss() -> Option<usize> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let kb = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<usize>()
+        .ok()?;
+    Some(kb << 10)
+}
+
+/// Resets the peak resident set size to the current one (Linux), so that the
+/// peak of a phase can be measured
+pub fn reset_peak_rss() {
+    let _ = fs::write("/proc/self/clear_refs", "5");
+}
+
+/// Progress sink recording the peak RSS of each phase (see [peak_rss]) while
+/// forwarding the progress to the given sink
+pub struct MemoryReport<'a> {
+    inner: &'a dyn ProgressSink,
+    current: Mutex<Option<String>>,
+    phases: Mutex<Vec<(String, Option<usize>)>>,
+}
+
+impl<'a> MemoryReport<'a> {
+    pub fn new(inner: &'a dyn ProgressSink) -> Self {
+        MemoryReport {
+            inner,
+            current: Mutex::new(None),
+            phases: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The phases finished so far, with their peak RSS if it is known
+    pub fn phases(&self) -> Vec<(String, Option<usize>)> {
+        self.phases.lock().unwrap().clone()
+    }
+
+    /// Prints the peak RSS of each phase, if the platform reports it
+    pub fn print(&self) {
+        let phases = self.phases();
+        if phases.iter().all(|(_, peak)| peak.is_none()) {
+            return;
+        }
+        println!("Peak memory usage:");
+        for (phase, peak) in phases {
+            let peak = peak.map_or("unknown".to_string(), format_mem_size);
+            println!("  {phase}: {peak}");
+        }
+    }
+
+    fn finish_phase(&self) {
+        if let Some(phase) = self.current.lock().unwrap().take() {
+            self.phases.lock().unwrap().push((phase, peak_rss()));
+        }
+    }
+}
+
+impl ProgressSink for MemoryReport<'_> {
+    fn start(&self, len: usize, msg: &str) {
+        self.finish_phase();
+        reset_peak_rss();
+        *self.current.lock().unwrap() = Some(msg.to_string());
+        self.inner.start(len, msg);
+    }
+
+    fn inc(&self, delta: usize) {
+        self.inner.inc(delta);
+    }
+
+    fn finish(&self) {
+        self.finish_phase();
+        self.inner.finish();
+    }
+}
+
+#[cfg(test)]
+mod mem_tests {
+    use blstrs::{G1Affine, Scalar};
+    use halo2curves::{
+        ff::Field,
+        group::{prime::PrimeCurveAffine, Curve},
+        serde::SerdeObject,
+    };
+    use rand_core::OsRng;
+
+    use crate::{
+        ceremony::{G1_SIZE, READ_CHUNK_SIZE},
+        mem::{
+            g1_layout_is_raw, huge_vec, in_memory_footprint, parse_mem_size,
+            read_g1_points_in_place, streaming_window, structure_chunks, MemoryReport,
+            HUGE_PAGE_THRESHOLD, MIN_STREAMING_WINDOW,
+        },
+        progress::{NoProgress, ProgressSink},
+    };
+
+    #[test]
+    fn memory_sizes_are_parsed() {
+        assert_eq!(parse_mem_size("4096"), Ok(4096));
+        assert_eq!(parse_mem_size("512M"), Ok(512 << 20));
+        assert_eq!(parse_mem_size("8GiB"), Ok(8 << 30));
+        assert_eq!(parse_mem_size("2 gb"), Ok(2 << 30));
+        assert!(parse_mem_size("8X").is_err());
+        assert!(parse_mem_size("G").is_err());
+        assert_eq!(streaming_window(0), MIN_STREAMING_WINDOW);
+    }
+
+    #[test]
+    fn structure_chunks_fit_the_budget() {
+        let n = 1 << 20;
+        assert_eq!(structure_chunks(n, in_memory_footprint(n, 1)), Some(1));
+        assert_eq!(structure_chunks(n, in_memory_footprint(n, 1) - 1), Some(2));
+        assert_eq!(structure_chunks(n, in_memory_footprint(n, 8)), Some(8));
+        assert_eq!(structure_chunks(n, n * G1_SIZE), None);
+    }
+
+    #[test]
+    fn phases_are_reported() {
+        let report = MemoryReport::new(&NoProgress);
+        report.start(1, "read");
+        report.start(1, "verify");
+        report.finish();
+        let phases: Vec<String> = report.phases().into_iter().map(|(p, _)| p).collect();
+        assert_eq!(phases, ["read", "verify"]);
+    }
+
+    #[test]
+    fn huge_vectors_are_usable() {
+        let n = HUGE_PAGE_THRESHOLD / 8;
+        let mut vec = huge_vec::<u64>(n);
+        assert!(vec.is_empty() && vec.capacity() >= n);
+        vec.extend(0..n as u64);
+        assert_eq!(vec[n - 1], n as u64 - 1);
+    }
+
+    #[test]
+    fn g1_points_are_read_in_place() {
+        if !g1_layout_is_raw() {
+            return;
+        }
+        let points: Vec<G1Affine> = (0..READ_CHUNK_SIZE + 5)
+            .map(|_| (G1Affine::generator() * Scalar::random(OsRng)).to_affine())
+            .collect();
+        let mut bytes: Vec<u8> = points.iter().flat_map(|p| p.to_raw_bytes()).collect();
+
+        let mut chunks = Vec::new();
+        let read = read_g1_points_in_place(&mut bytes.as_slice(), points.len(), |chunk| {
+            chunks.push(chunk.len() / G1_SIZE)
+        });
+        assert_eq!(read.unwrap().unwrap(), points);
+        assert_eq!(chunks, vec![READ_CHUNK_SIZE, 5]);
+
+        let truncated = &bytes[..bytes.len() - 1];
+        let read = read_g1_points_in_place(&mut &*truncated, points.len(), |_| ());
+        assert!(read.unwrap().is_err());
+
+        // A point off the curve
+        bytes[G1_SIZE + 10] ^= 1;
+        let read = read_g1_points_in_place(&mut bytes.as_slice(), points.len(), |_| ());
+        assert!(read.unwrap().is_err());
+    }
+}
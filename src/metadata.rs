@@ -0,0 +1,259 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Metadata accompanying an update proof.
+//!
+//! The metadata of `proofs/proof<N>` is stored as JSON in
+//! `proofs/proof<N>.json`, and the one of a proof stored in a contribution
+//! directory (`proofs/<NNNN>-<contributor>/proof`) in the `meta.json` file of
+//! that directory. It records the digest of the proof file it belongs
+//! to, so that it cannot be silently moved to another contribution, together
+//! with the hashes of out-of-band evidence (videos, photos, ...) of the
+//! contribution and of the inputs the toxic waste was derived from.
+//!
+//! Contributors choose how much of this they disclose (see [MetadataLevel]),
+//! and the level chosen is recorded, so that auditors know which fields to
+//! expect.
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audit::read_audited,
+    cache::{sha256_file, write_json},
+    error::SrsError,
+    utils::{CONTRIBUTION_METADATA_FILE, CONTRIBUTION_PROOF_FILE},
+};
+
+/// Hash of an external artifact attesting the contribution
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attestation {
+    /// Name of the artifact file (without its directory)
+    pub file_name: String,
+    /// SHA-256 digest (hex) of the artifact
+    pub sha256: String,
+}
+
+impl Attestation {
+    /// Hashes the artifact at the given path
+    pub fn from_file(path: &Path) -> Result<Self, SrsError> {
+        Ok(Attestation {
+            file_name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            sha256: hex::encode(sha256_file(path)?),
+        })
+    }
+}
+
+/// Source of an input of the toxic waste, see
+/// [crate::utils::generate_toxic_waste]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntropySource {
+    /// Typed by the contributor (or passed as argument)
+    Keyboard,
+    /// Randomness of the operating system
+    Os,
+    /// Randomness of the hardware (the RNG of the CPU, or its jitter)
+    Hardware,
+    /// Content of a file
+    File,
+    /// Content of the standard input (e.g. piped from another source)
+    Stdin,
+    /// Public randomness of a beacon (e.g. a Drand round)
+    Beacon,
+}
+
+/// Hash of an input of the toxic waste. Inputs are listed in the order they
+/// are absorbed, so that the derivation procedure can be audited without
+/// revealing the inputs
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntropyInput {
+    pub source: EntropySource,
+    /// Name of the input file (without its directory), for a file input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    /// SHA-256 digest (hex) of the input, unless withheld (see
+    /// [MetadataLevel::Minimal])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+/// What the metadata of a proof discloses
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataLevel {
+    /// The digests of the proof and of the SRS, the ceremony ID, the hashes
+    /// of the attestations, and the source, file name and digest of each
+    /// input of the toxic waste
+    #[default]
+    Full,
+    /// Same as [MetadataLevel::Full], but only the source of each input of
+    /// the toxic waste (the digest of a weak entropy string could be brute
+    /// forced)
+    Minimal,
+    /// Only the digest of the proof, which binds the metadata to it
+    None,
+}
+
+/// Metadata of an update proof
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofMetadata {
+    /// SHA-256 digest (hex) of the proof file this metadata belongs to
+    pub proof_sha256: String,
+    /// What the metadata discloses (metadata written before the levels were
+    /// introduced is complete)
+    #[serde(default)]
+    pub level: MetadataLevel,
+    /// GitHub handle of the contributor, as in the contributor registry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contributor: Option<String>,
+    /// ID (hex) of the ceremony, see
+    /// [crate::state::CeremonyManifest::ceremony_id]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ceremony_id: Option<String>,
+    /// SHA-256 digest (hex) of the SRS produced by the contribution, hashed
+    /// while it was written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srs_sha256: Option<String>,
+    /// Hashes of external artifacts attesting the contribution
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attestations: Vec<Attestation>,
+    /// Hashes of the inputs of the toxic waste, in the order they were used
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entropy: Vec<EntropyInput>,
+    /// CPU features detected on the machine that computed the contribution
+    /// (see [crate::cpu::CpuFeatures]), empty if it had none of them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_features: Option<Vec<String>>,
+}
+
+impl ProofMetadata {
+    /// Metadata for the proof stored at the given path
+    pub fn new(proof_path: &Path) -> Result<Self, SrsError> {
+        Ok(ProofMetadata {
+            proof_sha256: hex::encode(sha256_file(proof_path)?),
+            ..Default::default()
+        })
+    }
+
+    /// Keeps only the fields the given level discloses, and records it
+    pub fn restrict(self, level: MetadataLevel) -> Self {
+        match level {
+            MetadataLevel::Full => ProofMetadata { level, ..self },
+            MetadataLevel::Minimal => ProofMetadata {
+                level,
+                entropy: self
+                    .entropy
+                    .into_iter()
+                    .map(|input| EntropyInput {
+                        source: input.source,
+                        file_name: None,
+                        sha256: None,
+                    })
+                    .collect(),
+                ..self
+            },
+            MetadataLevel::None => ProofMetadata {
+                proof_sha256: self.proof_sha256,
+                level,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Path of the metadata of the proof stored at the given path
+    pub fn path_for(proof_path: &Path) -> PathBuf {
+        if proof_path.file_name() == Some(OsStr::new(CONTRIBUTION_PROOF_FILE)) {
+            proof_path.with_file_name(CONTRIBUTION_METADATA_FILE)
+        } else {
+            proof_path.with_extension("json")
+        }
+    }
+
+    /// Reads the metadata of the proof stored at the given path, if any.
+    /// Fails if the metadata is malformed or does not belong to the proof
+    pub fn read_for(proof_path: &Path) -> Result<Option<Self>, SrsError> {
+        let metadata_path = Self::path_for(proof_path);
+        let Ok(bytes) = read_audited(&metadata_path) else {
+            return Ok(None);
+        };
+        let metadata: Self = serde_json::from_slice(&bytes).map_err(|err| {
+            SrsError::Deserialization(format!("Malformed proof metadata {metadata_path:?}: {err}"))
+        })?;
+
+        if metadata.proof_sha256 != hex::encode(sha256_file(proof_path)?) {
+            return Err(SrsError::Verification(format!(
+                "The metadata {metadata_path:?} does not belong to {proof_path:?}"
+            )));
+        }
+        Ok(Some(metadata))
+    }
+
+    /// Writes the metadata next to the proof stored at the given path
+    pub fn write_for(&self, proof_path: &Path) -> Result<(), SrsError> {
+        write_json(&Self::path_for(proof_path), self)
+    }
+}
+
+#[cfg(test)]
+mod metadata_tests {
+    use crate::metadata::{EntropyInput, EntropySource, MetadataLevel, ProofMetadata};
+
+    #[test]
+    fn levels_restrict_the_metadata() {
+        let metadata = ProofMetadata {
+            proof_sha256: "00".repeat(32),
+            srs_sha256: Some("11".repeat(32)),
+            entropy: vec![EntropyInput {
+                source: EntropySource::File,
+                file_name: Some("dice_rolls.txt".to_string()),
+                sha256: Some("22".repeat(32)),
+            }],
+            cpu_features: Some(vec!["adx".to_string(), "bmi2".to_string()]),
+            ..Default::default()
+        };
+
+        let full = metadata.clone().restrict(MetadataLevel::Full);
+        assert_eq!(full, metadata);
+
+        let minimal = metadata.clone().restrict(MetadataLevel::Minimal);
+        assert_eq!(minimal.level, MetadataLevel::Minimal);
+        assert_eq!(minimal.srs_sha256, metadata.srs_sha256);
+        assert_eq!(minimal.cpu_features, metadata.cpu_features);
+        assert_eq!(minimal.entropy[0].source, EntropySource::File);
+        assert_eq!(minimal.entropy[0].file_name, None);
+        assert_eq!(minimal.entropy[0].sha256, None);
+
+        let none = metadata.clone().restrict(MetadataLevel::None);
+        assert_eq!(none.proof_sha256, metadata.proof_sha256);
+        assert_eq!(none.srs_sha256, None);
+        assert!(none.entropy.is_empty());
+        assert_eq!(none.cpu_features, None);
+        let json = serde_json::to_value(&none).unwrap();
+        assert_eq!(json["level"], "none");
+
+        // Metadata written before the levels were introduced is complete
+        let legacy: ProofMetadata =
+            serde_json::from_str(&format!(r#"{{"proof_sha256":"{}"}}"#, "00".repeat(32))).unwrap();
+        assert_eq!(legacy.level, MetadataLevel::Full);
+    }
+}
@@ -0,0 +1,190 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Multi-scalar multiplication over G1 with a tunable algorithm and window.
+//!
+//! The heuristics behind [msm_best] are tuned for a particular class of
+//! machines and are noticeably suboptimal on some AMD and ARM CPUs. This
+//! module lets the caller pick the algorithm (and, for Pippenger, the bucket
+//! window size) used for the large MSMs of the verification commands.
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use clap::ValueEnum;
+use halo2curves::{
+    ff::PrimeField,
+    group::Group,
+    msm::{msm_best, msm_parallel},
+};
+use rayon::prelude::*;
+
+/// Largest window size accepted for the bucket method; beyond this the
+/// buckets no longer fit in cache and the memory usage explodes.
+pub const MAX_MSM_WINDOW: usize = 20;
+
+/// The MSM algorithm to use
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum MsmAlgo {
+    /// Let halo2curves pick its best algorithm
+    #[default]
+    Best,
+    /// The parallel MSM of halo2curves, chunking the bases across threads
+    Parallel,
+    /// Bucket method (Pippenger) with a configurable window size
+    Pippenger,
+}
+
+/// Configuration of the MSMs performed by the verification commands
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MsmConfig {
+    pub algo: MsmAlgo,
+    /// Window size (in bits) for [MsmAlgo::Pippenger]; derived from the
+    /// number of bases if not provided
+    pub window: Option<usize>,
+}
+
+impl MsmConfig {
+    /// Configuration of `algo`, with the given window size. Fails if the
+    /// window size is out of range, or given for another algorithm than
+    /// [MsmAlgo::Pippenger] (the only one with a window)
+    pub fn new(algo: MsmAlgo, window: Option<usize>) -> Result<Self, String> {
+        match window {
+            Some(_) if algo != MsmAlgo::Pippenger => Err(format!(
+                "The MSM window size only applies to the Pippenger MSM, not to {algo:?}"
+            )),
+            Some(c) if !(1..=MAX_MSM_WINDOW).contains(&c) => Err(format!(
+                "MSM window must be between 1 and {MAX_MSM_WINDOW}, got {c}"
+            )),
+            _ => Ok(MsmConfig { algo, window }),
+        }
+    }
+
+    /// Computes sum_i coeffs[i] * bases[i] with the configured algorithm
+    pub fn msm(&self, coeffs: &[Scalar], bases: &[G1Affine]) -> G1Projective {
+        assert_eq!(coeffs.len(), bases.len(), "MSM length mismatch");
+
+        match self.algo {
+            MsmAlgo::Best => msm_best(coeffs, bases),
+            MsmAlgo::Parallel => msm_parallel(coeffs, bases),
+            MsmAlgo::Pippenger => {
+                let window = self.window.unwrap_or_else(|| default_window(bases.len()));
+                msm_pippenger(coeffs, bases, window)
+            }
+        }
+    }
+}
+
+/// Default window size for `n` bases, ceil(ln(n)), the usual Pippenger
+/// heuristic
+pub fn default_window(n: usize) -> usize {
+    if n < 32 {
+        3
+    } else {
+        ((n as f64).ln().ceil() as usize).min(MAX_MSM_WINDOW)
+    }
+}
+
+/// Returns the `c`-bit digit of the little-endian scalar `bytes` starting at
+/// bit `start`
+fn window_digit(bytes: &[u8; 32], start: usize, c: usize) -> usize {
+    (0..c)
+        .map(|i| start + i)
+        .take_while(|&bit| bit < 256)
+        .fold(0, |digit, bit| {
+            digit | ((((bytes[bit / 8] >> (bit % 8)) & 1) as usize) << (bit - start))
+        })
+}
+
+/// Bucket method MSM with windows of `c` bits; windows are processed in
+/// parallel
+pub fn msm_pippenger(coeffs: &[Scalar], bases: &[G1Affine], c: usize) -> G1Projective {
+    assert!(
+        (1..=MAX_MSM_WINDOW).contains(&c),
+        "MSM window must be between 1 and {MAX_MSM_WINDOW}, got {c}"
+    );
+
+    let scalars: Vec<[u8; 32]> = coeffs.par_iter().map(|s| s.to_bytes_le()).collect();
+    let nr_windows = Scalar::NUM_BITS.div_ceil(c as u32) as usize;
+
+    let window_sums: Vec<G1Projective> = (0..nr_windows)
+        .into_par_iter()
+        .map(|w| {
+            let mut buckets = vec![G1Projective::identity(); (1 << c) - 1];
+            for (scalar, base) in scalars.iter().zip(bases) {
+                let digit = window_digit(scalar, w * c, c);
+                if digit != 0 {
+                    buckets[digit - 1] += base;
+                }
+            }
+
+            // sum_j j * buckets[j - 1], via running sums
+            let mut running_sum = G1Projective::identity();
+            let mut window_sum = G1Projective::identity();
+            for bucket in buckets.into_iter().rev() {
+                running_sum += bucket;
+                window_sum += running_sum;
+            }
+            window_sum
+        })
+        .collect();
+
+    window_sums
+        .into_iter()
+        .rev()
+        .fold(G1Projective::identity(), |acc, window_sum| {
+            (0..c).fold(acc, |acc, _| acc.double()) + window_sum
+        })
+}
+
+#[cfg(test)]
+mod msm_tests {
+    use blstrs::{G1Affine, Scalar};
+    use halo2curves::{
+        ff::Field,
+        group::{prime::PrimeCurveAffine, Curve},
+        msm::msm_best,
+    };
+    use rand_core::OsRng;
+
+    use crate::msm::{msm_pippenger, MsmAlgo, MsmConfig};
+
+    #[test]
+    fn pippenger_matches_msm_best() {
+        let n = 1 << 8;
+        let coeffs: Vec<Scalar> = (0..n).map(|_| Scalar::random(OsRng)).collect();
+        let bases: Vec<G1Affine> = (0..n)
+            .map(|_| (G1Affine::generator() * Scalar::random(OsRng)).to_affine())
+            .collect();
+
+        let expected = msm_best(&coeffs, &bases);
+        for c in [1, 4, 7, 13] {
+            assert_eq!(msm_pippenger(&coeffs, &bases, c), expected);
+        }
+
+        for algo in [MsmAlgo::Best, MsmAlgo::Parallel, MsmAlgo::Pippenger] {
+            let config = MsmConfig { algo, window: None };
+            assert_eq!(config.msm(&coeffs, &bases), expected);
+        }
+    }
+
+    #[test]
+    fn windows_are_only_given_to_pippenger() {
+        assert!(MsmConfig::new(MsmAlgo::Pippenger, Some(13)).is_ok());
+        assert!(MsmConfig::new(MsmAlgo::Best, None).is_ok());
+        assert!(MsmConfig::new(MsmAlgo::Best, Some(13)).is_err());
+        assert!(MsmConfig::new(MsmAlgo::Parallel, Some(13)).is_err());
+        assert!(MsmConfig::new(MsmAlgo::Pippenger, Some(0)).is_err());
+        assert!(MsmConfig::new(MsmAlgo::Pippenger, Some(21)).is_err());
+    }
+}
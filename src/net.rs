@@ -0,0 +1,44 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! HTTP client shared by the online commands (Drand fetch, GitHub automation,
+//! notifications).
+//!
+//! A single agent is used for the whole process, so that connections to the
+//! same host are reused, and every request is bounded by timeouts instead of
+//! hanging on an unresponsive server.
+
+use std::{sync::OnceLock, time::Duration};
+
+use ureq::{Agent, AgentBuilder};
+
+/// Maximum time to establish a connection
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum time without receiving (or sending) any data on a connection
+pub const IO_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The HTTP agent of the process
+pub fn agent() -> &'static Agent {
+    static AGENT: OnceLock<Agent> = OnceLock::new();
+    AGENT.get_or_init(|| {
+        AgentBuilder::new()
+            .timeout_connect(CONNECT_TIMEOUT)
+            .timeout_read(IO_TIMEOUT)
+            .timeout_write(IO_TIMEOUT)
+            .user_agent(concat!("midnight-srs-utils/", env!("CARGO_PKG_VERSION")))
+            .build()
+    })
+}
@@ -0,0 +1,192 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Notifications of verified (or rejected) contributions, sent by the watcher
+//! to the channels the ceremony operations team coordinates in.
+
+use std::{fmt, time::Duration};
+
+use serde_json::{json, Value};
+
+use crate::net::agent;
+
+/// Outcome of the verification of a contribution
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContributionEvent {
+    /// Index of the contribution, as in `proofs/proof<index>`
+    pub index: usize,
+    /// Contributor, as listed in PARTICIPANTS.md, if known
+    pub contributor: Option<String>,
+    /// SHA-256 digest (hex) of the proof file
+    pub proof_sha256: String,
+    /// SHA-256 digest (hex) of the updated SRS, if available
+    pub srs_sha256: Option<String>,
+    /// Transcript hash (hex) of the chain after this contribution
+    pub transcript: String,
+    pub verification_time: Duration,
+    /// Why the contribution was rejected, if it was
+    pub failure: Option<String>,
+}
+
+impl ContributionEvent {
+    /// Fields of the event as (label, value) pairs
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("Contribution", self.index.to_string()),
+            (
+                "Contributor",
+                self.contributor.clone().unwrap_or("unknown".to_string()),
+            ),
+            ("Proof SHA-256", format!("`{}`", self.proof_sha256)),
+        ];
+        if let Some(srs_sha256) = &self.srs_sha256 {
+            fields.push(("SRS SHA-256", format!("`{srs_sha256}`")));
+        }
+        fields.push(("Transcript", format!("`{}`", self.transcript)));
+        fields.push((
+            "Verification time",
+            format!("{:.2?}", self.verification_time),
+        ));
+        if let Some(failure) = &self.failure {
+            fields.push(("Failure", failure.clone()));
+        }
+        fields
+    }
+}
+
+impl fmt::Display for ContributionEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.failure {
+            None => write!(f, "Contribution {} verified", self.index),
+            Some(_) => write!(f, "Contribution {} REJECTED", self.index),
+        }
+    }
+}
+
+/// A channel contribution events are sent to
+pub trait NotificationSink {
+    fn notify(&self, event: &ContributionEvent) -> Result<(), std::io::Error>;
+}
+
+fn post_json(url: &str, body: Value) -> Result<(), std::io::Error> {
+    agent()
+        .post(url)
+        .send_json(body)
+        .map(|_| ())
+        .map_err(|e| std::io::Error::other(format!("Webhook request failed: {e}")))
+}
+
+/// Slack incoming webhook
+pub struct SlackWebhook {
+    pub url: String,
+}
+
+impl SlackWebhook {
+    /// Block Kit message of the event
+    pub fn message(event: &ContributionEvent) -> Value {
+        let fields: Vec<Value> = event
+            .fields()
+            .into_iter()
+            .map(
+                |(label, value)| json!({ "type": "mrkdwn", "text": format!("*{label}*\n{value}") }),
+            )
+            .collect();
+        let emoji = if event.failure.is_none() {
+            ":white_check_mark:"
+        } else {
+            ":x:"
+        };
+
+        json!({
+            "text": event.to_string(),
+            "blocks": [
+                {
+                    "type": "section",
+                    "text": { "type": "mrkdwn", "text": format!("{emoji} *{event}*") },
+                },
+                // Slack allows at most 10 fields per section
+                { "type": "section", "fields": fields },
+            ],
+        })
+    }
+}
+
+impl NotificationSink for SlackWebhook {
+    fn notify(&self, event: &ContributionEvent) -> Result<(), std::io::Error> {
+        post_json(&self.url, Self::message(event))
+    }
+}
+
+/// Discord webhook
+pub struct DiscordWebhook {
+    pub url: String,
+}
+
+impl DiscordWebhook {
+    /// Embed message of the event
+    pub fn message(event: &ContributionEvent) -> Value {
+        let fields: Vec<Value> = event
+            .fields()
+            .into_iter()
+            .map(|(label, value)| json!({ "name": label, "value": value, "inline": false }))
+            .collect();
+        // Green or red side bar
+        let color = if event.failure.is_none() {
+            0x2ecc71
+        } else {
+            0xe74c3c
+        };
+
+        json!({
+            "content": event.to_string(),
+            "embeds": [{ "title": event.to_string(), "color": color, "fields": fields }],
+        })
+    }
+}
+
+impl NotificationSink for DiscordWebhook {
+    fn notify(&self, event: &ContributionEvent) -> Result<(), std::io::Error> {
+        post_json(&self.url, Self::message(event))
+    }
+}
+
+#[cfg(test)]
+mod notify_tests {
+    use std::time::Duration;
+
+    use crate::notify::{ContributionEvent, DiscordWebhook, SlackWebhook};
+
+    #[test]
+    fn messages_contain_the_event() {
+        let event = ContributionEvent {
+            index: 3,
+            contributor: Some("Alice (alice)".to_string()),
+            proof_sha256: "ab".repeat(32),
+            srs_sha256: None,
+            transcript: "cd".repeat(32),
+            verification_time: Duration::from_millis(1500),
+            failure: Some("bad proof".to_string()),
+        };
+
+        let slack = SlackWebhook::message(&event).to_string();
+        let discord = DiscordWebhook::message(&event).to_string();
+        for message in [slack, discord] {
+            assert!(message.contains("Contribution 3 REJECTED"));
+            assert!(message.contains("Alice (alice)"));
+            assert!(message.contains(&"ab".repeat(32)));
+            assert!(message.contains("bad proof"));
+        }
+    }
+}
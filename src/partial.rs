@@ -0,0 +1,318 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verification of the structure of an SRS split across machines.
+//!
+//! The batched check of [crate::ceremony::SRS::verify_structure_with] only
+//! needs S = sum_i r^i [tau^i]_1, which splits into sums over ranges of G1
+//! points. Each machine verifies a range of the SRS with the batching scalar
+//! r derived from a seed shared by the audit team (chosen once the SRS is
+//! fixed), and emits a [PartialAttestation] with its partial sum. [aggregate]
+//! checks that the attestations cover the whole SRS and performs the final
+//! pairing check.
+
+use std::{
+    io::{BufReader, Read, Seek, SeekFrom},
+    ops::Range,
+    path::Path,
+};
+
+use blake2::Blake2b512;
+use blstrs::{pairing, G1Affine, G1Projective, G2Affine, Scalar};
+use halo2curves::{
+    ff::{Field, FromUniformBytes},
+    group::{prime::PrimeCurveAffine, Curve, Group},
+    serde::SerdeObject,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    audit::{audit_check, read_audited},
+    cache::write_json,
+    ceremony::{batch_g1_points, nr_g1_points, G1_SIZE, G2_SIZE, UPDATE_WINDOW_SIZE},
+    error::SrsError,
+    msm::MsmConfig,
+    progress::ProgressSink,
+    release::SrsEncoding,
+    utils::{decode_g1_point, decode_g2_point, open_file, read_g2_point, TranscriptHasher},
+};
+
+/// Domain tag of the derivation of the batching scalar from the seed
+const BATCHING_DOMAIN: &[u8] = b"midnight-srs/range-batching";
+
+/// Parses a range of G1 points, `START..END`
+pub fn parse_range(range: &str) -> Result<Range<usize>, String> {
+    let (start, end) = range
+        .split_once("..")
+        .ok_or_else(|| format!("Expected START..END, got {range:?}"))?;
+    let parse = |bound: &str| {
+        bound
+            .trim()
+            .parse::<usize>()
+            .map_err(|err| format!("Invalid bound {bound:?}: {err}"))
+    };
+    let range = parse(start)?..parse(end)?;
+    if range.is_empty() {
+        return Err(format!("The range {range:?} is empty"));
+    }
+    Ok(range)
+}
+
+/// Batching scalar derived from the seed shared by the machines
+pub fn batching_scalar(seed: &[u8; 32]) -> Scalar {
+    let mut hasher = Blake2b512::init();
+    hasher.absorb(BATCHING_DOMAIN);
+    hasher.absorb(seed);
+    Scalar::from_uniform_bytes(&hasher.squeeze())
+}
+
+/// Result of the verification of a range of G1 points of an SRS. Points are
+/// stored as hex of their raw encoding
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialAttestation {
+    /// Number of G1 points of the whole SRS
+    pub nr_g1_points: usize,
+    /// Verified range of G1 points, `start..end`
+    pub start: usize,
+    pub end: usize,
+    /// Seed (hex) of the batching scalar r
+    pub seed: String,
+    /// sum_i r^i [tau^i]_1 over the range
+    pub sum: String,
+    /// Last G1 point of the range
+    pub last_g1_point: String,
+    /// The G2 points of the SRS, [1]_2 and [tau]_2
+    pub g2s: [String; 2],
+    /// SHA-256 digest (hex) of the bytes of the range
+    pub range_sha256: String,
+}
+
+impl PartialAttestation {
+    /// Writes the attestation to the given file
+    pub fn save(&self, path: &Path) -> Result<(), SrsError> {
+        write_json(path, self)
+    }
+
+    /// Reads the attestation stored in the given file. Fails if it is
+    /// malformed
+    pub fn load(path: &Path) -> Result<Self, SrsError> {
+        let bytes = read_audited(path).map_err(SrsError::io("read", path))?;
+        serde_json::from_slice(&bytes).map_err(|err| {
+            SrsError::Deserialization(format!("Malformed partial attestation {path:?}: {err}"))
+        })
+    }
+}
+
+fn g1_from_hex(hex: &str) -> Result<G1Affine, String> {
+    hex::decode(hex)
+        .ok()
+        .and_then(|bytes| decode_g1_point(&bytes))
+        .ok_or_else(|| format!("Malformed G1 point {hex}"))
+}
+
+fn g2_from_hex(hex: &str) -> Result<G2Affine, String> {
+    hex::decode(hex)
+        .ok()
+        .and_then(|bytes| decode_g2_point(&bytes))
+        .ok_or_else(|| format!("Malformed G2 point {hex}"))
+}
+
+/// Verifies the G1 points `range` of the SRS stored in the given file (that
+/// none is zero, and that the first point of the SRS is the generator), and
+/// attests to their batched sum with the scalar derived from `seed`
+pub fn attest_range(
+    path: &Path,
+    range: Range<usize>,
+    seed: &[u8; 32],
+    msm: &MsmConfig,
+    progress: &dyn ProgressSink,
+) -> Result<PartialAttestation, SrsError> {
+    let n = nr_g1_points(path)?;
+    assert!(
+        range.start < range.end && range.end <= n,
+        "Invalid range {range:?} of an SRS of {n} G1 points"
+    );
+
+    let mut file = open_file(path)?;
+    let mut g2_bytes = [0u8; 2 * G2_SIZE];
+    file.seek(SeekFrom::Start((n * G1_SIZE) as u64))
+        .and_then(|_| file.read_exact(&mut g2_bytes))
+        .map_err(SrsError::io("read", path))?;
+    let g2s = [
+        read_g2_point(&g2_bytes[..G2_SIZE])?,
+        read_g2_point(&g2_bytes[G2_SIZE..])?,
+    ];
+
+    file.seek(SeekFrom::Start((range.start * G1_SIZE) as u64))
+        .map_err(SrsError::io("read", path))?;
+    let mut reader = BufReader::new(file);
+    let mut hasher = Sha256::new();
+    progress.start(range.len(), "Verifying a range of the SRS");
+    let (sum, last_g1_point) = batch_g1_points(
+        path,
+        &mut reader,
+        SrsEncoding::Raw,
+        range.clone(),
+        &batching_scalar(seed),
+        UPDATE_WINDOW_SIZE,
+        msm,
+        &mut hasher,
+        progress,
+    )?;
+    progress.finish();
+
+    Ok(PartialAttestation {
+        nr_g1_points: n,
+        start: range.start,
+        end: range.end,
+        seed: hex::encode(seed),
+        sum: hex::encode(sum.to_affine().to_raw_bytes()),
+        last_g1_point: hex::encode(last_g1_point.to_raw_bytes()),
+        g2s: g2s.map(|point| hex::encode(point.to_raw_bytes())),
+        range_sha256: hex::encode(hasher.finalize()),
+    })
+}
+
+/// Combines partial attestations into the batched pairing check of the whole
+/// SRS. Returns the number of G1 points of the SRS, or the reason the
+/// attestations do not prove a valid structure
+pub fn aggregate(attestations: &[PartialAttestation]) -> Result<usize, String> {
+    let result = check_attestations(attestations);
+    audit_check(
+        "aggregate",
+        json!({
+            "ranges": attestations
+                .iter()
+                .map(|attestation| format!("{}..{}", attestation.start, attestation.end))
+                .collect::<Vec<_>>(),
+            "range_sha256s": attestations
+                .iter()
+                .map(|attestation| &attestation.range_sha256)
+                .collect::<Vec<_>>(),
+        }),
+        result.is_ok(),
+    );
+    result
+}
+
+fn check_attestations(attestations: &[PartialAttestation]) -> Result<usize, String> {
+    let mut attestations: Vec<&PartialAttestation> = attestations.iter().collect();
+    attestations.sort_by_key(|attestation| attestation.start);
+    let first = *attestations.first().ok_or("There are no attestations")?;
+    let n = first.nr_g1_points;
+
+    let mut end = 0;
+    for attestation in &attestations {
+        if (
+            &attestation.seed,
+            &attestation.g2s,
+            attestation.nr_g1_points,
+        ) != (&first.seed, &first.g2s, n)
+        {
+            return Err(format!(
+                "The attestation of {}..{} is not of the same SRS and seed",
+                attestation.start, attestation.end
+            ));
+        }
+        if attestation.start != end || attestation.end <= attestation.start {
+            return Err(format!(
+                "The attestation of {}..{} does not start where the previous one ends ({end})",
+                attestation.start, attestation.end
+            ));
+        }
+        end = attestation.end;
+    }
+    if end != n {
+        return Err(format!("The attestations cover 0..{end}, not 0..{n}"));
+    }
+
+    let seed: [u8; 32] = hex::decode(&first.seed)
+        .ok()
+        .and_then(|seed| seed.try_into().ok())
+        .ok_or("Malformed seed")?;
+    let r = batching_scalar(&seed);
+    let g2_generator = g2_from_hex(&first.g2s[0])?;
+    let tau_g2 = g2_from_hex(&first.g2s[1])?;
+    if g2_generator != G2Affine::generator() {
+        return Err("Expected G2 generator".to_string());
+    }
+    if tau_g2 == G2Affine::identity() || tau_g2 == g2_generator {
+        return Err("[tau]_2 is zero or the generator".to_string());
+    }
+
+    let mut sum = G1Projective::identity();
+    for attestation in &attestations {
+        sum += g1_from_hex(&attestation.sum)?;
+    }
+    let last_g1_point = g1_from_hex(&attestations.last().unwrap().last_g1_point)?;
+
+    // See [crate::ceremony::SRS::verify_structure_file]
+    let r_inv = r.invert().unwrap();
+    let batched_lhs_g1 = (sum - last_g1_point * r.pow_vartime([n as u64 - 1])).to_affine();
+    let batched_rhs_g1 = ((sum - G1Projective::generator()) * r_inv).to_affine();
+    if pairing(&batched_lhs_g1, &tau_g2) != pairing(&batched_rhs_g1, &g2_generator) {
+        return Err("The batched pairing check fails".to_string());
+    }
+    Ok(n)
+}
+
+#[cfg(test)]
+mod partial_tests {
+    use std::path::Path;
+
+    use rand_core::OsRng;
+
+    use crate::{
+        ceremony::SRS,
+        msm::MsmConfig,
+        partial::{aggregate, attest_range, parse_range},
+        progress::NoProgress,
+    };
+
+    #[test]
+    fn ranges_are_parsed() {
+        assert_eq!(parse_range("0..1024"), Ok(0..1024));
+        assert!(parse_range("10..10").is_err());
+        assert!(parse_range("10-20").is_err());
+    }
+
+    #[test]
+    fn partial_attestations_are_aggregated() {
+        let mut srs = SRS::generate(1 << 10, OsRng);
+        let path = Path::new("/tmp/test_partial_attestations");
+        srs.write_to_file(path).unwrap();
+        let seed = [5u8; 32];
+        let msm = MsmConfig::default();
+        let attest = |start, end| attest_range(path, start..end, &seed, &msm, &NoProgress).unwrap();
+
+        let attestations = vec![attest(700, 1024), attest(0, 300), attest(300, 700)];
+        assert_eq!(aggregate(&attestations), Ok(1 << 10));
+        assert!(
+            aggregate(&attestations[..2]).is_err(),
+            "300..700 is missing"
+        );
+        let other_seed = attest_range(path, 300..700, &[6u8; 32], &msm, &NoProgress).unwrap();
+        assert!(
+            aggregate(&[attestations[0].clone(), attestations[1].clone(), other_seed]).is_err()
+        );
+
+        srs.g1s.swap(500, 501);
+        srs.write_to_file(path).unwrap();
+        let attestations = vec![attest(0, 300), attest(300, 700), attest(700, 1024)];
+        assert!(aggregate(&attestations).is_err());
+    }
+}
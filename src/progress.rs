@@ -0,0 +1,136 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Progress reporting for long-running SRS operations.
+//!
+//! Reading, updating and verifying an SRS report their progress into a
+//! [ProgressSink]. The CLI uses [TerminalProgress], which draws indicatif
+//! bars; library consumers (e.g. a web UI) can provide their own sink, or
+//! [NoProgress] to discard progress altogether.
+
+use std::sync::RwLock;
+
+use indicatif::ProgressBar;
+
+use crate::utils::initialize_progress_bar;
+
+/// Receiver of the progress of a long-running operation. An operation is
+/// made of phases, each one consisting of a known number of steps.
+///
+/// Steps may be reported concurrently from several worker threads.
+pub trait ProgressSink: Sync {
+    /// Starts a new phase of `len` steps, described by `msg`
+    fn start(&self, len: usize, msg: &str);
+
+    /// Reports that `delta` more steps of the current phase are done
+    fn inc(&self, delta: usize);
+
+    /// Ends the current phase
+    fn finish(&self);
+}
+
+/// Ignores all progress
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn start(&self, _len: usize, _msg: &str) {}
+
+    fn inc(&self, _delta: usize) {}
+
+    fn finish(&self) {}
+}
+
+/// Displays a terminal progress bar for each phase
+#[derive(Default)]
+pub struct TerminalProgress {
+    bar: RwLock<Option<ProgressBar>>,
+}
+
+impl ProgressSink for TerminalProgress {
+    fn start(&self, len: usize, msg: &str) {
+        let pb = initialize_progress_bar(len, Some(msg.to_string()));
+        if let Some(old) = self.bar.write().unwrap().replace(pb) {
+            old.finish_and_clear();
+        }
+    }
+
+    fn inc(&self, delta: usize) {
+        if let Some(pb) = self.bar.read().unwrap().as_ref() {
+            pb.inc(delta as u64);
+        }
+    }
+
+    fn finish(&self) {
+        if let Some(pb) = self.bar.write().unwrap().take() {
+            pb.finish_and_clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod progress_tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    };
+
+    use blstrs::Scalar;
+    use halo2curves::ff::Field;
+    use rand_core::OsRng;
+
+    use crate::{ceremony::SRS, msm::MsmConfig, progress::ProgressSink, state::CeremonyContext};
+
+    /// Records every phase and the number of steps reported in it
+    #[derive(Default)]
+    struct RecordingSink {
+        phases: Mutex<Vec<(String, usize)>>,
+        steps: AtomicUsize,
+    }
+
+    impl ProgressSink for RecordingSink {
+        fn start(&self, len: usize, msg: &str) {
+            self.steps.store(0, Ordering::SeqCst);
+            self.phases.lock().unwrap().push((msg.to_string(), len));
+        }
+
+        fn inc(&self, delta: usize) {
+            self.steps.fetch_add(delta, Ordering::SeqCst);
+        }
+
+        fn finish(&self) {
+            let (_, len) = self.phases.lock().unwrap().last().cloned().unwrap();
+            assert_eq!(self.steps.load(Ordering::SeqCst), len);
+        }
+    }
+
+    #[test]
+    fn progress_is_reported() {
+        let sink = RecordingSink::default();
+
+        let mut srs = SRS::generate(1 << 8, OsRng);
+        srs.update_with_progress(&CeremonyContext::for_tests(), &Scalar::random(OsRng), &sink);
+        srs.verify_structure_with(&MsmConfig::default(), &sink)
+            .unwrap();
+
+        // The update, then the powers, the two MSMs and the pairings of the
+        // structure check
+        let phases = sink.phases.lock().unwrap();
+        assert_eq!(phases.len(), 5);
+        assert_eq!(phases[0].1, 1 << 8);
+        assert!(phases[1..4].iter().all(|(_, len)| *len == (1 << 8) - 1));
+        assert_eq!(phases[4], ("Computing the pairings".to_string(), 2));
+    }
+}
@@ -0,0 +1,254 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lazy access to the points of an SRS file, for downstream provers.
+//!
+//! A prover often needs only a slice of the powers of tau. [SrsReader] reads
+//! the G1 points it is asked for from the file (written with
+//! [crate::ceremony::SRS::write_to_file]) and decodes them, so that the rest
+//! of the file is neither read nor verified. It does not check the structure
+//! of the SRS, which is verified once and for all with
+//! `srs_utils verify-structure`.
+//!
+//! With the `mmap` feature, [SrsReader] maps the file in memory instead, and
+//! [crate::ceremony::SRS::read_from_file] and the `srs_consistency` binary
+//! also parse the points from a mapping of the file ([map_file]), instead of
+//! copying the whole file into a heap buffer first.
+
+use std::{borrow::Cow, fs::File, ops::Range, path::Path};
+#[cfg(not(feature = "mmap"))]
+use std::{
+    io::{Read, Seek, SeekFrom},
+    sync::Mutex,
+};
+
+use blstrs::{G1Affine, G2Affine};
+use halo2curves::serde::SerdeObject;
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+use rayon::prelude::*;
+
+use crate::{
+    ceremony::{nr_g1_points, G1_SIZE, G2_SIZE},
+    error::SrsError,
+    utils::{decode_g1_point, decode_g2_point, open_file},
+};
+
+/// An SRS file whose points are decoded on demand
+pub struct SrsReader {
+    #[cfg(feature = "mmap")]
+    mmap: Mmap,
+    #[cfg(not(feature = "mmap"))]
+    file: Mutex<File>,
+    nr_g1_points: usize,
+    validate: bool,
+}
+
+impl SrsReader {
+    /// Opens (maps, with the `mmap` feature) the SRS stored at the given path.
+    /// The points are validated as they are decoded (see
+    /// [crate::utils::decode_g1_point]). Fails if the size of the file is
+    /// not the one of an SRS
+    pub fn open(path: &Path) -> Result<Self, SrsError> {
+        let nr_g1_points = nr_g1_points(path)?;
+        let file = open_file(path)?;
+        Ok(Self {
+            #[cfg(feature = "mmap")]
+            mmap: map(&file, path)?,
+            #[cfg(not(feature = "mmap"))]
+            file: Mutex::new(file),
+            nr_g1_points,
+            validate: true,
+        })
+    }
+
+    /// The bytes of the given range of the file
+    #[cfg(feature = "mmap")]
+    fn bytes(&self, range: Range<usize>) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&self.mmap[range])
+    }
+
+    /// The bytes of the given range of the file
+    #[cfg(not(feature = "mmap"))]
+    fn bytes(&self, range: Range<usize>) -> Cow<'_, [u8]> {
+        let mut file = self.file.lock().unwrap();
+        let mut bytes = vec![0u8; range.len()];
+        file.seek(SeekFrom::Start(range.start as u64))
+            .and_then(|_| file.read_exact(&mut bytes))
+            .expect("Cannot read the SRS file");
+        Cow::Owned(bytes)
+    }
+
+    /// Whether the points are validated (canonical encoding, on the curve,
+    /// in the subgroup) as they are decoded. Only skip it for a file whose
+    /// structure was verified, and which cannot have been modified since
+    pub fn with_validation(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Number of G1 points of the SRS
+    pub fn len(&self) -> usize {
+        self.nr_g1_points
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nr_g1_points == 0
+    }
+
+    fn decode_g1(&self, bytes: &[u8]) -> G1Affine {
+        if self.validate {
+            decode_g1_point(bytes).expect("Failed to read G1 point")
+        } else {
+            G1Affine::from_raw_bytes_unchecked(bytes)
+        }
+    }
+
+    /// The `i`-th G1 point, [tau^i]_1
+    pub fn g1_point(&self, i: usize) -> G1Affine {
+        assert!(
+            i < self.nr_g1_points,
+            "The SRS only has {} G1 points",
+            self.nr_g1_points
+        );
+        self.decode_g1(&self.bytes(i * G1_SIZE..(i + 1) * G1_SIZE))
+    }
+
+    /// The G1 points of the given range, decoded in parallel
+    pub fn g1_points(&self, range: Range<usize>) -> Vec<G1Affine> {
+        assert!(
+            range.start <= range.end && range.end <= self.nr_g1_points,
+            "Invalid range {range:?} of an SRS of {} G1 points",
+            self.nr_g1_points
+        );
+        self.bytes(range.start * G1_SIZE..range.end * G1_SIZE)
+            .par_chunks(G1_SIZE)
+            .map(|bytes| self.decode_g1(bytes))
+            .collect()
+    }
+
+    /// Iterates over the G1 points of the given range, decoding them one at
+    /// a time
+    pub fn iter_g1_points(&self, range: Range<usize>) -> impl Iterator<Item = G1Affine> + '_ {
+        range.map(|i| self.g1_point(i))
+    }
+
+    /// The G2 points, [1]_2 and [tau]_2
+    pub fn g2_points(&self) -> [G2Affine; 2] {
+        let offset = self.nr_g1_points * G1_SIZE;
+        [0, 1].map(|i| {
+            let bytes = self.bytes(offset + i * G2_SIZE..offset + (i + 1) * G2_SIZE);
+            if self.validate {
+                decode_g2_point(&bytes).expect("Failed to read G2 point")
+            } else {
+                G2Affine::from_raw_bytes_unchecked(&bytes)
+            }
+        })
+    }
+}
+
+/// Maps the file at the given path in memory, read-only
+#[cfg(feature = "mmap")]
+pub fn map_file(path: &Path) -> Result<Mmap, SrsError> {
+    map(&open_file(path)?, path)
+}
+
+#[cfg(feature = "mmap")]
+fn map(file: &File, path: &Path) -> Result<Mmap, SrsError> {
+    // SAFETY: the map is read-only. Like any reader, it sees the file change
+    // if another process modifies it, which is why the points are validated
+    // by default
+    unsafe { Mmap::map(file) }.map_err(SrsError::io("map", path))
+}
+
+#[cfg(test)]
+mod reader_tests {
+    use std::{fs, path::Path};
+
+    use rand_core::OsRng;
+
+    #[cfg(feature = "mmap")]
+    use crate::{
+        cache::sidecar_path, ceremony::G2_SIZE, error::SrsError, progress::NoProgress,
+        reader::map_file,
+    };
+    use crate::{
+        ceremony::{G1_SIZE, SRS},
+        reader::SrsReader,
+    };
+
+    #[test]
+    fn points_are_read_on_demand() {
+        let srs = SRS::generate(1 << 10, OsRng);
+        let path = Path::new("/tmp/test_srs_reader");
+        srs.write_to_file(path).unwrap();
+
+        let reader = SrsReader::open(path).unwrap();
+        assert_eq!(reader.len(), 1 << 10);
+        assert_eq!(reader.g1_point(7), srs.g1s[7]);
+        assert_eq!(reader.g1_points(100..300), srs.g1s[100..300]);
+        assert!(reader.iter_g1_points(0..5).eq(srs.g1s[..5].iter().copied()));
+        assert_eq!(reader.g2_points(), srs.g2s);
+
+        // The unvalidated points are only read
+        let mut bytes = fs::read(path).unwrap();
+        bytes[5 * G1_SIZE + 10] ^= 1;
+        fs::write(path, bytes).unwrap();
+        let reader = SrsReader::open(path).unwrap();
+        assert_eq!(reader.g1_points(0..5), srs.g1s[..5]);
+        assert!(std::panic::catch_unwind(|| reader.g1_point(5)).is_err());
+        let reader = reader.with_validation(false);
+        assert_ne!(reader.g1_point(5), srs.g1s[5]);
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn mapped_files_are_checked() {
+        let srs = SRS::generate(1 << 6, OsRng);
+        let path = Path::new("/tmp/test_srs_mapped");
+        srs.write_to_file(path).unwrap();
+        assert_eq!(&map_file(path).unwrap()[..], &fs::read(path).unwrap()[..]);
+        assert_eq!(
+            SRS::read_from_file_with_progress(path, &NoProgress).unwrap(),
+            srs
+        );
+
+        // The sidecar is checked against the mapped bytes
+        let mut bytes = fs::read(path).unwrap();
+        bytes[3 * G1_SIZE + 10] ^= 1;
+        fs::write(path, bytes).unwrap();
+        assert!(matches!(
+            SRS::read_from_file_with_progress(path, &NoProgress),
+            Err(SrsError::Verification(_))
+        ));
+
+        // A G2 point off the curve is reported, not panicked on
+        srs.write_to_file(path).unwrap();
+        fs::remove_file(sidecar_path(path)).unwrap();
+        let mut bytes = fs::read(path).unwrap();
+        let len = bytes.len();
+        bytes[len - G2_SIZE - 1] ^= 1;
+        fs::write(path, bytes).unwrap();
+        assert!(matches!(
+            SRS::read_from_file_with_progress(path, &NoProgress),
+            Err(SrsError::Deserialization(msg)) if msg.contains("G2")
+        ));
+
+        assert!(matches!(
+            map_file(Path::new("/tmp/test_srs_mapped_missing")),
+            Err(SrsError::Io { .. })
+        ));
+    }
+}
@@ -0,0 +1,304 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Registry of the expected contributors.
+//!
+//! The registry (`./contributors.json`) lists, for each registered
+//! contributor, their GitHub handle, their public key and the index of the
+//! contribution they are expected to make. A registered contributor signs the
+//! SHA-256 digest of their update proof with a Schnorr signature over G1,
+//! stored in the `signature` file of their contribution directory (see
+//! [crate::utils::ProofEntry]).
+//!
+//! Contributions made before the registry was introduced are neither
+//! registered nor signed, and are not checked against it.
+
+use std::{io::Write, path::Path};
+
+use blake2::Blake2b512;
+use blstrs::{G1Affine, Scalar};
+use halo2curves::{
+    ff::{Field, FromUniformBytes},
+    group::{prime::PrimeCurveAffine, Curve},
+};
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    audit::{audit_check, read_audited},
+    cache::sha256_file,
+    ceremony::{G1_COMPRESSED_SIZE, SCALAR_SIZE},
+    error::SrsError,
+    metadata::ProofMetadata,
+    schnorr::read_compressed_g1_point,
+    utils::{create_file, ProofEntry, TranscriptHasher},
+};
+
+/// Default location of the contributor registry
+pub const CONTRIBUTORS_PATH: &str = "./contributors.json";
+
+/// Domain tag of the transcript of contribution signatures
+const SIGNATURE_DOMAIN: &[u8] = b"midnight-srs/contribution-signature";
+
+/// Size of a serialized [ContributionSignature]: the public key, followed by
+/// the Schnorr signature (a point and a scalar)
+pub const SIGNATURE_SIZE: usize = 2 * G1_COMPRESSED_SIZE + SCALAR_SIZE;
+
+/// Schnorr signature of the SHA-256 digest of an update proof
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContributionSignature {
+    pub public_key: G1Affine,
+    a: G1Affine,
+    z: Scalar,
+}
+
+fn challenge(public_key: &G1Affine, a: &G1Affine, proof_sha256: &[u8; 32]) -> Scalar {
+    let mut hasher = Blake2b512::init();
+    hasher.absorb(SIGNATURE_DOMAIN);
+    hasher.absorb(&public_key.to_compressed());
+    hasher.absorb(&a.to_compressed());
+    hasher.absorb(proof_sha256);
+    Scalar::from_uniform_bytes(&hasher.squeeze())
+}
+
+impl ContributionSignature {
+    /// Signs the digest of an update proof with the given secret key
+    pub fn sign(secret_key: &Scalar, proof_sha256: &[u8; 32]) -> Self {
+        let public_key = (G1Affine::generator() * secret_key).to_affine();
+        let r = Scalar::random(OsRng);
+        let a = (G1Affine::generator() * r).to_affine();
+        let e = challenge(&public_key, &a, proof_sha256);
+        ContributionSignature {
+            public_key,
+            a,
+            z: r + secret_key * e,
+        }
+    }
+
+    /// Whether this is a valid signature of the given digest
+    pub fn is_valid(&self, proof_sha256: &[u8; 32]) -> bool {
+        let e = challenge(&self.public_key, &self.a, proof_sha256);
+        !bool::from(self.public_key.is_identity())
+            && G1Affine::generator() * self.z == self.public_key * e + self.a
+    }
+
+    /// Public key (hex of its compressed encoding), as in the registry
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.public_key.to_compressed())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.public_key.to_compressed().to_vec();
+        bytes.extend(self.a.to_compressed());
+        bytes.extend(self.z.to_bytes_be());
+        bytes
+    }
+
+    /// Deserializes a signature, if the bytes are a (canonical) encoding of
+    /// one
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != SIGNATURE_SIZE {
+            return None;
+        }
+        let (public_key, bytes) = bytes.split_at(G1_COMPRESSED_SIZE);
+        let (a, z) = bytes.split_at(G1_COMPRESSED_SIZE);
+        Some(ContributionSignature {
+            public_key: read_compressed_g1_point(public_key)?,
+            a: read_compressed_g1_point(a)?,
+            z: Option::from(Scalar::from_bytes_be(z.try_into().unwrap()))?,
+        })
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> Result<(), SrsError> {
+        create_file(path)?
+            .write_all(&self.to_bytes())
+            .map_err(SrsError::io("write", path))
+    }
+
+    /// Reads the signature stored at the given path
+    pub fn read_from_file(path: &Path) -> Result<Self, SrsError> {
+        let bytes = read_audited(path).map_err(SrsError::io("read", path))?;
+        Self::from_bytes(&bytes)
+            .ok_or_else(|| SrsError::Deserialization(format!("Malformed signature {path:?}")))
+    }
+}
+
+/// A registered contributor
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contributor {
+    /// GitHub handle
+    pub handle: String,
+    /// Public key (hex of its compressed encoding)
+    pub public_key: String,
+    /// Index of the contribution the contributor is expected to make
+    pub index: usize,
+}
+
+/// The contributor registry
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ContributorRegistry {
+    pub contributors: Vec<Contributor>,
+}
+
+impl ContributorRegistry {
+    /// Loads the registry from the given file, if it exists. Fails if it is
+    /// malformed
+    pub fn load(path: &Path) -> Result<Option<Self>, SrsError> {
+        let Ok(bytes) = read_audited(path) else {
+            return Ok(None);
+        };
+        serde_json::from_slice(&bytes).map(Some).map_err(|err| {
+            SrsError::Deserialization(format!("Malformed contributor registry {path:?}: {err}"))
+        })
+    }
+
+    /// Checks the contribution stored in the given entry against the
+    /// registry, with its metadata if any. Returns the reason of the failure,
+    /// if it fails
+    pub fn check(
+        &self,
+        entry: &ProofEntry,
+        metadata: Option<&ProofMetadata>,
+    ) -> Result<(), String> {
+        let signature = entry
+            .signature_path()
+            .filter(|path| path.exists())
+            .map(|path| ContributionSignature::read_from_file(&path))
+            .transpose()
+            .map_err(|err| err.to_string())?;
+        let names: Vec<&str> = [
+            entry.contributor.as_deref(),
+            metadata.and_then(|metadata| metadata.contributor.as_deref()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let proof_sha256 = sha256_file(&entry.path()).map_err(|err| err.to_string())?;
+        let result = self.check_signature(entry.index, &names, &proof_sha256, signature.as_ref());
+        audit_check(
+            "contributor-signature",
+            json!({
+                "index": entry.index,
+                "proof_sha256": hex::encode(proof_sha256),
+                "signer": signature.as_ref().map(ContributionSignature::public_key_hex),
+            }),
+            result.is_ok(),
+        );
+        result
+    }
+
+    /// Checks that the `index`-th proof, whose contributor is designated by
+    /// `names` (by its contribution directory and metadata), is signed by the
+    /// registered contributor expected at that index
+    fn check_signature(
+        &self,
+        index: usize,
+        names: &[&str],
+        proof_sha256: &[u8; 32],
+        signature: Option<&ContributionSignature>,
+    ) -> Result<(), String> {
+        let expected = self.contributors.iter().find(|c| c.index == index);
+        let signature = match (expected, signature) {
+            // Not registered, e.g. made before the registry was introduced
+            (None, None) => return Ok(()),
+            (Some(expected), None) => {
+                return Err(format!(
+                    "proof{index} is not signed by {}, the registered contributor",
+                    expected.handle
+                ))
+            }
+            (_, Some(signature)) => signature,
+        };
+
+        if !signature.is_valid(proof_sha256) {
+            return Err(format!("The signature of proof{index} is invalid"));
+        }
+        let public_key = signature.public_key_hex();
+        let signer = self
+            .contributors
+            .iter()
+            .find(|c| c.public_key == public_key)
+            .ok_or_else(|| format!("proof{index} is signed by an unknown key {public_key}"))?;
+        if signer.index != index {
+            return Err(format!(
+                "proof{index} is signed by {}, who is registered for contribution {}",
+                signer.handle, signer.index
+            ));
+        }
+        if let Some(name) = names.iter().find(|name| **name != signer.handle) {
+            return Err(format!(
+                "proof{index} is attributed to {name} but signed by {}",
+                signer.handle
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod registry_tests {
+    use blstrs::Scalar;
+    use halo2curves::ff::Field;
+    use rand_core::OsRng;
+
+    use crate::registry::{ContributionSignature, Contributor, ContributorRegistry};
+
+    #[test]
+    fn signatures_are_checked_against_the_registry() {
+        let (alice_key, bob_key) = (Scalar::random(OsRng), Scalar::random(OsRng));
+        let digest = [7u8; 32];
+        let alice = ContributionSignature::sign(&alice_key, &digest);
+        let bob = ContributionSignature::sign(&bob_key, &digest);
+
+        let signature = ContributionSignature::from_bytes(&alice.to_bytes()).unwrap();
+        assert_eq!(signature, alice);
+        assert!(signature.is_valid(&digest));
+        assert!(!signature.is_valid(&[8u8; 32]));
+
+        let registry = ContributorRegistry {
+            contributors: vec![Contributor {
+                handle: "alice".to_string(),
+                public_key: alice.public_key_hex(),
+                index: 40,
+            }],
+        };
+
+        // Unregistered, unsigned contributions are not checked
+        assert!(registry.check_signature(39, &[], &digest, None).is_ok());
+
+        assert!(registry
+            .check_signature(40, &["alice"], &digest, Some(&alice))
+            .is_ok());
+        assert!(registry.check_signature(40, &[], &digest, None).is_err());
+        assert!(registry
+            .check_signature(40, &["alice"], &[8u8; 32], Some(&alice))
+            .is_err());
+        assert!(registry
+            .check_signature(40, &[], &digest, Some(&bob))
+            .unwrap_err()
+            .contains("unknown key"));
+        assert!(registry
+            .check_signature(41, &[], &digest, Some(&alice))
+            .unwrap_err()
+            .contains("registered for contribution 40"));
+        assert!(registry
+            .check_signature(40, &["bob"], &digest, Some(&alice))
+            .unwrap_err()
+            .contains("attributed to bob"));
+    }
+}
@@ -0,0 +1,363 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Release artifacts of the final SRS.
+//!
+//! Downstream stacks expect different encodings of the points, so the final
+//! SRS is released both with uncompressed (96-byte G1, 192-byte G2) and
+//! compressed (48-byte G1, 96-byte G2) points, in the standard (zcash)
+//! serialization. Both files are read back and checked to decode to the very
+//! points of the final SRS.
+
+use std::{
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use blstrs::{G1Affine, G2Affine};
+use halo2curves::serde::SerdeObject;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cache::sha256_file,
+    ceremony::{
+        nr_g1_points, G1_COMPRESSED_SIZE, G1_SIZE, G2_COMPRESSED_SIZE, G2_SIZE, SRS,
+        UPDATE_WINDOW_SIZE,
+    },
+    error::SrsError,
+    utils::{
+        create_file, decode_g1_point, decode_g2_point, open_file, read_g1_point, read_g2_point,
+    },
+};
+
+/// Encoding of the points of an SRS file. All of them are read by
+/// [SRS::read_from_file], which detects the encoding
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SrsEncoding {
+    /// Raw points (96-byte G1, 192-byte G2), as updated in the ceremony
+    #[default]
+    Raw,
+    /// Uncompressed points (96-byte G1, 192-byte G2) in the standard (zcash)
+    /// serialization
+    Uncompressed,
+    /// Compressed points (48-byte G1, 96-byte G2) in the standard (zcash)
+    /// serialization, for half-size files
+    Compressed,
+}
+
+impl SrsEncoding {
+    /// Sizes of the encoded G1 and G2 points
+    pub fn point_sizes(self) -> (usize, usize) {
+        match self {
+            SrsEncoding::Raw | SrsEncoding::Uncompressed => (G1_SIZE, G2_SIZE),
+            SrsEncoding::Compressed => (G1_COMPRESSED_SIZE, G2_COMPRESSED_SIZE),
+        }
+    }
+
+    pub fn encode_g1(self, point: &G1Affine) -> Vec<u8> {
+        match self {
+            SrsEncoding::Raw => point.to_raw_bytes(),
+            SrsEncoding::Uncompressed => point.to_uncompressed().to_vec(),
+            SrsEncoding::Compressed => point.to_compressed().to_vec(),
+        }
+    }
+
+    pub fn encode_g2(self, point: &G2Affine) -> Vec<u8> {
+        match self {
+            SrsEncoding::Raw => point.to_raw_bytes(),
+            SrsEncoding::Uncompressed => point.to_uncompressed().to_vec(),
+            SrsEncoding::Compressed => point.to_compressed().to_vec(),
+        }
+    }
+
+    /// The G1 point of which the bytes are the encoding, as written by
+    /// [SrsEncoding::encode_g1]. `None` if they are not (canonical)
+    pub fn decode_g1(self, bytes: &[u8]) -> Option<G1Affine> {
+        let point = match self {
+            SrsEncoding::Raw => decode_g1_point(bytes),
+            SrsEncoding::Uncompressed => bytes
+                .try_into()
+                .ok()
+                .and_then(|bytes| Option::from(G1Affine::from_uncompressed(bytes))),
+            SrsEncoding::Compressed => bytes
+                .try_into()
+                .ok()
+                .and_then(|bytes| Option::from(G1Affine::from_compressed(bytes))),
+        };
+        point.filter(|point| self.encode_g1(point) == bytes)
+    }
+
+    /// Same as [SrsEncoding::decode_g1], for G2 points
+    pub fn decode_g2(self, bytes: &[u8]) -> Option<G2Affine> {
+        let point = match self {
+            SrsEncoding::Raw => decode_g2_point(bytes),
+            SrsEncoding::Uncompressed => bytes
+                .try_into()
+                .ok()
+                .and_then(|bytes| Option::from(G2Affine::from_uncompressed(bytes))),
+            SrsEncoding::Compressed => bytes
+                .try_into()
+                .ok()
+                .and_then(|bytes| Option::from(G2Affine::from_compressed(bytes))),
+        };
+        point.filter(|point| self.encode_g2(point) == bytes)
+    }
+}
+
+/// Hashes of the release artifacts, as recorded in the ceremony manifest
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseArtifacts {
+    /// SHA-256 digest (hex) of the SRS with uncompressed points
+    pub uncompressed_sha256: String,
+    /// SHA-256 digest (hex) of the SRS with compressed points
+    pub compressed_sha256: String,
+    /// Whether both encodings decode to the points of the final SRS
+    pub encodings_match: bool,
+}
+
+/// The encodings of the release artifacts
+const RELEASE_ENCODINGS: [SrsEncoding; 2] = [SrsEncoding::Uncompressed, SrsEncoding::Compressed];
+
+/// Writes the SRS stored at `srs_path` with uncompressed and compressed
+/// points, then checks both files against it. The SRS is streamed, and each
+/// file is the one [SRS::write_to_file_as] writes
+pub fn write_release_artifacts(
+    srs_path: &Path,
+    uncompressed_path: &Path,
+    compressed_path: &Path,
+) -> Result<ReleaseArtifacts, SrsError> {
+    let n = nr_g1_points(srs_path)?;
+    let read_error = SrsError::io("read", srs_path);
+
+    let mut reader = BufReader::new(open_file(srs_path)?);
+    let paths = [uncompressed_path, compressed_path];
+    let mut writers = Vec::with_capacity(paths.len());
+    for (path, encoding) in paths.into_iter().zip(RELEASE_ENCODINGS) {
+        writers.push((BufWriter::new(create_file(path)?), encoding, path));
+    }
+
+    let mut bytes = vec![0u8; UPDATE_WINDOW_SIZE.min(n) * G1_SIZE];
+    let mut offset = 0;
+    while offset < n {
+        let len = UPDATE_WINDOW_SIZE.min(n - offset);
+        let bytes = &mut bytes[..len * G1_SIZE];
+        reader.read_exact(bytes).map_err(read_error)?;
+
+        let window: Vec<G1Affine> = bytes
+            .par_chunks(G1_SIZE)
+            .map(read_g1_point)
+            .collect::<Result<_, _>>()?;
+        for (writer, encoding, path) in &mut writers {
+            for point in &window {
+                writer
+                    .write_all(&encoding.encode_g1(point))
+                    .map_err(SrsError::io("write", path))?;
+            }
+        }
+        offset += len;
+    }
+
+    let mut g2_bytes = [0u8; G2_SIZE];
+    for _ in 0..2 {
+        reader.read_exact(&mut g2_bytes).map_err(read_error)?;
+        let point = read_g2_point(&g2_bytes)?;
+        for (writer, encoding, path) in &mut writers {
+            writer
+                .write_all(&encoding.encode_g2(&point))
+                .map_err(SrsError::io("write", path))?;
+        }
+    }
+    for (writer, _, path) in &mut writers {
+        writer.flush().map_err(SrsError::io("write", path))?;
+    }
+    drop(writers);
+
+    Ok(ReleaseArtifacts {
+        uncompressed_sha256: hex::encode(sha256_file(uncompressed_path)?),
+        compressed_sha256: hex::encode(sha256_file(compressed_path)?),
+        encodings_match: encodings_match(srs_path, uncompressed_path, compressed_path)?,
+    })
+}
+
+/// Reads back an SRS written with the points in the given encoding, as
+/// [SRS::write_to_file_as] and [write_release_artifacts] do. Fails if a point
+/// is not a valid encoding
+pub fn read_release_srs(path: &Path, encoding: SrsEncoding) -> Result<SRS, SrsError> {
+    let (g1_size, g2_size) = encoding.point_sizes();
+    let read_error = SrsError::io("read", path);
+    let file = open_file(path)?;
+    let len = file.metadata().map_err(read_error)?.len() as usize;
+    if len <= 2 * g2_size || (len - 2 * g2_size) % g1_size != 0 {
+        return Err(SrsError::Deserialization(format!(
+            "Invalid size of {path:?}: {len} bytes"
+        )));
+    }
+    let n = (len - 2 * g2_size) / g1_size;
+
+    let decode_g1 = |bytes: &[u8]| -> Result<G1Affine, SrsError> {
+        encoding
+            .decode_g1(bytes)
+            .ok_or_else(|| SrsError::Deserialization(format!("Invalid G1 point in {path:?}")))
+    };
+    let mut reader = BufReader::new(file);
+    let mut g1s = Vec::with_capacity(n);
+    let mut bytes = vec![0u8; UPDATE_WINDOW_SIZE.min(n) * g1_size];
+    while g1s.len() < n {
+        let len = UPDATE_WINDOW_SIZE.min(n - g1s.len());
+        let bytes = &mut bytes[..len * g1_size];
+        reader.read_exact(bytes).map_err(read_error)?;
+        let window: Vec<G1Affine> = bytes
+            .par_chunks(g1_size)
+            .map(decode_g1)
+            .collect::<Result<_, _>>()?;
+        g1s.extend(window);
+    }
+
+    let mut g2_bytes = vec![0u8; g2_size];
+    let mut read_g2 = || -> Result<G2Affine, SrsError> {
+        reader.read_exact(&mut g2_bytes).map_err(read_error)?;
+        encoding
+            .decode_g2(&g2_bytes)
+            .ok_or_else(|| SrsError::Deserialization(format!("Invalid G2 point in {path:?}")))
+    };
+    let g2s = [read_g2()?, read_g2()?];
+    Ok(SRS { g1s, g2s })
+}
+
+/// Whether the files at `uncompressed_path` and `compressed_path` decode to
+/// the points of the SRS stored at `srs_path`
+pub fn encodings_match(
+    srs_path: &Path,
+    uncompressed_path: &Path,
+    compressed_path: &Path,
+) -> Result<bool, SrsError> {
+    let n = nr_g1_points(srs_path)?;
+
+    let paths = [uncompressed_path, compressed_path];
+    let mut encoded = Vec::with_capacity(paths.len());
+    for (path, encoding) in paths.into_iter().zip(RELEASE_ENCODINGS) {
+        let file = open_file(path)?;
+        let (g1_size, g2_size) = encoding.point_sizes();
+        let len = file.metadata().map_err(SrsError::io("read", path))?.len();
+        if len != (n * g1_size + 2 * g2_size) as u64 {
+            return Ok(false);
+        }
+        encoded.push((BufReader::new(file), encoding, path));
+    }
+
+    let read_error = SrsError::io("read", srs_path);
+    let mut reader = BufReader::new(open_file(srs_path)?);
+    let mut bytes = vec![0u8; UPDATE_WINDOW_SIZE.min(n) * G1_SIZE];
+    let mut offset = 0;
+    while offset < n {
+        let len = UPDATE_WINDOW_SIZE.min(n - offset);
+        let bytes = &mut bytes[..len * G1_SIZE];
+        reader.read_exact(bytes).map_err(read_error)?;
+        let window: Vec<G1Affine> = bytes
+            .par_chunks(G1_SIZE)
+            .map(read_g1_point)
+            .collect::<Result<_, _>>()?;
+
+        for (reader, encoding, path) in &mut encoded {
+            let (g1_size, _) = encoding.point_sizes();
+            let mut encoded_bytes = vec![0u8; len * g1_size];
+            reader
+                .read_exact(&mut encoded_bytes)
+                .map_err(SrsError::io("read", path))?;
+            // The encodings are unique, so the bytes are compared
+            let matching = window
+                .par_iter()
+                .zip(encoded_bytes.par_chunks(g1_size))
+                .all(|(point, bytes)| encoding.encode_g1(point) == bytes);
+            if !matching {
+                return Ok(false);
+            }
+        }
+        offset += len;
+    }
+
+    let mut g2_bytes = [0u8; G2_SIZE];
+    for _ in 0..2 {
+        reader.read_exact(&mut g2_bytes).map_err(read_error)?;
+        let point = read_g2_point(&g2_bytes)?;
+        for (reader, encoding, path) in &mut encoded {
+            let (_, g2_size) = encoding.point_sizes();
+            let mut encoded_bytes = vec![0u8; g2_size];
+            reader
+                .read_exact(&mut encoded_bytes)
+                .map_err(SrsError::io("read", path))?;
+            if encoding.encode_g2(&point) != encoded_bytes {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod release_tests {
+    use std::{fs, path::Path};
+
+    use rand_core::OsRng;
+
+    use crate::{
+        ceremony::SRS,
+        release::{encodings_match, read_release_srs, write_release_artifacts, SrsEncoding},
+    };
+
+    #[test]
+    fn release_encodings_match() {
+        let srs_path = Path::new("/tmp/test_release_srs");
+        let uncompressed_path = Path::new("/tmp/test_release_srs.uncompressed");
+        let compressed_path = Path::new("/tmp/test_release_srs.compressed");
+        let srs = SRS::generate(1 << 8, OsRng);
+        srs.write_to_file(srs_path).unwrap();
+
+        let artifacts =
+            write_release_artifacts(srs_path, uncompressed_path, compressed_path).unwrap();
+        assert!(artifacts.encodings_match);
+        assert_eq!(
+            read_release_srs(uncompressed_path, SrsEncoding::Uncompressed).unwrap(),
+            srs
+        );
+        assert_eq!(
+            read_release_srs(compressed_path, SrsEncoding::Compressed).unwrap(),
+            srs
+        );
+
+        // The same files are written by the SRS itself
+        let converted_path = Path::new("/tmp/test_release_srs.converted");
+        srs.write_to_file_as(converted_path, SrsEncoding::Compressed)
+            .unwrap();
+        assert_eq!(
+            fs::read(converted_path).unwrap(),
+            fs::read(compressed_path).unwrap()
+        );
+        assert_eq!(SRS::read_from_file(converted_path).unwrap(), srs);
+        srs.write_to_file_as(converted_path, SrsEncoding::Uncompressed)
+            .unwrap();
+        assert_eq!(
+            fs::read(converted_path).unwrap(),
+            fs::read(uncompressed_path).unwrap()
+        );
+
+        // Flip a bit of the first compressed point
+        let mut compressed = fs::read(compressed_path).unwrap();
+        compressed[47] ^= 1;
+        fs::write(compressed_path, compressed).unwrap();
+        assert!(!encodings_match(srs_path, uncompressed_path, compressed_path).unwrap());
+    }
+}
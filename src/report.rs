@@ -0,0 +1,390 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Human-readable reports of the state of the ceremony.
+//!
+//! A [CeremonyReport] is collected from the artifacts on disk (the anchor
+//! point, the chain of update proofs, the participants list and the SRS files)
+//! and can be rendered as a static HTML page for publication, or as a markdown
+//! ledger for the repository.
+
+use std::{fmt::Write, fs, path::Path, process::Command};
+
+use blstrs::G1Affine;
+use halo2curves::serde::SerdeObject;
+
+use crate::{
+    cache::{extend_transcript, sha256_file},
+    ceremony::G1_SIZE,
+    error::SrsError,
+    schnorr::UpdateProof,
+    utils::{open_update_proof_dirs, read_g1_point_from_file},
+};
+
+/// A row of the participants table (see `PARTICIPANTS.md`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Participant {
+    pub name: String,
+    pub handle: String,
+    pub affiliation: String,
+    /// SHA-256 digest (hex) of the SRS produced by the participant
+    pub srs_sha256: String,
+}
+
+/// Verification status of a single update proof
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContributionRecord {
+    /// Index of the contribution, as in `proofs/proof<index>`
+    pub index: usize,
+    /// SHA-256 digest (hex) of the proof file
+    pub proof_sha256: String,
+    /// [tau]_1 before and after the contribution (hex of the raw encoding)
+    pub g: String,
+    pub h: String,
+    /// Date (YYYY-MM-DD) the proof was committed, if known from git
+    pub date: Option<String>,
+    /// SHA-256 digest (hex) of the resulting `srs<index>`, if available
+    pub srs_sha256: Option<String>,
+    /// Reason why the contribution does not verify, if any
+    pub failure: Option<String>,
+}
+
+impl ContributionRecord {
+    pub fn is_valid(&self) -> bool {
+        self.failure.is_none()
+    }
+
+    /// The participant who produced this contribution, identified by the
+    /// digest of the resulting SRS
+    pub fn contributor<'a>(&self, participants: &'a [Participant]) -> Option<&'a Participant> {
+        let srs_sha256 = self.srs_sha256.as_ref()?;
+        participants.iter().find(|p| &p.srs_sha256 == srs_sha256)
+    }
+}
+
+/// State of the ceremony, as derived from the artifacts on disk
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CeremonyReport {
+    /// [tau]_1 of the SRS the chain starts from (hex of the raw encoding)
+    pub anchor: String,
+    pub contributions: Vec<ContributionRecord>,
+    pub participants: Vec<Participant>,
+    /// SHA-256 digest (hex) of the final SRS
+    pub final_srs_sha256: String,
+    /// Whether [tau]_1 of the final SRS matches the end of the chain
+    pub final_srs_matches: bool,
+    /// Transcript hash (hex) of the chain of proof files
+    pub transcript: String,
+}
+
+impl CeremonyReport {
+    /// Collects the report for the chain of update proofs of `proofs_dir`,
+    /// ending at the given SRS. The intermediate `srs<index>` files are looked
+    /// up next to it. The verification failures are recorded, not raised; only
+    /// an unreadable or malformed file fails
+    pub fn collect(
+        final_srs_path: &Path,
+        proofs_dir: &Path,
+        participants_path: &Path,
+    ) -> Result<Self, SrsError> {
+        let srs_dir = final_srs_path.parent().unwrap_or(Path::new("."));
+        let anchor = read_g1_point_from_file(Path::new("./filecoin_srs_g1_point"), 0)?;
+
+        let mut g = anchor;
+        let mut transcript = [0u8; 32];
+        let mut contributions = Vec::new();
+        for (i, entry) in open_update_proof_dirs(proofs_dir)?.iter().enumerate() {
+            let proof_path = entry.path();
+            let digest = sha256_file(&proof_path)?;
+            let proof = UpdateProof::read_from_file(&proof_path)?;
+
+            let failure = if proof.g != g {
+                Some("does not extend the previous contribution".to_string())
+            } else if proof.g == proof.h {
+                Some("does not change the SRS".to_string())
+            } else if !proof.is_valid() {
+                Some("invalid Schnorr proof".to_string())
+            } else {
+                None
+            };
+
+            let srs_path = srs_dir.join(format!("srs{}", i + 1));
+            let srs_sha256 = match srs_path.exists() {
+                true => Some(hex::encode(sha256_file(&srs_path)?)),
+                false => None,
+            };
+
+            transcript = extend_transcript(&transcript, &digest);
+            contributions.push(ContributionRecord {
+                index: i + 1,
+                proof_sha256: hex::encode(digest),
+                g: encode_point(&proof.g),
+                h: encode_point(&proof.h),
+                date: git_commit_date(&proof_path),
+                srs_sha256,
+                failure,
+            });
+            g = proof.h;
+        }
+
+        let final_g1_point = read_g1_point_from_file(final_srs_path, G1_SIZE)?;
+
+        Ok(CeremonyReport {
+            anchor: encode_point(&anchor),
+            contributions,
+            participants: read_participants(participants_path),
+            final_srs_sha256: hex::encode(sha256_file(final_srs_path)?),
+            final_srs_matches: final_g1_point == g,
+            transcript: hex::encode(transcript),
+        })
+    }
+
+    /// Whether the whole chain, including the final SRS, verifies
+    pub fn is_valid(&self) -> bool {
+        self.final_srs_matches && self.contributions.iter().all(|c| c.is_valid())
+    }
+
+    /// Renders the report as a standalone HTML page
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let status = if self.is_valid() { "VALID" } else { "INVALID" };
+
+        html.push_str(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Midnight SRS ceremony report</title>\n<style>\n\
+             body { font-family: sans-serif; margin: 2em; }\n\
+             table { border-collapse: collapse; }\n\
+             td, th { border: 1px solid #ccc; padding: 0.3em 0.6em; }\n\
+             code { font-size: 0.85em; word-break: break-all; }\n\
+             .ok { color: #186a3b; } .fail { color: #a93226; }\n\
+             </style>\n</head>\n<body>\n<h1>Midnight SRS ceremony report</h1>\n",
+        );
+
+        let class = if self.is_valid() { "ok" } else { "fail" };
+        writeln!(html, "<h2>Summary</h2>\n<table>").unwrap();
+        writeln!(
+            html,
+            "<tr><th>Chain status</th><td class=\"{class}\">{status}</td></tr>"
+        )
+        .unwrap();
+        writeln!(
+            html,
+            "<tr><th>Contributions</th><td>{}</td></tr>",
+            self.contributions.len()
+        )
+        .unwrap();
+        for (label, value) in [
+            ("Anchor [tau]_1", &self.anchor),
+            ("Final SRS SHA-256", &self.final_srs_sha256),
+            ("Proofs transcript hash", &self.transcript),
+        ] {
+            writeln!(
+                html,
+                "<tr><th>{label}</th><td><code>{}</code></td></tr>",
+                escape_html(value)
+            )
+            .unwrap();
+        }
+        writeln!(html, "</table>").unwrap();
+
+        writeln!(html, "<h2>Chain of update proofs</h2>\n<table>").unwrap();
+        writeln!(
+            html,
+            "<tr><th>#</th><th>Proof SHA-256</th><th>Status</th></tr>"
+        )
+        .unwrap();
+        for c in &self.contributions {
+            let (class, status) = match &c.failure {
+                None => ("ok", "verified".to_string()),
+                Some(failure) => ("fail", escape_html(failure)),
+            };
+            writeln!(
+                html,
+                "<tr><td>{}</td><td><code>{}</code></td><td class=\"{class}\">{status}</td></tr>",
+                c.index, c.proof_sha256
+            )
+            .unwrap();
+        }
+        writeln!(html, "</table>").unwrap();
+
+        writeln!(html, "<h2>Participants</h2>\n<table>").unwrap();
+        writeln!(
+            html,
+            "<tr><th>Name</th><th>GitHub handle</th><th>Affiliation</th><th>SRS SHA-256</th></tr>"
+        )
+        .unwrap();
+        for p in &self.participants {
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td><code>{}</code></td></tr>",
+                escape_html(&p.name),
+                escape_html(&p.handle),
+                escape_html(&p.affiliation),
+                escape_html(&p.srs_sha256)
+            )
+            .unwrap();
+        }
+        writeln!(html, "</table>\n</body>\n</html>").unwrap();
+
+        html
+    }
+}
+
+impl CeremonyReport {
+    /// Renders the report as a markdown ledger of the contributions
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+
+        writeln!(md, "# SRS Ceremony Contributions\n").unwrap();
+        writeln!(
+            md,
+            "This file is generated by `srs_utils <PATH-TO-FINAL-SRS> report --markdown`\n\
+             from the verified artifacts; do not edit it by hand.\n"
+        )
+        .unwrap();
+        writeln!(
+            md,
+            "| # | Contributor | Date | Proof SHA2-256 | SRS SHA2-256 | Status |"
+        )
+        .unwrap();
+        writeln!(
+            md,
+            "| - | ----------- | ---- | -------------- | ------------ | ------ |"
+        )
+        .unwrap();
+        for c in &self.contributions {
+            let contributor = c
+                .contributor(&self.participants)
+                .map(|p| format!("{} (@{})", p.name, p.handle))
+                .unwrap_or_else(|| "-".to_string());
+            let status = match &c.failure {
+                None => "verified".to_string(),
+                Some(failure) => format!("**FAILED**: {failure}"),
+            };
+            writeln!(
+                md,
+                "| {} | {} | {} | `{}` | {} | {} |",
+                c.index,
+                contributor,
+                c.date.as_deref().unwrap_or("-"),
+                c.proof_sha256,
+                c.srs_sha256
+                    .as_ref()
+                    .map(|h| format!("`{h}`"))
+                    .unwrap_or_else(|| "-".to_string()),
+                status
+            )
+            .unwrap();
+        }
+
+        writeln!(
+            md,
+            "\nFinal SRS SHA2-256: `{}` ({})\n\nProofs transcript hash: `{}`",
+            self.final_srs_sha256,
+            if self.final_srs_matches {
+                "matches the chain"
+            } else {
+                "**does NOT match the chain**"
+            },
+            self.transcript
+        )
+        .unwrap();
+
+        md
+    }
+}
+
+/// Date (YYYY-MM-DD) of the last commit touching the given file, if it is
+/// tracked by git
+fn git_commit_date(path: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%cs", "--"])
+        .arg(path)
+        .output()
+        .ok()?;
+    let date = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !date.is_empty()).then_some(date)
+}
+
+/// Hex encoding of the raw bytes of a G1 point
+fn encode_point(p: &G1Affine) -> String {
+    hex::encode(p.to_raw_bytes())
+}
+
+/// Escapes the characters with a special meaning in HTML
+pub fn escape_html(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&#39;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// Parses the participants table of the given markdown file. Returns an empty
+/// list if the file does not exist
+pub fn read_participants(path: &Path) -> Vec<Participant> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+
+    content
+        .lines()
+        .filter(|line| line.trim_start().starts_with('|'))
+        .map(|line| {
+            line.trim()
+                .trim_matches('|')
+                .split('|')
+                .map(|cell| cell.trim().to_string())
+                .collect::<Vec<_>>()
+        })
+        // Skip the header and the separator rows
+        .filter(|cells| cells.len() == 4 && cells[0] != "Name" && !cells[0].starts_with("---"))
+        .map(|cells| Participant {
+            name: cells[0].clone(),
+            handle: cells[1].clone(),
+            affiliation: cells[2].clone(),
+            srs_sha256: cells[3].clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod report_tests {
+    use std::path::Path;
+
+    use crate::report::{escape_html, read_participants};
+
+    #[test]
+    fn participants_are_parsed() {
+        let participants = read_participants(Path::new("./PARTICIPANTS.md"));
+        assert!(!participants.is_empty());
+        assert!(participants
+            .iter()
+            .all(|p| p.srs_sha256.len() == 64 && !p.handle.is_empty()));
+    }
+
+    #[test]
+    fn html_is_escaped() {
+        assert_eq!(
+            escape_html("<a href=\"x\">&'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+}
@@ -0,0 +1,267 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checkpoints of a resumable update.
+//!
+//! An update of a large SRS takes hours. With `update --checkpoint`, the
+//! number of G1 points already written to the new SRS is recorded regularly
+//! (see [crate::ceremony::SRS::update_file_resumable]), together with the
+//! toxic waste encrypted under a passphrase, so that an update interrupted by
+//! a crash (or Ctrl-C) resumes with the same toxic waste instead of starting
+//! over. The key is derived from the passphrase with Argon2id, and the toxic
+//! waste is encrypted with ChaCha20-Poly1305, authenticating the SRS files it
+//! applies to.
+//!
+//! The checkpoint is overwritten before it is removed once the update is
+//! complete. On file systems that do not write in place (copy-on-write, SSD
+//! wear leveling), the overwritten blocks may survive: the passphrase is what
+//! protects the toxic waste then.
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use argon2::Argon2;
+use blstrs::Scalar;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use halo2curves::serde::SerdeObject;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use crate::{
+    ceremony::{G1_SIZE, SCALAR_SIZE},
+    error::SrsError,
+    metadata::EntropyInput,
+    utils::{create_file, read_g1_point_from_file},
+};
+
+/// Default location of the checkpoint of a resumable update
+pub const UPDATE_CHECKPOINT_PATH: &str = "./.update_checkpoint.json";
+
+/// Size of the salt of the key derivation
+const SALT_SIZE: usize = 16;
+
+/// Size of a ChaCha20-Poly1305 nonce
+const NONCE_SIZE: usize = 12;
+
+/// Checkpoint of an update of `old_srs` into `new_srs`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateCheckpoint {
+    pub old_srs: PathBuf,
+    pub new_srs: PathBuf,
+    /// [tau]_1 of the old SRS (hex of its raw encoding), which identifies it
+    pub old_tau_g1: String,
+    /// Number of G1 points already written to the new SRS
+    pub processed: usize,
+    /// Inputs the toxic waste was derived from, recorded in the metadata of
+    /// the proof
+    pub entropy: Vec<EntropyInput>,
+    /// Salt (hex) of the key derivation
+    salt: String,
+    /// Nonce (hex) of the encryption
+    nonce: String,
+    /// Encrypted toxic waste (hex), with its authentication tag
+    encrypted_secret: String,
+}
+
+/// Derives the encryption key of the toxic waste from the passphrase
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .expect("Failed to derive the checkpoint key");
+    key
+}
+
+impl UpdateCheckpoint {
+    /// Starts the checkpoint of an update of `old_srs` into `new_srs` with
+    /// the toxic waste `nu`, encrypted under the passphrase
+    pub fn new(
+        old_srs: &Path,
+        new_srs: &Path,
+        nu: &Scalar,
+        passphrase: &str,
+        entropy: Vec<EntropyInput>,
+    ) -> Result<Self, SrsError> {
+        let mut salt = [0u8; SALT_SIZE];
+        let mut nonce = [0u8; NONCE_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+        let old_tau_g1 = hex::encode(read_g1_point_from_file(old_srs, G1_SIZE)?.to_raw_bytes());
+
+        let mut key = derive_key(passphrase, &salt);
+        let mut secret = nu.to_bytes_be();
+        let encrypted_secret = ChaCha20Poly1305::new(Key::from_slice(&key))
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &secret,
+                    aad: old_tau_g1.as_bytes(),
+                },
+            )
+            .expect("Failed to encrypt the toxic waste");
+        key.zeroize();
+        secret.zeroize();
+
+        Ok(UpdateCheckpoint {
+            old_srs: old_srs.to_path_buf(),
+            new_srs: new_srs.to_path_buf(),
+            old_tau_g1,
+            processed: 0,
+            entropy,
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            encrypted_secret: hex::encode(encrypted_secret),
+        })
+    }
+
+    /// Whether the checkpoint is the one of an update of `old_srs` into
+    /// `new_srs`
+    pub fn applies_to(&self, old_srs: &Path, new_srs: &Path) -> Result<bool, SrsError> {
+        if self.old_srs != old_srs || self.new_srs != new_srs || !new_srs.exists() {
+            return Ok(false);
+        }
+        let old_tau_g1 = read_g1_point_from_file(old_srs, G1_SIZE)?;
+        Ok(self.old_tau_g1 == hex::encode(old_tau_g1.to_raw_bytes()))
+    }
+
+    /// Decrypts the toxic waste with the passphrase, or says why it cannot be
+    pub fn secret(&self, passphrase: &str) -> Result<Scalar, String> {
+        let decode = |field: &str| hex::decode(field).map_err(|_| "Malformed checkpoint");
+        let salt = decode(&self.salt)?;
+        let nonce = decode(&self.nonce)?;
+        if nonce.len() != NONCE_SIZE {
+            return Err("Malformed checkpoint".to_string());
+        }
+
+        let mut key = derive_key(passphrase, &salt);
+        let secret = ChaCha20Poly1305::new(Key::from_slice(&key)).decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &decode(&self.encrypted_secret)?,
+                aad: self.old_tau_g1.as_bytes(),
+            },
+        );
+        key.zeroize();
+        let mut secret = secret.map_err(|_| "Wrong passphrase, or corrupted checkpoint")?;
+
+        let nu = <[u8; SCALAR_SIZE]>::try_from(secret.as_slice())
+            .ok()
+            .and_then(|bytes| Option::from(Scalar::from_bytes_be(&bytes)))
+            .ok_or_else(|| "Malformed toxic waste in the checkpoint".to_string());
+        secret.zeroize();
+        nu
+    }
+
+    /// Writes the checkpoint to the given file. The previous checkpoint is
+    /// replaced atomically, so that a crash leaves one of them intact
+    pub fn save(&self, path: &Path) -> Result<(), SrsError> {
+        let tmp_path = path.with_extension("tmp");
+        let write_error = SrsError::io("write", &tmp_path);
+        let mut file = create_file(&tmp_path)?;
+        serde_json::to_writer_pretty(&mut file, self).map_err(|err| write_error(err.into()))?;
+        file.sync_all().map_err(write_error)?;
+        fs::rename(&tmp_path, path).map_err(SrsError::io("write", path))
+    }
+
+    /// Reads the checkpoint stored in the given file, if any. Fails if it is
+    /// malformed
+    pub fn load(path: &Path) -> Result<Option<Self>, SrsError> {
+        let Ok(bytes) = fs::read(path) else {
+            return Ok(None);
+        };
+        serde_json::from_slice(&bytes).map(Some).map_err(|err| {
+            SrsError::Deserialization(format!("Malformed update checkpoint {path:?}: {err}"))
+        })
+    }
+}
+
+/// Overwrites the checkpoint stored in the given file with zeros, then
+/// removes it
+pub fn erase_checkpoint(path: &Path) -> Result<(), SrsError> {
+    let Ok(metadata) = fs::metadata(path) else {
+        return Ok(());
+    };
+    let write_error = SrsError::io("erase", path);
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(SrsError::io("open", path))?;
+    file.write_all(&vec![0u8; metadata.len() as usize])
+        .and_then(|_| file.sync_all())
+        .map_err(write_error)?;
+    drop(file);
+    fs::remove_file(path).map_err(write_error)
+}
+
+#[cfg(test)]
+mod resume_tests {
+    use std::{fs, path::Path};
+
+    use blstrs::Scalar;
+    use halo2curves::ff::Field;
+    use rand_core::OsRng;
+
+    use crate::{
+        ceremony::SRS,
+        resume::{erase_checkpoint, UpdateCheckpoint},
+    };
+
+    #[test]
+    fn checkpoints_keep_the_secret() {
+        let old_path = Path::new("/tmp/test_resume_old");
+        let new_path = Path::new("/tmp/test_resume_new");
+        let other_path = Path::new("/tmp/test_resume_other");
+        SRS::generate(1 << 4, OsRng)
+            .write_to_file(old_path)
+            .unwrap();
+        SRS::generate(1 << 4, OsRng)
+            .write_to_file(other_path)
+            .unwrap();
+        fs::write(new_path, []).unwrap();
+
+        let nu = Scalar::random(OsRng);
+        let mut checkpoint =
+            UpdateCheckpoint::new(old_path, new_path, &nu, "passphrase", vec![]).unwrap();
+        checkpoint.processed = 42;
+        let path = Path::new("/tmp/test_resume_checkpoint.json");
+        checkpoint.save(path).unwrap();
+
+        let loaded = UpdateCheckpoint::load(path).unwrap().unwrap();
+        assert_eq!(loaded, checkpoint);
+        assert!(loaded.applies_to(old_path, new_path).unwrap());
+        assert!(!loaded.applies_to(other_path, new_path).unwrap());
+        assert_eq!(loaded.secret("passphrase"), Ok(nu));
+        assert!(loaded.secret("wrong passphrase").is_err());
+        assert!(!fs::read_to_string(path)
+            .unwrap()
+            .contains(&hex::encode(nu.to_bytes_be())));
+
+        // The encryption is bound to the old SRS
+        let mut moved = loaded.clone();
+        moved.old_tau_g1 = "00".to_string();
+        assert!(moved.secret("passphrase").is_err());
+
+        erase_checkpoint(path).unwrap();
+        assert!(!path.exists());
+        assert!(UpdateCheckpoint::load(path).unwrap().is_none());
+    }
+}
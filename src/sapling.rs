@@ -0,0 +1,270 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Import of the Zcash Sapling powers-of-tau transcripts.
+//!
+//! The Sapling MPC (https://github.com/ebfull/powersoftau) is an independent
+//! powers-of-tau ceremony over BLS12-381. Its `challenge` files hold the
+//! Blake2b-512 digest of the previous response, followed by the accumulator
+//! with uncompressed points:
+//!
+//! * [tau^i]_1, for i < 2 * TAU_POWERS_LENGTH - 1
+//! * [tau^i]_2, for i < TAU_POWERS_LENGTH
+//! * [alpha * tau^i]_1, for i < TAU_POWERS_LENGTH
+//! * [beta * tau^i]_1, for i < TAU_POWERS_LENGTH
+//! * [beta]_2
+//!
+//! Its `response` files hold the same fields with compressed points, followed
+//! by the public key of the contribution. Points use the standard (zcash)
+//! serialization. Contrary to the Filecoin SRS, the powers are in coefficient
+//! form, so they are read directly.
+
+use std::{
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use blstrs::{G1Affine, G2Affine};
+use halo2curves::group::prime::PrimeCurveAffine;
+
+use crate::{
+    ceremony::{G1_COMPRESSED_SIZE, G1_SIZE, G2_COMPRESSED_SIZE, G2_SIZE},
+    error::SrsError,
+    external::ExternalTranscript,
+    utils::open_file,
+};
+
+/// Number of powers of tau in G2 of the Sapling accumulator
+pub const SAPLING_TAU_POWERS_LENGTH: usize = 1 << 21;
+
+/// Location [tau]_1 of the Sapling transcript is extracted to
+pub const SAPLING_G1_POINT_PATH: &str = "./sapling_srs_g1_point";
+
+/// Size of the digest of the previous response, heading a transcript
+const SAPLING_HASH_SIZE: usize = 64;
+
+/// Point encoding of a Sapling transcript
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SaplingEncoding {
+    /// Uncompressed points, as in `challenge` files
+    Uncompressed,
+    /// Compressed points, as in `response` files
+    Compressed,
+}
+
+impl SaplingEncoding {
+    fn g1_size(self) -> usize {
+        match self {
+            SaplingEncoding::Uncompressed => G1_SIZE,
+            SaplingEncoding::Compressed => G1_COMPRESSED_SIZE,
+        }
+    }
+
+    fn g2_size(self) -> usize {
+        match self {
+            SaplingEncoding::Uncompressed => G2_SIZE,
+            SaplingEncoding::Compressed => G2_COMPRESSED_SIZE,
+        }
+    }
+
+    fn read_g1_point(self, bytes: &[u8]) -> Result<G1Affine, SrsError> {
+        let point: Option<G1Affine> = match self {
+            SaplingEncoding::Uncompressed => G1Affine::from_uncompressed(bytes.try_into().unwrap()),
+            SaplingEncoding::Compressed => G1Affine::from_compressed(bytes.try_into().unwrap()),
+        }
+        .into();
+        point
+            .filter(|point| match self {
+                SaplingEncoding::Uncompressed => point.to_uncompressed()[..] == *bytes,
+                SaplingEncoding::Compressed => point.to_compressed()[..] == *bytes,
+            })
+            .ok_or_else(|| {
+                SrsError::Deserialization("Invalid G1 point in Sapling transcript".to_string())
+            })
+    }
+
+    fn read_g2_point(self, bytes: &[u8]) -> Result<G2Affine, SrsError> {
+        let point: Option<G2Affine> = match self {
+            SaplingEncoding::Uncompressed => G2Affine::from_uncompressed(bytes.try_into().unwrap()),
+            SaplingEncoding::Compressed => G2Affine::from_compressed(bytes.try_into().unwrap()),
+        }
+        .into();
+        point
+            .filter(|point| match self {
+                SaplingEncoding::Uncompressed => point.to_uncompressed()[..] == *bytes,
+                SaplingEncoding::Compressed => point.to_compressed()[..] == *bytes,
+            })
+            .ok_or_else(|| {
+                SrsError::Deserialization("Invalid G2 point in Sapling transcript".to_string())
+            })
+    }
+}
+
+/// [tau]_1 and [tau]_2 of a Sapling transcript
+pub struct SaplingTranscript {
+    tau_powers_g1: Vec<G1Affine>,
+    tau_g2: G2Affine,
+}
+
+impl SaplingTranscript {
+    /// Reads the transcript stored at the given path, whose accumulator has
+    /// `tau_powers_length` powers of tau in G2, and keeps its first
+    /// `nr_powers` powers of tau in G1. The encoding of the points is told
+    /// by the compression flag of the first one
+    pub fn read(path: &Path, tau_powers_length: usize, nr_powers: usize) -> Result<Self, SrsError> {
+        if nr_powers >= 2 * tau_powers_length {
+            return Err(SrsError::Verification(format!(
+                "The Sapling transcript has {} powers in G1, not {nr_powers}",
+                2 * tau_powers_length - 1
+            )));
+        }
+        let read_error = SrsError::io("read", path);
+        let mut file = open_file(path)?;
+        file.seek(SeekFrom::Start(SAPLING_HASH_SIZE as u64))
+            .map_err(read_error)?;
+
+        let mut flags = [0u8; 1];
+        file.read_exact(&mut flags).map_err(read_error)?;
+        let encoding = if flags[0] & 0x80 != 0 {
+            SaplingEncoding::Compressed
+        } else {
+            SaplingEncoding::Uncompressed
+        };
+        let (g1_size, g2_size) = (encoding.g1_size(), encoding.g2_size());
+
+        let mut g1_bytes = vec![0u8; nr_powers * g1_size];
+        file.seek(SeekFrom::Start(SAPLING_HASH_SIZE as u64))
+            .map_err(read_error)?;
+        file.read_exact(&mut g1_bytes).map_err(read_error)?;
+
+        let mut g2_bytes = vec![0u8; 2 * g2_size];
+        let g2_offset = SAPLING_HASH_SIZE + (2 * tau_powers_length - 1) * g1_size;
+        file.seek(SeekFrom::Start(g2_offset as u64))
+            .map_err(read_error)?;
+        file.read_exact(&mut g2_bytes).map_err(read_error)?;
+
+        let (g2, tau_g2) = g2_bytes.split_at(g2_size);
+        if encoding.read_g2_point(g2)? != G2Affine::generator() {
+            return Err(SrsError::Verification(
+                "The Sapling powers of tau in G2 do not start with the generator".to_string(),
+            ));
+        }
+        Ok(SaplingTranscript {
+            tau_powers_g1: g1_bytes
+                .chunks_exact(g1_size)
+                .map(|bytes| encoding.read_g1_point(bytes))
+                .collect::<Result<_, _>>()?,
+            tau_g2: encoding.read_g2_point(tau_g2)?,
+        })
+    }
+}
+
+impl ExternalTranscript for SaplingTranscript {
+    const NAME: &'static str = "Zcash Sapling";
+
+    const G1_POINT_PATH: &'static str = SAPLING_G1_POINT_PATH;
+
+    fn parse(path: &Path, nr_powers: usize) -> Result<Self, SrsError> {
+        Self::read(path, SAPLING_TAU_POWERS_LENGTH, nr_powers)
+    }
+
+    fn tau_powers_g1(&self) -> &[G1Affine] {
+        &self.tau_powers_g1
+    }
+
+    fn tau_g2(&self) -> G2Affine {
+        self.tau_g2
+    }
+}
+
+#[cfg(test)]
+mod sapling_tests {
+    use std::{fs, path::Path};
+
+    use blstrs::{G1Affine, G2Affine, Scalar};
+    use halo2curves::{
+        ff::Field,
+        group::{prime::PrimeCurveAffine, Curve},
+    };
+    use rand_core::OsRng;
+
+    use crate::{
+        external::ExternalTranscript,
+        sapling::{SaplingEncoding, SaplingTranscript},
+        utils::powers,
+    };
+
+    /// A Sapling accumulator with `tau_powers_length` powers of tau in G2,
+    /// and its tau
+    fn sapling_transcript(
+        tau_powers_length: usize,
+        encoding: SaplingEncoding,
+    ) -> (Vec<u8>, Scalar) {
+        let tau = Scalar::random(OsRng);
+        let g1 = |s: &Scalar| (G1Affine::generator() * s).to_affine();
+        let g2 = |s: &Scalar| (G2Affine::generator() * s).to_affine();
+        let encode_g1 = |point: G1Affine| match encoding {
+            SaplingEncoding::Uncompressed => point.to_uncompressed().to_vec(),
+            SaplingEncoding::Compressed => point.to_compressed().to_vec(),
+        };
+        let encode_g2 = |point: G2Affine| match encoding {
+            SaplingEncoding::Uncompressed => point.to_uncompressed().to_vec(),
+            SaplingEncoding::Compressed => point.to_compressed().to_vec(),
+        };
+
+        let (alpha, beta) = (Scalar::random(OsRng), Scalar::random(OsRng));
+        let taus = powers(&tau, 2 * tau_powers_length - 1);
+        let mut bytes = vec![0u8; 64];
+        bytes.extend(taus.iter().flat_map(|s| encode_g1(g1(s))));
+        bytes.extend(
+            taus[..tau_powers_length]
+                .iter()
+                .flat_map(|s| encode_g2(g2(s))),
+        );
+        for factor in [alpha, beta] {
+            bytes.extend(
+                taus[..tau_powers_length]
+                    .iter()
+                    .flat_map(|s| encode_g1(g1(&(factor * s)))),
+            );
+        }
+        bytes.extend(encode_g2(g2(&beta)));
+        (bytes, tau)
+    }
+
+    #[test]
+    fn sapling_transcripts_are_read() {
+        let path = Path::new("/tmp/test_sapling_transcript");
+        for encoding in [SaplingEncoding::Uncompressed, SaplingEncoding::Compressed] {
+            let (bytes, tau) = sapling_transcript(8, encoding);
+            fs::write(path, &bytes).unwrap();
+
+            let transcript = SaplingTranscript::read(path, 8, 4).unwrap();
+            transcript.validate().unwrap();
+            let expected: Vec<G1Affine> = powers(&tau, 4)
+                .iter()
+                .map(|s| (G1Affine::generator() * s).to_affine())
+                .collect();
+            assert_eq!(transcript.tau_powers_g1(), expected);
+            assert_eq!(
+                transcript.tau_g2(),
+                (G2Affine::generator() * tau).to_affine()
+            );
+
+            // The powers of tau in G2 are not where 4 powers would put them
+            assert!(SaplingTranscript::read(path, 4, 4).is_err());
+        }
+    }
+}
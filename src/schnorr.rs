@@ -17,102 +17,617 @@ use std::{
     path::Path,
 };
 
-use blake2::Blake2b512;
-use blstrs::{G1Affine, Scalar};
-use halo2curves::{
-    ff::{Field, FromUniformBytes},
-    group::Curve,
-    serde::SerdeObject,
-};
-use rand_core::OsRng;
+use blstrs::{G1Affine, G2Affine, Scalar};
+use halo2curves::{ff::Field, group::Curve, serde::SerdeObject};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
 
 use crate::{
-    ceremony::{G1_SIZE, SCALAR_SIZE},
-    utils::{create_file, hash_points, open_file, read_g1_point},
+    cache::{check_sidecar_digest, write_sidecar},
+    ceremony::{G1_COMPRESSED_SIZE, G1_SIZE, G2_COMPRESSED_SIZE, SCALAR_SIZE},
+    core::{schnorr_challenge, verify_g2_update, verify_schnorr},
+    error::SrsError,
+    sniff::sniff_bytes,
+    state::CeremonyContext,
+    utils::{create_file, decode_g1_point, open_file, TranscriptHash},
 };
 
-#[derive(Clone, Debug)]
+/// Size of a serialized [UpdateProof] in the v1 format: the Schnorr proof (a
+/// point and a scalar), followed by g and h
+pub const UPDATE_PROOF_SIZE: usize = 3 * G1_SIZE + SCALAR_SIZE;
+
+/// Magic bytes starting an update proof in the v2 format
+pub const PROOF_V2_MAGIC: [u8; 4] = *b"UPv2";
+
+/// Size of a serialized [UpdateProof] in the v2 format
+pub const UPDATE_PROOF_V2_SIZE: usize = PROOF_V2_MAGIC.len()
+    + 2
+    + 3 * G1_COMPRESSED_SIZE
+    + SCALAR_SIZE
+    + 2 * G2_COMPRESSED_SIZE
+    + 2 * SRS_HASH_SIZE
+    + CEREMONY_ID_SIZE;
+
+/// Flag of the header of v2 proofs made in a rehearsal (see
+/// [UpdateProof::rehearsal])
+pub const PROOF_FLAG_REHEARSAL: u8 = 1;
+
+pub use crate::core::SCHNORR_DOMAIN;
+
+/// Size of the SHA-256 digests of the SRS recorded in v2 proofs
+const SRS_HASH_SIZE: usize = 32;
+
+/// Size of the ceremony ID recorded in v2 proofs
+const CEREMONY_ID_SIZE: usize = 32;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct SchnorrProof(G1Affine, Scalar);
 
 impl SchnorrProof {
-    /// Create a proof of knowledge of x such that x * G = H
-    pub fn prove(g: G1Affine, h: G1Affine, x: &Scalar) -> Self {
-        let r = Scalar::random(OsRng);
+    /// Create a proof of knowledge of x such that x * G = H, with the given
+    /// transcript hash function and ceremony ID (see
+    /// [UpdateProof::transcript_hash] and [UpdateProof::ceremony_id])
+    pub fn prove(
+        g: G1Affine,
+        h: G1Affine,
+        x: &Scalar,
+        transcript_hash: Option<TranscriptHash>,
+        ceremony_id: Option<[u8; 32]>,
+    ) -> Self {
+        Self::prove_with_rng(g, h, x, transcript_hash, ceremony_id, OsRng)
+    }
+
+    /// Same as [SchnorrProof::prove], drawing the nonce from `rng`
+    pub fn prove_with_rng(
+        g: G1Affine,
+        h: G1Affine,
+        x: &Scalar,
+        transcript_hash: Option<TranscriptHash>,
+        ceremony_id: Option<[u8; 32]>,
+        rng: impl RngCore,
+    ) -> Self {
+        let r = Scalar::random(rng);
         let a = (g * r).to_affine();
 
-        let e = Scalar::from_uniform_bytes(&hash_points::<Blake2b512>(&[g, h, a]));
+        let e = schnorr_challenge(transcript_hash, ceremony_id, &[g, h, a]);
 
         let z = r + x * e;
         SchnorrProof(a, z)
     }
 
-    /// Verify a proof of knowledge of the dlog of H in base G; panics if the
-    /// proof is not accepted
-    pub fn verify(&self, g: G1Affine, h: G1Affine) {
-        let (a, z) = (self.0, self.1);
-        let e = Scalar::from_uniform_bytes(&hash_points::<Blake2b512>(&[g, h, a]));
-        assert_eq!(g * z, h * e + a)
+    /// Whether this is a valid proof of knowledge of the dlog of H in base G
+    pub fn is_valid(
+        &self,
+        g: G1Affine,
+        h: G1Affine,
+        transcript_hash: Option<TranscriptHash>,
+        ceremony_id: Option<[u8; 32]>,
+    ) -> bool {
+        verify_schnorr(g, h, (self.0, self.1), transcript_hash, ceremony_id)
+    }
+
+    /// The commitment of the proof, [r]g
+    pub fn a(&self) -> G1Affine {
+        self.0
     }
+
+    /// The response of the proof, r + x * e
+    pub fn z(&self) -> Scalar {
+        self.1
+    }
+
+    /// The Fiat-Shamir challenge e of this proof of knowledge of the dlog of
+    /// H in base G
+    pub fn challenge(
+        &self,
+        g: G1Affine,
+        h: G1Affine,
+        transcript_hash: Option<TranscriptHash>,
+        ceremony_id: Option<[u8; 32]>,
+    ) -> Scalar {
+        schnorr_challenge(transcript_hash, ceremony_id, &[g, h, self.0])
+    }
+}
+
+/// The G2 side of an update: g2 is [tau]_2 of the previous SRS and h2 is
+/// [tau']_2 of the new SRS
+#[derive(Clone, Debug, PartialEq)]
+pub struct G2Update {
+    pub g2: G2Affine,
+    pub h2: G2Affine,
 }
 
-#[derive(Clone, Debug)]
+impl G2Update {
+    /// Whether the same scalar was applied to the G2 point as to the G1 point
+    /// g (updated to h), i.e. e(h, g2) = e(g, h2)
+    pub fn is_valid(&self, g: G1Affine, h: G1Affine) -> bool {
+        verify_g2_update(g, h, self.g2, self.h2)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 /// An update proof is a proof of knowledge of the dlog of h in base g, where
-/// g is [tau]_1 of the previous SRS and h is [tau']_1 of the new SRS
+/// g is [tau]_1 of the previous SRS and h is [tau']_1 of the new SRS. Proofs
+/// in the v2 format also carry the G2 side of the update, the SHA-256 digests
+/// of both SRS files and the ID of the ceremony they belong to
 pub struct UpdateProof {
     pub g: G1Affine,
     pub h: G1Affine,
     schnorr_proof: SchnorrProof,
+    pub g2_update: Option<G2Update>,
+    /// SHA-256 digest of the updated SRS (file)
+    pub input_srs_hash: Option<[u8; 32]>,
+    /// SHA-256 digest of the resulting SRS (file)
+    pub srs_hash: Option<[u8; 32]>,
+    /// Hash function of the transcript of the Schnorr proof, recorded in the
+    /// header of v2 proofs. `None` for v1 proofs, whose transcript is untagged
+    /// Blake2b512
+    pub transcript_hash: Option<TranscriptHash>,
+    /// ID of the ceremony (see [crate::state::CeremonyManifest::ceremony_id]),
+    /// bound into the transcript of the Schnorr proof of v2 proofs
+    pub ceremony_id: Option<[u8; 32]>,
+    /// Whether the proof was made in a rehearsal, whose ceremony ID is derived
+    /// under its own domain tag. Only v2 proofs can be, with the
+    /// [PROOF_FLAG_REHEARSAL]
+    pub rehearsal: bool,
 }
 
 impl UpdateProof {
-    pub fn create(g: G1Affine, h: G1Affine, x: &Scalar) -> Self {
+    /// Creates a proof with the transcript hash function and the ceremony ID
+    /// of the given ceremony, marked as made in a rehearsal if it is one
+    pub fn create(ctx: &CeremonyContext, g: G1Affine, h: G1Affine, x: &Scalar) -> Self {
+        UpdateProof {
+            rehearsal: ctx.rehearsal,
+            ..Self::create_with(
+                g,
+                h,
+                x,
+                Some(ctx.transcript_hash),
+                Some(ctx.ceremony_id),
+                OsRng,
+            )
+        }
+    }
+
+    /// Creates a proof with the given transcript hash function (`None` for
+    /// legacy v1 proofs) and ceremony ID, drawing the nonce of the
+    /// Schnorr proof from `rng` (e.g. to produce test vectors)
+    pub fn create_with(
+        g: G1Affine,
+        h: G1Affine,
+        x: &Scalar,
+        transcript_hash: Option<TranscriptHash>,
+        ceremony_id: Option<[u8; 32]>,
+        rng: impl RngCore,
+    ) -> Self {
         UpdateProof {
-            schnorr_proof: SchnorrProof::prove(g, h, x),
+            schnorr_proof: SchnorrProof::prove_with_rng(g, h, x, transcript_hash, ceremony_id, rng),
             g,
             h,
+            g2_update: None,
+            input_srs_hash: None,
+            srs_hash: None,
+            transcript_hash,
+            ceremony_id,
+            rehearsal: false,
+        }
+    }
+
+    /// Attaches the G2 side of the update, from g2 to h2
+    pub fn with_g2_update(self, g2: G2Affine, h2: G2Affine) -> Self {
+        UpdateProof {
+            g2_update: Some(G2Update { g2, h2 }),
+            ..self
+        }
+    }
+
+    /// Attaches the SHA-256 digests of the updated and resulting SRS
+    pub fn with_srs_hashes(self, input_srs_hash: [u8; 32], srs_hash: [u8; 32]) -> Self {
+        UpdateProof {
+            input_srs_hash: Some(input_srs_hash),
+            srs_hash: Some(srs_hash),
+            ..self
+        }
+    }
+
+    pub fn schnorr_proof(&self) -> &SchnorrProof {
+        &self.schnorr_proof
+    }
+
+    /// Whether the Schnorr proof of this update, and its G2 side if any, are
+    /// valid
+    pub fn is_valid(&self) -> bool {
+        self.schnorr_proof
+            .is_valid(self.g, self.h, self.transcript_hash, self.ceremony_id)
+            && self
+                .g2_update
+                .as_ref()
+                .is_none_or(|update| update.is_valid(self.g, self.h))
+    }
+}
+
+/// Encoding of an update proof file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofFormat {
+    /// Uncompressed points, without header, as in the original ceremony
+    V1,
+    /// [PROOF_V2_MAGIC], the [TranscriptHash::id] and a flags byte (see
+    /// [PROOF_FLAG_REHEARSAL]), followed by the fields of the v1 format with
+    /// compressed points, the compressed points of the [G2Update], the SRS
+    /// digests and the ceremony ID
+    V2,
+}
+
+impl ProofFormat {
+    /// Size of a serialized [UpdateProof] in this format
+    pub fn proof_size(self) -> usize {
+        match self {
+            ProofFormat::V1 => UPDATE_PROOF_SIZE,
+            ProofFormat::V2 => UPDATE_PROOF_V2_SIZE,
+        }
+    }
+
+    /// Format of the given proof: [ProofFormat::V1] for legacy proofs, with
+    /// the untagged transcript and no ceremony ID, [ProofFormat::V2] otherwise
+    pub fn for_proof(proof: &UpdateProof) -> Self {
+        match (proof.transcript_hash, proof.ceremony_id, proof.rehearsal) {
+            (None, None, false) => ProofFormat::V1,
+            _ => ProofFormat::V2,
         }
     }
 
-    pub fn verify(&self) {
-        self.schnorr_proof.verify(self.g, self.h)
+    /// Format of the given serialized proof, if any
+    pub fn of(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() == UPDATE_PROOF_V2_SIZE && bytes.starts_with(&PROOF_V2_MAGIC) {
+            Some(ProofFormat::V2)
+        } else if bytes.len() == UPDATE_PROOF_SIZE {
+            Some(ProofFormat::V1)
+        } else {
+            None
+        }
     }
 }
 
+/// Decodes a compressed G1 point, rejecting encodings that are not canonical
+/// or not in the prime-order subgroup
+pub(crate) fn read_compressed_g1_point(bytes: &[u8]) -> Option<G1Affine> {
+    let bytes: [u8; G1_COMPRESSED_SIZE] = bytes.try_into().ok()?;
+    let point = Option::<G1Affine>::from(G1Affine::from_compressed(&bytes))?;
+    (point.to_compressed() == bytes).then_some(point)
+}
+
+/// Same as [read_compressed_g1_point], for G2 points
+fn read_compressed_g2_point(bytes: &[u8]) -> Option<G2Affine> {
+    let bytes: [u8; G2_COMPRESSED_SIZE] = bytes.try_into().ok()?;
+    let point = Option::<G2Affine>::from(G2Affine::from_compressed(&bytes))?;
+    (point.to_compressed() == bytes).then_some(point)
+}
+
 // (De-)Serialization functionality
 impl UpdateProof {
-    pub fn write_to_file(&self, path: &Path) {
-        let mut bytes = self.schnorr_proof.0.to_raw_bytes();
+    /// Serializes the proof in the given format. The v1 format only encodes
+    /// the Schnorr proof and its points, the v2 format requires every field
+    pub fn to_bytes(&self, format: ProofFormat) -> Vec<u8> {
+        if format == ProofFormat::V1 {
+            assert!(
+                ProofFormat::for_proof(self) == ProofFormat::V1,
+                "v1 proofs only support the untagged Blake2b512 transcript, without ceremony ID"
+            );
+            let mut bytes = Vec::with_capacity(UPDATE_PROOF_SIZE);
+            bytes.extend(self.schnorr_proof.0.to_raw_bytes());
+            bytes.extend(self.schnorr_proof.1.to_bytes_be());
+            bytes.extend(self.g.to_raw_bytes());
+            bytes.extend(self.h.to_raw_bytes());
+            return bytes;
+        }
+        let transcript_hash = self
+            .transcript_hash
+            .expect("v2 proofs record their transcript hash function");
+        let update = self
+            .g2_update
+            .as_ref()
+            .expect("v2 proofs carry the G2 update");
+        let flags = if self.rehearsal {
+            PROOF_FLAG_REHEARSAL
+        } else {
+            0
+        };
+        let mut bytes = Vec::with_capacity(UPDATE_PROOF_V2_SIZE);
+        bytes.extend(PROOF_V2_MAGIC);
+        bytes.extend([transcript_hash.id(), flags]);
+        bytes.extend(self.schnorr_proof.0.to_compressed());
         bytes.extend(self.schnorr_proof.1.to_bytes_be());
-        bytes.extend(self.g.to_raw_bytes());
-        bytes.extend(self.h.to_raw_bytes());
+        bytes.extend(self.g.to_compressed());
+        bytes.extend(self.h.to_compressed());
+        bytes.extend(update.g2.to_compressed());
+        bytes.extend(update.h2.to_compressed());
+        bytes.extend(
+            self.input_srs_hash
+                .expect("v2 proofs carry the SRS digests"),
+        );
+        bytes.extend(self.srs_hash.expect("v2 proofs carry the SRS digests"));
+        bytes.extend(self.ceremony_id.expect("v2 proofs carry the ceremony ID"));
+        bytes
+    }
+
+    /// Deserializes a proof in any of the [ProofFormat]s. Fails with a
+    /// [SrsError::Deserialization] if the bytes are not a (canonical) encoding
+    /// of a proof
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SrsError> {
+        Self::decode(bytes).map_err(SrsError::Deserialization)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let format = ProofFormat::of(bytes).ok_or_else(|| {
+            format!(
+                "An update proof has {UPDATE_PROOF_SIZE} (v1) or {UPDATE_PROOF_V2_SIZE} (v2) bytes, got {}",
+                bytes.len()
+            )
+        })?;
+        let mut bytes = bytes;
+        let mut take = |len: usize| {
+            let (head, rest) = bytes.split_at(len);
+            bytes = rest;
+            head
+        };
+        let scalar = |bytes: &[u8]| {
+            Option::<Scalar>::from(Scalar::from_bytes_be(bytes.try_into().unwrap()))
+                .ok_or("Failed to deserialize scalar of Schnorr proof")
+        };
+
+        if format == ProofFormat::V1 {
+            let read_point = |bytes| decode_g1_point(bytes).ok_or("Failed to read G1 point");
+            return Ok(Self {
+                schnorr_proof: SchnorrProof(read_point(take(G1_SIZE))?, scalar(take(SCALAR_SIZE))?),
+                g: read_point(take(G1_SIZE))?,
+                h: read_point(take(G1_SIZE))?,
+                g2_update: None,
+                input_srs_hash: None,
+                srs_hash: None,
+                transcript_hash: None,
+                ceremony_id: None,
+                rehearsal: false,
+            });
+        }
+
+        let header = take(PROOF_V2_MAGIC.len() + 2);
+        let (id, flags) = (
+            header[PROOF_V2_MAGIC.len()],
+            header[PROOF_V2_MAGIC.len() + 1],
+        );
+        let transcript_hash = TranscriptHash::from_id(id)
+            .ok_or_else(|| format!("Unknown transcript hash function {id} in update proof"))?;
+        if flags & !PROOF_FLAG_REHEARSAL != 0 {
+            return Err(format!("Unknown flags {flags:#04x} in update proof"));
+        }
+        let read_point =
+            |bytes| read_compressed_g1_point(bytes).ok_or("Failed to read compressed G1 point");
+        let read_g2_point =
+            |bytes| read_compressed_g2_point(bytes).ok_or("Failed to read compressed G2 point");
 
-        let mut file = create_file(path);
-        file.write_all(&bytes)
-            .expect("Could not write update proof to file");
+        Ok(Self {
+            schnorr_proof: SchnorrProof(
+                read_point(take(G1_COMPRESSED_SIZE))?,
+                scalar(take(SCALAR_SIZE))?,
+            ),
+            g: read_point(take(G1_COMPRESSED_SIZE))?,
+            h: read_point(take(G1_COMPRESSED_SIZE))?,
+            g2_update: Some(G2Update {
+                g2: read_g2_point(take(G2_COMPRESSED_SIZE))?,
+                h2: read_g2_point(take(G2_COMPRESSED_SIZE))?,
+            }),
+            input_srs_hash: Some(take(SRS_HASH_SIZE).try_into().unwrap()),
+            srs_hash: Some(take(SRS_HASH_SIZE).try_into().unwrap()),
+            ceremony_id: Some(take(CEREMONY_ID_SIZE).try_into().unwrap()),
+            transcript_hash: Some(transcript_hash),
+            rehearsal: flags & PROOF_FLAG_REHEARSAL != 0,
+        })
     }
 
-    pub fn read_from_file(path: &Path) -> Self {
-        let mut file = open_file(path);
-        let mut point_buf = [0u8; G1_SIZE];
-        let mut scalar_buf = [0u8; SCALAR_SIZE];
+    /// Writes the proof to the given file, in the format of
+    /// [ProofFormat::for_proof], with its checksum sidecar
+    pub fn write_to_file(&self, path: &Path) -> Result<(), SrsError> {
+        let bytes = self.to_bytes(ProofFormat::for_proof(self));
+        create_file(path)?
+            .write_all(&bytes)
+            .map_err(SrsError::io("write", path))?;
+        write_sidecar(path, &Sha256::digest(&bytes).into())
+    }
+
+    /// Writes the proof to the given file, then reads it back and checks that
+    /// it decodes to this very proof and that it verifies
+    pub fn write_to_file_validated(&self, path: &Path) -> Result<(), SrsError> {
+        self.write_to_file(path)?;
+
+        let file_len = open_file(path)?
+            .metadata()
+            .map_err(SrsError::io("read", path))?
+            .len() as usize;
+        if file_len != ProofFormat::for_proof(self).proof_size() {
+            return Err(SrsError::Verification(format!(
+                "The update proof written to {path:?} has an unexpected size"
+            )));
+        }
+
+        let read_back = Self::read_from_file(path)?;
+        if &read_back != self {
+            return Err(SrsError::Verification(format!(
+                "The update proof written to {path:?} does not match the computed one"
+            )));
+        }
+        if !read_back.is_valid() {
+            return Err(SrsError::Verification("Invalid update proof".to_string()));
+        }
+        Ok(())
+    }
 
-        file.read_exact(&mut point_buf).expect("Not enough bytes");
-        let schnorr_point = read_g1_point(&point_buf);
+    /// Reads the proof stored at the given path, checking it against its
+    /// checksum sidecar first, if there is one
+    pub fn read_from_file(path: &Path) -> Result<Self, SrsError> {
+        let mut bytes = Vec::with_capacity(UPDATE_PROOF_SIZE);
+        open_file(path)?
+            .read_to_end(&mut bytes)
+            .map_err(SrsError::io("read", path))?;
+        check_sidecar_digest(path, &Sha256::digest(&bytes).into())?;
+        if ProofFormat::of(&bytes).is_none() {
+            return Err(SrsError::Deserialization(format!(
+                "{path:?} is not an update proof (detected format: {})",
+                sniff_bytes(&bytes, bytes.len() as u64)
+            )));
+        }
+        Self::from_bytes(&bytes)
+    }
+}
 
-        file.read_exact(&mut scalar_buf).expect("Not enough bytes");
-        let schnorr_scalar = Scalar::from_bytes_be(&scalar_buf)
-            .expect("Failed to deserialize scalar of Schnorr proof");
+#[cfg(test)]
+mod schnorr_tests {
+    use std::path::Path;
 
-        file.read_exact(&mut point_buf).expect("Not enough bytes");
-        let g = read_g1_point(&point_buf);
+    use blstrs::{G1Affine, G2Affine, Scalar};
+    use halo2curves::{
+        ff::Field,
+        group::{prime::PrimeCurveAffine, Curve},
+    };
+    use rand_core::OsRng;
 
-        file.read_exact(&mut point_buf).expect("Not enough bytes");
-        let h = read_g1_point(&point_buf);
+    use crate::{
+        schnorr::{
+            read_compressed_g1_point, G2Update, ProofFormat, SchnorrProof, UpdateProof,
+            PROOF_FLAG_REHEARSAL, PROOF_V2_MAGIC,
+        },
+        state::CeremonyContext,
+        utils::TranscriptHash,
+    };
 
-        Self {
-            schnorr_proof: SchnorrProof(schnorr_point, schnorr_scalar),
+    fn random_update_proof(
+        transcript_hash: Option<TranscriptHash>,
+        ceremony_id: Option<[u8; 32]>,
+    ) -> UpdateProof {
+        let tau = Scalar::random(OsRng);
+        let (g, g2) = (
+            (G1Affine::generator() * tau).to_affine(),
+            (G2Affine::generator() * tau).to_affine(),
+        );
+        let x = Scalar::random(OsRng);
+        let h = (g * x).to_affine();
+        UpdateProof {
+            schnorr_proof: SchnorrProof::prove(g, h, &x, transcript_hash, ceremony_id),
             g,
             h,
+            g2_update: Some(G2Update {
+                g2,
+                h2: (g2 * x).to_affine(),
+            }),
+            input_srs_hash: Some([1u8; 32]),
+            srs_hash: Some([2u8; 32]),
+            transcript_hash,
+            ceremony_id,
+            rehearsal: false,
         }
     }
+
+    #[test]
+    fn update_proof_round_trip() {
+        let g = (G1Affine::generator() * Scalar::random(OsRng)).to_affine();
+        let g2 = G2Affine::generator();
+        let x = Scalar::random(OsRng);
+        let proof = UpdateProof::create(&CeremonyContext::for_tests(), g, (g * x).to_affine(), &x)
+            .with_g2_update(g2, (g2 * x).to_affine())
+            .with_srs_hashes([1u8; 32], [2u8; 32]);
+        proof
+            .write_to_file_validated(Path::new("/tmp/test_update_proof"))
+            .unwrap();
+
+        for transcript_hash in [
+            TranscriptHash::Blake2b512,
+            TranscriptHash::Sha3_512,
+            TranscriptHash::Blake3Xof,
+        ] {
+            let proof = random_update_proof(Some(transcript_hash), Some([3u8; 32]));
+            let bytes = proof.to_bytes(ProofFormat::V2);
+            assert_eq!(bytes.len(), ProofFormat::V2.proof_size());
+            assert_eq!(ProofFormat::of(&bytes), Some(ProofFormat::V2));
+            assert_eq!(UpdateProof::from_bytes(&bytes).unwrap(), proof);
+        }
+
+        // Proofs made in a rehearsal are told apart by a flag of their header
+        let proof = UpdateProof {
+            rehearsal: true,
+            ..random_update_proof(Some(TranscriptHash::Blake2b512), Some([3u8; 32]))
+        };
+        let mut bytes = proof.to_bytes(ProofFormat::V2);
+        assert!(bytes.starts_with(&PROOF_V2_MAGIC));
+        assert_eq!(bytes[PROOF_V2_MAGIC.len() + 1], PROOF_FLAG_REHEARSAL);
+        assert_eq!(UpdateProof::from_bytes(&bytes).unwrap(), proof);
+
+        // Unknown flags are rejected
+        bytes[PROOF_V2_MAGIC.len() + 1] |= 2;
+        assert!(UpdateProof::from_bytes(&bytes).is_err());
+
+        // Legacy proofs, without the G2 side of the update and the SRS digests
+        let proof = random_update_proof(None, None);
+        let bytes = proof.to_bytes(ProofFormat::V1);
+        assert_eq!(bytes.len(), ProofFormat::V1.proof_size());
+        assert_eq!(ProofFormat::of(&bytes), Some(ProofFormat::V1));
+
+        let read_back = UpdateProof::from_bytes(&bytes).unwrap();
+        assert_eq!(read_back.g2_update, None);
+        assert_eq!(read_back.srs_hash, None);
+        assert_eq!(read_back.h, proof.h);
+        assert!(read_back.is_valid());
+    }
+
+    #[test]
+    fn transcripts_are_domain_separated() {
+        let mut proof = random_update_proof(Some(TranscriptHash::Sha3_512), None);
+        assert!(proof.is_valid());
+
+        proof.transcript_hash = Some(TranscriptHash::Blake2b512);
+        assert!(!proof.is_valid());
+
+        let mut proof = random_update_proof(Some(TranscriptHash::Blake2b512), None);
+        proof.transcript_hash = None;
+        assert!(!proof.is_valid());
+    }
+
+    #[test]
+    fn proofs_are_bound_to_their_ceremony() {
+        let mut proof = random_update_proof(Some(TranscriptHash::Blake2b512), Some([3u8; 32]));
+        assert!(proof.is_valid());
+
+        proof.ceremony_id = Some([4u8; 32]);
+        assert!(!proof.is_valid());
+
+        proof.ceremony_id = None;
+        assert!(!proof.is_valid());
+    }
+
+    #[test]
+    fn g2_update_must_match_g1_update() {
+        let mut proof = random_update_proof(None, None);
+        assert!(proof.is_valid());
+
+        let update = proof.g2_update.as_mut().unwrap();
+        update.h2 = (update.h2 * Scalar::from(2)).to_affine();
+        assert!(!proof.is_valid());
+    }
+
+    #[test]
+    fn non_canonical_points_are_rejected() {
+        let point = (G1Affine::generator() * Scalar::random(OsRng)).to_affine();
+        let bytes = point.to_compressed();
+        assert_eq!(read_compressed_g1_point(&bytes), Some(point));
+
+        // Without the compression flag
+        let mut uncompressed_flag = bytes;
+        uncompressed_flag[0] &= 0x7f;
+        assert_eq!(read_compressed_g1_point(&uncompressed_flag), None);
+
+        // Point at infinity with a non-zero x-coordinate
+        let mut infinity = [0u8; 48];
+        infinity[0] = 0xc0;
+        assert!(read_compressed_g1_point(&infinity).is_some());
+        infinity[47] = 1;
+        assert_eq!(read_compressed_g1_point(&infinity), None);
+    }
 }
@@ -0,0 +1,231 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Self-test of the build.
+//!
+//! [run_selftest] exercises the arithmetic and the encodings the ceremony
+//! relies on against golden values embedded in the binary: the Filecoin
+//! [tau]_1 and the first update proofs of the original ceremony, with their
+//! SHA-256 digests, and a tiny SRS whose Lagrange basis is known in advance
+//! (for tau a root of unity). A broken build (e.g. miscompiled assembly or
+//! missing CPU features) fails it before any ceremony file is touched.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use blstrs::{pairing, G1Affine, G2Affine, Scalar};
+use halo2curves::{
+    group::{prime::PrimeCurveAffine, Curve},
+    serde::SerdeObject,
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    ceremony::SRS,
+    lagrange::{is_lagrange_basis, lagrange_basis, root_of_unity},
+    msm::MsmConfig,
+    progress::NoProgress,
+    schnorr::{read_compressed_g1_point, ProofFormat, UpdateProof},
+    utils::{powers, read_g1_point},
+};
+
+/// [tau]_1 of the Filecoin SRS, anchoring the original ceremony
+const GENESIS_G1_POINT: &[u8] = include_bytes!("../filecoin_srs_g1_point");
+
+/// SHA-256 digest (hex) of [GENESIS_G1_POINT]
+const GENESIS_G1_POINT_SHA256: &str =
+    "2d3c62eec11a4e83edd35ca1933a608c0148a10224b674834c64181571c9df21";
+
+/// The first two (v1) update proofs of the original ceremony, with their
+/// SHA-256 digests (hex)
+const GOLDEN_PROOFS: [(&[u8], &str); 2] = [
+    (
+        include_bytes!("../proofs/proof1"),
+        "6c21fb77865903990f1ce686bd6a3e405726ee4d009d1b5b0afe3d199c1abea9",
+    ),
+    (
+        include_bytes!("../proofs/proof2"),
+        "439b5961db1d782cf2786fbcada9a1513d9c6e294f2d4f80c1094f0f721756ba",
+    ),
+];
+
+/// log2 of the number of points of the SRS of the pairing and FFT checks
+const TINY_SRS_LOG2_LEN: u32 = 3;
+
+/// Outcome of a check of the self-test: its name, and the reason of its
+/// failure if it failed
+pub type CheckOutcome = (&'static str, Result<(), String>);
+
+/// Runs all the checks of the self-test, in order
+pub fn run_selftest() -> Vec<CheckOutcome> {
+    let checks: [(&'static str, fn() -> Result<(), String>); 5] = [
+        ("Digests of the embedded artifacts", check_digests),
+        ("Serialization of points and proofs", check_serialization),
+        ("Schnorr verification", check_schnorr),
+        ("Pairing checks", check_pairings),
+        ("FFT", check_fft),
+    ];
+    // Failed checks are reported as such, not as panic messages
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let outcomes = checks
+        .into_iter()
+        .map(|(name, check)| {
+            let outcome = catch_unwind(AssertUnwindSafe(check)).unwrap_or_else(|err| {
+                Err(err
+                    .downcast_ref::<String>()
+                    .cloned()
+                    .or_else(|| err.downcast_ref::<&str>().map(|err| err.to_string()))
+                    .unwrap_or_else(|| "panicked".to_string()))
+            });
+            (name, outcome)
+        })
+        .collect();
+    std::panic::set_hook(hook);
+    outcomes
+}
+
+/// Fails with the given reason unless the condition holds
+fn ensure(condition: bool, reason: &str) -> Result<(), String> {
+    condition.then_some(()).ok_or_else(|| reason.to_string())
+}
+
+fn check_digests() -> Result<(), String> {
+    ensure(
+        hex::encode(Sha256::digest(GENESIS_G1_POINT)) == GENESIS_G1_POINT_SHA256,
+        "Wrong digest of the genesis point",
+    )?;
+    for (i, (bytes, sha256)) in GOLDEN_PROOFS.iter().enumerate() {
+        ensure(
+            hex::encode(Sha256::digest(bytes)) == *sha256,
+            &format!("Wrong digest of proof{}", i + 1),
+        )?;
+    }
+    Ok(())
+}
+
+fn check_serialization() -> Result<(), String> {
+    let genesis = read_g1_point(GENESIS_G1_POINT).map_err(|err| err.to_string())?;
+    ensure(
+        genesis.to_raw_bytes() == GENESIS_G1_POINT,
+        "The genesis point is not re-encoded identically",
+    )?;
+    ensure(
+        read_compressed_g1_point(&genesis.to_compressed()) == Some(genesis),
+        "Compressed points do not round-trip",
+    )?;
+
+    for (i, (bytes, _)) in GOLDEN_PROOFS.iter().enumerate() {
+        let proof = UpdateProof::from_bytes(bytes).map_err(|err| err.to_string())?;
+        ensure(
+            proof.to_bytes(ProofFormat::V1) == *bytes,
+            &format!("proof{} is not re-encoded identically", i + 1),
+        )?;
+        ensure(
+            ProofFormat::for_proof(&proof) == ProofFormat::V1,
+            &format!("proof{} is not detected as a v1 proof", i + 1),
+        )?;
+    }
+    Ok(())
+}
+
+fn check_schnorr() -> Result<(), String> {
+    let mut g = read_g1_point(GENESIS_G1_POINT).map_err(|err| err.to_string())?;
+    for (i, (bytes, _)) in GOLDEN_PROOFS.iter().enumerate() {
+        let proof = UpdateProof::from_bytes(bytes).map_err(|err| err.to_string())?;
+        ensure(
+            proof.g == g,
+            &format!("proof{} does not extend the chain", i + 1),
+        )?;
+        ensure(proof.is_valid(), &format!("proof{} is rejected", i + 1))?;
+        g = proof.h;
+    }
+
+    // The same proof, for the inverse update, must be rejected
+    let mut proof = UpdateProof::from_bytes(GOLDEN_PROOFS[0].0).map_err(|err| err.to_string())?;
+    (proof.g, proof.h) = (proof.h, proof.g);
+    ensure(!proof.is_valid(), "A forged proof is accepted")
+}
+
+/// The SRS of the 2^[TINY_SRS_LOG2_LEN]-th root of unity
+fn tiny_srs() -> SRS {
+    let tau = root_of_unity(TINY_SRS_LOG2_LEN);
+    SRS {
+        g1s: powers(&tau, 1 << TINY_SRS_LOG2_LEN)
+            .iter()
+            .map(|power| (G1Affine::generator() * power).to_affine())
+            .collect(),
+        g2s: [
+            G2Affine::generator(),
+            (G2Affine::generator() * tau).to_affine(),
+        ],
+    }
+}
+
+fn check_pairings() -> Result<(), String> {
+    let (g1, g2) = (G1Affine::generator(), G2Affine::generator());
+    let x = Scalar::from(0x5eed);
+    ensure(
+        pairing(&(g1 * x).to_affine(), &g2) == pairing(&g1, &(g2 * x).to_affine()),
+        "The pairing is not bilinear",
+    )?;
+    ensure(
+        pairing(&g1, &g2) != pairing(&g1, &(g2 * x).to_affine()),
+        "The pairing is degenerate",
+    )?;
+
+    let mut srs = tiny_srs();
+    srs.verify_structure_with(&MsmConfig::default(), &NoProgress)
+        .map_err(|err| err.to_string())?;
+    srs.g1s[2] = srs.g1s[3];
+    let broken = srs.verify_structure_with(&MsmConfig::default(), &NoProgress);
+    ensure(broken.is_err(), "An SRS with a wrong point is accepted")
+}
+
+fn check_fft() -> Result<(), String> {
+    // For tau = w, L_i(tau) = 1 if i = 1, and 0 otherwise
+    let srs = tiny_srs();
+    let lagrange = lagrange_basis(&srs.g1s, TINY_SRS_LOG2_LEN);
+    for (i, point) in lagrange.iter().enumerate() {
+        let expected = match i {
+            1 => G1Affine::generator(),
+            _ => G1Affine::identity(),
+        };
+        ensure(
+            *point == expected,
+            &format!("Wrong point {i} of the Lagrange basis"),
+        )?;
+    }
+    ensure(
+        is_lagrange_basis(
+            &srs.g1s,
+            &lagrange,
+            TINY_SRS_LOG2_LEN,
+            &MsmConfig::default(),
+        ),
+        "The Lagrange basis is rejected",
+    )
+}
+
+#[cfg(test)]
+mod selftest_tests {
+    use crate::selftest::run_selftest;
+
+    #[test]
+    fn selftest_passes() {
+        for (name, outcome) in run_selftest() {
+            assert_eq!(outcome, Ok(()), "{name}");
+        }
+    }
+}
@@ -0,0 +1,262 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of the format of a file.
+//!
+//! The files handled by the tools (SRS in their various encodings,
+//! Barretenberg transcripts, update proofs) have no common header, but every
+//! SRS starts with the G1 generator and their sizes are constrained, so the
+//! format of a file is told apart from its size and first bytes. Readers use
+//! it to decode an SRS in whichever format it is given, and to say what a
+//! file they cannot decode looks like.
+
+use std::{fmt, path::Path};
+
+use blstrs::G1Affine;
+use halo2curves::{group::prime::PrimeCurveAffine, serde::SerdeObject};
+
+use crate::{
+    ceremony::{G1_COMPRESSED_SIZE, G1_SIZE, G2_COMPRESSED_SIZE, G2_SIZE},
+    error::SrsError,
+    formats::barretenberg_manifest,
+    release::SrsEncoding,
+    schnorr::ProofFormat,
+    utils::{fill_buffer, open_file},
+};
+
+/// Number of leading bytes the format is detected from, enough to hold a
+/// whole update proof
+const SNIFF_LEN: usize = 1024;
+
+/// Format of a file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileFormat {
+    /// SRS with raw point encodings, as written by
+    /// [crate::ceremony::SRS::write_to_file]
+    RawSrs {
+        nr_g1_points: usize,
+    },
+    /// SRS with uncompressed points in the standard (zcash) serialization,
+    /// see [crate::release]
+    UncompressedSrs {
+        nr_g1_points: usize,
+    },
+    /// SRS with compressed points in the standard (zcash) serialization
+    CompressedSrs {
+        nr_g1_points: usize,
+    },
+    /// Barretenberg transcript, see [crate::formats]
+    BarretenbergTranscript {
+        transcript_number: u32,
+        total_transcripts: u32,
+        /// G1 points of the whole SRS, the generator included
+        nr_g1_points: usize,
+    },
+    UpdateProof(ProofFormat),
+    Unknown,
+}
+
+impl fmt::Display for FileFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileFormat::RawSrs { nr_g1_points } => {
+                write!(f, "SRS with raw points ({nr_g1_points} G1 points)")
+            }
+            FileFormat::UncompressedSrs { nr_g1_points } => {
+                write!(f, "SRS with uncompressed points ({nr_g1_points} G1 points)")
+            }
+            FileFormat::CompressedSrs { nr_g1_points } => {
+                write!(f, "SRS with compressed points ({nr_g1_points} G1 points)")
+            }
+            FileFormat::BarretenbergTranscript {
+                transcript_number,
+                total_transcripts,
+                nr_g1_points,
+            } => write!(
+                f,
+                "Barretenberg transcript {transcript_number} of {total_transcripts} ({nr_g1_points} G1 points in total)"
+            ),
+            FileFormat::UpdateProof(format) => write!(f, "update proof ({format:?})"),
+            FileFormat::Unknown => write!(f, "unknown format"),
+        }
+    }
+}
+
+impl FileFormat {
+    /// Number of G1 points of the SRS, if the file holds (part of) one
+    pub fn nr_g1_points(&self) -> Option<usize> {
+        match *self {
+            FileFormat::RawSrs { nr_g1_points }
+            | FileFormat::UncompressedSrs { nr_g1_points }
+            | FileFormat::CompressedSrs { nr_g1_points }
+            | FileFormat::BarretenbergTranscript { nr_g1_points, .. } => Some(nr_g1_points),
+            FileFormat::UpdateProof(_) | FileFormat::Unknown => None,
+        }
+    }
+
+    /// Encoding of the points, if the file holds an SRS written by
+    /// [crate::ceremony::SRS::write_to_file_as]
+    pub fn srs_encoding(&self) -> Option<SrsEncoding> {
+        match self {
+            FileFormat::RawSrs { .. } => Some(SrsEncoding::Raw),
+            FileFormat::UncompressedSrs { .. } => Some(SrsEncoding::Uncompressed),
+            FileFormat::CompressedSrs { .. } => Some(SrsEncoding::Compressed),
+            _ => None,
+        }
+    }
+}
+
+/// Number of G1 points of an SRS of `len` bytes, with G1 and G2 points of the
+/// given sizes, if that is a valid size
+fn nr_g1_points(len: u64, g1_size: usize, g2_size: usize) -> Option<usize> {
+    let g2s_len = 2 * g2_size as u64;
+    (len > g2s_len && (len - g2s_len) % g1_size as u64 == 0)
+        .then(|| ((len - g2s_len) / g1_size as u64) as usize)
+}
+
+/// Format of a file of `len` bytes starting with `head` (its first
+/// [SNIFF_LEN] bytes, or the whole file if shorter)
+pub fn sniff_bytes(head: &[u8], len: u64) -> FileFormat {
+    let generator = G1Affine::generator();
+    let srs_formats: [(Vec<u8>, usize, usize, fn(usize) -> FileFormat); 3] = [
+        (generator.to_raw_bytes(), G1_SIZE, G2_SIZE, |nr_g1_points| {
+            FileFormat::RawSrs { nr_g1_points }
+        }),
+        (
+            generator.to_uncompressed().to_vec(),
+            G1_SIZE,
+            G2_SIZE,
+            |nr_g1_points| FileFormat::UncompressedSrs { nr_g1_points },
+        ),
+        (
+            generator.to_compressed().to_vec(),
+            G1_COMPRESSED_SIZE,
+            G2_COMPRESSED_SIZE,
+            |nr_g1_points| FileFormat::CompressedSrs { nr_g1_points },
+        ),
+    ];
+    for (prefix, g1_size, g2_size, format) in srs_formats {
+        if let (true, Some(n)) = (
+            head.starts_with(&prefix),
+            nr_g1_points(len, g1_size, g2_size),
+        ) {
+            return format(n);
+        }
+    }
+
+    if let Some(manifest) = barretenberg_manifest(head, len) {
+        return FileFormat::BarretenbergTranscript {
+            transcript_number: manifest.transcript_number,
+            total_transcripts: manifest.total_transcripts,
+            nr_g1_points: manifest.total_g1_points as usize + 1,
+        };
+    }
+    if head.len() as u64 == len {
+        if let Some(format) = ProofFormat::of(head) {
+            return FileFormat::UpdateProof(format);
+        }
+    }
+    FileFormat::Unknown
+}
+
+/// Format of the file stored at the given path
+pub fn sniff_format(path: &Path) -> Result<FileFormat, SrsError> {
+    let read_error = SrsError::io("read", path);
+    let mut file = open_file(path)?;
+    let len = file.metadata().map_err(read_error)?.len();
+    let mut head = vec![0u8; SNIFF_LEN];
+    let read = fill_buffer(&mut file, &mut head).map_err(read_error)?;
+    Ok(sniff_bytes(&head[..read], len))
+}
+
+#[cfg(test)]
+mod sniff_tests {
+    use std::{fs, path::Path};
+
+    use blstrs::Scalar;
+    use halo2curves::{ff::Field, group::Curve};
+    use rand_core::OsRng;
+
+    use crate::{
+        ceremony::SRS,
+        formats::{barretenberg_transcript_path, write_barretenberg_transcripts},
+        release::write_release_artifacts,
+        schnorr::{ProofFormat, UpdateProof},
+        sniff::{sniff_format, FileFormat},
+        state::CeremonyContext,
+    };
+
+    #[test]
+    fn formats_are_detected() {
+        let srs = SRS::generate(1 << 4, OsRng);
+        let srs_path = Path::new("/tmp/test_sniff_srs");
+        let uncompressed_path = Path::new("/tmp/test_sniff_srs.uncompressed");
+        let compressed_path = Path::new("/tmp/test_sniff_srs.compressed");
+        srs.write_to_file(srs_path).unwrap();
+        write_release_artifacts(srs_path, uncompressed_path, compressed_path).unwrap();
+        let nr_g1_points = 1 << 4;
+
+        assert_eq!(
+            sniff_format(srs_path).unwrap(),
+            FileFormat::RawSrs { nr_g1_points }
+        );
+        assert_eq!(
+            sniff_format(uncompressed_path).unwrap(),
+            FileFormat::UncompressedSrs { nr_g1_points }
+        );
+        assert_eq!(
+            sniff_format(compressed_path).unwrap(),
+            FileFormat::CompressedSrs { nr_g1_points }
+        );
+        // The readers accept every encoding of an SRS
+        assert_eq!(SRS::read_from_file(compressed_path).unwrap(), srs);
+        assert_eq!(SRS::read_from_file(uncompressed_path).unwrap(), srs);
+
+        let dir = Path::new("/tmp/test_sniff_barretenberg");
+        write_barretenberg_transcripts(&srs, dir, 10).unwrap();
+        assert_eq!(
+            sniff_format(&barretenberg_transcript_path(dir, 1)).unwrap(),
+            FileFormat::BarretenbergTranscript {
+                transcript_number: 1,
+                total_transcripts: 2,
+                nr_g1_points,
+            }
+        );
+        assert_eq!(
+            SRS::read_from_file(&barretenberg_transcript_path(dir, 0)).unwrap(),
+            srs
+        );
+
+        let proof_path = Path::new("/tmp/test_sniff_proof");
+        let x = Scalar::random(OsRng);
+        UpdateProof::create(
+            &CeremonyContext::for_tests(),
+            srs.g1s[1],
+            (srs.g1s[1] * x).to_affine(),
+            &x,
+        )
+        .with_g2_update(srs.g2s[1], (srs.g2s[1] * x).to_affine())
+        .with_srs_hashes([1u8; 32], [2u8; 32])
+        .write_to_file(proof_path)
+        .unwrap();
+        assert_eq!(
+            sniff_format(proof_path).unwrap(),
+            FileFormat::UpdateProof(ProofFormat::V2)
+        );
+
+        fs::write(proof_path, [7u8; 100]).unwrap();
+        assert_eq!(sniff_format(proof_path).unwrap(), FileFormat::Unknown);
+    }
+}
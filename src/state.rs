@@ -0,0 +1,605 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Phases of the ceremony.
+//!
+//! The ceremony goes through the phases of [CeremonyPhase] in order, and the
+//! current phase is recorded in the ceremony manifest (`./ceremony.json`),
+//! together with the state of the chain of update proofs at each transition.
+//! Operations check the phase they are allowed in, so that e.g. updating the
+//! SRS after the beacon commitment is rejected.
+//!
+//! The manifest also determines the ID of the ceremony
+//! ([CeremonyManifest::ceremony_id]), stamped into the update proofs and their
+//! metadata so that artifacts of different ceremonies (or rehearsals) cannot
+//! be mixed. SRS files are headerless; they are bound to the ceremony through
+//! the SRS digests recorded in the proofs.
+//!
+//! A rehearsal ([CeremonyManifest::rehearsal]) derives its ID under its own
+//! domain tag, and its proofs are marked as such
+//! ([crate::schnorr::PROOF_FLAG_REHEARSAL]), so that the verification of a
+//! production ceremony rejects them outright ([check_rehearsal]).
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use blstrs::G1Affine;
+use halo2curves::serde::SerdeObject;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    audit::read_audited,
+    cache::{extend_transcript, sha256_file, write_json},
+    drand::BeaconFiles,
+    error::SrsError,
+    metadata::ProofMetadata,
+    release::ReleaseArtifacts,
+    schnorr::UpdateProof,
+    utils::{open_update_proof_dirs, proofs_dir, read_g1_point_from_file, TranscriptHash},
+};
+
+/// Default location of the ceremony manifest
+pub const DEFAULT_MANIFEST_PATH: &str = "./ceremony.json";
+
+/// Location of [tau]_1 of the Filecoin SRS, the genesis of every chain of
+/// update proofs
+pub const GENESIS_G1_POINT_PATH: &str = "./filecoin_srs_g1_point";
+
+/// Domain tag of the ceremony ID
+const CEREMONY_ID_DOMAIN: &[u8] = b"midnight-srs/ceremony-id";
+
+/// Domain tag of the ceremony ID of a rehearsal
+const REHEARSAL_ID_DOMAIN: &[u8] = b"midnight-srs/rehearsal-ceremony-id";
+
+/// A phase of the ceremony, in chronological order
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum CeremonyPhase {
+    /// The initial SRS and the tooling are being prepared
+    Setup,
+    /// Participants contribute updates of the SRS
+    OpenContributions,
+    /// Contributions are closed and a future beacon round is committed to
+    BeaconCommitment,
+    /// The beacon round is revealed and the last update is seeded with it
+    BeaconReveal,
+    /// The final SRS is verified and the release artifacts are produced
+    Finalization,
+    /// The final SRS is published
+    Published,
+}
+
+impl CeremonyPhase {
+    /// The phase following this one, if any
+    pub fn next(self) -> Option<Self> {
+        use CeremonyPhase::*;
+        match self {
+            Setup => Some(OpenContributions),
+            OpenContributions => Some(BeaconCommitment),
+            BeaconCommitment => Some(BeaconReveal),
+            BeaconReveal => Some(Finalization),
+            Finalization => Some(Published),
+            Published => None,
+        }
+    }
+}
+
+impl fmt::Display for CeremonyPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = serde_json::to_value(self).unwrap();
+        write!(f, "{}", name.as_str().unwrap())
+    }
+}
+
+/// A transition to a phase, with the state of the chain at that point
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseTransition {
+    pub phase: CeremonyPhase,
+    /// Number of update proofs at the transition
+    pub nr_proofs: usize,
+    /// Transcript hash (hex) of the update proofs at the transition, see
+    /// [crate::cache::ChainHead::extend]
+    pub transcript: String,
+}
+
+/// A mirror of the ceremony artifacts, as of its last synchronization (see
+/// [crate::storage::mirror])
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mirror {
+    /// Storage location of the mirror, see [crate::storage::open_storage]
+    pub location: String,
+    /// Time of the last synchronization, in seconds since the Unix epoch
+    pub synced_at: u64,
+    /// Number of objects of the mirror
+    pub nr_objects: usize,
+    /// Digest (hex) of the listing of the objects, see
+    /// [crate::storage::listing_digest]
+    pub listing: String,
+}
+
+/// A parameter set run as its own chain of update proofs, stored in
+/// `proofs/<name>`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParameterSet {
+    pub name: String,
+    /// log2 of the number of G1 points of the SRS
+    pub log2_len: usize,
+}
+
+/// The ceremony manifest
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CeremonyManifest {
+    /// Name of the ceremony, telling e.g. a rehearsal from the real ceremony
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Whether the ceremony is a rehearsal, whose update proofs cannot enter
+    /// a production ceremony
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub rehearsal: bool,
+    pub phase: CeremonyPhase,
+    /// Past transitions, oldest first
+    #[serde(default)]
+    pub transitions: Vec<PhaseTransition>,
+    /// Parameter sets run in parallel, besides the chain in `proofs`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sets: Vec<ParameterSet>,
+    /// Digests of the beacon commitment and reveal files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub beacon: Option<BeaconFiles>,
+    /// Release artifacts of the final SRS, recorded during finalization
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release: Option<ReleaseArtifacts>,
+    /// Hash function of the transcript of the Schnorr proofs of new
+    /// contributions
+    #[serde(default)]
+    pub transcript_hash: TranscriptHash,
+    /// Mirrors of the ceremony artifacts, kept in case the primary host
+    /// disappears
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<Mirror>,
+    /// Index (from 1) of the first contribution of the main chain whose
+    /// update proof must carry the ceremony ID, the G2 side of the update and
+    /// the SRS digests; the earlier ones predate them (e.g. 39, after the 38
+    /// v1 proofs of the original ceremony). The chains of the parameter sets
+    /// are stamped from their first contribution
+    #[serde(default = "first_contribution")]
+    pub stamped_from: usize,
+}
+
+fn first_contribution() -> usize {
+    1
+}
+
+impl Default for CeremonyManifest {
+    fn default() -> Self {
+        CeremonyManifest {
+            name: None,
+            rehearsal: false,
+            phase: CeremonyPhase::Setup,
+            transitions: Vec::new(),
+            sets: Vec::new(),
+            beacon: None,
+            release: None,
+            transcript_hash: TranscriptHash::default(),
+            mirrors: Vec::new(),
+            stamped_from: first_contribution(),
+        }
+    }
+}
+
+impl CeremonyManifest {
+    /// Loads the manifest from the given file. Fails if the file is missing
+    /// or malformed
+    pub fn load(path: &Path) -> Result<Self, SrsError> {
+        let bytes = read_audited(path).map_err(|source| SrsError::Io {
+            action: "read",
+            path: path.to_path_buf(),
+            source,
+        })?;
+        serde_json::from_slice(&bytes).map_err(|err| {
+            SrsError::Deserialization(format!("Malformed ceremony manifest {path:?}: {err}"))
+        })
+    }
+
+    /// Writes the manifest to the given file
+    pub fn save(&self, path: &Path) -> Result<(), SrsError> {
+        write_json(path, self)
+    }
+
+    /// Fails with a [SrsError::Verification] unless the ceremony is in the
+    /// given phase, the only one in which `operation` is allowed
+    pub fn require(&self, phase: CeremonyPhase, operation: &str) -> Result<(), SrsError> {
+        if self.phase != phase {
+            return Err(SrsError::Verification(format!(
+                "Cannot {operation} in the {} phase of the ceremony (only allowed in the {phase} phase)",
+                self.phase
+            )));
+        }
+        Ok(())
+    }
+
+    /// The parameter set with the given name. Panics if the manifest does not
+    /// declare it
+    pub fn parameter_set(&self, name: &str) -> &ParameterSet {
+        self.sets
+            .iter()
+            .find(|set| set.name == name)
+            .unwrap_or_else(|| panic!("Unknown parameter set '{name}'"))
+    }
+
+    /// ID of the ceremony (of the given parameter set, if any) whose chain of
+    /// update proofs starts at `genesis`: SHA-256 of a domain tag (its own for
+    /// a rehearsal), the genesis point and the configuration (name of the
+    /// ceremony, transcript hash function and parameter set)
+    pub fn ceremony_id(&self, genesis: &G1Affine, parameter_set: Option<&str>) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(if self.rehearsal {
+            REHEARSAL_ID_DOMAIN
+        } else {
+            CEREMONY_ID_DOMAIN
+        });
+        hasher.update(genesis.to_raw_bytes());
+        let name = self.name.as_deref().unwrap_or_default();
+        hasher.update((name.len() as u64).to_le_bytes());
+        hasher.update(name);
+        hasher.update([self.transcript_hash.id()]);
+        if let Some(set) = parameter_set.map(|name| self.parameter_set(name)) {
+            hasher.update((set.name.len() as u64).to_le_bytes());
+            hasher.update(&set.name);
+            hasher.update((set.log2_len as u64).to_le_bytes());
+        }
+        hasher.finalize().into()
+    }
+
+    /// Moves the ceremony to the given phase, recording the state of the
+    /// chain of update proofs. Panics unless `phase` directly follows the
+    /// current phase
+    pub fn advance(&mut self, phase: CeremonyPhase, nr_proofs: usize, transcript: [u8; 32]) {
+        assert_eq!(
+            self.phase.next(),
+            Some(phase),
+            "The ceremony cannot move from the {} phase to the {phase} phase",
+            self.phase
+        );
+        self.phase = phase;
+        self.transitions.push(PhaseTransition {
+            phase,
+            nr_proofs,
+            transcript: hex::encode(transcript),
+        });
+    }
+
+    /// Records the synchronization of a mirror, replacing the previous record
+    /// of the same location
+    pub fn record_mirror(&mut self, mirror: Mirror) {
+        match self
+            .mirrors
+            .iter_mut()
+            .find(|recorded| recorded.location == mirror.location)
+        {
+            Some(recorded) => *recorded = mirror,
+            None => self.mirrors.push(mirror),
+        }
+    }
+}
+
+/// The chain of update proofs worked on: the ceremony (or parameter set) it
+/// belongs to, and how its new proofs are made
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CeremonyContext {
+    /// See [CeremonyManifest::ceremony_id]
+    pub ceremony_id: [u8; 32],
+    /// Hash function of the transcript of the Schnorr proofs of new
+    /// contributions
+    pub transcript_hash: TranscriptHash,
+    /// Whether the ceremony is a rehearsal, see [CeremonyManifest::rehearsal]
+    pub rehearsal: bool,
+    /// The parameter set of the chain, if not the main one
+    pub parameter_set: Option<String>,
+    /// Index of the first contribution of the chain that must be stamped, see
+    /// [CeremonyManifest::stamped_from]
+    pub stamped_from: usize,
+}
+
+impl CeremonyContext {
+    /// Context of the chain of the given parameter set (the main chain if
+    /// `None`) of the ceremony, starting at `genesis`
+    pub fn new(
+        manifest: &CeremonyManifest,
+        genesis: &G1Affine,
+        parameter_set: Option<&str>,
+    ) -> Self {
+        CeremonyContext {
+            ceremony_id: manifest.ceremony_id(genesis, parameter_set),
+            transcript_hash: manifest.transcript_hash,
+            rehearsal: manifest.rehearsal,
+            parameter_set: parameter_set.map(str::to_string),
+            stamped_from: match parameter_set {
+                None => manifest.stamped_from,
+                Some(_) => first_contribution(),
+            },
+        }
+    }
+
+    /// Context of the ceremony recorded in the manifest at the default
+    /// location, whose chains start at the Filecoin [tau]_1
+    pub fn load(parameter_set: Option<&str>) -> Result<Self, SrsError> {
+        let manifest = CeremonyManifest::load(Path::new(DEFAULT_MANIFEST_PATH))?;
+        let genesis = read_g1_point_from_file(Path::new(GENESIS_G1_POINT_PATH), 0)?;
+        Ok(Self::new(&manifest, &genesis, parameter_set))
+    }
+
+    /// Directory of the update proofs of the chain, see [proofs_dir]
+    pub fn proofs_dir(&self) -> PathBuf {
+        proofs_dir(self.parameter_set.as_deref())
+    }
+}
+
+/// Checks that the update proof of the `index`-th contribution and its
+/// metadata, when stamped with a ceremony ID, belong to the given ceremony.
+/// From [CeremonyContext::stamped_from] on, the proof must be stamped, and
+/// carry the G2 side of the update and the SRS digests. Returns the reason of
+/// the failure, if it fails
+pub fn check_ceremony_id(
+    ctx: &CeremonyContext,
+    index: usize,
+    proof: &UpdateProof,
+    metadata: Option<&ProofMetadata>,
+) -> Result<(), String> {
+    check_stamped(ctx, index, proof)?;
+    let expected = hex::encode(ctx.ceremony_id);
+    let stamps = [
+        ("proof", proof.ceremony_id.map(hex::encode)),
+        (
+            "metadata",
+            metadata.and_then(|metadata| metadata.ceremony_id.clone()),
+        ),
+    ];
+    for (artifact, stamp) in stamps {
+        if let Some(stamp) = stamp.filter(|stamp| *stamp != expected) {
+            return Err(format!(
+                "The {artifact} belongs to ceremony {stamp}, not to this ceremony ({expected})"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the update proof of the `index`-th contribution carries the
+/// ceremony ID, the G2 side of the update and the SRS digests, if they are
+/// mandatory from that contribution on (see [CeremonyContext::stamped_from]).
+/// Returns the reason of the failure, if it fails
+pub fn check_stamped(
+    ctx: &CeremonyContext,
+    index: usize,
+    proof: &UpdateProof,
+) -> Result<(), String> {
+    if index < ctx.stamped_from {
+        return Ok(());
+    }
+    let missing: Vec<&str> = [
+        ("the ceremony ID", proof.ceremony_id.is_none()),
+        ("the G2 update", proof.g2_update.is_none()),
+        (
+            "the SRS digests",
+            proof.input_srs_hash.is_none() || proof.srs_hash.is_none(),
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(field, missing)| missing.then_some(field))
+    .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "The proof lacks {}, mandatory from contribution {}",
+            missing.join(", "),
+            ctx.stamped_from
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that the update proof was not made in a rehearsal, unless the
+/// ceremony (a rehearsal iff `rehearsal`) is one. Returns the reason of the
+/// failure, if it fails
+pub fn check_rehearsal(rehearsal: bool, proof: &UpdateProof) -> Result<(), String> {
+    if proof.rehearsal && !rehearsal {
+        return Err(
+            "The proof was made in a rehearsal, it cannot enter a production ceremony".to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Number of update proofs of the given directory and their transcript hash
+pub fn chain_state(proofs_dir: &Path) -> Result<(usize, [u8; 32]), SrsError> {
+    let proof_dirs = open_update_proof_dirs(proofs_dir)?;
+    let transcript = proof_dirs.iter().try_fold([0u8; 32], |transcript, entry| {
+        Ok::<_, SrsError>(extend_transcript(&transcript, &sha256_file(&entry.path())?))
+    })?;
+    Ok((proof_dirs.len(), transcript))
+}
+
+#[cfg(test)]
+mod state_tests {
+    use blstrs::{G1Affine, Scalar};
+    use halo2curves::{
+        ff::Field,
+        group::{prime::PrimeCurveAffine, Curve},
+    };
+    use rand_core::OsRng;
+
+    use crate::{
+        schnorr::UpdateProof,
+        state::{
+            check_ceremony_id, check_rehearsal, CeremonyContext, CeremonyManifest, CeremonyPhase,
+            Mirror, ParameterSet,
+        },
+        utils::TranscriptHash,
+    };
+
+    #[cfg(test)]
+    impl CeremonyContext {
+        /// ONLY FOR TESTS
+        ///
+        /// Context of the main chain of a ceremony with the default manifest,
+        /// starting at the G1 generator
+        pub(crate) fn for_tests() -> Self {
+            CeremonyContext::new(&CeremonyManifest::default(), &G1Affine::generator(), None)
+        }
+    }
+
+    #[test]
+    fn ceremony_ids_differ() {
+        let manifest = CeremonyManifest::default();
+        let rehearsal = CeremonyManifest {
+            name: Some("rehearsal".to_string()),
+            ..Default::default()
+        };
+        let tagged = CeremonyManifest {
+            rehearsal: true,
+            ..Default::default()
+        };
+        let genesis = G1Affine::generator();
+        let id = |manifest: &CeremonyManifest, genesis| manifest.ceremony_id(genesis, None);
+        assert_eq!(id(&manifest, &genesis), id(&manifest, &genesis));
+        assert_ne!(id(&manifest, &genesis), id(&rehearsal, &genesis));
+        assert_ne!(id(&manifest, &genesis), id(&tagged, &genesis));
+        assert_ne!(
+            id(&manifest, &genesis),
+            id(&manifest, &G1Affine::identity())
+        );
+
+        let manifest = CeremonyManifest {
+            sets: vec![ParameterSet {
+                name: "small".to_string(),
+                log2_len: 10,
+            }],
+            ..Default::default()
+        };
+        assert_ne!(
+            manifest.ceremony_id(&genesis, None),
+            manifest.ceremony_id(&genesis, Some("small"))
+        );
+    }
+
+    #[test]
+    fn rehearsal_proofs_are_rejected_in_production() {
+        let g = G1Affine::generator();
+        let x = Scalar::random(OsRng);
+        let proof = UpdateProof::create_with(
+            g,
+            (g * x).to_affine(),
+            &x,
+            Some(TranscriptHash::default()),
+            Some([1u8; 32]),
+            OsRng,
+        );
+        assert!(check_rehearsal(false, &proof).is_ok());
+
+        let mut proof = proof;
+        proof.rehearsal = true;
+        assert!(check_rehearsal(true, &proof).is_ok());
+        assert!(check_rehearsal(false, &proof).is_err());
+    }
+
+    #[test]
+    fn stamps_are_mandatory_from_the_recorded_index() {
+        let manifest: CeremonyManifest =
+            serde_json::from_str(r#"{"phase":"setup","transitions":[],"stamped_from":39}"#)
+                .unwrap();
+        let genesis = G1Affine::generator();
+        let ctx = CeremonyContext::new(&manifest, &genesis, None);
+        assert_eq!(ctx.stamped_from, 39);
+        assert_eq!(
+            CeremonyContext::new(&manifest, &genesis, Some("small")).stamped_from,
+            1
+        );
+
+        let x = Scalar::random(OsRng);
+        let h = (genesis * x).to_affine();
+        let legacy = UpdateProof::create_with(genesis, h, &x, None, None, OsRng);
+        assert!(check_ceremony_id(&ctx, 38, &legacy, None).is_ok());
+        assert_eq!(
+            check_ceremony_id(&ctx, 39, &legacy, None).unwrap_err(),
+            "The proof lacks the ceremony ID, the G2 update, the SRS digests, \
+             mandatory from contribution 39"
+        );
+        let stamped = UpdateProof::create(&ctx, genesis, h, &x);
+        assert_eq!(
+            check_ceremony_id(&ctx, 39, &stamped, None).unwrap_err(),
+            "The proof lacks the G2 update, the SRS digests, mandatory from contribution 39"
+        );
+    }
+
+    #[test]
+    fn phases_advance_in_order() {
+        let mut manifest = CeremonyManifest::default();
+        assert!(manifest.require(CeremonyPhase::Setup, "initialize").is_ok());
+
+        let mut phase = CeremonyPhase::Setup;
+        while let Some(next) = phase.next() {
+            assert!(next > phase);
+            manifest.advance(next, 0, [0u8; 32]);
+            phase = next;
+        }
+        assert_eq!(manifest.phase, CeremonyPhase::Published);
+        assert_eq!(manifest.transitions.len(), 5);
+        assert!(manifest
+            .require(CeremonyPhase::Setup, "initialize")
+            .is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot move from the open-contributions phase")]
+    fn phases_cannot_be_skipped() {
+        let mut manifest = CeremonyManifest {
+            phase: CeremonyPhase::OpenContributions,
+            ..Default::default()
+        };
+        manifest.advance(CeremonyPhase::Finalization, 0, [0u8; 32]);
+    }
+
+    #[test]
+    fn mirrors_are_recorded_once() {
+        let mut manifest = CeremonyManifest::default();
+        let mirror = |location: &str, synced_at| Mirror {
+            location: location.to_string(),
+            synced_at,
+            nr_objects: 3,
+            listing: hex::encode([0u8; 32]),
+        };
+        manifest.record_mirror(mirror("/mnt/backup", 1));
+        manifest.record_mirror(mirror("github:midnightntwrk/mirror@main", 2));
+        manifest.record_mirror(mirror("/mnt/backup", 3));
+        assert_eq!(
+            manifest.mirrors,
+            vec![
+                mirror("/mnt/backup", 3),
+                mirror("github:midnightntwrk/mirror@main", 2)
+            ]
+        );
+
+        let json = serde_json::to_string(&manifest).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CeremonyManifest>(&json).unwrap(),
+            manifest
+        );
+    }
+}
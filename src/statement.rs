@@ -0,0 +1,364 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verification statements of independent auditors.
+//!
+//! An auditor who verified an artifact of the ceremony (e.g. the final SRS)
+//! publishes a [SignedStatement]: the digest of the artifact, the version of
+//! the tools used, the checks run and their result, signed with the auditor's
+//! key (a Schnorr signature, see [ContributionSignature]). The statements
+//! collected from all the auditors are combined into a [StatementReport],
+//! showing how many independent parties (distinct keys) verified each
+//! artifact, for publication with the final parameters.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use blstrs::Scalar;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    audit::read_audited, cache::write_json, error::SrsError, registry::ContributionSignature,
+};
+
+/// Default directory of the verification statements
+pub const STATEMENTS_DIR: &str = "./attestations";
+
+/// First line of the canonical text of a statement
+const STATEMENT_HEADER: &str = "midnight-srs verification statement v1";
+
+/// What an auditor verified, and the result
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationStatement {
+    /// Version of the tools used
+    pub tool_version: String,
+    /// Name of the verified artifact (e.g. `srs`)
+    pub artifact: String,
+    /// SHA-256 digest (hex) of the verified artifact
+    pub artifact_sha256: String,
+    /// Checks run (e.g. `verify-structure`)
+    pub checks: Vec<String>,
+    /// Whether all the checks passed
+    pub passed: bool,
+    /// Time of the verification, in seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl VerificationStatement {
+    /// Statement of the checks of the artifact with the given digest, run now
+    /// with this version of the tools
+    pub fn new(artifact: &str, artifact_sha256: &[u8; 32], checks: &[&str], passed: bool) -> Self {
+        VerificationStatement {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            artifact: artifact.to_string(),
+            artifact_sha256: hex::encode(artifact_sha256),
+            checks: checks.iter().map(|check| check.to_string()).collect(),
+            passed,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("The clock is before the Unix epoch")
+                .as_secs(),
+        }
+    }
+
+    /// Canonical text of the statement, whose SHA-256 digest is signed
+    pub fn canonical_text(&self) -> String {
+        format!(
+            "{STATEMENT_HEADER}\n\
+             I verified {} with SHA-256 {} with the checks {} at time {} using srs_utils {}: {}\n",
+            self.artifact,
+            self.artifact_sha256,
+            self.checks.join(","),
+            self.timestamp,
+            self.tool_version,
+            if self.passed { "passed" } else { "failed" }
+        )
+    }
+
+    fn digest(&self) -> [u8; 32] {
+        Sha256::digest(self.canonical_text()).into()
+    }
+}
+
+/// A statement, with the signature (hex, see [ContributionSignature::to_bytes])
+/// of its canonical text
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedStatement {
+    pub statement: VerificationStatement,
+    pub signature: String,
+}
+
+impl SignedStatement {
+    /// Signs the statement with the given secret key
+    pub fn sign(statement: VerificationStatement, secret_key: &Scalar) -> Self {
+        let signature = ContributionSignature::sign(secret_key, &statement.digest());
+        SignedStatement {
+            statement,
+            signature: hex::encode(signature.to_bytes()),
+        }
+    }
+
+    /// The signature, if it is well formed
+    pub fn signature(&self) -> Option<ContributionSignature> {
+        ContributionSignature::from_bytes(&hex::decode(&self.signature).ok()?)
+    }
+
+    /// The public key of the signer, if the statement is validly signed
+    pub fn signer(&self) -> Option<String> {
+        self.signature()
+            .filter(|signature| signature.is_valid(&self.statement.digest()))
+            .map(|signature| signature.public_key_hex())
+    }
+
+    /// Checks that the statement is validly signed, and is about the artifact
+    /// with the given digest. Returns the public key of the signer, or why the
+    /// statement does not hold
+    pub fn check_against(&self, artifact_sha256: &[u8; 32]) -> Result<String, String> {
+        let signer = self.signer().ok_or("is not validly signed")?;
+        if self.statement.artifact_sha256 != hex::encode(artifact_sha256) {
+            return Err(format!(
+                "is about another {} (SHA-256 {})",
+                self.statement.artifact, self.statement.artifact_sha256
+            ));
+        }
+        Ok(signer)
+    }
+
+    /// Path of the statement in the given directory, named after the
+    /// artifact, the signer and the time of the verification
+    pub fn path_in(&self, dir: &Path) -> PathBuf {
+        let signer = self.signer().unwrap_or_default();
+        dir.join(format!(
+            "{}-{}-{}.json",
+            &self.statement.artifact_sha256[..16.min(self.statement.artifact_sha256.len())],
+            &signer[..16.min(signer.len())],
+            self.statement.timestamp
+        ))
+    }
+
+    /// Writes the statement to the given file
+    pub fn save(&self, path: &Path) -> Result<(), SrsError> {
+        write_json(path, self)
+    }
+
+    /// Reads the statement stored in the given file, or why it cannot be read
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = read_audited(path).map_err(|err| format!("cannot be read ({err})"))?;
+        serde_json::from_slice(&bytes).map_err(|err| format!("is malformed ({err})"))
+    }
+}
+
+/// The statement files (`*.json`) of the given directory, sorted by name
+pub fn statement_files(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("Failed to open dir '{:?}': {}", dir, err))
+        .map(|entry| entry.expect("Invalid statement file").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Verifications of an artifact by the auditors
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ArtifactVerifications {
+    /// Names the artifact was verified under
+    pub names: BTreeSet<String>,
+    /// Keys (hex) of the auditors for whom all the checks passed
+    pub passed: BTreeSet<String>,
+    /// Keys (hex) of the auditors for whom some check failed
+    pub failed: BTreeSet<String>,
+    /// All the checks run
+    pub checks: BTreeSet<String>,
+    /// Versions of the tools used
+    pub tool_versions: BTreeSet<String>,
+}
+
+/// Combined verifications of the artifacts, by their SHA-256 digest (hex)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StatementReport {
+    pub artifacts: BTreeMap<String, ArtifactVerifications>,
+    /// Statements that are not counted, with the reason
+    pub rejected: Vec<(String, String)>,
+}
+
+impl StatementReport {
+    /// Combines the given statements, each with its source (e.g. its file
+    /// name) or the reason it cannot be read. Statements that are not validly
+    /// signed are rejected
+    pub fn collect(statements: Vec<(String, Result<SignedStatement, String>)>) -> Self {
+        let mut report = StatementReport::default();
+        for (source, statement) in statements {
+            let statement = match statement {
+                Ok(statement) => statement,
+                Err(reason) => {
+                    report.rejected.push((source, reason));
+                    continue;
+                }
+            };
+            let Some(signer) = statement.signer() else {
+                report
+                    .rejected
+                    .push((source, "is not validly signed".to_string()));
+                continue;
+            };
+
+            let statement = statement.statement;
+            let artifact = report
+                .artifacts
+                .entry(statement.artifact_sha256)
+                .or_default();
+            artifact.names.insert(statement.artifact);
+            artifact.checks.extend(statement.checks);
+            artifact.tool_versions.insert(statement.tool_version);
+            if statement.passed {
+                artifact.passed.insert(signer);
+            } else {
+                artifact.failed.insert(signer);
+            }
+        }
+        report
+    }
+
+    /// Collects the statements stored in the given directory (see
+    /// [statement_files])
+    pub fn collect_dir(dir: &Path) -> Self {
+        Self::collect(
+            statement_files(dir)
+                .iter()
+                .map(|path| (path.display().to_string(), SignedStatement::load(path)))
+                .collect(),
+        )
+    }
+
+    /// Renders the report as markdown, for publication with the final
+    /// parameters
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+
+        writeln!(md, "# Independent Verifications\n").unwrap();
+        writeln!(
+            md,
+            "This file is generated by `srs_utils - statement-report` from the\n\
+             signed verification statements of the auditors.\n"
+        )
+        .unwrap();
+        writeln!(
+            md,
+            "| Artifact | SHA2-256 | Verified by | Failed for | Checks | Tool versions |"
+        )
+        .unwrap();
+        writeln!(
+            md,
+            "| -------- | -------- | ----------- | ---------- | ------ | ------------- |"
+        )
+        .unwrap();
+        let join = |set: &BTreeSet<String>| set.iter().cloned().collect::<Vec<_>>().join(", ");
+        for (sha256, artifact) in &self.artifacts {
+            writeln!(
+                md,
+                "| {} | `{}` | {} | {} | {} | {} |",
+                join(&artifact.names),
+                sha256,
+                artifact.passed.len(),
+                artifact.failed.len(),
+                join(&artifact.checks),
+                join(&artifact.tool_versions),
+            )
+            .unwrap();
+        }
+
+        if !self.rejected.is_empty() {
+            writeln!(md, "\nRejected statements:\n").unwrap();
+            for (source, reason) in &self.rejected {
+                writeln!(md, "- `{source}` {reason}").unwrap();
+            }
+        }
+        md
+    }
+}
+
+#[cfg(test)]
+mod statement_tests {
+    use std::path::Path;
+
+    use blstrs::Scalar;
+    use halo2curves::ff::Field;
+    use rand_core::OsRng;
+
+    use crate::statement::{SignedStatement, StatementReport, VerificationStatement};
+
+    #[test]
+    fn statements_are_combined() {
+        let keys: Vec<Scalar> = (0..3).map(|_| Scalar::random(OsRng)).collect();
+        let srs = VerificationStatement::new("srs", &[1u8; 32], &["verify-structure"], true);
+        let proofs = VerificationStatement::new("proofs", &[2u8; 32], &["verify-chain"], false);
+
+        let mut forged = SignedStatement::sign(srs.clone(), &keys[2]);
+        forged.statement.passed = false;
+        let statements = vec![
+            (
+                "a".to_string(),
+                Ok(SignedStatement::sign(srs.clone(), &keys[0])),
+            ),
+            (
+                "b".to_string(),
+                Ok(SignedStatement::sign(srs.clone(), &keys[1])),
+            ),
+            // The same auditor twice counts once
+            ("c".to_string(), Ok(SignedStatement::sign(srs, &keys[1]))),
+            ("d".to_string(), Ok(SignedStatement::sign(proofs, &keys[0]))),
+            ("e".to_string(), Ok(forged)),
+            ("f".to_string(), Err("is malformed".to_string())),
+        ];
+        let report = StatementReport::collect(statements);
+
+        let srs = &report.artifacts[&hex::encode([1u8; 32])];
+        assert_eq!((srs.passed.len(), srs.failed.len()), (2, 0));
+        let proofs = &report.artifacts[&hex::encode([2u8; 32])];
+        assert_eq!((proofs.passed.len(), proofs.failed.len()), (0, 1));
+        let rejected: Vec<&str> = report.rejected.iter().map(|(s, _)| s.as_str()).collect();
+        assert_eq!(rejected, ["e", "f"]);
+        assert!(report.to_markdown().contains("| srs |"));
+    }
+
+    #[test]
+    fn statements_are_checked_against_artifacts() {
+        let key = Scalar::random(OsRng);
+        let statement = VerificationStatement::new("srs", &[1u8; 32], &["verify-chain"], true);
+        let signed = SignedStatement::sign(statement, &key);
+        let signer = signed.check_against(&[1u8; 32]).unwrap();
+        assert!(signed.path_in(Path::new("attestations")).ends_with(format!(
+            "{}-{}-{}.json",
+            &hex::encode([1u8; 32])[..16],
+            &signer[..16],
+            signed.statement.timestamp
+        )));
+        assert!(signed.check_against(&[2u8; 32]).is_err());
+
+        let mut tampered = signed.clone();
+        tampered
+            .statement
+            .checks
+            .push("verify-structure".to_string());
+        assert!(tampered.check_against(&[1u8; 32]).is_err());
+    }
+}
@@ -0,0 +1,728 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage of the ceremony artifacts.
+//!
+//! The verification pipeline reads (and writes) artifacts through the
+//! [Storage] trait, whatever the place they live in: a local directory
+//! ([LocalStorage]), a web server or mirror ([HttpStorage]) or a branch of
+//! the ceremony repository ([GitHubStorage]). Objects are designated by keys,
+//! their `/`-separated path relative to the root of the storage (e.g.
+//! `proofs/proof3`), and every transfer reports the SHA-256 digest of the
+//! bytes moved, so that they can be checked against the digests recorded by
+//! the ceremony.
+//!
+//! New backends (e.g. SFTP, S3 or IPFS) implement [Storage] and are selected
+//! by [open_storage], from which the commands open their storage.
+//!
+//! [mirror] copies every artifact of a storage to another one, so that the
+//! parameters remain available if the primary host disappears. It writes the
+//! listing of the mirrored objects to the [INDEX_KEY] object of the mirror,
+//! through which a web server lists its objects.
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::{Component, Path, PathBuf},
+    process,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    cache::{parse_sidecar, sidecar_content, SIDECAR_EXTENSION},
+    github::{GitHub, RepoFile},
+    net::agent,
+    progress::ProgressSink,
+    utils::{create_file, open_file},
+};
+
+/// Size of the buffer of the transfers
+const COPY_BUFFER_SIZE: usize = 1 << 20;
+
+/// Key of the listing of the objects of a mirror, in the format of a
+/// `sha256sum` file (see [listing])
+pub const INDEX_KEY: &str = "SHA256SUMS";
+
+/// Size and SHA-256 digest of an object
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ObjectInfo {
+    pub size: u64,
+    pub sha256: [u8; 32],
+}
+
+impl ObjectInfo {
+    pub fn of(bytes: &[u8]) -> Self {
+        ObjectInfo {
+            size: bytes.len() as u64,
+            sha256: Sha256::digest(bytes).into(),
+        }
+    }
+}
+
+/// Reader hashing the bytes read through it
+struct HashedReader<'a> {
+    reader: &'a mut dyn Read,
+    hasher: Sha256,
+    size: u64,
+}
+
+impl Read for HashedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.size += n as u64;
+        Ok(n)
+    }
+}
+
+/// Copies `reader` into `writer`, returning the size and digest of the bytes
+/// copied
+pub fn copy_hashed(reader: &mut dyn Read, writer: &mut dyn Write) -> io::Result<ObjectInfo> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+    let mut size = 0;
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        hasher.update(&buffer[..n]);
+        writer.write_all(&buffer[..n])?;
+        size += n as u64;
+    }
+    Ok(ObjectInfo {
+        size,
+        sha256: hasher.finalize().into(),
+    })
+}
+
+fn unsupported(storage: &dyn Storage, operation: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{} does not support {operation}", storage.location()),
+    )
+}
+
+/// A place the artifacts of the ceremony are stored in
+pub trait Storage: Send + Sync {
+    /// Location of the storage, as given to [open_storage]
+    fn location(&self) -> String;
+
+    /// Streams the object with the given key into `writer`
+    fn get(&self, key: &str, writer: &mut dyn Write) -> io::Result<ObjectInfo>;
+
+    /// Stores the bytes of `reader` as the object with the given key,
+    /// replacing it if it exists
+    fn put(&self, key: &str, reader: &mut dyn Read) -> io::Result<ObjectInfo>;
+
+    /// Size and digest of the object with the given key, `None` if there is
+    /// none
+    fn stat(&self, key: &str) -> io::Result<Option<ObjectInfo>>;
+
+    /// Keys of the objects starting with `prefix`, sorted
+    fn list(&self, prefix: &str) -> io::Result<Vec<String>>;
+
+    /// The given range of bytes of the object with the given key. Backends
+    /// able to read part of an object override the default, which gets the
+    /// whole object
+    fn get_range(&self, key: &str, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.get(key, &mut bytes)?;
+        bytes
+            .get(range.start as usize..range.end as usize)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))
+    }
+
+    /// The object with the given key
+    fn get_bytes(&self, key: &str) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.get(key, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Same as [Storage::get], failing with [io::ErrorKind::InvalidData] if
+    /// the object does not have the given digest. The bytes written to
+    /// `writer` must then be discarded
+    fn get_verified(
+        &self,
+        key: &str,
+        writer: &mut dyn Write,
+        sha256: &[u8; 32],
+    ) -> io::Result<ObjectInfo> {
+        let info = self.get(key, writer)?;
+        if info.sha256 != *sha256 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{key} of {} has the SHA-256 digest {}, expected {}",
+                    self.location(),
+                    hex::encode(info.sha256),
+                    hex::encode(sha256)
+                ),
+            ));
+        }
+        Ok(info)
+    }
+
+    /// Same as [Storage::put], then checks that the stored object has the
+    /// digest of the bytes put
+    fn put_verified(&self, key: &str, reader: &mut dyn Read) -> io::Result<ObjectInfo> {
+        let info = self.put(key, reader)?;
+        match self.stat(key)? {
+            Some(stored) if stored == info => Ok(info),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{key} does not have the SHA-256 digest {} once stored in {}",
+                    hex::encode(info.sha256),
+                    self.location()
+                ),
+            )),
+        }
+    }
+}
+
+/// Outcome of a [mirror]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MirrorReport {
+    /// Number of objects copied
+    pub copied: usize,
+    /// Number of objects the target already stored, with the same digest
+    pub unchanged: usize,
+    /// Digest of the listing of the mirrored objects, see [listing_digest]
+    pub listing: [u8; 32],
+}
+
+/// Listing of objects, given by their key and SHA-256 digest: the
+/// `<sha256>  <key>` lines (as in a `sha256sum` file), sorted by key
+pub fn listing(objects: &[(String, [u8; 32])]) -> String {
+    let mut objects: Vec<_> = objects.iter().collect();
+    objects.sort_by(|a, b| a.0.cmp(&b.0));
+    objects
+        .into_iter()
+        .map(|(key, sha256)| sidecar_content(key, sha256))
+        .collect()
+}
+
+/// The objects of a listing written by [listing], `None` if it is malformed
+pub fn parse_listing(content: &[u8]) -> Option<Vec<(String, [u8; 32])>> {
+    String::from_utf8_lossy(content)
+        .lines()
+        .map(|line| {
+            let (sha256, key) = line.split_once("  ")?;
+            Some((key.to_string(), parse_sidecar(sha256.as_bytes())?))
+        })
+        .collect()
+}
+
+/// SHA-256 digest of the [listing] of the given objects. Two storages with the
+/// same listing digest store the same objects
+pub fn listing_digest(objects: &[(String, [u8; 32])]) -> [u8; 32] {
+    Sha256::digest(listing(objects)).into()
+}
+
+/// Copies every object of `source` to `target`, unless the target already
+/// stores it with the same digest, then writes their listing to the
+/// [INDEX_KEY] object of the target. Each object is checked against its
+/// checksum sidecar (`<key>.sha256`) in the source, if there is one, and the
+/// stored copy against the digest of the bytes read (see
+/// [Storage::put_verified]). An object whose sidecar has the digest of the
+/// copy of the target is not downloaded. Objects are staged in a temporary
+/// file, so that an SRS is never held in memory
+pub fn mirror(
+    source: &dyn Storage,
+    target: &dyn Storage,
+    progress: &dyn ProgressSink,
+) -> io::Result<MirrorReport> {
+    let staging_path = std::env::temp_dir().join(format!("srs-mirror-{}", process::id()));
+    let mut staging = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&staging_path)?;
+    let report = mirror_objects(source, target, &mut staging, progress);
+    drop(staging);
+    let _ = fs::remove_file(&staging_path);
+    report
+}
+
+fn mirror_objects(
+    source: &dyn Storage,
+    target: &dyn Storage,
+    staging: &mut File,
+    progress: &dyn ProgressSink,
+) -> io::Result<MirrorReport> {
+    let keys: Vec<String> = source
+        .list("")?
+        .into_iter()
+        .filter(|key| key != INDEX_KEY)
+        .collect();
+    let mut objects = Vec::with_capacity(keys.len());
+    let (mut copied, mut unchanged) = (0, 0);
+    progress.start(
+        keys.len(),
+        &format!("Mirroring {} to {}", source.location(), target.location()),
+    );
+    for key in &keys {
+        let sidecar_key = format!("{key}.{SIDECAR_EXTENSION}");
+        let expected = if keys.contains(&sidecar_key) {
+            Some(
+                parse_sidecar(&source.get_bytes(&sidecar_key)?).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Malformed checksum sidecar {sidecar_key}"),
+                    )
+                })?,
+            )
+        } else {
+            None
+        };
+
+        let stored = target.stat(key)?;
+        if let (Some(sha256), Some(stored)) = (&expected, &stored) {
+            if stored.sha256 == *sha256 {
+                unchanged += 1;
+                objects.push((key.clone(), *sha256));
+                progress.inc(1);
+                continue;
+            }
+        }
+
+        staging.set_len(0)?;
+        staging.rewind()?;
+        let info = match &expected {
+            Some(sha256) => source.get_verified(key, staging, sha256)?,
+            None => source.get(key, staging)?,
+        };
+        if stored == Some(info) {
+            unchanged += 1;
+        } else {
+            staging.rewind()?;
+            let stored = target.put_verified(key, staging)?;
+            if stored != info {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{key} changed while being mirrored"),
+                ));
+            }
+            copied += 1;
+        }
+        objects.push((key.clone(), info.sha256));
+        progress.inc(1);
+    }
+    progress.finish();
+
+    let index = listing(&objects);
+    target.put_verified(INDEX_KEY, &mut index.as_bytes())?;
+    Ok(MirrorReport {
+        copied,
+        unchanged,
+        listing: Sha256::digest(index).into(),
+    })
+}
+
+/// Opens the storage at the given location:
+/// - an `http://` or `https://` URL (see [HttpStorage]),
+/// - `github:owner/name@ref`, a branch (or commit) of a GitHub repository, with
+///   the token of the GITHUB_TOKEN environment variable (see [GitHubStorage]),
+/// - a local directory otherwise
+pub fn open_storage(location: &str) -> Box<dyn Storage> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        return Box::new(HttpStorage::new(location));
+    }
+    if let Some(repo) = location.strip_prefix("github:") {
+        let (repo, git_ref) = repo.split_once('@').unwrap_or((repo, "main"));
+        let token = std::env::var("GITHUB_TOKEN")
+            .expect("A GitHub storage requires a token in the GITHUB_TOKEN environment variable");
+        return Box::new(GitHubStorage::new(GitHub::new(token), repo, git_ref));
+    }
+    Box::new(LocalStorage::new(location))
+}
+
+/// Storage in a local directory, the keys being paths relative to it
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        LocalStorage { root: root.into() }
+    }
+
+    /// Path of the object with the given key. Fails unless the key is a
+    /// relative path within the storage
+    fn path(&self, key: &str) -> io::Result<PathBuf> {
+        let relative = Path::new(key);
+        let within = relative.components().next().is_some()
+            && relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+        if !within {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid storage key '{key}'"),
+            ));
+        }
+        Ok(self.root.join(relative))
+    }
+
+    fn open(&self, key: &str) -> io::Result<File> {
+        Ok(open_file(&self.path(key)?)?)
+    }
+}
+
+impl Storage for LocalStorage {
+    fn location(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    fn get(&self, key: &str, writer: &mut dyn Write) -> io::Result<ObjectInfo> {
+        copy_hashed(&mut self.open(key)?, writer)
+    }
+
+    fn put(&self, key: &str, reader: &mut dyn Read) -> io::Result<ObjectInfo> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = create_file(&path)?;
+        let info = copy_hashed(reader, &mut file)?;
+        file.sync_all()?;
+        Ok(info)
+    }
+
+    fn stat(&self, key: &str) -> io::Result<Option<ObjectInfo>> {
+        if !self.path(key)?.is_file() {
+            return Ok(None);
+        }
+        copy_hashed(&mut self.open(key)?, &mut io::sink()).map(Some)
+    }
+
+    fn list(&self, prefix: &str) -> io::Result<Vec<String>> {
+        let mut keys = Vec::new();
+        let mut dirs = vec![self.root.clone()];
+        while let Some(dir) = dirs.pop() {
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            };
+            for entry in entries {
+                let path = entry?.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                let key = path
+                    .strip_prefix(&self.root)
+                    .unwrap()
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                if key.starts_with(prefix) {
+                    keys.push(key);
+                }
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    fn get_range(&self, key: &str, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let mut file = self.open(key)?;
+        file.seek(SeekFrom::Start(range.start))?;
+        let mut bytes = vec![0u8; (range.end - range.start) as usize];
+        file.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// Storage served over HTTP (e.g. a mirror of the artifacts), the keys being
+/// paths relative to its base URL. Objects are uploaded with `PUT` requests
+/// (e.g. to a WebDAV server), and listed from the [INDEX_KEY] object written
+/// by [mirror], as a web server does not list its objects
+pub struct HttpStorage {
+    base_url: String,
+}
+
+impl HttpStorage {
+    pub fn new(base_url: &str) -> Self {
+        HttpStorage {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn call(&self, key: &str, range: Option<&Range<u64>>) -> io::Result<ureq::Response> {
+        let url = format!("{}/{key}", self.base_url);
+        let mut request = agent().get(&url);
+        if let Some(range) = range {
+            request = request.set("Range", &format!("bytes={}-{}", range.start, range.end - 1));
+        }
+        request.call().map_err(|err| match err {
+            ureq::Error::Status(404, _) => {
+                io::Error::new(io::ErrorKind::NotFound, format!("GET {url}: not found"))
+            }
+            err => io::Error::other(format!("GET {url} failed: {err}")),
+        })
+    }
+}
+
+impl Storage for HttpStorage {
+    fn location(&self) -> String {
+        self.base_url.clone()
+    }
+
+    fn get(&self, key: &str, writer: &mut dyn Write) -> io::Result<ObjectInfo> {
+        copy_hashed(&mut self.call(key, None)?.into_reader(), writer)
+    }
+
+    fn put(&self, key: &str, reader: &mut dyn Read) -> io::Result<ObjectInfo> {
+        let url = format!("{}/{key}", self.base_url);
+        let mut reader = HashedReader {
+            reader,
+            hasher: Sha256::new(),
+            size: 0,
+        };
+        agent()
+            .put(&url)
+            .send(&mut reader)
+            .map_err(|err| io::Error::other(format!("PUT {url} failed: {err}")))?;
+        Ok(ObjectInfo {
+            size: reader.size,
+            sha256: reader.hasher.finalize().into(),
+        })
+    }
+
+    fn stat(&self, key: &str) -> io::Result<Option<ObjectInfo>> {
+        match self.get(key, &mut io::sink()) {
+            Ok(info) => Ok(Some(info)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn list(&self, prefix: &str) -> io::Result<Vec<String>> {
+        let index = match self.get_bytes(INDEX_KEY) {
+            Ok(index) => index,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(unsupported(self, &format!("listing (no {INDEX_KEY})")))
+            }
+            Err(err) => return Err(err),
+        };
+        let objects = parse_listing(&index).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Malformed {INDEX_KEY} of {}", self.location()),
+            )
+        })?;
+        let mut keys: Vec<String> = objects
+            .into_iter()
+            .map(|(key, _)| key)
+            .chain([INDEX_KEY.to_string()])
+            .filter(|key| key.starts_with(prefix))
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+
+    fn get_range(&self, key: &str, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let response = self.call(key, Some(&range))?;
+        // The server may ignore the range and send the whole object
+        let partial = response.status() == 206;
+        let mut reader = response.into_reader();
+        if !partial {
+            io::copy(&mut (&mut reader).take(range.start), &mut io::sink())?;
+        }
+        let mut bytes = Vec::new();
+        reader
+            .take(range.end - range.start)
+            .read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != range.end - range.start {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
+        Ok(bytes)
+    }
+}
+
+/// Storage on a branch (or commit) of a GitHub repository, through the
+/// contents API (files of up to 1 MB, e.g. the update proofs). Objects are
+/// put by committing them to the branch, which the token must be allowed to
+/// push to
+pub struct GitHubStorage {
+    github: GitHub,
+    repo: String,
+    git_ref: String,
+}
+
+impl GitHubStorage {
+    /// Storage on `git_ref` of `repo` (as owner/name)
+    pub fn new(github: GitHub, repo: &str, git_ref: &str) -> Self {
+        GitHubStorage {
+            github,
+            repo: repo.to_string(),
+            git_ref: git_ref.to_string(),
+        }
+    }
+}
+
+impl Storage for GitHubStorage {
+    fn location(&self) -> String {
+        format!("github:{}@{}", self.repo, self.git_ref)
+    }
+
+    fn get(&self, key: &str, writer: &mut dyn Write) -> io::Result<ObjectInfo> {
+        let bytes = self.github.file_content(&self.repo, &self.git_ref, key)?;
+        writer.write_all(&bytes)?;
+        Ok(ObjectInfo::of(&bytes))
+    }
+
+    fn put(&self, key: &str, reader: &mut dyn Read) -> io::Result<ObjectInfo> {
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content)?;
+        let info = ObjectInfo::of(&content);
+        let file = RepoFile {
+            path: key.to_string(),
+            content,
+        };
+        self.github
+            .put_file(&self.repo, &self.git_ref, &file, &format!("Mirror {key}"))?;
+        Ok(info)
+    }
+
+    fn stat(&self, key: &str) -> io::Result<Option<ObjectInfo>> {
+        match self.get(key, &mut io::sink()) {
+            Ok(info) => Ok(Some(info)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Lists the files of every directory, failing rather than returning part
+    /// of them if the repository is too large to be listed at once
+    fn list(&self, prefix: &str) -> io::Result<Vec<String>> {
+        let mut keys: Vec<String> = self
+            .github
+            .tree_files(&self.repo, &self.git_ref)?
+            .into_iter()
+            .filter(|key| key.starts_with(prefix))
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod storage_tests {
+    use std::{fs, io};
+
+    use crate::{
+        cache::sidecar_content,
+        progress::NoProgress,
+        storage::{mirror, parse_listing, LocalStorage, ObjectInfo, Storage, INDEX_KEY},
+    };
+
+    #[test]
+    fn local_objects_are_checksummed() {
+        let root = "/tmp/test_local_storage";
+        let _ = fs::remove_dir_all(root);
+        let storage = LocalStorage::new(root);
+
+        let info = storage
+            .put_verified("proofs/proof1", &mut &b"proof"[..])
+            .unwrap();
+        assert_eq!(info, ObjectInfo::of(b"proof"));
+        storage.put("srs1", &mut &b"srs"[..]).unwrap();
+        assert_eq!(storage.stat("srs1").unwrap(), Some(ObjectInfo::of(b"srs")));
+        assert_eq!(storage.stat("srs2").unwrap(), None);
+        assert_eq!(storage.get_range("srs1", 1..3).unwrap(), b"rs");
+
+        assert_eq!(storage.list("").unwrap(), vec!["proofs/proof1", "srs1"]);
+        assert_eq!(storage.list("proofs/").unwrap(), vec!["proofs/proof1"]);
+
+        let mut bytes = Vec::new();
+        assert!(storage
+            .get_verified("proofs/proof1", &mut bytes, &info.sha256)
+            .is_ok());
+        let err = storage
+            .get_verified("srs1", &mut io::sink(), &info.sha256)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // Keys cannot escape the storage
+        assert!(storage.get_bytes("../etc/passwd").is_err());
+        assert!(storage.get_bytes("/etc/passwd").is_err());
+    }
+
+    #[test]
+    fn mirrors_are_checked() {
+        let (source_root, target_root) = ("/tmp/test_mirror_source", "/tmp/test_mirror_target");
+        let (copy_root, fresh_root) = ("/tmp/test_mirror_copy", "/tmp/test_mirror_fresh");
+        for root in [source_root, target_root, copy_root, fresh_root] {
+            let _ = fs::remove_dir_all(root);
+        }
+        let (source, target) = (
+            LocalStorage::new(source_root),
+            LocalStorage::new(target_root),
+        );
+        source.put("proofs/proof1", &mut &b"proof"[..]).unwrap();
+        source.put("srs1", &mut &b"srs"[..]).unwrap();
+        let sidecar = sidecar_content("srs1", &ObjectInfo::of(b"srs").sha256);
+        source.put("srs1.sha256", &mut sidecar.as_bytes()).unwrap();
+
+        let report = mirror(&source, &target, &NoProgress).unwrap();
+        assert_eq!((report.copied, report.unchanged), (3, 0));
+        let mut keys = source.list("").unwrap();
+        keys.push(INDEX_KEY.to_string());
+        assert_eq!(target.list("").unwrap(), keys);
+        assert_eq!(target.get_bytes("srs1").unwrap(), b"srs");
+
+        // The index lists the mirrored objects, with the listing digest
+        let index = target.get_bytes(INDEX_KEY).unwrap();
+        assert_eq!(ObjectInfo::of(&index).sha256, report.listing);
+        let objects = parse_listing(&index).unwrap();
+        assert_eq!(objects.len(), 3);
+        assert!(objects.contains(&("srs1".to_string(), ObjectInfo::of(b"srs").sha256)));
+
+        // Only the modified objects are copied again
+        source.put("proofs/proof1", &mut &b"proof!"[..]).unwrap();
+        let report2 = mirror(&source, &target, &NoProgress).unwrap();
+        assert_eq!((report2.copied, report2.unchanged), (1, 2));
+        assert_ne!(report2.listing, report.listing);
+
+        // A mirror is mirrored without its index
+        let copy = LocalStorage::new(copy_root);
+        let report3 = mirror(&target, &copy, &NoProgress).unwrap();
+        assert_eq!((report3.copied, report3.unchanged), (3, 0));
+        assert_eq!(report3.listing, report2.listing);
+
+        // An object whose sidecar matches the copy of the target is not
+        // downloaded, but an object not matching its sidecar is not mirrored
+        source.put("srs1", &mut &b"SRS"[..]).unwrap();
+        let report4 = mirror(&source, &target, &NoProgress).unwrap();
+        assert_eq!((report4.copied, report4.unchanged), (0, 3));
+        assert_eq!(target.get_bytes("srs1").unwrap(), b"srs");
+        let fresh = LocalStorage::new(fresh_root);
+        let err = mirror(&source, &fresh, &NoProgress).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(fresh.stat("srs1").unwrap(), None);
+    }
+}
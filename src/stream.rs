@@ -0,0 +1,360 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Streaming IO of the large SRS files.
+//!
+//! An update streams tens of GB from the old SRS file to the new one. With
+//! the `io-uring` feature, on Linux, the files are read and written through
+//! io_uring with [STREAM_QUEUE_DEPTH] blocks in flight, so that the reads
+//! ahead and the writes behind overlap with the deserialization and the
+//! re-randomization of the points. Otherwise, or if the kernel refuses to set
+//! up a ring, they fall back to standard buffered IO.
+
+use std::{
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    error::SrsError,
+    utils::{create_file, open_file},
+};
+
+/// Size of the blocks read or written at once
+pub const STREAM_BLOCK_SIZE: usize = 1 << 20;
+
+/// Number of blocks in flight
+pub const STREAM_QUEUE_DEPTH: usize = 8;
+
+/// Reader of the file at the given path, read sequentially from its start
+pub fn stream_reader(path: &Path) -> Result<Box<dyn Read + Send>, SrsError> {
+    let file = open_file(path)?;
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    let file = match uring::UringReader::new(file) {
+        Ok(reader) => return Ok(Box::new(reader)),
+        Err((file, _)) => file,
+    };
+    Ok(Box::new(BufReader::with_capacity(STREAM_BLOCK_SIZE, file)))
+}
+
+/// Writer of the file created at the given path, written sequentially. The
+/// writer must be flushed for write errors to be reported
+pub fn stream_writer(path: &Path) -> Result<Box<dyn Write + Send>, SrsError> {
+    let file = create_file(path)?;
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    let file = match uring::UringWriter::new(file) {
+        Ok(writer) => return Ok(Box::new(writer)),
+        Err((file, _)) => file,
+    };
+    Ok(Box::new(BufWriter::with_capacity(STREAM_BLOCK_SIZE, file)))
+}
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+mod uring {
+    use std::{
+        collections::VecDeque,
+        fs::File,
+        io::{self, Read, Write},
+        os::unix::{fs::FileExt, io::AsRawFd},
+    };
+
+    use io_uring::{cqueue, opcode, squeue, types, IoUring};
+
+    use super::{STREAM_BLOCK_SIZE, STREAM_QUEUE_DEPTH};
+
+    /// A ring with a block buffer per entry, identified by its index. The
+    /// buffers never move while operations on them are in flight
+    struct Ring {
+        ring: IoUring,
+        file: File,
+        buffers: Vec<Vec<u8>>,
+        /// File offset and length of the operation on each buffer
+        ops: Vec<(u64, usize)>,
+        /// Result of the operation on each buffer, once completed
+        results: Vec<Option<i32>>,
+        in_flight: usize,
+    }
+
+    impl Ring {
+        fn new(file: File) -> Result<Self, (File, io::Error)> {
+            match IoUring::new(STREAM_QUEUE_DEPTH as u32) {
+                Ok(ring) => Ok(Ring {
+                    ring,
+                    file,
+                    buffers: vec![vec![0u8; STREAM_BLOCK_SIZE]; STREAM_QUEUE_DEPTH],
+                    ops: vec![(0, 0); STREAM_QUEUE_DEPTH],
+                    results: vec![None; STREAM_QUEUE_DEPTH],
+                    in_flight: 0,
+                }),
+                Err(err) => Err((file, err)),
+            }
+        }
+
+        fn submit(&mut self, i: usize, entry: squeue::Entry, offset: u64, len: usize) {
+            self.ops[i] = (offset, len);
+            self.results[i] = None;
+            // SAFETY: the buffer of the entry outlives the operation, which
+            // is waited for before the buffer is reused or dropped
+            unsafe {
+                self.ring
+                    .submission()
+                    .push(&entry.user_data(i as u64))
+                    .expect("The submission queue is full");
+            }
+            self.in_flight += 1;
+        }
+
+        fn submit_read(&mut self, i: usize, offset: u64, len: usize) -> io::Result<()> {
+            let fd = types::Fd(self.file.as_raw_fd());
+            let entry = opcode::Read::new(fd, self.buffers[i].as_mut_ptr(), len as u32)
+                .offset(offset)
+                .build();
+            self.submit(i, entry, offset, len);
+            self.ring.submit().map(|_| ())
+        }
+
+        fn submit_write(&mut self, i: usize, offset: u64, len: usize) -> io::Result<()> {
+            let fd = types::Fd(self.file.as_raw_fd());
+            let entry = opcode::Write::new(fd, self.buffers[i].as_ptr(), len as u32)
+                .offset(offset)
+                .build();
+            self.submit(i, entry, offset, len);
+            self.ring.submit().map(|_| ())
+        }
+
+        /// Waits for the operation on the given buffer, and returns the
+        /// number of bytes it transferred
+        fn wait_for(&mut self, i: usize) -> io::Result<usize> {
+            while self.results[i].is_none() {
+                self.ring.submit_and_wait(1)?;
+                let completed: Vec<cqueue::Entry> = self.ring.completion().collect();
+                for entry in completed {
+                    self.results[entry.user_data() as usize] = Some(entry.result());
+                    self.in_flight -= 1;
+                }
+            }
+            let result = self.results[i].unwrap();
+            if result < 0 {
+                return Err(io::Error::from_raw_os_error(-result));
+            }
+            Ok(result as usize)
+        }
+
+        /// Waits for all the operations in flight
+        fn drain(&mut self) {
+            while self.in_flight > 0 && self.ring.submit_and_wait(1).is_ok() {
+                self.in_flight -= self.ring.completion().count();
+            }
+        }
+    }
+
+    /// Reader with [STREAM_QUEUE_DEPTH] blocks read ahead
+    pub(super) struct UringReader {
+        ring: Ring,
+        file_len: u64,
+        /// Offset of the next block to read ahead
+        next_offset: u64,
+        /// Buffers being read, in file order
+        order: VecDeque<usize>,
+        /// Buffer being consumed, position in it and length of its block
+        current: Option<(usize, usize, usize)>,
+    }
+
+    impl UringReader {
+        pub(super) fn new(file: File) -> Result<Self, (File, io::Error)> {
+            let file_len = match file.metadata() {
+                Ok(metadata) => metadata.len(),
+                Err(err) => return Err((file, err)),
+            };
+            let mut reader = UringReader {
+                ring: Ring::new(file)?,
+                file_len,
+                next_offset: 0,
+                order: VecDeque::new(),
+                current: None,
+            };
+            for i in 0..STREAM_QUEUE_DEPTH {
+                if let Err(err) = reader.read_ahead(i) {
+                    reader.ring.drain();
+                    let UringReader { ring, .. } = reader;
+                    return Err((ring.file, err));
+                }
+            }
+            Ok(reader)
+        }
+
+        /// Reads the next block ahead into the given buffer, if any is left
+        fn read_ahead(&mut self, i: usize) -> io::Result<()> {
+            if self.next_offset >= self.file_len {
+                return Ok(());
+            }
+            let len = STREAM_BLOCK_SIZE.min((self.file_len - self.next_offset) as usize);
+            self.ring.submit_read(i, self.next_offset, len)?;
+            self.order.push_back(i);
+            self.next_offset += len as u64;
+            Ok(())
+        }
+    }
+
+    impl Read for UringReader {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            loop {
+                if let Some((i, pos, len)) = self.current {
+                    if pos < len {
+                        let n = out.len().min(len - pos);
+                        out[..n].copy_from_slice(&self.ring.buffers[i][pos..pos + n]);
+                        self.current = Some((i, pos + n, len));
+                        return Ok(n);
+                    }
+                    self.current = None;
+                    self.read_ahead(i)?;
+                }
+
+                let Some(i) = self.order.pop_front() else {
+                    return Ok(0);
+                };
+                let read = self.ring.wait_for(i)?;
+                let (offset, len) = self.ring.ops[i];
+                if read < len {
+                    // Short read: the rest of the block is read synchronously
+                    let Ring { file, buffers, .. } = &mut self.ring;
+                    file.read_exact_at(&mut buffers[i][read..len], offset + read as u64)?;
+                }
+                self.current = Some((i, 0, len));
+            }
+        }
+    }
+
+    impl Drop for UringReader {
+        fn drop(&mut self) {
+            self.ring.drain();
+        }
+    }
+
+    /// Writer with [STREAM_QUEUE_DEPTH] blocks written behind
+    pub(super) struct UringWriter {
+        ring: Ring,
+        /// Offset of the next block to write
+        next_offset: u64,
+        /// Buffer being filled, and the length filled
+        current: usize,
+        filled: usize,
+        /// Whether each buffer is being written
+        pending: Vec<bool>,
+    }
+
+    impl UringWriter {
+        pub(super) fn new(file: File) -> Result<Self, (File, io::Error)> {
+            Ok(UringWriter {
+                ring: Ring::new(file)?,
+                next_offset: 0,
+                current: 0,
+                filled: 0,
+                pending: vec![false; STREAM_QUEUE_DEPTH],
+            })
+        }
+
+        /// Waits for the write of the given buffer, completing a short write
+        /// synchronously
+        fn complete(&mut self, i: usize) -> io::Result<()> {
+            if !self.pending[i] {
+                return Ok(());
+            }
+            self.pending[i] = false;
+            let written = self.ring.wait_for(i)?;
+            let (offset, len) = self.ring.ops[i];
+            if written < len {
+                let Ring { file, buffers, .. } = &self.ring;
+                file.write_all_at(&buffers[i][written..len], offset + written as u64)?;
+            }
+            Ok(())
+        }
+
+        /// Writes the current buffer behind, and moves to the next one
+        fn write_behind(&mut self) -> io::Result<()> {
+            if self.filled == 0 {
+                return Ok(());
+            }
+            let i = self.current;
+            self.ring.submit_write(i, self.next_offset, self.filled)?;
+            self.pending[i] = true;
+            self.next_offset += self.filled as u64;
+
+            self.current = (i + 1) % STREAM_QUEUE_DEPTH;
+            self.filled = 0;
+            self.complete(self.current)
+        }
+    }
+
+    impl Write for UringWriter {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            let n = bytes.len().min(STREAM_BLOCK_SIZE - self.filled);
+            self.ring.buffers[self.current][self.filled..self.filled + n]
+                .copy_from_slice(&bytes[..n]);
+            self.filled += n;
+            if self.filled == STREAM_BLOCK_SIZE {
+                self.write_behind()?;
+            }
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.write_behind()?;
+            for i in 0..STREAM_QUEUE_DEPTH {
+                self.complete(i)?;
+            }
+            Ok(())
+        }
+    }
+
+    impl Drop for UringWriter {
+        fn drop(&mut self) {
+            let _ = self.flush();
+            self.ring.drain();
+        }
+    }
+}
+
+#[cfg(test)]
+mod stream_tests {
+    use std::{
+        fs,
+        io::{Read, Write},
+        path::Path,
+    };
+
+    use crate::stream::{stream_reader, stream_writer, STREAM_BLOCK_SIZE};
+
+    #[test]
+    fn streams_round_trip() {
+        let path = Path::new("/tmp/test_stream");
+        // Several blocks, the last one partial, written in odd-sized chunks
+        let bytes: Vec<u8> = (0..10 * STREAM_BLOCK_SIZE + 12345)
+            .map(|i| (i % 251) as u8)
+            .collect();
+
+        let mut writer = stream_writer(path).unwrap();
+        for chunk in bytes.chunks(96 * 1000 + 7) {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.flush().unwrap();
+        drop(writer);
+        assert_eq!(fs::read(path).unwrap(), bytes);
+
+        let mut read = Vec::new();
+        stream_reader(path).unwrap().read_to_end(&mut read).unwrap();
+        assert_eq!(read, bytes);
+    }
+}
@@ -13,53 +13,172 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 use std::{
-    fs::{self, DirEntry, File, ReadDir},
-    io::{Read, Seek, SeekFrom, Write},
+    fs::{self, File, ReadDir},
+    io::{self, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
 };
 
-use blake2::{digest::consts::U64, Blake2b512, Digest};
+use blake2::{Blake2b512, Digest};
 use blstrs::{G1Affine, G2Affine, Scalar};
 use halo2curves::{ff::Field, serde::SerdeObject};
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use rand_chacha::ChaCha20Rng;
 use rand_core::{CryptoRng, RngCore, SeedableRng};
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+pub use crate::core::{hash_points, TranscriptHash, TranscriptHasher};
+use crate::{
+    audit::{audit_open, is_auditing},
+    cache::{check_sidecar_digest, sha256_file, SIDECAR_EXTENSION},
+    cancel::wipe,
+    ceremony::{G1_SIZE, G2_SIZE},
+    error::SrsError,
+    health::health_check,
+    hwrng::{hardware_randomness, HARDWARE_INPUT_SIZE},
+    journal::{journal_input, journal_output},
+    metadata::{EntropyInput, EntropySource},
+};
+
+/// Opens the file at the given path
+pub fn open_file(path: &Path) -> Result<File, SrsError> {
+    audit_open(path);
+    journal_input(path);
+    File::open(path).map_err(|source| SrsError::Io {
+        action: "open",
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Creates a file at the given path. Fails in auditor mode, which is
+/// read-only (see [crate::audit])
+pub fn create_file(path: &Path) -> Result<File, SrsError> {
+    let io_error = |source| SrsError::Io {
+        action: "create",
+        path: path.to_path_buf(),
+        source,
+    };
+    if is_auditing() {
+        return Err(io_error(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "auditor mode is read-only",
+        )));
+    }
+    journal_output(path);
+    File::create(path).map_err(io_error)
+}
+
+/// Whether the path designates the standard input (`-`)
+pub fn is_stdin(path: &Path) -> bool {
+    path == Path::new("-")
+}
 
-use crate::ceremony::G1_SIZE;
+/// Opens the file at the given path for reading, or the standard input if the
+/// path is `-`
+pub fn open_input(path: &Path) -> Result<Box<dyn Read>, SrsError> {
+    if is_stdin(path) {
+        audit_open(path);
+        journal_input(path);
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(open_file(path)?))
+    }
+}
 
-/// Opens the file at the given path, panics if something goes wrong
-pub fn open_file(path: &Path) -> File {
-    File::open(path).unwrap_or_else(|err| panic!("Failed to open file '{:?}': {}", path, err))
+/// Reads from `reader` until `buffer` is full or the end of the stream, and
+/// returns the number of bytes read
+pub fn fill_buffer(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(filled)
 }
 
-/// Creates a file at the given path, panics if something goes wrong
-pub fn create_file(path: &Path) -> File {
-    File::create(path).unwrap_or_else(|err| panic!("Failed to create file '{:?}': {}", path, err))
+/// Opens the directory at the given path
+pub fn open_dir(path: &Path) -> Result<ReadDir, SrsError> {
+    fs::read_dir(path).map_err(|source| SrsError::Io {
+        action: "open",
+        path: path.to_path_buf(),
+        source,
+    })
 }
 
-/// Opens the directory at the given path, panics if something goes wrong
-pub fn open_dir(path: &Path) -> ReadDir {
-    fs::read_dir(path).unwrap_or_else(|err| panic!("Failed to open dir '{:?}': {}", path, err))
+/// Modulus of the base field, as little-endian 64-bit limbs
+const FP_MODULUS: [u64; 6] = [
+    0xb9fe_ffff_ffff_aaab,
+    0x1eab_fffe_b153_ffff,
+    0x6730_d2a0_f6b0_f624,
+    0x6477_4b84_f385_12bf,
+    0x4b1b_a7b6_434b_acd7,
+    0x1a01_11ea_397f_e69a,
+];
+
+/// Whether every base field element of the raw encoding of a point is
+/// reduced, i.e. less than the modulus. The raw encoding stores each of them
+/// as the little-endian 64-bit limbs of its Montgomery form, which
+/// [SerdeObject::from_raw_bytes] does not reduce
+pub(crate) fn is_reduced(bytes: &[u8]) -> bool {
+    bytes.chunks(48).all(|element| {
+        let limbs = element
+            .chunks(8)
+            .map(|limb| u64::from_le_bytes(limb.try_into().unwrap()));
+        limbs.rev().lt(FP_MODULUS.iter().rev().copied())
+    })
 }
 
-/// Read a G1 point from the given buffer, panics if something goes wrong
-pub fn read_g1_point(bytes: &[u8]) -> G1Affine {
-    G1Affine::from_raw_bytes(bytes).expect("Failed to read G1 point")
+/// Decodes a raw G1 point, rejecting encodings that are not canonical (with
+/// an unreduced coordinate, or another encoding of the identity) or not in
+/// the prime-order subgroup
+pub fn decode_g1_point(bytes: &[u8]) -> Option<G1Affine> {
+    if bytes.len() != G1_SIZE || !is_reduced(bytes) {
+        return None;
+    }
+    let point = G1Affine::from_raw_bytes(bytes)?;
+    (point.to_raw_bytes() == bytes).then_some(point)
 }
 
-/// Read a G2 point from the given buffer, panics if something goes wrong
-pub fn read_g2_point(bytes: &[u8]) -> G2Affine {
-    G2Affine::from_raw_bytes(bytes).expect("Failed to read G2 point")
+/// Same as [decode_g1_point], for G2 points
+pub fn decode_g2_point(bytes: &[u8]) -> Option<G2Affine> {
+    if bytes.len() != G2_SIZE || !is_reduced(bytes) {
+        return None;
+    }
+    let point = G2Affine::from_raw_bytes(bytes)?;
+    (point.to_raw_bytes() == bytes).then_some(point)
 }
 
-/// Reads a G1 point from the given file after skipping `offset` bytes, panics
-/// if something goes wrong
-pub fn read_g1_point_from_file(path: &Path, offset: usize) -> G1Affine {
-    let mut file = open_file(path);
+/// Read a G1 point from the given buffer, failing on a non-canonical
+/// encoding (see [decode_g1_point])
+pub fn read_g1_point(bytes: &[u8]) -> Result<G1Affine, SrsError> {
+    decode_g1_point(bytes)
+        .ok_or_else(|| SrsError::Deserialization("Failed to read G1 point".to_string()))
+}
+
+/// Read a G2 point from the given buffer, failing on a non-canonical
+/// encoding (see [decode_g2_point])
+pub fn read_g2_point(bytes: &[u8]) -> Result<G2Affine, SrsError> {
+    decode_g2_point(bytes)
+        .ok_or_else(|| SrsError::Deserialization("Failed to read G2 point".to_string()))
+}
+
+/// Reads a G1 point from the given file after skipping `offset` bytes
+pub fn read_g1_point_from_file(path: &Path, offset: usize) -> Result<G1Affine, SrsError> {
+    let mut file = open_file(path)?;
 
-    file.seek(SeekFrom::Start(offset as u64)).unwrap();
     let mut bytes = [0u8; G1_SIZE];
-    file.read_exact(&mut bytes).expect("Invalid read exact");
+    file.seek(SeekFrom::Start(offset as u64))
+        .and_then(|_| file.read_exact(&mut bytes))
+        .map_err(|source| SrsError::Io {
+            action: "read",
+            path: path.to_path_buf(),
+            source,
+        })?;
 
     read_g1_point(&bytes)
 }
@@ -75,32 +194,26 @@ pub fn compare_bytes(
     offset1: i64,
     offset2: i64,
     num_bytes: usize,
-) -> bool {
-    let mut file1 = open_file(path1);
-    let mut file2 = open_file(path2);
-
-    let seek_point1 = if offset1 >= 0 {
-        SeekFrom::Start(offset1 as u64)
-    } else {
-        SeekFrom::End(offset1)
+) -> Result<bool, SrsError> {
+    let read_bytes = |path: &Path, offset: i64| -> Result<Vec<u8>, SrsError> {
+        let mut file = open_file(path)?;
+        let seek_point = if offset >= 0 {
+            SeekFrom::Start(offset as u64)
+        } else {
+            SeekFrom::End(offset)
+        };
+        let mut bytes = vec![0u8; num_bytes];
+        file.seek(seek_point)
+            .and_then(|_| file.read_exact(&mut bytes))
+            .map_err(|source| SrsError::Io {
+                action: "read",
+                path: path.to_path_buf(),
+                source,
+            })?;
+        Ok(bytes)
     };
 
-    let seek_point2 = if offset2 >= 0 {
-        SeekFrom::Start(offset2 as u64)
-    } else {
-        SeekFrom::End(offset2)
-    };
-
-    file1.seek(seek_point1).expect("Seeking file1");
-    file2.seek(seek_point2).expect("Seeking file2");
-
-    let mut bytes1 = vec![0u8; num_bytes];
-    let mut bytes2 = vec![0u8; num_bytes];
-
-    file1.read_exact(&mut bytes1).expect("Reading file1");
-    file2.read_exact(&mut bytes2).expect("Reading file2");
-
-    bytes1 == bytes2
+    Ok(read_bytes(path1, offset1)? == read_bytes(path2, offset2)?)
 }
 
 /// Returns n powers of the given scalar: 1, s, s^2, ..., s^(n-1)
@@ -110,24 +223,18 @@ pub fn powers(s: &Scalar, n: usize) -> Vec<Scalar> {
         .collect()
 }
 
-/// Hashes (with the specified hash function) the given slice of points
-pub fn hash_points<H>(points: &[G1Affine]) -> [u8; 64]
-where
-    H: Digest<OutputSize = U64>,
-{
-    let mut hasher = H::new();
-    for p in points {
-        hasher.update(p.to_raw_bytes());
-    }
-    hasher.finalize().into()
-}
-
-/// Initialize progress bar for display progress of verifying and updating SRS
+/// Initialize progress bar for display progress of verifying and updating SRS.
+///
+/// Besides the percentage, the bar reports the throughput (points/s) and the
+/// ETA, so that multi-hour operations can be told apart from stuck ones.
 pub fn initialize_progress_bar(nr_points: usize, msg: Option<String>) -> ProgressBar {
     let pb = ProgressBar::new(nr_points as u64);
     pb.set_style(
         ProgressStyle::default_bar()
-            .template("[{elapsed_precise}] [{bar:40.cyan/blue}] {percent}% {msg}")
+            .template(
+                "{prefix}[{elapsed_precise}] [{bar:40.cyan/blue}] {percent}% \
+                 ({per_sec}, ETA {eta}) {msg}",
+            )
             .unwrap()
             .progress_chars("#-"),
     );
@@ -137,52 +244,310 @@ pub fn initialize_progress_bar(nr_points: usize, msg: Option<String>) -> Progres
     pb
 }
 
-/// Open all update proof directories from the default folder; return a vector
-/// of them sorted by the canonical order
-pub fn open_update_proof_dirs() -> Vec<DirEntry> {
-    let path = Path::new("./proofs");
-    let mut proof_files: Vec<(usize, DirEntry)> = Vec::new();
-    for entry in open_dir(path) {
-        let entry = entry.expect("Invalid proof file");
-        let file_name = entry
-            .file_name()
-            .into_string()
-            .expect("Failed to parse canonical file name");
-
-        if let Some(number) = file_name
-            .strip_prefix("proof")
-            .and_then(|s| s.parse::<usize>().ok())
+/// Initialize a progress bar for the given phase (e.g. "[1/2]") and attach it
+/// to `mp`, so that several bars can be displayed at once
+pub fn initialize_phase_progress_bar(
+    mp: &MultiProgress,
+    nr_points: usize,
+    phase: &str,
+    msg: Option<String>,
+) -> ProgressBar {
+    let pb = mp.add(initialize_progress_bar(nr_points, msg));
+    pb.set_prefix(format!("{phase} "));
+    pb
+}
+
+/// Directory of the update proofs of the given parameter set: `proofs` for
+/// the main chain, `proofs/<set>` otherwise
+pub fn proofs_dir(parameter_set: Option<&str>) -> PathBuf {
+    match parameter_set {
+        None => PathBuf::from("proofs"),
+        Some(set) => Path::new("proofs").join(set),
+    }
+}
+
+/// Path of a file in the repository (e.g. `proofs/proof5`), as used by git
+/// and the GitHub API: its components are always separated by `/`, including
+/// on Windows
+pub fn repo_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Name of the proof file in a contribution directory
+pub const CONTRIBUTION_PROOF_FILE: &str = "proof";
+
+/// Name of the metadata file in a contribution directory
+pub const CONTRIBUTION_METADATA_FILE: &str = "meta.json";
+
+/// Name of the signature file in a contribution directory
+pub const CONTRIBUTION_SIGNATURE_FILE: &str = "signature";
+
+/// An update proof of the proofs directory, stored either as a file
+/// `proof<N>` (with its metadata in `proof<N>.json`) or in a contribution
+/// directory `<NNNN>-<contributor>`, next to the files belonging to it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofEntry {
+    /// Index of the proof in the chain, starting from 1
+    pub index: usize,
+    /// Contributor, as named by the contribution directory
+    pub contributor: Option<String>,
+    proof_path: PathBuf,
+}
+
+impl ProofEntry {
+    /// Path of the proof file
+    pub fn path(&self) -> PathBuf {
+        self.proof_path.clone()
+    }
+
+    /// Contribution directory of the proof, if it is stored in one
+    pub fn contribution_dir(&self) -> Option<&Path> {
+        self.contributor
+            .as_ref()
+            .map(|_| self.proof_path.parent().unwrap())
+    }
+
+    /// Path of the signature of the proof, in its contribution directory
+    pub fn signature_path(&self) -> Option<PathBuf> {
+        self.contribution_dir()
+            .map(|dir| dir.join(CONTRIBUTION_SIGNATURE_FILE))
+    }
+}
+
+/// Open all update proof directories from the given folder (see [proofs_dir]);
+/// return a vector of them sorted by the canonical order
+pub fn open_update_proof_dirs(proofs_dir: &Path) -> Result<Vec<ProofEntry>, SrsError> {
+    read_update_proof_dir(proofs_dir)
+}
+
+/// Index of the proof stored in a file with the given name (`proof<N>`), if
+/// it is one. Fails if the index is not written canonically (e.g. `proof07`)
+fn proof_file_index(file_name: &str) -> Result<Option<usize>, SrsError> {
+    let Some(digits) = file_name.strip_prefix("proof") else {
+        return Ok(None);
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Ok(None);
+    }
+    let index = parse_index(digits, file_name)?;
+    if digits != index.to_string() || index == 0 {
+        return Err(SrsError::Deserialization(format!(
+            "Non-canonical proof file name {file_name:?}, expected \"proof{index}\" (numbered from 1)"
+        )));
+    }
+    Ok(Some(index))
+}
+
+/// Index and contributor of a contribution directory (`<NNNN>-<contributor>`),
+/// if the name is the one of a contribution directory. Fails if the index is
+/// not written canonically (4 digits at least, e.g. `0007-alice`)
+fn contribution_dir_index(dir_name: &str) -> Result<Option<(usize, String)>, SrsError> {
+    let Some((digits, contributor)) = dir_name.split_once('-') else {
+        return Ok(None);
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Ok(None);
+    }
+    let index = parse_index(digits, dir_name)?;
+    if digits != format!("{index:04}") || index == 0 || contributor.is_empty() {
+        return Err(SrsError::Deserialization(format!(
+            "Non-canonical contribution directory {dir_name:?}, expected \"{index:04}-<contributor>\" (numbered from 1)"
+        )));
+    }
+    Ok(Some((index, contributor.to_string())))
+}
+
+/// The proof index written with the given digits in the name of a file
+fn parse_index(digits: &str, file_name: &str) -> Result<usize, SrsError> {
+    digits.parse().map_err(|_| {
+        SrsError::Deserialization(format!("Proof index of {file_name:?} out of range"))
+    })
+}
+
+/// Contribution directory of the `index`-th proof of the given folder, by the
+/// given contributor
+pub fn contribution_dir(proofs_dir: &Path, index: usize, contributor: &str) -> PathBuf {
+    proofs_dir.join(format!("{index:04}-{contributor}"))
+}
+
+/// Update proofs of the given directory, sorted by index. Besides the proofs
+/// (`proof<N>`), their metadata (`proof<N>.json`) and their checksum sidecars
+/// (`proof<N>.sha256`, checked against the proofs), the directory may only
+/// hold contribution directories, the directories of parameter sets and
+/// hidden files. Fails on unexpected files, on gaps in the numbering of the
+/// proofs, on indices used twice and on sidecars not matching their proofs
+fn read_update_proof_dir(dir: &Path) -> Result<Vec<ProofEntry>, SrsError> {
+    let io_error = |source| SrsError::Io {
+        action: "read",
+        path: dir.to_path_buf(),
+        source,
+    };
+    let layout_error = |msg: String| Err(SrsError::Verification(msg));
+
+    let mut proofs: Vec<ProofEntry> = Vec::new();
+    let mut metadata_indices = Vec::new();
+    let mut sidecar_indices = Vec::new();
+    for entry in fs::read_dir(dir).map_err(io_error)? {
+        let entry = entry.map_err(io_error)?;
+        let Ok(file_name) = entry.file_name().into_string() else {
+            return layout_error(format!("Non-UTF-8 file name {:?}", entry.path()));
+        };
+        let is_dir = entry.file_type().map_err(io_error)?.is_dir();
+
+        if let Some(index) = proof_file_index(&file_name)? {
+            if is_dir {
+                return layout_error(format!("{:?} is a directory", entry.path()));
+            }
+            proofs.push(ProofEntry {
+                index,
+                contributor: None,
+                proof_path: entry.path(),
+            });
+        } else if let Some(index) = file_name
+            .strip_suffix(".json")
+            .map(proof_file_index)
+            .transpose()?
+            .flatten()
         {
-            proof_files.push((number, entry));
+            metadata_indices.push(index);
+        } else if let Some(index) = file_name
+            .strip_suffix(SIDECAR_EXTENSION)
+            .and_then(|name| name.strip_suffix('.'))
+            .map(proof_file_index)
+            .transpose()?
+            .flatten()
+        {
+            sidecar_indices.push(index);
+        } else if let Some((index, contributor)) = is_dir
+            .then(|| contribution_dir_index(&file_name))
+            .transpose()?
+            .flatten()
+        {
+            let proof_path = entry.path().join(CONTRIBUTION_PROOF_FILE);
+            if !proof_path.is_file() {
+                return layout_error(format!(
+                    "The contribution directory {:?} has no {CONTRIBUTION_PROOF_FILE} file",
+                    entry.path()
+                ));
+            }
+            proofs.push(ProofEntry {
+                index,
+                contributor: Some(contributor),
+                proof_path,
+            });
+        } else if !is_dir && !file_name.starts_with('.') {
+            return layout_error(format!("Unexpected file {file_name:?} in {dir:?}"));
         }
     }
 
-    // Sort files by extracted number
-    proof_files.sort_by_key(|&(num, _)| num);
+    // Sort proofs by extracted number
+    proofs.sort_by_key(|proof| proof.index);
+
+    for (i, proof) in proofs.iter().enumerate() {
+        if proof.index <= i {
+            return layout_error(format!(
+                "Proof {} is stored twice in {dir:?}: {:?} and {:?}",
+                proof.index,
+                proofs[i - 1].proof_path,
+                proof.proof_path
+            ));
+        }
+        if proof.index != i + 1 {
+            return layout_error(format!(
+                "proof{} is missing from {dir:?} (found proof{})",
+                i + 1,
+                proof.index
+            ));
+        }
+    }
+    let is_proof_file =
+        |index: usize| index <= proofs.len() && proofs[index - 1].contributor.is_none();
+    for index in metadata_indices {
+        if !is_proof_file(index) {
+            return layout_error(format!(
+                "proof{index}.json in {dir:?} is the metadata of a missing proof"
+            ));
+        }
+    }
+    for index in sidecar_indices {
+        if !is_proof_file(index) {
+            return layout_error(format!(
+                "proof{index}.{SIDECAR_EXTENSION} in {dir:?} is the checksum sidecar of a missing proof"
+            ));
+        }
+        let proof_path = &proofs[index - 1].proof_path;
+        check_sidecar_digest(proof_path, &sha256_file(proof_path)?)?;
+    }
 
-    proof_files.into_iter().map(|(_, dir)| dir).collect()
+    Ok(proofs)
 }
 
-/// Create path for new SRS file based on previous number of updates
-pub fn derive_new_path(old_path: &Path) -> (PathBuf, PathBuf) {
-    let proofs_path = Path::new("proofs/");
-
-    let n = open_dir(proofs_path).filter_map(|entry| entry.ok()).count() + 1;
+/// Create path for new SRS file based on previous number of updates in the
+/// given proofs folder
+pub fn derive_new_path(proofs_dir: &Path, old_path: &Path) -> Result<(PathBuf, PathBuf), SrsError> {
+    // Only count the proofs themselves, not their metadata
+    let n = open_update_proof_dirs(proofs_dir)?.len() + 1;
 
     let new_srs_path = old_path.parent().unwrap().join(format!("srs{n}"));
-    let new_proof_path = proofs_path.join(format!("proof{n}"));
+    let new_proof_path = proofs_dir.join(format!("proof{n}"));
+
+    Ok((new_srs_path, new_proof_path))
+}
+
+/// Records the SHA-256 digest of an input of the toxic waste
+fn entropy_input(source: EntropySource, file_name: Option<String>, input: &[u8]) -> EntropyInput {
+    EntropyInput {
+        source,
+        file_name,
+        sha256: Some(hex::encode(Sha256::digest(input))),
+    }
+}
 
-    (new_srs_path, new_proof_path)
+/// Runs the health tests on an input of the toxic waste, recording why it
+/// fails them
+fn check_health(unhealthy: &mut Vec<String>, input: &EntropyInput, bytes: &[u8]) {
+    if let Err(reason) = health_check(bytes) {
+        let name = match &input.file_name {
+            Some(file_name) => file_name.clone(),
+            None => format!("{:?}", input.source).to_lowercase(),
+        };
+        unhealthy.push(format!("{name} input: {reason}"));
+    }
 }
 
-/// Generates a scalar from various randomness sources
+/// Generates a scalar from various randomness sources: the user input, the OS
+/// randomness, the hardware randomness (if `hardware`), then the content of the
+/// given files (the standard input for `-`) and the given (public) beacon
+/// values, in this order. Returns it with the hashes of the inputs used, to be
+/// recorded in the proof metadata.
+///
+/// The inputs other than the beacons must pass the health tests of
+/// [crate::health], unless `force` is set (then a warning is printed).
+///
+/// All intermediate secret material (entropy buffers, seed, RNG state) is
+/// erased before returning; the caller is responsible for erasing the returned
+/// scalar (e.g. by wrapping it in a [crate::cancel::Secret]).
 pub fn generate_toxic_waste(
     mut rng: impl RngCore + CryptoRng,
     entropy: Option<String>,
     os_randomness: Option<bool>,
-) -> Scalar {
+    hardware: bool,
+    files: &[PathBuf],
+    beacons: &[Vec<u8>],
+    force: bool,
+) -> Result<(Scalar, Vec<EntropyInput>), SrsError> {
+    let stdin_error = |source| SrsError::Io {
+        action: "read",
+        path: PathBuf::from("-"),
+        source,
+    };
     // Use Blake2b for combining output from different entropy sources
     let mut hasher = Blake2b512::new();
+    let mut inputs = Vec::new();
+    let mut unhealthy = Vec::new();
 
     // Read random user input (or get it from argument)
     let mut user_input = String::new();
@@ -192,32 +557,361 @@ pub fn generate_toxic_waste(
         println!("\nPlease, provide external entropy (e.g. by hitting your keyboard randomly), then press [ENTER]");
         std::io::stdin()
             .read_line(&mut user_input)
-            .expect("Failed to read user input");
+            .map_err(stdin_error)?;
     }
     hasher.update(user_input.trim());
-
-    if os_randomness.unwrap_or_else(|| {
-        let mut answer = String::new();
-        print!("\nDo you also want to include randomness from your OS? (Recommended) [Y/n] ");
-        std::io::stdout().flush().unwrap();
-
-        std::io::stdin()
-            .read_line(&mut answer)
-            .expect("Failed to read answer");
-
-        let answer = answer.trim().to_lowercase();
-        answer != "n" && answer != "no"
-    }) {
+    inputs.push(entropy_input(
+        EntropySource::Keyboard,
+        None,
+        user_input.trim().as_bytes(),
+    ));
+    check_health(&mut unhealthy, &inputs[0], user_input.trim().as_bytes());
+    user_input.zeroize();
+
+    let os_randomness = match os_randomness {
+        Some(os_randomness) => os_randomness,
+        None => {
+            let mut answer = String::new();
+            print!("\nDo you also want to include randomness from your OS? (Recommended) [Y/n] ");
+            // The prompt is only a hint, the answer is read regardless
+            let _ = std::io::stdout().flush();
+
+            std::io::stdin()
+                .read_line(&mut answer)
+                .map_err(stdin_error)?;
+
+            let answer = answer.trim().to_lowercase();
+            answer != "n" && answer != "no"
+        }
+    };
+    if os_randomness {
         println!("Including OS randomness...");
         let mut os_input = [0u8; 512];
-        rng.try_fill_bytes(&mut os_input).expect("512 bytes");
+        rng.fill_bytes(&mut os_input);
         hasher.update(os_input);
+        inputs.push(entropy_input(EntropySource::Os, None, &os_input));
+        check_health(&mut unhealthy, inputs.last().unwrap(), &os_input);
+        os_input.zeroize();
     } else {
         println!("Skipping OS randomness...");
     }
 
-    // Hash it all together and use hash as seed for RNG
-    let digest: [u8; 32] = hasher.finalize()[0..32].try_into().unwrap();
+    if hardware {
+        let mut hardware_input = [0u8; HARDWARE_INPUT_SIZE];
+        let generator = hardware_randomness(&mut hardware_input);
+        println!("Including hardware randomness ({generator})...");
+        hasher.update(hardware_input);
+        inputs.push(entropy_input(
+            EntropySource::Hardware,
+            None,
+            &hardware_input,
+        ));
+        check_health(&mut unhealthy, inputs.last().unwrap(), &hardware_input);
+        hardware_input.zeroize();
+    }
+
+    for path in files {
+        let mut file_input = Vec::new();
+        if is_stdin(path) {
+            println!("Including the standard input...");
+            open_input(path)?
+                .read_to_end(&mut file_input)
+                .map_err(stdin_error)?;
+            hasher.update(&file_input);
+            inputs.push(entropy_input(EntropySource::Stdin, None, &file_input));
+        } else {
+            println!("Including the content of {path:?}...");
+            open_file(path)?
+                .read_to_end(&mut file_input)
+                .map_err(|source| SrsError::Io {
+                    action: "read",
+                    path: path.clone(),
+                    source,
+                })?;
+            hasher.update(&file_input);
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned());
+            inputs.push(entropy_input(EntropySource::File, file_name, &file_input));
+        }
+        check_health(&mut unhealthy, inputs.last().unwrap(), &file_input);
+        file_input.zeroize();
+    }
+
+    for beacon in beacons {
+        hasher.update(beacon);
+        inputs.push(entropy_input(EntropySource::Beacon, None, beacon));
+    }
+
+    if !unhealthy.is_empty() {
+        let report = unhealthy.join("\n  ");
+        if force {
+            println!(
+                "\nWarning: the entropy inputs look degenerate, proceeding anyway:\n  {report}"
+            );
+        } else {
+            hasher.reset();
+            return Err(SrsError::Verification(format!(
+                "The entropy inputs look degenerate:\n  {report}\n\
+                 Provide other inputs, or pass --force to proceed anyway"
+            )));
+        }
+    }
+
+    // Hash it all together and use hash as seed for RNG. Resetting the hasher
+    // clears the entropy absorbed so far
+    let mut output = hasher.finalize_reset();
+    let mut digest: [u8; 32] = output[0..32].try_into().unwrap();
+    output.as_mut_slice().zeroize();
+
+    let mut rng = ChaCha20Rng::from_seed(digest);
+    digest.zeroize();
+
+    let nu = Scalar::random(&mut rng);
+    wipe(&mut rng, ChaCha20Rng::from_seed([0u8; 32]));
+
+    Ok((nu, inputs))
+}
+
+#[cfg(test)]
+mod utils_tests {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use blstrs::{G1Affine, Scalar};
+    use halo2curves::{
+        ff::Field,
+        group::{prime::PrimeCurveAffine, Curve},
+        serde::SerdeObject,
+    };
+    use rand_core::OsRng;
+
+    use crate::{
+        cache::{sidecar_content, sidecar_path},
+        ceremony::SRS,
+        error::SrsError,
+        metadata::EntropySource,
+        progress::NoProgress,
+        state::CeremonyContext,
+        utils::{
+            decode_g1_point, generate_toxic_waste, open_update_proof_dirs, read_update_proof_dir,
+            repo_path, FP_MODULUS,
+        },
+    };
+
+    /// Creates a fresh proofs directory holding empty files with the given
+    /// names, and the directory of a parameter set
+    fn proofs_dir_with(name: &str, files: &[&str]) -> PathBuf {
+        let dir = Path::new("/tmp").join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("set")).unwrap();
+        for file in files {
+            fs::write(dir.join(file), []).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn proofs_are_sorted() {
+        let mut files: Vec<String> = (1..=10).rev().map(|i| format!("proof{i}")).collect();
+        files.push("proof2.json".to_string());
+        files.push(".gitkeep".to_string());
+        let files: Vec<&str> = files.iter().map(String::as_str).collect();
+        let dir = proofs_dir_with("test_proofs_sorted", &files);
+
+        let names: Vec<String> = read_update_proof_dir(&dir)
+            .unwrap()
+            .iter()
+            .map(|entry| {
+                entry
+                    .path()
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        let expected: Vec<String> = (1..=10).map(|i| format!("proof{i}")).collect();
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn contribution_dirs_are_read() {
+        let dir = proofs_dir_with("test_proofs_contribution_dirs", &["proof1", "proof1.json"]);
+        fs::create_dir(dir.join("0002-alice")).unwrap();
+        fs::write(dir.join("0002-alice/proof"), []).unwrap();
+        fs::write(dir.join("0002-alice/meta.json"), []).unwrap();
+
+        let proofs = read_update_proof_dir(&dir).unwrap();
+        assert_eq!(proofs.len(), 2);
+        assert_eq!(proofs[0].contributor, None);
+        assert_eq!(proofs[1].contributor.as_deref(), Some("alice"));
+        assert_eq!(proofs[1].path(), dir.join("0002-alice/proof"));
+        assert_eq!(
+            proofs[1].signature_path(),
+            Some(dir.join("0002-alice/signature"))
+        );
+
+        fs::create_dir(dir.join("0001-bob")).unwrap();
+        fs::write(dir.join("0001-bob/proof"), []).unwrap();
+        let err = read_update_proof_dir(&dir).unwrap_err();
+        assert!(err.to_string().starts_with("Proof 1 is stored twice"));
+    }
+
+    #[test]
+    fn gaps_are_detected() {
+        let dir = proofs_dir_with("test_proofs_gap", &["proof1", "proof3"]);
+        let err = read_update_proof_dir(&dir).unwrap_err();
+        assert!(err.to_string().starts_with("proof2 is missing"), "{err}");
+    }
+
+    #[test]
+    fn non_canonical_names_are_detected() {
+        let dir = proofs_dir_with("test_proofs_non_canonical", &["proof1", "proof01"]);
+        let err = read_update_proof_dir(&dir).unwrap_err();
+        assert!(matches!(err, SrsError::Deserialization(_)));
+        assert!(err.to_string().starts_with("Non-canonical proof file name"));
+    }
 
-    Scalar::random(ChaCha20Rng::from_seed(digest))
+    #[test]
+    fn unexpected_files_are_detected() {
+        let dir = proofs_dir_with("test_proofs_unexpected", &["proof1", "proof2.bak"]);
+        let err = read_update_proof_dir(&dir).unwrap_err();
+        assert!(err.to_string().starts_with("Unexpected file"), "{err}");
+    }
+
+    #[test]
+    fn updated_proofs_are_read() {
+        let dir = proofs_dir_with("test_proofs_updated", &[]);
+        let ctx = CeremonyContext::for_tests();
+        let mut old_path = PathBuf::from("/tmp/test_proofs_updated_srs0");
+        SRS::generate(1 << 8, OsRng)
+            .write_to_file(&old_path)
+            .unwrap();
+        for i in 1..=2 {
+            let new_path = PathBuf::from(format!("/tmp/test_proofs_updated_srs{i}"));
+            let nu = Scalar::random(OsRng);
+            let proof = SRS::update_file(&ctx, &old_path, &new_path, &nu, 100, &NoProgress)
+                .unwrap()
+                .unwrap();
+            proof
+                .write_to_file_validated(&dir.join(format!("proof{i}")))
+                .unwrap();
+            old_path = new_path;
+        }
+        assert!(sidecar_path(&dir.join("proof2")).is_file());
+        assert_eq!(open_update_proof_dirs(&dir).unwrap().len(), 2);
+
+        fs::write(
+            sidecar_path(&dir.join("proof1")),
+            sidecar_content("proof1", &[0u8; 32]),
+        )
+        .unwrap();
+        let err = open_update_proof_dirs(&dir).unwrap_err();
+        assert!(matches!(err, SrsError::Verification(_)), "{err}");
+
+        fs::remove_file(dir.join("proof1.sha256")).unwrap();
+        fs::write(dir.join("proof3.sha256"), []).unwrap();
+        let err = open_update_proof_dirs(&dir).unwrap_err();
+        assert!(
+            err.to_string().contains("sidecar of a missing proof"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn repo_paths_use_forward_slashes() {
+        let path = Path::new("proofs").join("set").join("proof5");
+        assert_eq!(repo_path(&path), "proofs/set/proof5");
+    }
+
+    #[test]
+    fn non_canonical_encodings_are_rejected() {
+        let point = (G1Affine::generator() * Scalar::random(OsRng)).to_affine();
+        let mut bytes = point.to_raw_bytes();
+        assert_eq!(decode_g1_point(&bytes), Some(point));
+        assert_eq!(decode_g1_point(&bytes[1..]), None);
+
+        // The x-coordinate plus the modulus, which is still below 2^384
+        let mut carry = 0u128;
+        for (limb, modulus) in bytes.chunks_mut(8).zip(FP_MODULUS) {
+            let sum =
+                u64::from_le_bytes(limb.try_into().unwrap()) as u128 + modulus as u128 + carry;
+            limb.copy_from_slice(&(sum as u64).to_le_bytes());
+            carry = sum >> 64;
+        }
+        assert_eq!(carry, 0);
+        assert_eq!(decode_g1_point(&bytes), None);
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn windows_paths_are_converted() {
+        assert_eq!(
+            repo_path(Path::new(r"proofs\set\proof5")),
+            "proofs/set/proof5"
+        );
+    }
+
+    #[test]
+    fn keyboard_entropy_ignores_line_endings() {
+        // The console of Windows terminates the line with CRLF
+        let derive = |line: &str| {
+            generate_toxic_waste(
+                OsRng,
+                Some(line.to_string()),
+                Some(false),
+                false,
+                &[],
+                &[],
+                false,
+            )
+            .unwrap()
+        };
+        let (nu, inputs) = derive("keyboard mashing\r\n");
+        let (nu_lf, inputs_lf) = derive("keyboard mashing\n");
+        assert_eq!(nu, nu_lf);
+        assert_eq!(inputs, inputs_lf);
+    }
+
+    #[test]
+    fn entropy_inputs_are_recorded() {
+        let file = Path::new("/tmp/test_entropy_file");
+        fs::write(file, b"hardware randomness").unwrap();
+        let files = [file.to_path_buf()];
+        let beacons = [vec![7u8; 32]];
+        let derive = || {
+            generate_toxic_waste(
+                OsRng,
+                Some("keyboard mashing".to_string()),
+                Some(false),
+                false,
+                &files,
+                &beacons,
+                false,
+            )
+            .unwrap()
+        };
+
+        let (nu, inputs) = derive();
+        assert_eq!(derive().0, nu, "The derivation is deterministic");
+        let sources: Vec<_> = inputs.iter().map(|input| input.source).collect();
+        assert_eq!(
+            sources,
+            [
+                EntropySource::Keyboard,
+                EntropySource::File,
+                EntropySource::Beacon
+            ]
+        );
+        assert_eq!(inputs[1].file_name.as_deref(), Some("test_entropy_file"));
+        assert_eq!(
+            inputs[0].sha256,
+            Some(hex::encode(<sha2::Sha256 as sha2::Digest>::digest(
+                b"keyboard mashing"
+            )))
+        );
+    }
 }
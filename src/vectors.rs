@@ -0,0 +1,240 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Known-answer test vectors.
+//!
+//! [test_vectors] runs a small ceremony, deterministically derived from a
+//! seed, and records all its intermediate values, so that third-party
+//! implementations of the verifier can be checked against them. Points are
+//! encoded (hex) as in SRS files, i.e. uncompressed, and scalars (hex) in
+//! big-endian, as in update proofs.
+
+use blstrs::{G1Affine, G2Affine, Scalar};
+use halo2curves::{
+    ff::Field,
+    group::{prime::PrimeCurveAffine, Curve},
+    serde::SerdeObject,
+};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    cache::extend_transcript,
+    ceremony::SRS,
+    progress::NoProgress,
+    schnorr::{ProofFormat, UpdateProof, SCHNORR_DOMAIN},
+    state::{CeremonyContext, CeremonyManifest},
+    utils::{powers, TranscriptHash},
+};
+
+fn scalar_hex(scalar: &Scalar) -> String {
+    hex::encode(scalar.to_bytes_be())
+}
+
+/// An SRS, with its SHA-256 digest (as a file)
+#[derive(Clone, Debug, Serialize)]
+pub struct SrsVector {
+    pub g1s: Vec<String>,
+    pub g2s: Vec<String>,
+    pub sha256: String,
+}
+
+impl SrsVector {
+    fn of(srs: &SRS) -> Self {
+        SrsVector {
+            g1s: srs
+                .g1s
+                .iter()
+                .map(|point| hex::encode(point.to_raw_bytes()))
+                .collect(),
+            g2s: srs
+                .g2s
+                .iter()
+                .map(|point| hex::encode(point.to_raw_bytes()))
+                .collect(),
+            sha256: hex::encode(srs.sha256()),
+        }
+    }
+}
+
+/// An update proof, with the values of its Schnorr proof
+#[derive(Clone, Debug, Serialize)]
+pub struct ProofVector {
+    /// The [ProofFormat] of `bytes`, e.g. `v2`
+    pub format: String,
+    pub g: String,
+    pub h: String,
+    /// Commitment of the Schnorr proof
+    pub a: String,
+    /// Fiat-Shamir challenge of the Schnorr proof
+    pub challenge: String,
+    /// Response of the Schnorr proof
+    pub z: String,
+    /// The serialized proof, as stored in a proof file
+    pub bytes: String,
+    pub sha256: String,
+}
+
+impl ProofVector {
+    fn of(proof: &UpdateProof, format: ProofFormat) -> Self {
+        let schnorr_proof = proof.schnorr_proof();
+        let challenge =
+            schnorr_proof.challenge(proof.g, proof.h, proof.transcript_hash, proof.ceremony_id);
+        let bytes = proof.to_bytes(format);
+        ProofVector {
+            format: format!("{format:?}").to_lowercase(),
+            g: hex::encode(proof.g.to_raw_bytes()),
+            h: hex::encode(proof.h.to_raw_bytes()),
+            a: hex::encode(schnorr_proof.a().to_raw_bytes()),
+            challenge: scalar_hex(&challenge),
+            z: scalar_hex(&schnorr_proof.z()),
+            sha256: hex::encode(Sha256::digest(&bytes)),
+            bytes: hex::encode(bytes),
+        }
+    }
+}
+
+/// A contribution of the test ceremony
+#[derive(Clone, Debug, Serialize)]
+pub struct ContributionVector {
+    /// Toxic waste of the contribution
+    pub nu: String,
+    /// The resulting SRS
+    pub srs: SrsVector,
+    /// The update proof, in the latest format
+    pub proof: ProofVector,
+    /// The same update, proved in the legacy v1 format (untagged Blake2b512
+    /// transcript), as the proofs of the original ceremony
+    pub legacy_proof: ProofVector,
+    /// Transcript hash of the chain of (latest format) proofs so far, see
+    /// [crate::cache::ChainHead::extend]
+    pub chain_transcript: String,
+}
+
+/// Test vectors of a small ceremony
+#[derive(Clone, Debug, Serialize)]
+pub struct TestVectors {
+    /// Seed (hex) of the ChaCha20 RNG all the secrets are drawn from
+    pub seed: String,
+    pub transcript_hash: TranscriptHash,
+    /// Domain tag of the Schnorr transcript of the latest proof format
+    pub schnorr_domain: String,
+    /// ID of the test ceremony, see [CeremonyManifest::ceremony_id]
+    pub ceremony_id: String,
+    /// The initial SRS, for a tau drawn from the seed
+    pub genesis: SrsVector,
+    pub contributions: Vec<ContributionVector>,
+}
+
+/// Runs a ceremony of `nr_contributions` updates of an SRS of 2^`log2_len` G1
+/// points, deterministically derived from `seed`, and records its values
+pub fn test_vectors(seed: [u8; 32], log2_len: usize, nr_contributions: usize) -> TestVectors {
+    let mut rng = ChaCha20Rng::from_seed(seed);
+
+    let tau = Scalar::random(&mut rng);
+    let mut srs = SRS {
+        g1s: powers(&tau, 1 << log2_len)
+            .iter()
+            .map(|power| (G1Affine::generator() * power).to_affine())
+            .collect(),
+        g2s: [
+            G2Affine::generator(),
+            (G2Affine::generator() * tau).to_affine(),
+        ],
+    };
+    let genesis = SrsVector::of(&srs);
+
+    let ctx = CeremonyContext::new(&CeremonyManifest::default(), &srs.g1s[1], None);
+    let (transcript_hash, ceremony_id) = (ctx.transcript_hash, ctx.ceremony_id);
+
+    let mut chain_transcript = [0u8; 32];
+    let contributions = (0..nr_contributions)
+        .map(|_| {
+            let nu = Scalar::random(&mut rng);
+            let (g, g2) = (srs.g1s[1], srs.g2s[1]);
+            let input_srs_hash = srs.sha256();
+            // Only the points are kept, the proof is recreated with a nonce
+            // drawn from the seed
+            srs.update_with_progress(&ctx, &nu, &NoProgress);
+
+            let proof = UpdateProof::create_with(
+                g,
+                srs.g1s[1],
+                &nu,
+                Some(transcript_hash),
+                Some(ceremony_id),
+                &mut rng,
+            )
+            .with_g2_update(g2, srs.g2s[1])
+            .with_srs_hashes(input_srs_hash, srs.sha256());
+            let legacy_proof = UpdateProof::create_with(g, srs.g1s[1], &nu, None, None, &mut rng);
+
+            let proof = ProofVector::of(&proof, ProofFormat::for_proof(&proof));
+            let mut proof_sha256 = [0u8; 32];
+            hex::decode_to_slice(&proof.sha256, &mut proof_sha256).unwrap();
+            chain_transcript = extend_transcript(&chain_transcript, &proof_sha256);
+
+            ContributionVector {
+                nu: scalar_hex(&nu),
+                srs: SrsVector::of(&srs),
+                proof,
+                legacy_proof: ProofVector::of(&legacy_proof, ProofFormat::V1),
+                chain_transcript: hex::encode(chain_transcript),
+            }
+        })
+        .collect();
+
+    TestVectors {
+        seed: hex::encode(seed),
+        transcript_hash,
+        schnorr_domain: String::from_utf8_lossy(SCHNORR_DOMAIN).into_owned(),
+        ceremony_id: hex::encode(ceremony_id),
+        genesis,
+        contributions,
+    }
+}
+
+#[cfg(test)]
+mod vectors_tests {
+    use crate::{schnorr::UpdateProof, vectors::test_vectors};
+
+    #[test]
+    fn test_vectors_are_deterministic() {
+        let vectors = test_vectors([1u8; 32], 3, 2);
+        assert_eq!(
+            serde_json::to_string(&vectors).unwrap(),
+            serde_json::to_string(&test_vectors([1u8; 32], 3, 2)).unwrap()
+        );
+        assert_ne!(
+            serde_json::to_string(&vectors).unwrap(),
+            serde_json::to_string(&test_vectors([2u8; 32], 3, 2)).unwrap()
+        );
+
+        assert_eq!(vectors.genesis.g1s.len(), 8);
+        assert_eq!(vectors.contributions[0].proof.g, vectors.genesis.g1s[1]);
+        assert_eq!(
+            vectors.contributions[1].proof.g,
+            vectors.contributions[0].srs.g1s[1]
+        );
+        for contribution in &vectors.contributions {
+            for proof in [&contribution.proof, &contribution.legacy_proof] {
+                let bytes = hex::decode(&proof.bytes).unwrap();
+                assert!(UpdateProof::from_bytes(&bytes).unwrap().is_valid());
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+// This file is part of midnight-trusted-setup.
+// Copyright (C) 2025 Midnight Foundation
+// SPDX-License-Identifier: Apache-2.0
+// Licensed under the Apache License, Version 2.0 (the "License");
+// You may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interruption of an update by a signal. This lives in its own test binary
+//! since the cancellation flag is global to the process.
+
+#![cfg(unix)]
+
+use std::{path::Path, process::Command, thread, time::Duration};
+
+use blstrs::{G1Affine, G2Affine, Scalar};
+use halo2curves::{
+    ff::Field,
+    group::{prime::PrimeCurveAffine, Curve},
+};
+use rand_core::OsRng;
+use srs::{
+    cancel::{install_ctrlc_handler, is_cancelled, wipe_live_secrets, Secret},
+    ceremony::SRS,
+    progress::NoProgress,
+    state::{CeremonyContext, CeremonyManifest},
+    utils::powers,
+};
+
+#[test]
+fn signal_cancels_update_and_wipes_secret() {
+    let tau = Scalar::random(OsRng);
+    let srs = SRS {
+        g1s: powers(&tau, 1 << 8)
+            .iter()
+            .map(|power| (G1Affine::generator() * power).to_affine())
+            .collect(),
+        g2s: [
+            G2Affine::generator(),
+            (G2Affine::generator() * tau).to_affine(),
+        ],
+    };
+    let old_path = Path::new("/tmp/test_interrupt_old");
+    let new_path = Path::new("/tmp/test_interrupt_new");
+    srs.write_to_file(old_path).unwrap();
+
+    install_ctrlc_handler();
+    let nu_value = Scalar::random(OsRng);
+    let nu = Secret::new(nu_value, Scalar::ZERO);
+    let dropped = Secret::new(Scalar::random(OsRng), Scalar::ZERO);
+    drop(dropped);
+
+    // Deliberately inject a SIGTERM into this very process
+    let status = Command::new("kill")
+        .args(["-TERM", &std::process::id().to_string()])
+        .status()
+        .expect("Failed to run kill");
+    assert!(status.success());
+
+    for _ in 0..100 {
+        if is_cancelled() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    assert!(is_cancelled(), "The signal did not request cancellation");
+
+    // The update stops at the first safe point and leaves no output behind
+    let ctx = CeremonyContext::new(&CeremonyManifest::default(), &srs.g1s[1], None);
+    let proof = SRS::update_file(&ctx, old_path, new_path, &nu, 16, &NoProgress).unwrap();
+    assert!(proof.is_none());
+    assert!(!new_path.exists());
+    assert_eq!(*nu, nu_value);
+
+    // What a second signal does before exiting: the live secret is wiped
+    // (and the dropped one was unregistered)
+    wipe_live_secrets();
+    assert_eq!(*nu, Scalar::ZERO);
+}